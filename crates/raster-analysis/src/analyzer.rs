@@ -49,6 +49,7 @@ impl Analyzer {
 
         finalize_tile_averages(&mut metrics.tile_metrics);
         finalize_sequence_averages(&mut metrics.sequence_metrics);
+        finalize_cycle_estimates(&mut metrics);
 
         Ok(metrics)
     }
@@ -178,6 +179,14 @@ fn ingest_tile_record(metrics: &mut Metrics, record: &raster_runtime::TileProfil
     tile_metrics.total_other_wrapper_ns = tile_metrics
         .total_other_wrapper_ns
         .saturating_add(record.other_wrapper_ns);
+    if let Some(cycles) = record.cycles {
+        tile_metrics.cycle_samples += 1;
+        tile_metrics.total_sampled_cycles =
+            tile_metrics.total_sampled_cycles.saturating_add(cycles);
+        tile_metrics.total_sampled_proof_cycles = tile_metrics
+            .total_sampled_proof_cycles
+            .saturating_add(record.proof_cycles.unwrap_or_default());
+    }
 }
 
 fn ingest_sequence_record(metrics: &mut Metrics, record: &raster_runtime::SequenceProfileRecord) {
@@ -278,6 +287,34 @@ fn finalize_tile_averages(
     }
 }
 
+/// Average the sampled cycles of each tile and extrapolate them to every
+/// invocation of the run.
+fn finalize_cycle_estimates(metrics: &mut Metrics) {
+    let mut total_cycles = None::<u64>;
+    let mut total_proof_cycles = None::<u64>;
+    for tile_metrics in metrics.tile_metrics.values_mut() {
+        if tile_metrics.cycle_samples == 0 {
+            continue;
+        }
+        let avg_cycles = tile_metrics.total_sampled_cycles / tile_metrics.cycle_samples;
+        let avg_proof_cycles = tile_metrics.total_sampled_proof_cycles / tile_metrics.cycle_samples;
+        tile_metrics.estimated_cycles = Some(avg_cycles);
+        tile_metrics.estimated_proof_cycles = Some(avg_proof_cycles);
+        total_cycles = Some(
+            total_cycles
+                .unwrap_or_default()
+                .saturating_add(avg_cycles.saturating_mul(tile_metrics.invocations)),
+        );
+        total_proof_cycles = Some(
+            total_proof_cycles
+                .unwrap_or_default()
+                .saturating_add(avg_proof_cycles.saturating_mul(tile_metrics.invocations)),
+        );
+    }
+    metrics.total_estimated_cycles = total_cycles;
+    metrics.total_estimated_proof_cycles = total_proof_cycles;
+}
+
 fn finalize_sequence_averages(
     sequence_metrics: &mut std::collections::HashMap<String, SequenceMetrics>,
 ) {
@@ -330,6 +367,9 @@ mod tests {
                     trace_event_publish_ns: 0,
                     output_coordinate_publish_ns: 0,
                     other_wrapper_ns: 1,
+                    cycles: Some(1_000),
+                    proof_cycles: Some(65_536),
+                    limit_breach: None,
                 }),
                 ProfileRecord::Tile(TileProfileRecord {
                     invocation_index: 2,
@@ -349,6 +389,9 @@ mod tests {
                     trace_event_publish_ns: 1,
                     output_coordinate_publish_ns: 0,
                     other_wrapper_ns: 0,
+                    cycles: None,
+                    proof_cycles: None,
+                    limit_breach: None,
                 }),
                 ProfileRecord::Sequence(SequenceProfileRecord {
                     invocation_index: 3,
@@ -400,6 +443,10 @@ mod tests {
         assert_eq!(tile_metrics.avg_raster_overhead_ns, 6);
         assert_eq!(tile_metrics.avg_output_store_ns, 1);
         assert_eq!(tile_metrics.avg_trace_event_publish_ns, 0);
+        assert_eq!(tile_metrics.cycle_samples, 1);
+        assert_eq!(tile_metrics.estimated_cycles, Some(1_000));
+        assert_eq!(metrics.total_estimated_cycles, Some(2_000));
+        assert_eq!(metrics.total_estimated_proof_cycles, Some(131_072));
         let latest_tile_stats = metrics.latest_tile_stats.as_ref().unwrap();
         assert_eq!(latest_tile_stats.invocation_index, 2);
         assert_eq!(latest_tile_stats.tile_id, "alpha");
@@ -59,6 +59,12 @@ pub struct Metrics {
     pub total_sequence_end_event_publish_ns: u64,
     #[serde(default)]
     pub total_sequence_other_wrapper_ns: u64,
+    /// zkVM cycles of the whole run, extrapolated from the sampled tile
+    /// invocations. `None` when no invocation was executed on RISC0.
+    #[serde(default)]
+    pub total_estimated_cycles: Option<u64>,
+    #[serde(default)]
+    pub total_estimated_proof_cycles: Option<u64>,
     #[serde(default)]
     pub latest_tile_stats: Option<LatestTileStats>,
     pub tile_metrics: HashMap<TileId, TileMetrics>,
@@ -134,7 +140,18 @@ pub struct TileMetrics {
     pub total_other_wrapper_ns: u64,
     #[serde(default)]
     pub avg_other_wrapper_ns: u64,
+    /// Average zkVM cycles per invocation, over the sampled invocations.
     pub estimated_cycles: Option<u64>,
+    /// Average padded proof cycles per invocation, over the sampled invocations.
+    #[serde(default)]
+    pub estimated_proof_cycles: Option<u64>,
+    /// Number of invocations executed on RISC0 for cycle counts.
+    #[serde(default)]
+    pub cycle_samples: u64,
+    #[serde(default)]
+    pub total_sampled_cycles: u64,
+    #[serde(default)]
+    pub total_sampled_proof_cycles: u64,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -55,6 +55,28 @@ impl Report {
             }
         }
 
+        if let (Some(total_cycles), Some(total_proof_cycles)) = (
+            self.metrics.total_estimated_cycles,
+            self.metrics.total_estimated_proof_cycles,
+        ) {
+            lines.push(String::from("  zkVM Cost (RISC0 estimate)"));
+            lines.push(format!(
+                "    Total: {} cycles, {} proof cycles",
+                format_count(total_cycles),
+                format_count(total_proof_cycles)
+            ));
+            for (tile_id, metrics) in sampled_tiles(&self.metrics.tile_metrics) {
+                lines.push(format!(
+                    "    {}: avg {} cycles ({} proof), calls {}, sampled {}",
+                    tile_id.0,
+                    format_count(metrics.estimated_cycles.unwrap_or_default()),
+                    format_count(metrics.estimated_proof_cycles.unwrap_or_default()),
+                    format_count(metrics.invocations),
+                    format_count(metrics.cycle_samples)
+                ));
+            }
+        }
+
         lines.push(String::from("  Execution Shape"));
         lines.push(format!(
             "    Max nesting depth: {}",
@@ -88,6 +110,28 @@ fn sorted_tiles(
     entries
 }
 
+/// Tiles with cycle samples, by extrapolated proof cycles over the run.
+fn sampled_tiles(
+    tile_metrics: &std::collections::HashMap<raster_core::tile::TileId, TileMetrics>,
+) -> Vec<(&raster_core::tile::TileId, &TileMetrics)> {
+    let mut entries: Vec<_> = tile_metrics
+        .iter()
+        .filter(|(_, metrics)| metrics.cycle_samples > 0)
+        .collect();
+    let run_proof_cycles = |metrics: &TileMetrics| {
+        metrics
+            .estimated_proof_cycles
+            .unwrap_or_default()
+            .saturating_mul(metrics.invocations)
+    };
+    entries.sort_by(|left, right| {
+        run_proof_cycles(right.1)
+            .cmp(&run_proof_cycles(left.1))
+            .then_with(|| left.0 .0.cmp(&right.0 .0))
+    });
+    entries
+}
+
 fn format_duration(duration_ns: u64) -> String {
     if duration_ns >= 1_000_000_000 {
         format!("{:.3} s", duration_ns as f64 / 1_000_000_000.0)
@@ -183,8 +227,44 @@ mod tests {
         assert!(text.contains("    merge_tokens: total 21.740 ms, avg 1.449 ms, calls 15"));
         assert!(text.contains("    normalize: total 7.150 ms, avg 50.709 us, calls 141"));
         assert!(!text.contains("cold_tile"));
+        assert!(!text.contains("zkVM Cost"));
         assert!(text.contains("  Execution Shape"));
         assert!(text.contains("    Max nesting depth: 2"));
         assert!(text.contains("    Profile records: 1,251"));
     }
+
+    #[test]
+    fn text_report_lists_sampled_zkvm_cycles() {
+        let mut metrics = Metrics {
+            total_estimated_cycles: Some(1_500_000),
+            total_estimated_proof_cycles: Some(2_097_152),
+            ..Metrics::default()
+        };
+        metrics.tile_metrics.insert(
+            TileId::from("tokenize_chunk"),
+            TileMetrics {
+                invocations: 20,
+                estimated_cycles: Some(75_000),
+                estimated_proof_cycles: Some(131_072),
+                cycle_samples: 4,
+                ..TileMetrics::default()
+            },
+        );
+        metrics.tile_metrics.insert(
+            TileId::from("normalize"),
+            TileMetrics {
+                invocations: 3,
+                ..TileMetrics::default()
+            },
+        );
+
+        let text = Report::new(metrics).to_text();
+
+        assert!(text.contains("  zkVM Cost (RISC0 estimate)"));
+        assert!(text.contains("    Total: 1,500,000 cycles, 2,097,152 proof cycles"));
+        assert!(text.contains(
+            "    tokenize_chunk: avg 75,000 cycles (131,072 proof), calls 20, sampled 4"
+        ));
+        assert!(!text.contains("    normalize: avg"));
+    }
 }
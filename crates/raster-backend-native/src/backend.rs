@@ -3,21 +3,28 @@
 //! The native backend executes tiles as native code without zkVM overhead.
 //! It's useful for development, testing, and debugging but does not produce proofs.
 //!
-//! Native execution works via subprocess: the CLI builds the user's project
-//! and runs it with special arguments to execute a specific tile.
+//! Native execution works via subprocess: each tile is built into a runner
+//! binary that links the crate declaring the tile, and runs once per input.
 
 use raster_backend::{
     backend::HexString, ArtifactStore, Backend, CompilationArtifact, ExecutionMode,
     ResourceEstimate, TileExecutionResult,
 };
-use raster_core::{tile::TileMetadata, Error, Result};
+use raster_core::{
+    draft::TileReplayJournal,
+    postcard,
+    tile::{artifact_name, TileMetadata},
+    Error, Result,
+};
 
+use crate::runner_builder::RunnerBuilder;
 use serde::{Deserialize, Serialize};
 use std::{
     any::Any,
     fs,
+    io::Write,
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, Stdio},
 };
 
 /// Native executable - just a path to the compiled binary.
@@ -75,7 +82,7 @@ impl ArtifactStore for NativeArtifactStore {
 
         let artifact_dir = output_dir
             .join("tiles")
-            .join(&native_artifact.tile_id)
+            .join(artifact_name(&native_artifact.tile_id))
             .join("native");
 
         fs::create_dir_all(&artifact_dir).map_err(Error::Io)?;
@@ -101,7 +108,10 @@ impl ArtifactStore for NativeArtifactStore {
         output_dir: &Path,
         source_hash: Option<String>,
     ) -> Option<Box<dyn CompilationArtifact>> {
-        let artifact_dir = output_dir.join("tiles").join(tile_id).join("native");
+        let artifact_dir = output_dir
+            .join("tiles")
+            .join(artifact_name(tile_id))
+            .join("native");
 
         let manifest_content = fs::read_to_string(artifact_dir.join("manifest.json")).ok()?;
         let manifest: NativeManifest = serde_json::from_str(&manifest_content).ok()?;
@@ -128,13 +138,15 @@ impl ArtifactStore for NativeArtifactStore {
 /// This backend is primarily for development and testing. It does not
 /// support proof generation - use the RISC0 backend for that.
 ///
-/// Native execution works via subprocess: the user's project binary is
-/// invoked with special arguments to execute a specific tile.
+/// Native execution works via subprocess: each tile is built into a small
+/// runner binary that calls the tile's ABI wrapper on the input it is given.
 pub struct NativeBackend {
     /// Whether to simulate cycle counting (for testing).
     pub simulate_cycles: bool,
     /// Path to the user's project (for building).
     project_path: Option<PathBuf>,
+    /// Directory for runner crates and artifacts.
+    output_dir: Option<PathBuf>,
     /// Artifact store for caching compiled binaries.
     artifact_store: NativeArtifactStore,
 }
@@ -145,6 +157,7 @@ impl NativeBackend {
         Self {
             simulate_cycles: true,
             project_path: None,
+            output_dir: None,
             artifact_store: NativeArtifactStore,
         }
     }
@@ -154,6 +167,7 @@ impl NativeBackend {
         Self {
             simulate_cycles: false,
             project_path: None,
+            output_dir: None,
             artifact_store: NativeArtifactStore,
         }
     }
@@ -164,54 +178,30 @@ impl NativeBackend {
         self
     }
 
-    /// Find the Cargo target directory for a project.
-    /// Handles both workspace members and standalone projects.
-    fn find_target_path(project_path: &std::path::Path) -> Option<std::path::PathBuf> {
-        // Run cargo metadata to get the target directory
-        let output = std::process::Command::new("cargo")
-            .current_dir(project_path)
-            .args(["metadata", "--format-version", "1", "--no-deps"])
-            .output()
-            .ok()?;
-
-        if !output.status.success() {
-            return None;
-        }
-
-        let stdout = String::from_utf8(output.stdout).ok()?;
-        let meta: serde_json::Value = serde_json::from_str(&stdout).ok()?;
+    /// Set the output directory for runner crates and artifacts.
+    /// Defaults to `target/raster` under the project path.
+    pub fn with_output_dir(mut self, path: PathBuf) -> Self {
+        self.output_dir = Some(path);
+        self
+    }
 
-        meta.get("target_directory")
-            .and_then(|v| v.as_str())
-            .map(std::path::PathBuf::from)
+    fn output_dir(&self) -> Result<PathBuf> {
+        if let Some(output_dir) = &self.output_dir {
+            return Ok(output_dir.clone());
+        }
+        self.project_path
+            .as_ref()
+            .map(|path| path.join("target").join("raster"))
+            .ok_or_else(|| Error::Other("Native backend requires project_path to be set".into()))
     }
 
-    /// Extract the binary name from a Cargo.toml file.
-    fn extract_binary_name(project_path: &std::path::Path) -> Option<String> {
-        let cargo_toml = std::fs::read_to_string(project_path.join("Cargo.toml")).ok()?;
-
-        // Simple parsing: look for name = "..." in [package] section
-        let mut in_package = false;
-        for line in cargo_toml.lines() {
-            let trimmed = line.trim();
-            if trimmed == "[package]" {
-                in_package = true;
-                continue;
-            }
-            if trimmed.starts_with('[') {
-                in_package = false;
-                continue;
-            }
-            if in_package && trimmed.starts_with("name") {
-                if let Some(start) = line.find('"') {
-                    let rest = &line[start + 1..];
-                    if let Some(end) = rest.find('"') {
-                        return Some(rest[..end].to_string());
-                    }
-                }
-            }
+    /// Get the runner builder.
+    fn runner_builder(&self) -> Result<RunnerBuilder> {
+        let mut builder = RunnerBuilder::new(self.output_dir()?);
+        if let Some(ref path) = self.project_path {
+            builder = builder.with_user_crate(path.clone());
         }
-        None
+        Ok(builder)
     }
 }
 
@@ -229,50 +219,37 @@ impl Backend for NativeBackend {
     fn compile_tile(
         &self,
         tile_metadata: &TileMetadata,
-        _content_hash: Option<String>,
+        content_hash: Option<String>,
     ) -> Result<Box<dyn CompilationArtifact>> {
-        // Native backend builds the user's project
-        let project_path = self
-            .project_path
-            .as_ref()
-            .ok_or_else(|| Error::Other("Native backend requires project_path to be set".into()))?;
-
-        // Build the project
-        let output = Command::new("cargo")
-            .current_dir(project_path)
-            .args(["build", "--release"])
-            .output()
-            .map_err(|e| Error::Other(format!("Failed to run cargo build: {}", e)))?;
-
-        println!("cargo build output: {:?}", output);
-
-        if !output.status.success() {
-            return Err(Error::Other(format!(
-                "cargo build failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            )));
+        let tile_id = &tile_metadata.id.0;
+        let output_dir = self.output_dir()?;
+
+        if let Some(cached) = self
+            .artifact_store
+            .load(tile_id, &output_dir, content_hash.clone())
+        {
+            return Ok(cached);
         }
 
-        // Find the binary path
-        let binary_name = Self::extract_binary_name(project_path).ok_or_else(|| {
-            Error::Other("Could not determine binary name from Cargo.toml".into())
-        })?;
+        let binary_path = self
+            .runner_builder()?
+            .build_runner(tile_metadata)
+            .map_err(|e| {
+                Error::Other(format!(
+                    "Failed to build native runner for tile '{}': {}",
+                    tile_id, e
+                ))
+            })?;
 
-        let target_dir =
-            Self::find_target_path(project_path).unwrap_or_else(|| project_path.join("target"));
-        let binary_path = target_dir.join("release").join(&binary_name);
+        let artifact = NativeCompilationArtifact {
+            binary_path,
+            tile_id: tile_id.to_string(),
+        };
 
-        if !binary_path.exists() {
-            return Err(Error::Other(format!(
-                "Binary not found at: {}",
-                binary_path.display()
-            )));
-        }
+        self.artifact_store
+            .save(&artifact, &output_dir, content_hash)?;
 
-        Ok(Box::new(NativeCompilationArtifact {
-            binary_path,
-            tile_id: tile_metadata.id.0.clone(),
-        }))
+        Ok(Box::new(artifact))
     }
 
     fn execute_tile(
@@ -281,7 +258,63 @@ impl Backend for NativeBackend {
         input: &[u8],
         mode: ExecutionMode,
     ) -> Result<TileExecutionResult> {
-        todo!("resturcture native backend");
+        let native = compilation_artifact
+            .as_any()
+            .downcast_ref::<NativeCompilationArtifact>()
+            .ok_or_else(|| Error::Other("Expected NativeCompilationArtifact".into()))?;
+
+        if mode.generates_proof() {
+            return Err(Error::Other(
+                "Native backend does not support proof generation. Use the RISC0 backend.".into(),
+            ));
+        }
+
+        let output_file = tempfile::NamedTempFile::new()
+            .map_err(|e| Error::Other(format!("Failed to create output file: {}", e)))?;
+
+        let mut child = Command::new(&native.binary_path)
+            .arg(output_file.path())
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                Error::Other(format!(
+                    "Failed to run {}: {}",
+                    native.binary_path.display(),
+                    e
+                ))
+            })?;
+        child
+            .stdin
+            .take()
+            .ok_or_else(|| Error::Other("Failed to open runner stdin".into()))?
+            .write_all(input)
+            .map_err(Error::Io)?;
+        let status = child.wait_with_output().map_err(Error::Io)?;
+
+        if !status.status.success() {
+            return Err(Error::Other(format!(
+                "Tile '{}' failed: {}",
+                native.tile_id,
+                String::from_utf8_lossy(&status.stderr).trim()
+            )));
+        }
+
+        // The runner writes the wall-clock time, then the replay journal
+        let bytes = fs::read(output_file.path()).map_err(Error::Io)?;
+        let (wall_time, journal) = bytes.split_first_chunk::<8>().ok_or_else(|| {
+            Error::Other(format!(
+                "Tile '{}' produced a truncated output",
+                native.tile_id
+            ))
+        })?;
+        let replay_output: TileReplayJournal = postcard::from_bytes(journal)
+            .map_err(|e| Error::Other(format!("Failed to decode replay journal: {}", e)))?;
+
+        Ok(TileExecutionResult::native(
+            replay_output.output_bytes,
+            u64::from_le_bytes(*wall_time),
+        ))
     }
 
     fn artifact_store(&self) -> &dyn ArtifactStore {
@@ -1,3 +1,4 @@
 mod backend;
+mod runner_builder;
 
 pub use backend::{NativeArtifactStore, NativeBackend, NativeCompilationArtifact};
@@ -0,0 +1,547 @@
+//! Runner crate builder for native execution.
+//!
+//! Each tile is wrapped in a small host program that links the crate
+//! declaring the tile and calls the same ABI wrapper as the RISC0 guest, so
+//! both backends produce identical output bytes for the same input.
+//!
+//! The runner calls the wrapper through the symbol the `#[tile]` macro exports
+//! under the tile's module path rather than through a `use` path, so tiles in
+//! private modules can run. A crate without a library is linked through a
+//! generated library package compiling its binary root.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use cargo_toml::{Dependency, DepsSet, Manifest, OptionalFile, Package};
+use raster_core::tile::{artifact_name, TileMetadata};
+use raster_core::{Error, Result};
+use serde::Serialize;
+
+/// Prefix of the symbols the `#[tile]` macro exports for native runners.
+const NATIVE_ENTRY_SYMBOL: &str = "__raster_tile_native_entry";
+
+/// Configuration for building native runner crates.
+pub struct RunnerBuilder {
+    /// Output directory for artifacts.
+    output_dir: PathBuf,
+    /// Path to the user's crate that contains the tiles.
+    user_crate_path: Option<PathBuf>,
+}
+
+/// The crate a tile's runner links.
+struct TileCrate {
+    /// Manifest directory of the crate.
+    dir: PathBuf,
+    /// Package section of the manifest, with workspace inheritance resolved.
+    package: Package,
+    /// Rest of the manifest, with workspace inheritance resolved.
+    manifest: Manifest,
+    /// Name the crate is compiled under, which starts the tile's module path.
+    crate_name: String,
+    /// Root source file of the binary, for crates without a library.
+    bin_root: Option<PathBuf>,
+}
+
+impl RunnerBuilder {
+    /// Create a new runner builder with the given output directory.
+    pub fn new(output_dir: PathBuf) -> Self {
+        Self {
+            output_dir,
+            user_crate_path: None,
+        }
+    }
+
+    /// Set the path to the user's crate containing tiles.
+    pub fn with_user_crate(mut self, path: PathBuf) -> Self {
+        self.user_crate_path = Some(path);
+        self
+    }
+
+    /// Directory of the generated runner crate for a tile.
+    pub fn runner_dir(&self, tile_id: &str) -> PathBuf {
+        self.output_dir
+            .join("tiles")
+            .join(artifact_name(tile_id))
+            .join("native")
+            .join("runner")
+    }
+
+    /// Directory of the library package generated for a crate without a
+    /// library.
+    fn library_dir(&self, package: &str) -> PathBuf {
+        self.output_dir
+            .join("native-crates")
+            .join(artifact_name(package))
+    }
+
+    /// Cargo target directory shared by all runner crates, so the tile
+    /// crates and their dependencies are only compiled once.
+    fn target_dir(&self) -> PathBuf {
+        self.output_dir.join("native-target")
+    }
+
+    /// The crate a tile's runner links: the crate declaring the tile when it
+    /// is a dependency of the project, otherwise the user crate.
+    fn tile_crate(&self, tile: &TileMetadata) -> Result<TileCrate> {
+        let dir = match &tile.crate_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => self.user_crate_path.clone().ok_or_else(|| {
+                Error::Other("Native backend requires project_path to be set".into())
+            })?,
+        };
+        let manifest_path = dir.join("Cargo.toml");
+        let mut manifest = Manifest::from_path(&manifest_path).map_err(|e| {
+            Error::Other(format!("Failed to read {}: {}", manifest_path.display(), e))
+        })?;
+        let package = manifest.package.take().ok_or_else(|| {
+            Error::Other(format!(
+                "{} does not declare a package",
+                manifest_path.display()
+            ))
+        })?;
+
+        let (crate_name, bin_root) = match &manifest.lib {
+            Some(lib) => (
+                lib.name.clone().unwrap_or_else(|| package.name.clone()),
+                None,
+            ),
+            None => {
+                // Without a library, tiles are compiled into the default binary
+                let bin = manifest
+                    .bin
+                    .iter()
+                    .find(|bin| {
+                        bin.name.as_deref()
+                            == Some(package.default_run.as_deref().unwrap_or(&package.name))
+                    })
+                    .or(match manifest.bin.as_slice() {
+                        [bin] => Some(bin),
+                        _ => None,
+                    })
+                    .ok_or_else(|| {
+                        Error::Other(format!(
+                            "{} has neither a library nor a default binary",
+                            manifest_path.display()
+                        ))
+                    })?;
+                let name = bin.name.clone().unwrap_or_else(|| package.name.clone());
+                let root = dir.join(bin.path.as_deref().unwrap_or("src/main.rs"));
+                (name, Some(root))
+            }
+        };
+
+        Ok(TileCrate {
+            dir,
+            package,
+            manifest,
+            crate_name: crate_name.replace('-', "_"),
+            bin_root,
+        })
+    }
+
+    /// Generate the runner source for a tile.
+    ///
+    /// The generated program:
+    /// 1. Reads input bytes from stdin
+    /// 2. Calls the tile's ABI wrapper, timing the call
+    /// 3. Writes the wall-clock nanoseconds (little-endian `u64`) followed by
+    ///    the wrapper output to the file named by its first argument
+    ///
+    /// Output goes to a file rather than stdout so tiles remain free to print.
+    pub fn generate_runner_main(&self, tile: &TileMetadata, crate_name: &str) -> String {
+        let tile_path = tile.path.as_str();
+        let crate_name = crate_name.replace('-', "_");
+        let symbol = format!("{}::{}::{}", NATIVE_ENTRY_SYMBOL, crate_name, tile_path);
+
+        format!(
+            r##"//! Auto-generated native runner for tile: {tile_path}
+
+            use std::io::Read;
+
+            // Link the crate declaring the tile, which exports the entry below
+            extern crate {crate_name} as _;
+
+            extern "Rust" {{
+                #[link_name = "{symbol}"]
+                fn tile_entry(input: &[u8]) -> Result<Vec<u8>, String>;
+            }}
+
+            fn main() {{
+                let output_path = std::env::args().nth(1).expect("Missing output path");
+
+                let mut input = Vec::new();
+                std::io::stdin()
+                    .read_to_end(&mut input)
+                    .expect("Failed to read tile input");
+
+                let start = std::time::Instant::now();
+                // SAFETY: the #[tile] macro exports the symbol with this signature
+                let result = unsafe {{ tile_entry(&input) }};
+                let wall_time_ns = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+
+                match result {{
+                    Ok(output) => {{
+                        let mut bytes = wall_time_ns.to_le_bytes().to_vec();
+                        bytes.extend_from_slice(&output);
+                        std::fs::write(&output_path, bytes).expect("Failed to write tile output");
+                    }}
+                    Err(error) => {{
+                        eprintln!("{{}}", error);
+                        std::process::exit(1);
+                    }}
+                }}
+            }}
+            "##
+        )
+    }
+
+    /// Generate Cargo.toml for a runner crate.
+    pub fn generate_runner_cargo_toml(
+        &self,
+        tile: &TileMetadata,
+        crate_dir: &Path,
+        package: &str,
+    ) -> String {
+        format!(
+            r##"[package]
+            name = "raster-native-{runner}"
+            version = "0.1.0"
+            edition = "2021"
+
+            [dependencies]
+            {package} = {{ path = {crate_dir} }}
+
+            [workspace]
+            "##,
+            runner = artifact_name(&tile.id.0),
+            crate_dir = toml::Value::from(crate_dir.display().to_string()),
+        )
+    }
+
+    /// Write the library package of a crate without a library, compiling
+    /// its binary root with its dependencies and features, and return its
+    /// directory.
+    fn write_library_package(&self, tile_crate: &TileCrate, bin_root: &Path) -> Result<PathBuf> {
+        let package = &tile_crate.package;
+        let manifest = &tile_crate.manifest;
+        let dir = &tile_crate.dir;
+
+        let mut package_table = toml::Table::new();
+        package_table.insert("name".into(), package.name.clone().into());
+        package_table.insert("version".into(), to_toml(&package.version)?);
+        package_table.insert("edition".into(), to_toml(&package.edition)?);
+        if let Some(OptionalFile::Path(build)) = &package.build {
+            package_table.insert("build".into(), dir.join(build).display().to_string().into());
+        }
+
+        let mut lib_table = toml::Table::new();
+        lib_table.insert("name".into(), tile_crate.crate_name.clone().into());
+        lib_table.insert("path".into(), bin_root.display().to_string().into());
+
+        let mut target_table = toml::Table::new();
+        for (cfg, target) in &manifest.target {
+            let mut table = toml::Table::new();
+            table.insert(
+                "dependencies".into(),
+                to_toml(&absolute_paths(&target.dependencies, dir))?,
+            );
+            table.insert(
+                "build-dependencies".into(),
+                to_toml(&absolute_paths(&target.build_dependencies, dir))?,
+            );
+            target_table.insert(cfg.clone(), table.into());
+        }
+
+        let mut cargo_toml = toml::Table::new();
+        cargo_toml.insert("package".into(), package_table.into());
+        cargo_toml.insert("lib".into(), lib_table.into());
+        cargo_toml.insert(
+            "dependencies".into(),
+            to_toml(&absolute_paths(&manifest.dependencies, dir))?,
+        );
+        cargo_toml.insert(
+            "build-dependencies".into(),
+            to_toml(&absolute_paths(&manifest.build_dependencies, dir))?,
+        );
+        cargo_toml.insert("target".into(), target_table.into());
+        cargo_toml.insert("features".into(), to_toml(&manifest.features)?);
+        cargo_toml.insert("workspace".into(), toml::Table::new().into());
+
+        let library_dir = self.library_dir(&package.name);
+        fs::create_dir_all(&library_dir).map_err(Error::Io)?;
+        let contents =
+            toml::to_string(&cargo_toml).map_err(|e| Error::Serialization(e.to_string()))?;
+        fs::write(library_dir.join("Cargo.toml"), contents).map_err(Error::Io)?;
+        Ok(library_dir)
+    }
+
+    /// Build a runner crate and return the path to its binary.
+    pub fn build_runner(&self, tile: &TileMetadata) -> Result<PathBuf> {
+        let tile_crate = self.tile_crate(tile)?;
+        let linked_dir = match &tile_crate.bin_root {
+            Some(bin_root) => self.write_library_package(&tile_crate, bin_root)?,
+            None => tile_crate.dir.clone(),
+        };
+        let runner_dir = self.runner_dir(&tile.id.0);
+        let src_dir = runner_dir.join("src");
+        fs::create_dir_all(&src_dir).map_err(Error::Io)?;
+
+        fs::write(
+            src_dir.join("main.rs"),
+            self.generate_runner_main(tile, &tile_crate.crate_name),
+        )
+        .map_err(Error::Io)?;
+        fs::write(
+            runner_dir.join("Cargo.toml"),
+            self.generate_runner_cargo_toml(tile, &linked_dir, &tile_crate.package.name),
+        )
+        .map_err(Error::Io)?;
+
+        // Resolve the same dependency versions as the project
+        if let Some(lock) = find_cargo_lock(&tile_crate.dir) {
+            if !runner_dir.join("Cargo.lock").exists() {
+                fs::copy(lock, runner_dir.join("Cargo.lock")).map_err(Error::Io)?;
+            }
+        }
+
+        let output = Command::new("cargo")
+            .current_dir(&runner_dir)
+            .args(["build", "--release"])
+            .env("CARGO_TARGET_DIR", self.target_dir())
+            .output()
+            .map_err(|e| Error::Other(format!("Failed to run cargo build: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(Error::Other(format!(
+                "cargo build failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+
+        let binary_path = self
+            .target_dir()
+            .join("release")
+            .join(format!("raster-native-{}", artifact_name(&tile.id.0)));
+
+        if !binary_path.exists() {
+            return Err(Error::Other(format!(
+                "Binary not found at: {}",
+                binary_path.display()
+            )));
+        }
+
+        Ok(binary_path)
+    }
+}
+
+/// Convert a manifest value to TOML.
+fn to_toml(value: &impl Serialize) -> Result<toml::Value> {
+    toml::Value::try_from(value).map_err(|e| Error::Serialization(e.to_string()))
+}
+
+/// Dependencies with their paths resolved against the crate directory, so
+/// they hold from the generated library package.
+fn absolute_paths(dependencies: &DepsSet, dir: &Path) -> DepsSet {
+    dependencies
+        .iter()
+        .map(|(name, dependency)| {
+            let mut dependency = dependency.clone();
+            if let Dependency::Detailed(detail) = &mut dependency {
+                if let Some(path) = &mut detail.path {
+                    *path = dir.join(&*path).display().to_string();
+                }
+            }
+            (name.clone(), dependency)
+        })
+        .collect()
+}
+
+/// Find the lock file of the workspace containing `dir`.
+fn find_cargo_lock(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|ancestor| ancestor.join("Cargo.lock"))
+        .find(|lock| lock.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{NativeBackend, NativeCompilationArtifact};
+    use raster_backend::{Backend, ExecutionMode};
+    use raster_core::postcard;
+    use raster_core::tile::TileId;
+
+    fn tile(id: &str, path: &str) -> TileMetadata {
+        TileMetadata {
+            id: TileId(id.to_string()),
+            name: id.to_string(),
+            path: path.to_string(),
+            crate_dir: None,
+            description: None,
+            estimated_cycles: None,
+            max_memory: None,
+        }
+    }
+
+    /// Directory for crates built by these tests, inside the workspace so
+    /// runners resolve dependencies from its lock file, and shared so the
+    /// dependencies are only compiled once.
+    fn fixtures_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../target/raster-native-fixtures")
+            .canonicalize()
+            .or_else(|_| {
+                let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join("../../target/raster-native-fixtures");
+                fs::create_dir_all(&dir)?;
+                dir.canonicalize()
+            })
+            .expect("fixtures dir should be created")
+    }
+
+    /// Write a crate depending on `raster` with the given source file.
+    fn write_tile_crate(name: &str, file: &str, source: &str) -> PathBuf {
+        let raster_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../raster");
+        let dir = fixtures_dir().join(name);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(
+            dir.join("Cargo.toml"),
+            format!(
+                r#"[package]
+                name = "{name}"
+                version = "0.1.0"
+                edition = "2021"
+
+                [features]
+                default = ["std"]
+                std = ["raster/std"]
+
+                [dependencies]
+                raster = {{ path = {raster}, default-features = false }}
+
+                [workspace]
+                "#,
+                raster = toml::Value::from(raster_dir.display().to_string()),
+            ),
+        )
+        .unwrap();
+        fs::write(dir.join("src").join(file), source).unwrap();
+        dir
+    }
+
+    /// Build the runner of a tile and execute it on `input`.
+    fn run_tile(crate_dir: &Path, tile: &TileMetadata, input: u64) -> (u64, Option<u64>) {
+        let output_dir = fixtures_dir().join("out");
+        let binary_path = RunnerBuilder::new(output_dir.clone())
+            .with_user_crate(crate_dir.to_path_buf())
+            .build_runner(tile)
+            .unwrap_or_else(|e| panic!("runner should build: {}", e));
+        let artifact = NativeCompilationArtifact {
+            binary_path,
+            tile_id: tile.id.0.clone(),
+        };
+        let result = NativeBackend::new()
+            .with_output_dir(output_dir)
+            .execute_tile(
+                &artifact,
+                &postcard::to_allocvec(&input).unwrap(),
+                ExecutionMode::Estimate,
+            )
+            .unwrap_or_else(|e| panic!("tile should run: {}", e));
+        (
+            postcard::from_bytes(&result.output).unwrap(),
+            result.wall_time_ns,
+        )
+    }
+
+    #[test]
+    fn runner_main_links_the_exported_entry_of_the_tile() {
+        let builder = RunnerBuilder::new(PathBuf::from("/out"));
+        let main_rs =
+            builder.generate_runner_main(&tile("math::double", "math::double"), "my-tiles");
+        assert!(main_rs.contains("extern crate my_tiles as _;"));
+        assert!(main_rs
+            .contains(r#"#[link_name = "__raster_tile_native_entry::my_tiles::math::double"]"#));
+        assert!(main_rs.contains("tile_entry(&input)"));
+    }
+
+    #[test]
+    fn runner_package_is_named_after_the_tile() {
+        let builder = RunnerBuilder::new(PathBuf::from("/out"));
+        let cargo_toml = builder.generate_runner_cargo_toml(
+            &tile("math::double", "math::double"),
+            Path::new("/ws/my-tiles"),
+            "my-tiles",
+        );
+        assert!(cargo_toml.contains(r#"name = "raster-native-math-double""#));
+        assert!(cargo_toml.contains(r#"my-tiles = { path = "/ws/my-tiles" }"#));
+        assert_eq!(
+            builder.runner_dir("math::double"),
+            PathBuf::from("/out/tiles/math-double/native/runner")
+        );
+    }
+
+    #[test]
+    fn runner_cargo_toml_escapes_the_crate_dir() {
+        let builder = RunnerBuilder::new(PathBuf::from("/out"));
+        let crate_dir = r#"C:\ws\my "tiles""#;
+        let cargo_toml = builder.generate_runner_cargo_toml(
+            &tile("double", "double"),
+            Path::new(crate_dir),
+            "my-tiles",
+        );
+        let parsed: toml::Table = toml::from_str(&cargo_toml).expect("valid TOML");
+        assert_eq!(
+            parsed["dependencies"]["my-tiles"]["path"].as_str(),
+            Some(crate_dir)
+        );
+    }
+
+    #[test]
+    fn runner_executes_a_tile_in_a_private_module() {
+        let crate_dir = write_tile_crate(
+            "private-module-tiles",
+            "lib.rs",
+            r#"
+            mod math {
+                mod ops {
+                    use raster::prelude::*;
+
+                    #[tile]
+                    fn double(x: u64) -> u64 {
+                        x * 2
+                    }
+                }
+            }
+            "#,
+        );
+        let (output, wall_time_ns) = run_tile(&crate_dir, &tile("double", "math::ops::double"), 21);
+        assert_eq!(output, 42);
+        assert!(wall_time_ns.is_some());
+    }
+
+    #[test]
+    fn runner_executes_a_tile_of_a_binary_only_crate() {
+        let crate_dir = write_tile_crate(
+            "binary-only-tiles",
+            "main.rs",
+            r#"
+            mod tiles {
+                use raster::prelude::*;
+
+                #[tile]
+                pub fn triple(x: u64) -> u64 {
+                    x * 3
+                }
+            }
+
+            fn main() {
+                println!("{}", tiles::triple(1));
+            }
+            "#,
+        );
+        let (output, _) = run_tile(&crate_dir, &tile("triple", "tiles::triple"), 14);
+        assert_eq!(output, 42);
+    }
+}
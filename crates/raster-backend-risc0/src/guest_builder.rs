@@ -8,6 +8,8 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use raster_core::tile::{artifact_name, TileMetadata};
+
 /// Configuration for building guest crates.
 pub struct GuestBuilder {
     /// Output directory for artifacts.
@@ -97,9 +99,28 @@ impl GuestBuilder {
         None
     }
 
+    /// Path and package name of the crate a tile's guest links: the crate
+    /// declaring the tile when it is a dependency of the project, otherwise
+    /// the user crate.
+    fn tile_crate(&self, tile: &TileMetadata) -> (Option<PathBuf>, Option<String>) {
+        match &tile.crate_dir {
+            Some(dir) => {
+                let dir = PathBuf::from(dir);
+                let name = fs::read_to_string(dir.join("Cargo.toml"))
+                    .ok()
+                    .and_then(|cargo_toml| Self::extract_package_name(&cargo_toml));
+                (Some(dir), name)
+            }
+            None => (self.user_crate_path.clone(), self.user_crate_name.clone()),
+        }
+    }
+
     /// Get the artifact directory for a tile.
     pub fn artifact_dir(&self, tile_id: &str) -> PathBuf {
-        self.output_dir.join("tiles").join(tile_id).join("risc0")
+        self.output_dir
+            .join("tiles")
+            .join(artifact_name(tile_id))
+            .join("risc0")
     }
 
     /// Generate the guest crate source for a tile.
@@ -107,59 +128,85 @@ impl GuestBuilder {
     /// The generated guest program:
     /// 1. Reads input bytes from the zkVM environment
     /// 2. Calls the tile's ABI wrapper function directly
-    /// 3. Commits the serialized user output to the journal
-    pub fn generate_guest_main(&self, tile_id: &str) -> String {
-        // Convert tile_id to a valid Rust identifier (replace hyphens with underscores)
-        let fn_name = tile_id.replace('-', "_");
+    /// 3. Commits the serialized replay journal to the journal
+    ///
+    /// A tile that panics still halts cleanly: a panic hook commits a replay
+    /// journal attesting the failure, so a recorded `TileFailed` step can be
+    /// proven. A runtime error of the wrapper traps the guest instead.
+    ///
+    /// The wrapper is imported from the module of the tile function, in the
+    /// crate declaring the tile.
+    pub fn generate_guest_main(&self, tile: &TileMetadata) -> String {
+        let tile_path = tile.path.as_str();
+        let (module, fn_name) = match tile_path.rsplit_once("::") {
+            Some((module, fn_name)) => (format!("{}::", module), fn_name),
+            None => (String::new(), tile_path),
+        };
 
         // The wrapper function name follows the pattern from the #[tile] macro
         let wrapper_name = format!("__raster_tile_replay_entry_{}", fn_name);
 
         // Get the crate name for the import
         let crate_name = self
-            .user_crate_name
-            .as_ref()
+            .tile_crate(tile)
+            .1
             .map(|n| n.replace('-', "_"))
             .unwrap_or_else(|| "user_crate".to_string());
 
         format!(
-            r##"//! Auto-generated RISC0 guest program for tile: {tile_id}
+            r##"//! Auto-generated RISC0 guest program for tile: {tile_path}
             #![no_main]
-            #![no_std]
-
-            extern crate alloc;
 
-            use alloc::vec::Vec;
+            use raster::core::draft::TileReplayJournal;
+            use raster::core::trace::{{panic_payload_message, TileFailureKind}};
+            use risc0_zkvm::guest::env;
 
-            // Import the tile's ABI wrapper from the user crate
+            // Import the tile's ABI wrapper from the crate declaring the tile
             // The #[tile] macro generates a public wrapper function for each tile
-            use {crate_name}::{wrapper_name};
+            use {crate_name}::{module}{wrapper_name};
 
             risc0_zkvm::guest::entry!(main);
 
             fn main() {{
                 // Read input length first, then raw bytes
-                let input_len: u32 = risc0_zkvm::guest::env::read();
-                let mut input = alloc::vec![0u8; input_len as usize];
-                risc0_zkvm::guest::env::read_slice(&mut input);
+                let input_len: u32 = env::read();
+                let mut input = vec![0u8; input_len as usize];
+                env::read_slice(&mut input);
+
+                // A panicking tile commits the failure it ended in and halts.
+                std::panic::set_hook(Box::new(|info| {{
+                    let message = panic_payload_message(info.payload());
+                    let journal = TileReplayJournal::failed(TileFailureKind::Panic, &message);
+                    let journal = raster::core::postcard::to_allocvec(&journal)
+                        .expect("Failed to serialize failure journal");
+                    env::commit_slice(&journal);
+                    env::exit(0);
+                }}));
 
                 // Call the tile's ABI wrapper (runtime failures trap here).
-                let output = {wrapper_name}(&input).expect("Tile runtime failure");
+                let output = match {wrapper_name}(&input) {{
+                    Ok(output) => output,
+                    Err(error) => {{
+                        drop(std::panic::take_hook());
+                        panic!("Tile runtime failure: {{}}", error);
+                    }}
+                }};
 
                 // Commit output to the journal
-                risc0_zkvm::guest::env::commit_slice(&output);
+                env::commit_slice(&output);
             }}
             "##,
-            tile_id = tile_id,
+            tile_path = tile_path,
             crate_name = crate_name,
+            module = module,
             wrapper_name = wrapper_name
         )
     }
 
     /// Generate Cargo.toml for a guest crate.
-    pub fn generate_guest_cargo_toml(&self, tile_id: &str) -> String {
-        // User crate dependency with default-features = false for no_std
-        let user_crate_dep = match (&self.user_crate_path, &self.user_crate_name) {
+    pub fn generate_guest_cargo_toml(&self, tile: &TileMetadata) -> String {
+        // Tile crate dependency with default-features = false for no_std
+        let user_crate_dep = match self.tile_crate(tile) {
             (Some(path), Some(name)) => {
                 format!(
                     r#"{} = {{ path = "{}", default-features = false }}"#,
@@ -190,12 +237,12 @@ impl GuestBuilder {
 
         format!(
             r##"[package]
-            name = "raster-guest-{tile_id}"
+            name = "raster-guest-{package}"
             version = "0.1.0"
             edition = "2021"
 
             [dependencies]
-            risc0-zkvm = {{ version = "1.2", default-features = false }}
+            risc0-zkvm = {{ version = "1.2", default-features = false, features = ["std"] }}
             raster = {{ path = "{raster_path}", default-features = false }}
             {user_crate_dep}
 
@@ -205,7 +252,7 @@ impl GuestBuilder {
 
             [workspace]
             "##,
-            tile_id = tile_id,
+            package = artifact_name(&tile.id.0),
             user_crate_dep = user_crate_dep,
             raster_path = raster_path
         )
@@ -251,17 +298,21 @@ impl GuestBuilder {
     }
 
     /// Build a guest crate and return the path to the ELF.
-    pub fn build_guest(&self, tile_id: &str, guest_dir: &Path) -> Result<PathBuf, GuestBuildError> {
+    pub fn build_guest(
+        &self,
+        tile: &TileMetadata,
+        guest_dir: &Path,
+    ) -> Result<PathBuf, GuestBuildError> {
         // Create guest source directory
         let src_dir = guest_dir.join("src");
         fs::create_dir_all(&src_dir)?;
 
         // Write main.rs
-        let main_rs = self.generate_guest_main(tile_id);
+        let main_rs = self.generate_guest_main(tile);
         fs::write(src_dir.join("main.rs"), main_rs)?;
 
         // Write Cargo.toml
-        let cargo_toml = self.generate_guest_cargo_toml(tile_id);
+        let cargo_toml = self.generate_guest_cargo_toml(tile);
         fs::write(guest_dir.join("Cargo.toml"), cargo_toml)?;
 
         // Find RISC0's cargo with the riscv32im-risc0-zkvm-elf target
@@ -301,7 +352,7 @@ impl GuestBuilder {
             .join("target")
             .join("riscv32im-risc0-zkvm-elf")
             .join("release")
-            .join(format!("raster-guest-{}", tile_id));
+            .join(format!("raster-guest-{}", artifact_name(&tile.id.0)));
 
         if !elf_path.exists() {
             return Err(GuestBuildError::ElfNotFound(elf_path));
@@ -16,7 +16,10 @@ use std::any::Any;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use raster_core::{tile::TileMetadata, Error, Result};
+use raster_core::{
+    tile::{artifact_name, TileMetadata},
+    Error, Result,
+};
 
 /// RISC0 executable - ELF binary and image ID.
 #[derive(Debug, Clone)]
@@ -84,7 +87,7 @@ impl ArtifactStore for Risc0ArtifactStore {
 
         let artifact_dir = output_dir
             .join("tiles")
-            .join(&risc0_artifact.tile_id)
+            .join(artifact_name(&risc0_artifact.tile_id))
             .join("risc0");
 
         fs::create_dir_all(&artifact_dir).map_err(Error::Io)?;
@@ -119,7 +122,10 @@ impl ArtifactStore for Risc0ArtifactStore {
         output_dir: &Path,
         source_hash: Option<String>,
     ) -> Option<Box<dyn CompilationArtifact>> {
-        let artifact_dir = output_dir.join("tiles").join(tile_id).join("risc0");
+        let artifact_dir = output_dir
+            .join("tiles")
+            .join(artifact_name(tile_id))
+            .join("risc0");
 
         let manifest_content = fs::read_to_string(artifact_dir.join("manifest.json")).ok()?;
         let manifest: Risc0Manifest = serde_json::from_str(&manifest_content).ok()?;
@@ -229,12 +235,14 @@ impl Backend for Risc0Backend {
         let temp_dir = tempfile::tempdir()
             .map_err(|e| Error::Other(format!("Failed to create temp directory: {}", e)))?;
 
-        let guest_dir = temp_dir.path().join(format!("guest-{}", tile_id));
+        let guest_dir = temp_dir
+            .path()
+            .join(format!("guest-{}", artifact_name(tile_id)));
         fs::create_dir_all(&guest_dir)
             .map_err(|e| Error::Other(format!("Failed to create guest directory: {}", e)))?;
 
         // Build the guest
-        let elf_path = builder.build_guest(tile_id, &guest_dir).map_err(|e| {
+        let elf_path = builder.build_guest(metadata, &guest_dir).map_err(|e| {
             Error::Other(format!(
                 "Failed to build guest for tile '{}': {}",
                 tile_id, e
@@ -152,6 +152,11 @@ pub struct TileExecutionResult {
 
     /// Whether the proof was verified (if proof was generated).
     pub verified: Option<bool>,
+
+    /// Wall-clock time spent in the tile, in nanoseconds.
+    /// Only reported by backends that run tiles as native code, where it is
+    /// the meaningful cost measure in place of cycles.
+    pub wall_time_ns: Option<u64>,
 }
 
 impl TileExecutionResult {
@@ -164,6 +169,7 @@ impl TileExecutionResult {
             proof_cycles: Some(calculate_proof_cycles(cycles)),
             receipt: None,
             verified: None,
+            wall_time_ns: None,
         }
     }
 
@@ -176,6 +182,19 @@ impl TileExecutionResult {
             proof_cycles,
             receipt: Some(receipt),
             verified: Some(verified),
+            wall_time_ns: None,
+        }
+    }
+
+    /// Create a new execution result for a tile run as native code.
+    pub fn native(output: Vec<u8>, wall_time_ns: u64) -> Self {
+        Self {
+            output,
+            cycles: None,
+            proof_cycles: None,
+            receipt: None,
+            verified: None,
+            wall_time_ns: Some(wall_time_ns),
         }
     }
 }
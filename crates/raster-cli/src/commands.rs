@@ -1,40 +1,39 @@
 //! Command implementations for the Raster CLI.
+pub mod bundle;
+pub mod commitment;
+pub mod cycle_estimate;
+pub mod determinism;
+pub mod fraud_proof;
 pub mod run;
 pub mod tile;
 
-use crate::utils::encode::{decode_execution_output, encode_input};
+use crate::utils::encode::{decode_output, encode_sequence_arguments};
 
-use crate::{AnalyzeFormat, BackendType, TraceFormat};
+use crate::{AnalyzeFormat, BackendType, DiffFormat, GraphFormat, TraceFormat};
 use raster_analysis::{Analyzer, Report};
-use raster_backend::{Backend, ExecutionFailure, ExecutionMode};
+use raster_backend::ExecutionMode;
 use raster_backend_native::NativeBackend;
 use raster_backend_risc0::Risc0Backend;
-use raster_compiler::sequence::{FlattenedStep, SequenceDiscovery};
 use raster_compiler::tile::TileDiscovery;
 use raster_core::{Error, Result};
 
 use raster_compiler::Project;
-use raster_compiler::{Builder, CfsBuilder};
+use raster_compiler::{
+    Builder, CfsBuilder, CfsDiff, CfsGraph, RecordedInputs, SchemaGenerator, TileCycles, TileTiming,
+};
+use raster_core::cfs::ControlFlowSchema;
 use raster_runtime::{ExecutionProfile, ProfileRecord, ProfileStreamEvent};
 
 use raster_compiler::backend::BackendImpl;
 use std::env;
 use std::fs;
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::thread;
 use std::time::Duration;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Get the output directory for artifacts.
-fn output_dir() -> PathBuf {
-    env::current_dir()
-        .unwrap_or_else(|_| PathBuf::from("."))
-        .join("target")
-        .join("raster")
-}
-
 #[derive(Debug, Clone)]
 pub(crate) struct RunArtifacts {
     pub run_id: String,
@@ -42,28 +41,58 @@ pub(crate) struct RunArtifacts {
     pub trace_path: PathBuf,
     pub profile_path: PathBuf,
     pub profile_stream_path: PathBuf,
+    pub checkpoint_path: PathBuf,
 }
 
 impl RunArtifacts {
-    fn new(run_id: String, trace_format: TraceFormat) -> Self {
-        let run_dir = output_dir().join("runs").join(&run_id);
+    fn new(output_dir: &Path, run_id: String, trace_format: TraceFormat) -> Self {
+        let run_dir = output_dir.join("runs").join(&run_id);
         Self {
             trace_path: run_dir.join(trace_format.trace_file_name()),
             profile_path: run_dir.join("profile.json"),
             profile_stream_path: run_dir.join("profile.ndjson"),
+            checkpoint_path: run_dir.join("checkpoint.json"),
             run_dir,
             run_id,
         }
     }
 }
 
-pub(crate) fn create_run_artifacts(trace_format: TraceFormat) -> Result<RunArtifacts> {
+/// Allocate a new run directory under the project's `output_dir`.
+pub(crate) fn create_run_artifacts(
+    output_dir: &Path,
+    trace_format: TraceFormat,
+) -> Result<RunArtifacts> {
     let run_id = generate_run_id();
-    let artifacts = RunArtifacts::new(run_id, trace_format);
+    let artifacts = RunArtifacts::new(output_dir, run_id, trace_format);
     fs::create_dir_all(&artifacts.run_dir)?;
     Ok(artifacts)
 }
 
+/// Artifacts of an earlier run, to resume it from its last checkpoint.
+pub(crate) fn resume_run_artifacts(
+    output_dir: &Path,
+    run_id: &str,
+    trace_format: TraceFormat,
+) -> Result<RunArtifacts> {
+    let artifacts = RunArtifacts::new(output_dir, run_id.to_string(), trace_format);
+    if !artifacts.run_dir.exists() {
+        return Err(Error::Other(format!(
+            "No run '{}' in {}",
+            run_id,
+            artifacts.run_dir.display()
+        )));
+    }
+    if !artifacts.checkpoint_path.exists() {
+        return Err(Error::Other(format!(
+            "Run '{}' has no checkpoint to resume from; it either completed or was \
+             interrupted before its first checkpoint",
+            run_id
+        )));
+    }
+    Ok(artifacts)
+}
+
 fn generate_run_id() -> String {
     static RUN_ID_COUNTER: AtomicU64 = AtomicU64::new(0);
     let timestamp = SystemTime::now()
@@ -84,28 +113,36 @@ fn project_path() -> PathBuf {
     env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
 }
 
+/// Load the project from the workspace around the current directory,
+/// selecting `package` when running from a workspace root.
+pub(crate) fn load_project(package: Option<&str>) -> Result<Project> {
+    Project::for_package(project_path(), package)
+}
+
 /// Create a backend instance.
-fn create_backend(backend_type: BackendType) -> Result<BackendImpl> {
+pub(crate) fn create_backend(backend_type: BackendType, project: &Project) -> Result<BackendImpl> {
     match backend_type {
         BackendType::Native => Ok(BackendImpl::Native(
-            NativeBackend::new().with_project_path(project_path()),
+            NativeBackend::new()
+                .with_project_path(project.root_dir.clone())
+                .with_output_dir(project.output_dir.clone()),
         )),
         BackendType::Risc0 => Ok(BackendImpl::Risc0(
-            Risc0Backend::new(output_dir()).with_user_crate(project_path()),
+            Risc0Backend::new(project.output_dir.clone()).with_user_crate(project.root_dir.clone()),
         )),
         _ => return Err(Error::Other("Invalid backend type".into())),
     }
 }
 
 /// Build command: compile tiles using the specified backend.
-pub fn build(backend_type: BackendType, tile: Option<String>) -> Result<()> {
+pub fn build(backend_type: BackendType, tile: Option<String>, package: Option<&str>) -> Result<()> {
     println!(
         "Building tiles with {} backend...",
         backend_type_name(backend_type)
     );
     println!();
 
-    let project = Project::new(project_path())?;
+    let project = load_project(package)?;
 
     // Discover tiles from source files
     let discovery = TileDiscovery::new(&project);
@@ -125,7 +162,7 @@ pub fn build(backend_type: BackendType, tile: Option<String>) -> Result<()> {
     }
     println!();
 
-    let backend = create_backend(backend_type)?;
+    let backend = create_backend(backend_type, &project)?;
     let builder = Builder::new(&project, &backend);
 
     // if let Some(tile) = discovery.get(tile_id) {
@@ -356,10 +393,12 @@ fn backend_type_name(backend_type: BackendType) -> &'static str {
 /// Preview command: execute a sequence with cycle count breakdown.
 pub fn run_sequence(
     backend_type: BackendType,
-    tile_id: &str,
+    sequence_id: &str,
     input: Option<&str>,
     prove: bool,
     verify: bool,
+    inputs_from: Option<&str>,
+    package: Option<&str>,
 ) -> Result<()> {
     let mode = match (prove, verify) {
         (_, true) => ExecutionMode::prove_and_verify(),
@@ -367,137 +406,166 @@ pub fn run_sequence(
         (false, false) => ExecutionMode::Estimate,
     };
 
-    let project = Project::new(project_path())?;
+    let project = load_project(package)?;
+    let cfs = CfsBuilder::new(&project)
+        .build()
+        .map_err(|e| Error::Other(format!("Failed to build CFS: {}", e)))?;
+    let sequence = cfs
+        .sequences
+        .iter()
+        .find(|sequence| sequence.id == sequence_id)
+        .ok_or_else(|| Error::Other(format!("Sequence '{}' not found", sequence_id)))?;
+    let arguments = encode_sequence_arguments(&sequence.input_types, input)?;
+    let mut recorded_inputs = match inputs_from {
+        Some(run_id) => recorded_run_inputs(&project, &cfs, run_id)?,
+        None => RecordedInputs::new(),
+    };
 
+    let backend = create_backend(backend_type, &project)?;
+    let builder = Builder::new(&project, &backend);
     let tile_discovery = TileDiscovery::new(&project);
-    let sequence_discovery = SequenceDiscovery::new(&project, &tile_discovery);
-
-    let sequence = sequence_discovery.get(tile_id).unwrap();
+    let mut runner = builder.build_sequence_runner(cfs, sequence_id, &tile_discovery, mode)?;
 
     println!(
-        "Running sequence '{}' in preview mode...",
-        sequence.function.name
+        "Running sequence '{}' on the {} backend...",
+        runner.sequence_name(),
+        builder.backend_name()
     );
     println!();
 
-    let backend = create_backend(backend_type)?;
+    let run = runner.run(arguments, &mut recorded_inputs)?;
 
-    let builder = Builder::new(&project, &backend);
+    match backend_type {
+        BackendType::Native => print_wall_time_summary(&run.cycles_by_tile()),
+        BackendType::Risc0 => print_cycle_summary(&run.cycles_by_tile()),
+    }
 
-    for step in sequence.flatten(&sequence_discovery) {
-        match step {
-            FlattenedStep::Tile(tile) => {
-                let tile_runner = builder.build_tile_runner(&tile)?;
-                tile_runner.validate_input(input)?;
-                let input_bytes = encode_input(input)?;
-                let result = tile_runner.run(&input_bytes, mode)?;
-                match decode_execution_output(
-                    tile.function.output.as_deref().unwrap_or("()"),
-                    &result.output,
-                ) {
-                    Ok(output_display) => println!("  Output: {}", output_display),
-                    Err(ExecutionFailure::User(user_error)) => {
-                        println!("  User error: {}", user_error)
-                    }
-                    Err(ExecutionFailure::Runtime(err)) => return Err(err),
-                }
-            }
-            FlattenedStep::RecurTile(tile) => {
-                println!(
-                    "  RecurTile '{}' is not supported in preview mode",
-                    tile.function.name
-                );
-            }
-            FlattenedStep::RecurSequence(seq) => {
-                println!(
-                    "  RecurSequence '{}' is not supported in preview mode",
-                    seq.function.name
-                );
-            }
-            FlattenedStep::Sequence(seq) => {
-                println!("  Entering sequence '{}'...", seq.function.name);
-            }
-        }
+    println!();
+    match (&run.user_error, &run.output) {
+        (Some(user_error), _) => println!("User error: {}", user_error),
+        (None, Some(output)) => println!(
+            "Output: {}",
+            decode_output(output.ty.as_deref().unwrap_or("()"), &output.bytes)
+        ),
+        (None, None) => println!("Output: ()"),
     }
 
-    // let mut results: Vec<TileResult> = Vec::new();
+    Ok(())
+}
 
-    // // Execute each tile in sequence using TileRunner
-    // for (idx, tile_id) in tile_ids.iter().enumerate() {
-    //     // Check if compilation is needed before building
+/// The inputs every step of the earlier run `run_id` was called with.
+fn recorded_run_inputs(
+    project: &Project,
+    cfs: &ControlFlowSchema,
+    run_id: &str,
+) -> Result<RecordedInputs> {
+    let (trace_format, artifacts) = [TraceFormat::Binary, TraceFormat::Json]
+        .into_iter()
+        .map(|format| {
+            let artifacts = RunArtifacts::new(&project.output_dir, run_id.to_string(), format);
+            (format, artifacts)
+        })
+        .find(|(_, artifacts)| artifacts.trace_path.exists())
+        .ok_or_else(|| {
+            Error::Other(format!(
+                "No trace for run '{}' in {}",
+                run_id,
+                project.output_dir.join("runs").display()
+            ))
+        })?;
+    let (trace, trace_recorder, violation) =
+        run::load_trace_from_file(&artifacts.trace_path, trace_format, cfs)?;
+    if let Some(violation) = violation {
+        return Err(Error::Other(format!(
+            "Trace of run '{}' does not follow the CFS: {}",
+            run_id, violation
+        )));
+    }
 
-    //     // Build tile and get a runner (uses cache if available)
-    //     let runner = builder
-    //         .build_tile_runner(tile_id)
-    //         .with_context(|| format!("Failed to build tile '{}'", tile_id))?;
+    let mut inputs = RecordedInputs::new();
+    for step_record in trace.iter() {
+        let coordinates = step_record.coordinates();
+        if let Some(input) = trace_recorder.input_data_at(coordinates).flatten() {
+            inputs.insert(coordinates.clone(), input);
+        }
+    }
+    Ok(inputs)
+}
 
-    //     println!(
-    //         "[{}/{}] Executing tile '{}'...",
-    //         idx + 1,
-    //         tile_ids.len(),
-    //         tile_id
-    //     );
+/// Print the compute and proof cycles spent in each tile of a sequence run.
+fn print_cycle_summary(tiles: &[TileCycles]) {
+    println!("╔═══════════════════════════════════════════════════════════════════════╗");
+    println!("║                          Cycle Count Summary                          ║");
+    println!("╠════════════════════╦════════╦══════════════════╦══════════════════════╣");
+    println!("║ Tile               ║   Runs ║ Compute Cycles   ║ Proof Cycles         ║");
+    println!("╠════════════════════╬════════╬══════════════════╬══════════════════════╣");
 
-    //     // Execute in estimate mode using TileRunner
-    //     let result = runner
-    //         .run(&current_input, ExecutionMode::Estimate)
-    //         .with_context(|| format!("Failed to execute tile '{}'", tile_id))?;
+    let mut total_runs = 0usize;
+    let mut total_cycles = 0u64;
+    let mut total_proof_cycles = 0u64;
 
-    //     let cycles = result.cycles.unwrap_or(0);
-    //     let proof_cycles = result.proof_cycles.unwrap_or(0);
+    for tile in tiles {
+        println!(
+            "║ {:<18} ║ {:>6} ║ {:>16} ║ {:>20} ║",
+            truncate_str(&tile.tile_id, 18),
+            tile.runs,
+            format_number(tile.cycles),
+            format_number(tile.proof_cycles)
+        );
+        total_runs += tile.runs;
+        total_cycles += tile.cycles;
+        total_proof_cycles += tile.proof_cycles;
+    }
 
-    //     results.push(TileResult {
-    //         name: tile_id.clone(),
-    //         cycles,
-    //         proof_cycles,
-    //     });
+    println!("╠════════════════════╬════════╬══════════════════╬══════════════════════╣");
+    println!(
+        "║ {:<18} ║ {:>6} ║ {:>16} ║ {:>20} ║",
+        "TOTAL",
+        total_runs,
+        format_number(total_cycles),
+        format_number(total_proof_cycles)
+    );
+    println!("╚════════════════════╩════════╩══════════════════╩══════════════════════╝");
+}
 
-    //     // Output becomes input for next tile
-    //     current_input = result.output;
-    // }
+/// Print the wall-clock time spent in each tile of a native sequence run,
+/// which reports no cycles.
+fn print_wall_time_summary(tiles: &[TileCycles]) {
+    println!("╔═══════════════════════════════════════════════════════════════════════╗");
+    println!("║                           Wall Time Summary                           ║");
+    println!("╠════════════════════╦════════╦══════════════════╦══════════════════════╣");
+    println!("║ Tile               ║   Runs ║ Wall Time        ║ Per Run              ║");
+    println!("╠════════════════════╬════════╬══════════════════╬══════════════════════╣");
 
-    // // Print summary table
-    // println!();
-    // println!("╔══════════════════════════════════════════════════════════════╗");
-    // println!("║                     Cycle Count Summary                      ║");
-    // println!("╠════════════════════╦══════════════════╦══════════════════════╣");
-    // println!("║ Tile               ║ Compute Cycles   ║ Proof Cycles         ║");
-    // println!("╠════════════════════╬══════════════════╬══════════════════════╣");
-
-    // let mut total_cycles = 0u64;
-    // let mut total_proof_cycles = 0u64;
-
-    // for result in &results {
-    //     println!(
-    //         "║ {:<18} ║ {:>16} ║ {:>20} ║",
-    //         truncate_str(&result.name, 18),
-    //         format_number(result.cycles),
-    //         format_number(result.proof_cycles)
-    //     );
-    //     total_cycles += result.cycles;
-    //     total_proof_cycles += result.proof_cycles;
-    // }
+    let mut total_runs = 0usize;
+    let mut total_wall_time_ns = 0u64;
 
-    // println!("╠════════════════════╬══════════════════╬══════════════════════╣");
-    // println!(
-    //     "║ {:<18} ║ {:>16} ║ {:>20} ║",
-    //     "TOTAL",
-    //     format_number(total_cycles),
-    //     format_number(total_proof_cycles)
-    // );
-    // println!("╚════════════════════╩══════════════════╩══════════════════════╝");
-
-    // // Try to deserialize and display final output
-    // println!();
-    // if let Ok(output_str) = postcard::from_bytes::<String>(&current_input) {
-    //     println!("Output: \"{}\"", output_str);
-    // } else if let Ok(output_num) = postcard::from_bytes::<u64>(&current_input) {
-    //     println!("Output: {}", output_num);
-    // } else {
-    //     println!("Output: {} bytes", current_input.len());
-    // }
+    for tile in tiles {
+        println!(
+            "║ {:<18} ║ {:>6} ║ {:>16} ║ {:>20} ║",
+            truncate_str(&tile.tile_id, 18),
+            tile.runs,
+            format_duration(tile.wall_time_ns),
+            format_duration(tile.wall_time_ns / tile.runs.max(1) as u64)
+        );
+        total_runs += tile.runs;
+        total_wall_time_ns += tile.wall_time_ns;
+    }
 
-    Ok(())
+    println!("╠════════════════════╬════════╬══════════════════╬══════════════════════╣");
+    println!(
+        "║ {:<18} ║ {:>6} ║ {:>16} ║ {:>20} ║",
+        "TOTAL",
+        total_runs,
+        format_duration(total_wall_time_ns),
+        format_duration(total_wall_time_ns / total_runs.max(1) as u64)
+    );
+    println!("╚════════════════════╩════════╩══════════════════╩══════════════════════╝");
+}
+
+/// Format nanoseconds as a duration, e.g. `1.234ms`.
+fn format_duration(ns: u64) -> String {
+    format!("{:.3?}", std::time::Duration::from_nanos(ns))
 }
 
 /// Truncate a string to a maximum length.
@@ -526,13 +594,11 @@ fn format_number(n: u64) -> String {
 }
 
 /// CFS command: generate control flow schema.
-pub fn cfs(output: Option<String>) -> Result<()> {
+pub fn cfs(output: Option<String>, package: Option<&str>) -> Result<()> {
     println!("Generating control flow schema...");
     println!();
 
-    let root = project_path();
-
-    let project = Project::new(root)?;
+    let project = load_project(package)?;
     // Build the CFS
     let cfs_builder = CfsBuilder::new(&project);
     let cfs = cfs_builder
@@ -546,7 +612,7 @@ pub fn cfs(output: Option<String>) -> Result<()> {
     // Determine output path
     let output_path = match output {
         Some(path) => PathBuf::from(path),
-        None => output_dir().join("cfs.json"),
+        None => project.output_dir.join("cfs.json"),
     };
 
     // Create parent directories if needed
@@ -597,16 +663,140 @@ pub fn cfs(output: Option<String>) -> Result<()> {
     Ok(())
 }
 
+/// CFS diff command: report how the CFS changed between two program versions.
+///
+/// `paths` holds the old cfs.json and, optionally, the new one; without it the
+/// current project's CFS is built and used as the new side.
+pub fn cfs_diff(paths: &[String], format: DiffFormat, package: Option<&str>) -> Result<()> {
+    let old = read_cfs(&paths[0])?;
+    let new = match paths.get(1) {
+        Some(path) => read_cfs(path)?,
+        None => {
+            let project = load_project(package)?;
+            CfsBuilder::new(&project)
+                .build()
+                .map_err(|e| Error::Other(format!("Failed to build CFS: {}", e)))?
+        }
+    };
+
+    let diff = CfsDiff::between(&old, &new);
+    match format {
+        DiffFormat::Text => println!("{}", diff),
+        DiffFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&diff)
+                .map_err(|e| Error::Other(format!("Failed to serialize CFS diff: {}", e)))?
+        ),
+    }
+
+    Ok(())
+}
+
+pub fn cfs_graph(
+    format: GraphFormat,
+    profile: Option<String>,
+    output: Option<String>,
+    package: Option<&str>,
+) -> Result<()> {
+    let project = load_project(package)?;
+    let cfs = CfsBuilder::new(&project)
+        .build()
+        .map_err(|e| Error::Other(format!("Failed to build CFS: {}", e)))?;
+
+    let mut graph = CfsGraph::new(&cfs);
+    if let Some(profile) = profile {
+        let metrics = Analyzer::from_path(&profile)?.analyze()?;
+        let timings = metrics
+            .tile_metrics
+            .into_iter()
+            .map(|(tile_id, tile)| {
+                let timing = TileTiming {
+                    invocations: tile.invocations,
+                    total_duration_ns: tile.total_duration_ns,
+                };
+                (tile_id.0, timing)
+            })
+            .collect();
+        graph = graph.with_tile_timings(timings);
+    }
+
+    let rendered = match format {
+        GraphFormat::Dot => graph.to_dot(),
+        GraphFormat::Mermaid => graph.to_mermaid(),
+    };
+    match output {
+        Some(path) => {
+            let output_path = PathBuf::from(path);
+            if let Some(parent) = output_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&output_path, rendered)?;
+            println!("CFS graph written to: {}", output_path.display());
+        }
+        None => print!("{}", rendered),
+    }
+
+    Ok(())
+}
+
+fn read_cfs(path: &str) -> Result<ControlFlowSchema> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| Error::Other(format!("Failed to read CFS '{}': {}", path, e)))?;
+    ControlFlowSchema::from_json(&json)
+}
+
+pub fn schema(output: Option<String>, package: Option<&str>) -> Result<()> {
+    println!("Generating sequence schemas...");
+    println!();
+
+    let project = load_project(package)?;
+    let cfs = CfsBuilder::new(&project)
+        .build()
+        .map_err(|e| Error::Other(format!("Failed to build CFS: {}", e)))?;
+    let schemas = SchemaGenerator::new(&cfs).generate()?;
+
+    let output_dir = match output {
+        Some(path) => PathBuf::from(path),
+        None => project.output_dir.clone(),
+    };
+    fs::create_dir_all(&output_dir)?;
+
+    for schema in &schemas {
+        let json = serde_json::to_string_pretty(schema).map_err(|e| {
+            Error::Other(format!(
+                "Failed to serialize schema for sequence '{}' to JSON: {}",
+                schema.name, e
+            ))
+        })?;
+        let output_path = output_dir.join(format!("{}.schema.json", schema.name));
+        fs::write(&output_path, &json)?;
+        println!("  - {} -> {}", schema.name, output_path.display());
+    }
+
+    println!();
+    println!(
+        "Generated {} sequence schemas in: {}",
+        schemas.len(),
+        output_dir.display()
+    );
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::collections::HashSet;
 
+    fn output_dir() -> PathBuf {
+        project_path().join("target").join("raster")
+    }
+
     #[test]
     fn create_run_artifacts_returns_unique_run_scoped_paths() {
-        let first = create_run_artifacts(TraceFormat::Binary)
+        let first = create_run_artifacts(&output_dir(), TraceFormat::Binary)
             .expect("first artifact allocation should succeed");
-        let second = create_run_artifacts(TraceFormat::Json)
+        let second = create_run_artifacts(&output_dir(), TraceFormat::Json)
             .expect("second artifact allocation should succeed");
 
         assert_ne!(first.run_id, second.run_id);
@@ -618,6 +808,7 @@ mod tests {
             first.profile_stream_path,
             first.run_dir.join("profile.ndjson")
         );
+        assert_eq!(first.checkpoint_path, first.run_dir.join("checkpoint.json"));
         assert!(first.run_dir.exists());
         assert!(second.run_dir.exists());
     }
@@ -627,7 +818,7 @@ mod tests {
         let threads: Vec<_> = (0..4)
             .map(|_| {
                 std::thread::spawn(|| {
-                    create_run_artifacts(TraceFormat::Binary)
+                    create_run_artifacts(&output_dir(), TraceFormat::Binary)
                         .expect("artifact allocation should succeed")
                 })
             })
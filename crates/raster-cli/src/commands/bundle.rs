@@ -0,0 +1,114 @@
+//! Fraud proof bundles: one file that carries a dispute to a third party.
+//!
+//! `bundle create` packs the artifacts `run --commit` and `run --audit` leave
+//! next to the commitment together with the project's CFS and the input
+//! manifest; `bundle verify` checks such a file with nothing else at hand.
+
+use std::path::PathBuf;
+
+use raster_compiler::CfsBuilder;
+use raster_core::{Error, Result};
+use raster_prover::dev_mode::is_fake_receipt;
+use raster_prover::fraud_proof_bundle::FraudProofBundle;
+
+use crate::commands::load_project;
+use crate::commands::run::{
+    authorization_receipt_path, fraud_proof_bundle_path, fraud_proof_path, read_commitment_file,
+    read_receipt,
+};
+use crate::utils::authorization::read_json_source;
+
+/// Bundle the fraud proof against the commitment at `commit_path`.
+///
+/// The receipts default to the `.fraud-proof` and `.authorization` files
+/// written by `run --audit`, and the bundle to a `.bundle` file next to the
+/// commitment.
+pub fn create_bundle(
+    commit_path: &str,
+    input_manifest: Option<&str>,
+    proof_path: Option<&str>,
+    authorization_path: Option<&str>,
+    output: Option<&str>,
+    package: Option<&str>,
+) -> Result<()> {
+    let project = load_project(package)?;
+    let cfs = CfsBuilder::new(&project)
+        .build()
+        .map_err(|e| Error::Other(format!("Failed to build CFS: {}", e)))?;
+
+    let commitment = read_commitment_file(commit_path)?;
+    let manifest = read_json_source(input_manifest, "input manifest")?;
+    let proof_path = proof_path
+        .map(PathBuf::from)
+        .unwrap_or_else(|| fraud_proof_path(commit_path));
+    let fraud_proof = read_receipt(&proof_path, "fraud proof")?;
+    let authorization_path = authorization_path
+        .map(PathBuf::from)
+        .unwrap_or_else(|| authorization_receipt_path(commit_path));
+    let authorization_receipt = read_receipt(&authorization_path, "authorization receipt")?;
+
+    let bundle = FraudProofBundle::new(
+        commitment,
+        cfs,
+        manifest,
+        authorization_receipt,
+        fraud_proof,
+    );
+    bundle
+        .check_artifacts()
+        .map_err(|e| Error::Other(e.to_string()))?;
+    let bytes = bundle.to_bytes().map_err(|e| Error::Other(e.to_string()))?;
+
+    let output = output
+        .map(PathBuf::from)
+        .unwrap_or_else(|| fraud_proof_bundle_path(commit_path));
+    std::fs::write(&output, bytes).map_err(|e| {
+        Error::Other(format!(
+            "Failed to write fraud proof bundle '{}': {}",
+            output.display(),
+            e
+        ))
+    })?;
+
+    println!("Fraud proof bundle written: {}", output.display());
+
+    Ok(())
+}
+
+/// Verify the fraud proof bundle at `path`.
+pub fn verify_bundle(path: &str) -> Result<()> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        Error::Other(format!(
+            "Failed to read fraud proof bundle '{}': {}",
+            path, e
+        ))
+    })?;
+    let bundle = FraudProofBundle::from_bytes(&bytes).map_err(|e| {
+        Error::Other(format!(
+            "Failed to load fraud proof bundle '{}': {}",
+            path, e
+        ))
+    })?;
+
+    let verified = bundle.verify().map_err(|e| Error::Other(e.to_string()))?;
+
+    println!("Fraud proof bundle verified: {}", path);
+    if is_fake_receipt(&bundle.fraud_proof) || is_fake_receipt(&bundle.authorization_receipt) {
+        println!("  Dev-mode fake receipts: nothing was proven");
+    }
+    println!(
+        "  Project '{}' commitment diverges at trace step {}",
+        bundle.commitment.project_name, verified.divergence_position
+    );
+    println!(
+        "  Replayed window: steps {}..{}",
+        verified.window_start,
+        verified.window_start + verified.window_size
+    );
+    println!(
+        "  Manifest commitment: {}",
+        hex::encode(&verified.manifest_commitment)
+    );
+
+    Ok(())
+}
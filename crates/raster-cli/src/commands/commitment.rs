@@ -0,0 +1,29 @@
+//! Inspection of trace commitment files written by `cargo raster run --commit`.
+
+use raster_core::Result;
+
+use crate::commands::run::read_commitment_file;
+
+/// Print the header of the commitment file at `path`.
+pub fn inspect_commitment(path: &str) -> Result<()> {
+    let file = read_commitment_file(path)?;
+
+    println!("Commitment file: {}", path);
+    println!("  Format version: {}", file.format_version);
+    println!("  Tool version: {}", file.tool_version);
+    println!("  Project: {}", file.project_name);
+    println!("  CFS digest: {}", hex::encode(&file.cfs_digest));
+    println!(
+        "  Fraud-proof window: {} steps, {} bits per step",
+        file.fraud_proof_config.window_size, file.fraud_proof_config.bits_per_item
+    );
+    println!("  Seed: {}", hex::encode(&file.seed));
+    println!("  Trace length: {} steps", file.trace_len);
+    println!(
+        "  Manifest commitment: {}",
+        hex::encode(&file.manifest_commitment)
+    );
+    println!("  Revealed steps: {}", file.commitment.revealed_items.len());
+
+    Ok(())
+}
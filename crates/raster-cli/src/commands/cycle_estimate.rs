@@ -0,0 +1,122 @@
+//! zkVM cycle estimation for whole-program runs.
+//!
+//! The program runs natively to produce its trace. Each tile invocation in
+//! the trace is then executed again with its recorded input on the RISC0
+//! backend in estimate mode, and the cycle counts are merged into the run's
+//! execution profile so the analyze report can size tiles by zkVM cost.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use rand::seq::IteratorRandom;
+
+use raster_analysis::Analyzer;
+use raster_backend::{calculate_proof_cycles, ExecutionMode};
+use raster_compiler::builder::Builder;
+use raster_compiler::tile::TileDiscovery;
+use raster_compiler::Project;
+use raster_core::trace::{StepRecord, TileExecRecord, Trace};
+use raster_core::{Error, Result};
+use raster_runtime::{ExecutionProfile, TileCycleCount, TraceRecorder};
+
+use crate::commands::create_backend;
+use crate::BackendType;
+
+/// Execute the tile invocations of `trace` on RISC0 and record their cycles
+/// in the profile at `profile_path`, creating it if profiling was disabled.
+///
+/// With `samples_per_tile`, at most that many invocations of each tile are
+/// executed, picked at random; the report extrapolates the rest.
+pub(crate) fn estimate_tile_cycles(
+    project: &Project,
+    trace: &Trace,
+    trace_recorder: &TraceRecorder,
+    samples_per_tile: Option<usize>,
+    profile_path: &Path,
+    run_id: &str,
+) -> Result<()> {
+    let mut invocations: BTreeMap<&str, Vec<&TileExecRecord>> = BTreeMap::new();
+    for step_record in trace.iter() {
+        if let StepRecord::TileExec(record) = step_record {
+            invocations
+                .entry(record.tile_id.as_str())
+                .or_default()
+                .push(record);
+        }
+    }
+
+    let mut profile = if profile_path.exists() {
+        Analyzer::load_profile(profile_path)?
+    } else {
+        ExecutionProfile::new(Vec::new(), None, Some(run_id.to_string()))
+    };
+
+    let backend = create_backend(BackendType::Risc0, project)?;
+    let builder = Builder::new(project, &backend);
+    let discovery = TileDiscovery::new(project);
+
+    println!();
+    println!("Estimating zkVM cycles on the risc0 backend...");
+    let mut rng = rand::rng();
+    let mut counts = Vec::new();
+    for (tile_id, records) in invocations {
+        let tile = discovery
+            .get(tile_id)
+            .ok_or_else(|| Error::Other(format!("Tile '{}' not found in the project", tile_id)))?;
+        let runner = builder.build_tile_runner(tile)?;
+
+        let invocation_count = records.len();
+        let mut sampled = match samples_per_tile {
+            Some(samples) if samples < invocation_count => {
+                records.into_iter().choose_multiple(&mut rng, samples)
+            }
+            _ => records,
+        };
+        sampled.sort_by_key(|record| record.exec_index);
+
+        for record in &sampled {
+            let input = trace_recorder
+                .input_data_at(&record.coordinates)
+                .flatten()
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "The trace has no recorded input for '{}' at {:?}",
+                        tile_id, record.coordinates.0
+                    ))
+                })?;
+            let result = runner.run(&input, ExecutionMode::Estimate).map_err(|e| {
+                Error::Other(format!(
+                    "Failed to execute '{}' at {:?} on risc0: {}",
+                    tile_id, record.coordinates.0, e
+                ))
+            })?;
+            let cycles = result.cycles.ok_or_else(|| {
+                Error::Other(format!(
+                    "risc0 reported no cycle count for '{}' at {:?}",
+                    tile_id, record.coordinates.0
+                ))
+            })?;
+            let proof_cycles = result
+                .proof_cycles
+                .unwrap_or_else(|| calculate_proof_cycles(cycles));
+            counts.push(TileCycleCount {
+                tile_id: tile_id.to_string(),
+                coordinates: record.coordinates.clone(),
+                cycles,
+                proof_cycles,
+            });
+        }
+        println!(
+            "  {}: {} of {} invocation(s)",
+            tile_id,
+            sampled.len(),
+            invocation_count
+        );
+    }
+
+    profile.record_tile_cycles(counts);
+    let bytes = serde_json::to_vec_pretty(&profile)?;
+    fs::write(profile_path, bytes)?;
+    Ok(())
+}
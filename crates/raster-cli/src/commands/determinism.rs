@@ -0,0 +1,361 @@
+//! Determinism check for whole-program runs.
+//!
+//! Fraud proofs rely on every tile producing the same output for the same
+//! input. The check runs the built program several times, each run with its
+//! own artifacts dir, loads each trace through a `TraceRecorder` and compares
+//! the step records against the first run. The first step that differs is
+//! reported with the decoded inputs and outputs of both runs and the source
+//! location of the tile that produced it.
+//!
+//! With perturbation enabled, runs after the first get a different thread
+//! count (`RAYON_NUM_THREADS`), time zone, locale and environment size. Hash
+//! seeds need no help: `std` seeds `HashMap`s randomly in every process, so
+//! iteration-order dependencies show up across plain runs already.
+
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use raster_compiler::tile::TileDiscovery;
+use raster_compiler::{Project, StoredValue};
+use raster_core::cfs::ControlFlowSchema;
+use raster_core::trace::{StepRecord, Trace};
+use raster_core::{Error, Result};
+use raster_runtime::TraceRecorder;
+
+use crate::commands::create_run_artifacts;
+use crate::commands::run::load_trace_from_file;
+use crate::TraceFormat;
+
+/// Time zones cycled through by perturbed runs.
+const TIME_ZONES: [&str; 4] = [
+    "UTC",
+    "America/Los_Angeles",
+    "Asia/Kolkata",
+    "Pacific/Chatham",
+];
+
+/// Locales cycled through by perturbed runs.
+const LOCALES: [&str; 3] = ["C", "C.UTF-8", "POSIX"];
+
+/// How many times to run the program, and whether to perturb its
+/// environment between runs.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DeterminismCheck {
+    pub runs: usize,
+    pub perturb: bool,
+}
+
+/// One run of the program and the trace it recorded.
+struct RecordedRun {
+    run_dir: String,
+    trace: Trace,
+    trace_recorder: TraceRecorder,
+}
+
+/// Run the program at `binary_path` `runs` times and compare their traces.
+///
+/// Fails if a run exits unsuccessfully without its trace ending in a tile
+/// failure, or if any trace differs from the first one.
+pub(crate) fn check_determinism(
+    project: &Project,
+    cfs: &ControlFlowSchema,
+    binary_path: &Path,
+    input: Option<&str>,
+    input_manifest: Option<&str>,
+    trace_format: TraceFormat,
+    check: DeterminismCheck,
+) -> Result<()> {
+    let DeterminismCheck { runs, perturb } = check;
+    if runs < 2 {
+        return Err(Error::Other(
+            "--check-determinism needs at least 2 runs to compare".into(),
+        ));
+    }
+
+    println!();
+    println!("Determinism check");
+    println!("  Project: {}", project.name);
+    println!("  Runs: {}", runs);
+    if perturb {
+        println!("  Perturbing environment, thread count, time zone and locale");
+    }
+    println!();
+
+    let mut recorded = Vec::with_capacity(runs);
+    for run_index in 0..runs {
+        let run = record_run(
+            binary_path,
+            project,
+            cfs,
+            input,
+            input_manifest,
+            trace_format,
+            perturb.then_some(run_index),
+        )?;
+        println!(
+            "  Run {}: {} step(s), trace in {}",
+            run_index + 1,
+            run.trace.len(),
+            run.run_dir
+        );
+        recorded.push(run);
+    }
+
+    let (baseline, others) = recorded.split_first().expect("at least two runs");
+    let discovery = TileDiscovery::new(project);
+    let mut diverged = 0;
+    for (offset, run) in others.iter().enumerate() {
+        let Some(step) = first_divergence(&baseline.trace, &run.trace) else {
+            continue;
+        };
+        diverged += 1;
+        println!();
+        println!("Run {} diverged from run 1 at step {}:", offset + 2, step);
+        report_divergence(cfs, &discovery, step, (1, baseline), (offset + 2, run));
+    }
+
+    println!();
+    if diverged > 0 {
+        return Err(Error::Other(format!(
+            "{} of {} runs diverged from the first; the program is not deterministic",
+            diverged,
+            runs - 1
+        )));
+    }
+    println!(
+        "All {} runs recorded identical traces ({} steps)",
+        runs,
+        baseline.trace.len()
+    );
+    Ok(())
+}
+
+fn record_run(
+    binary_path: &Path,
+    project: &Project,
+    cfs: &ControlFlowSchema,
+    input: Option<&str>,
+    input_manifest: Option<&str>,
+    trace_format: TraceFormat,
+    perturbation: Option<usize>,
+) -> Result<RecordedRun> {
+    let artifacts = create_run_artifacts(&project.output_dir, trace_format)?;
+
+    let mut cmd = Command::new(binary_path);
+    cmd.current_dir(&project.root_dir);
+    cmd.env(raster_runtime::TRACE_PATH_ENV, &artifacts.trace_path);
+    cmd.env(
+        raster_runtime::TRACE_FORMAT_ENV,
+        trace_format.as_runtime_str(),
+    );
+    cmd.env(raster_runtime::CHECKPOINT_INTERVAL_ENV, "0");
+    if let Some(run_index) = perturbation.filter(|run_index| *run_index > 0) {
+        perturb_environment(&mut cmd, run_index);
+    }
+    if let Some(input_json) = input {
+        cmd.args(["--input", input_json]);
+    }
+    if let Some(manifest_json) = input_manifest {
+        cmd.args(["--input-manifest", manifest_json]);
+    }
+    cmd.stdout(Stdio::null());
+    cmd.stderr(Stdio::piped());
+
+    let mut child = cmd.spawn()?;
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        pipe.read_to_string(&mut stderr)?;
+    }
+    let status = child.wait()?;
+    let loaded = load_trace_from_file(&artifacts.trace_path, trace_format, cfs);
+    // A run that fails in a tile still leaves a complete trace to compare.
+    let ends_in_failure = matches!(
+        &loaded,
+        Ok((trace, _, None)) if matches!(trace.last(), Some(StepRecord::TileFailed(_)))
+    );
+    if !status.success() && !ends_in_failure {
+        return Err(Error::Other(format!(
+            "Run in {} exited with {}:\n{}",
+            artifacts.run_dir.display(),
+            status,
+            stderr.trim_end()
+        )));
+    }
+
+    let (trace, trace_recorder, violation) = loaded?;
+    if let Some(violation) = violation {
+        return Err(Error::Other(format!(
+            "Trace of run in {} does not follow the CFS: {}",
+            artifacts.run_dir.display(),
+            violation
+        )));
+    }
+    Ok(RecordedRun {
+        run_dir: artifacts.run_dir.display().to_string(),
+        trace,
+        trace_recorder,
+    })
+}
+
+/// Vary what a program could observe between runs without changing its input.
+fn perturb_environment(cmd: &mut Command, run_index: usize) {
+    cmd.env("RAYON_NUM_THREADS", (1usize << (run_index % 4)).to_string());
+    cmd.env("TZ", TIME_ZONES[run_index % TIME_ZONES.len()]);
+    cmd.env("LC_ALL", LOCALES[run_index % LOCALES.len()]);
+    // Growing the environment shifts the initial stack, and with it any
+    // address a program might leak into its output.
+    cmd.env("RASTER_DETERMINISM_PADDING", "x".repeat(run_index * 4096));
+}
+
+/// Index of the first step record where the traces differ, including one
+/// trace ending before the other.
+fn first_divergence(baseline: &Trace, other: &Trace) -> Option<usize> {
+    let mismatch = baseline
+        .iter()
+        .zip(other.iter())
+        .position(|(left, right)| left != right);
+    match mismatch {
+        Some(step) => Some(step),
+        None if baseline.len() != other.len() => Some(baseline.len().min(other.len())),
+        None => None,
+    }
+}
+
+fn report_divergence(
+    cfs: &ControlFlowSchema,
+    discovery: &TileDiscovery,
+    step: usize,
+    baseline: (usize, &RecordedRun),
+    other: (usize, &RecordedRun),
+) {
+    let records = [baseline, other].map(|(run_number, run)| (run_number, run, run.trace.get(step)));
+
+    for (run_number, _, record) in &records {
+        match record {
+            Some(record) => println!("  run {}: {}", run_number, describe_step(record)),
+            None => println!("  run {}: trace ends", run_number),
+        }
+    }
+
+    let tile_id = records
+        .iter()
+        .find_map(|(_, _, record)| record.and_then(step_tile_id));
+    let Some(tile_id) = tile_id else {
+        return;
+    };
+    if let Some(tile) = discovery.get(tile_id) {
+        let function = tile.function;
+        println!(
+            "  tile source: {}:{}:{} ({})",
+            function.path.display(),
+            function.location.line,
+            function.location.column,
+            function.cfs_path()
+        );
+    }
+
+    let tile_def = cfs.tiles.iter().find(|tile| tile.id == tile_id);
+    let input_type = tile_def.and_then(|tile| match tile.input_types.as_slice() {
+        [] => None,
+        [single] => Some(single.clone()),
+        many => Some(format!("({})", many.join(", "))),
+    });
+    let output_type = tile_def.and_then(|tile| tile.output_type.clone());
+
+    for (run_number, run, record) in &records {
+        let Some(record) = record else {
+            continue;
+        };
+        let io = run.trace_recorder.io_data_at(record.coordinates());
+        let (input, output) = match io {
+            Some((input, output, _)) => (input, output),
+            None => (None, None),
+        };
+        println!(
+            "  run {} input: {}",
+            run_number,
+            describe_value(input, input_type.as_deref())
+        );
+        println!(
+            "  run {} output: {}",
+            run_number,
+            describe_value(output, output_type.as_deref())
+        );
+    }
+}
+
+fn describe_step(record: &StepRecord) -> String {
+    match record {
+        StepRecord::TileExec(record) => format!(
+            "tile `{}` at {:?} in sequence `{}`",
+            record.tile_id, record.coordinates, record.sequence_id
+        ),
+        StepRecord::RecurTileExec(record) => format!(
+            "recur tile `{}` at {:?} in sequence `{}`",
+            record.recur_tile_id, record.coordinates, record.sequence_id
+        ),
+        StepRecord::RecurSequenceExec(record) => format!(
+            "recur sequence `{}` at {:?} in sequence `{}`",
+            record.recur_sequence_id, record.coordinates, record.sequence_id
+        ),
+        StepRecord::SequenceStart(record) => format!(
+            "start of sequence `{}` at {:?}",
+            record.sequence_id, record.coordinates
+        ),
+        StepRecord::SequenceEnd(record) => format!(
+            "end of sequence `{}` at {:?}",
+            record.sequence_id, record.coordinates
+        ),
+        StepRecord::TileFailed(record) => format!(
+            "failed tile `{}` at {:?} in sequence `{}`",
+            record.tile_id, record.coordinates, record.sequence_id
+        ),
+    }
+}
+
+fn step_tile_id(record: &StepRecord) -> Option<&str> {
+    match record {
+        StepRecord::TileExec(record) => Some(&record.tile_id),
+        StepRecord::RecurTileExec(record) => Some(&record.recur_tile_id),
+        StepRecord::TileFailed(record) => Some(&record.tile_id),
+        _ => None,
+    }
+}
+
+fn describe_value(bytes: Option<Vec<u8>>, ty: Option<&str>) -> String {
+    match bytes {
+        Some(bytes) => StoredValue::new(bytes, ty.map(str::to_string)).describe(),
+        None => "(not recorded)".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raster_core::cfs::CfsCoordinates;
+    use raster_core::trace::SequenceEndRecord;
+
+    fn sequence_end(exec_index: u64, output: u8) -> StepRecord {
+        StepRecord::SequenceEnd(SequenceEndRecord {
+            exec_index,
+            sequence_id: "main".to_string(),
+            coordinates: CfsCoordinates(Vec::new()),
+            output_commitment: vec![output],
+        })
+    }
+
+    #[test]
+    fn first_divergence_finds_changed_and_missing_steps() {
+        let baseline = Trace(vec![sequence_end(0, 1), sequence_end(1, 2)]);
+
+        assert_eq!(first_divergence(&baseline, &baseline.clone()), None);
+
+        let changed = Trace(vec![sequence_end(0, 1), sequence_end(1, 3)]);
+        assert_eq!(first_divergence(&baseline, &changed), Some(1));
+
+        let shorter = Trace(vec![sequence_end(0, 1)]);
+        assert_eq!(first_divergence(&baseline, &shorter), Some(1));
+        assert_eq!(first_divergence(&shorter, &baseline), Some(1));
+    }
+}
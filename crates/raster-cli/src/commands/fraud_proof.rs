@@ -0,0 +1,51 @@
+//! Standalone verification of fraud proofs written by `cargo raster run --audit`.
+//!
+//! Needs neither the project nor the program's inputs: the proof is checked
+//! against the transition and authorization guest images this CLI was built
+//! with and against the disputed commitment file alone, including the input
+//! manifest commitment in its header.
+
+use std::path::PathBuf;
+
+use raster_core::{Error, Result};
+use raster_prover::dev_mode::is_fake_receipt;
+use raster_prover::fraud_proof::verify_fraud_proof as verify_fraud_proof_receipt;
+
+use crate::commands::run::{fraud_proof_path, read_commitment_file, read_receipt};
+
+/// Verify the fraud proof at `proof_path` (by default the `.fraud-proof` file
+/// next to the commitment) against the commitment at `commit_path`.
+pub fn verify_fraud_proof(commit_path: &str, proof_path: Option<&str>) -> Result<()> {
+    let proof_path = proof_path
+        .map(PathBuf::from)
+        .unwrap_or_else(|| fraud_proof_path(commit_path));
+    let receipt = read_receipt(&proof_path, "fraud proof")?;
+    let commitment_file = read_commitment_file(commit_path)?;
+
+    let verified = verify_fraud_proof_receipt(
+        &receipt,
+        &commitment_file.commitment,
+        &commitment_file.manifest_commitment,
+    )
+    .map_err(|e| Error::Other(e.to_string()))?;
+
+    println!("Fraud proof verified: {}", proof_path.display());
+    if is_fake_receipt(&receipt) {
+        println!("  Dev-mode fake receipt: nothing was proven");
+    }
+    println!(
+        "  Commitment '{}' diverges at trace step {}",
+        commit_path, verified.divergence_position
+    );
+    println!(
+        "  Replayed window: steps {}..{}",
+        verified.window_start,
+        verified.window_start + verified.window_size
+    );
+    println!(
+        "  Manifest commitment: {}",
+        hex::encode(&verified.manifest_commitment)
+    );
+
+    Ok(())
+}
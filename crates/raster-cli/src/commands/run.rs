@@ -27,17 +27,23 @@ use raster_core::transition::{
 use raster_core::{Error, Result};
 
 use raster_prover::authorization::authorize_external_inputs;
-use raster_prover::precomputed::EMPTY_TRIE_NODES;
+use raster_prover::commitment_file::CommitmentFile;
+use raster_prover::dev_mode::is_fake_receipt;
+use raster_prover::precomputed::{EMPTY_TRIE_NODES, H256};
 use raster_prover::replay::{ReplayResult, Replayer};
 use raster_prover::trace::{
     Bytes, FraudEvidence, FraudProofConfig, SerializableFrontier, TraceCommitment, TraceTree,
     TraceVerifier, VerificationResult,
 };
 use raster_prover::transition::step_transitions;
-use raster_runtime::TraceRecorder;
+use raster_runtime::{Checkpoint, RecorderViolation, TraceRecorder};
 
-use crate::commands::create_run_artifacts;
-use crate::utils::authorization::{build_manifested_inputs, collect_external_input_commitments};
+use crate::commands::cycle_estimate::estimate_tile_cycles;
+use crate::commands::determinism::{check_determinism, DeterminismCheck};
+use crate::commands::{create_run_artifacts, load_project, resume_run_artifacts};
+use crate::utils::authorization::{
+    build_manifested_inputs, collect_external_input_commitments, read_json_source,
+};
 use crate::{BackendType, TraceFormat};
 
 pub fn run(
@@ -46,23 +52,20 @@ pub fn run(
     input_manifest: Option<&str>,
     commit_flag: Option<&str>,
     fraud_proof_config: Option<FraudProofConfig>,
+    seed: Option<H256>,
     audit_flag: Option<&str>,
     _verbose: bool,
     trace_format: TraceFormat,
     features: &[String],
     all_features: bool,
     no_default_features: bool,
+    package: Option<&str>,
+    cycle_samples: Option<usize>,
+    resume: Option<&str>,
+    checkpoint_every: u64,
+    determinism_check: Option<DeterminismCheck>,
 ) -> Result<()> {
-    if backend_type != BackendType::Native {
-        return Err(Error::Other(
-            "Only the native backend is supported for running entire programs. \
-             Use 'cargo raster run-tile' to execute individual tiles with the RISC0 backend."
-                .into(),
-        ));
-    }
-
-    let project_path = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    let project = Project::new(project_path).expect("Failed to read project");
+    let project = load_project(package)?;
 
     println!("Control Flow Schema build..");
     let cfs_builder = CfsBuilder::new(&project);
@@ -70,6 +73,14 @@ pub fn run(
         .build()
         .map_err(|e| Error::Other(format!("Failed to build CFS: {}", e)))?;
 
+    // Fail before building and running the program if the commitment under
+    // audit was made for a different CFS.
+    if let Some(commit_path) = audit_flag {
+        read_trace_commitment(commit_path)?
+            .verify_cfs(&cfs)
+            .map_err(|e| Error::Other(format!("Cannot audit '{}': {}", commit_path, e)))?;
+    }
+
     println!("Building project...");
 
     let mut build_command = Command::new("cargo");
@@ -105,7 +116,36 @@ pub fn run(
             binary_path.display()
         )));
     }
-    let artifacts = create_run_artifacts(trace_format)?;
+
+    if let Some(check) = determinism_check {
+        return check_determinism(
+            &project,
+            &cfs,
+            &binary_path,
+            input,
+            input_manifest,
+            trace_format,
+            check,
+        );
+    }
+
+    let artifacts = match resume {
+        Some(run_id) => resume_run_artifacts(&project.output_dir, run_id, trace_format)?,
+        None => create_run_artifacts(&project.output_dir, trace_format)?,
+    };
+    let checkpoint = match resume {
+        Some(run_id) => {
+            let checkpoint = Checkpoint::load(&artifacts.checkpoint_path)?;
+            if checkpoint.trace_format != trace_format.as_runtime_str() {
+                return Err(Error::Other(format!(
+                    "Run '{}' records a {} trace; resume it with --trace-format {}",
+                    run_id, checkpoint.trace_format, checkpoint.trace_format
+                )));
+            }
+            Some(checkpoint)
+        }
+        None => None,
+    };
     let trace_path = artifacts.trace_path.clone();
     let profile_path = artifacts.profile_path.clone();
     let profile_stream_path = artifacts.profile_stream_path.clone();
@@ -115,6 +155,12 @@ pub fn run(
     println!("  Run ID: {}", artifacts.run_id);
     println!("  Run artifacts dir: {}", artifacts.run_dir.display());
     println!("  Trace path: {}", trace_path.display());
+    if let Some(checkpoint) = &checkpoint {
+        println!(
+            "  Resuming after {} tile execution(s) ({} trace events)",
+            checkpoint.tile_exec_count, checkpoint.trace_event_count
+        );
+    }
     if profiling_enabled(features, all_features) {
         println!(
             "  Expected live profile stream: {}",
@@ -140,6 +186,17 @@ pub fn run(
         &profile_stream_path,
     );
     cmd.env(raster_runtime::PROFILE_RUN_ID_ENV, &artifacts.run_id);
+    cmd.env(
+        raster_runtime::CHECKPOINT_PATH_ENV,
+        &artifacts.checkpoint_path,
+    );
+    cmd.env(
+        raster_runtime::CHECKPOINT_INTERVAL_ENV,
+        checkpoint_every.to_string(),
+    );
+    if checkpoint.is_some() {
+        cmd.env(raster_runtime::RESUME_ENV, "1");
+    }
     if let Some(input_json) = input {
         cmd.args(["--input", input_json]);
     }
@@ -196,6 +253,12 @@ pub fn run(
         handle.join().expect("A child thread panicked");
     }
     println!("Process exited with: {}", status);
+    if !status.success() && artifacts.checkpoint_path.exists() {
+        println!(
+            "  Continue from the last checkpoint with: cargo raster run --resume {}",
+            artifacts.run_id
+        );
+    }
 
     let errors = Arc::try_unwrap(errors)
         .expect("Cant move list out of Mutex. Some thread still holding copy of Arc")
@@ -222,41 +285,136 @@ pub fn run(
         }
     }
 
-    let (mut trace, trace_recorder) = load_trace_from_file(&trace_path, trace_format, &cfs)?;
+    let (mut trace, trace_recorder, violation) =
+        load_trace_from_file(&trace_path, trace_format, &cfs)?;
+    // An audited trace that breaks the CFS is reported as a failed audit below.
+    if let (Some(violation), None) = (&violation, audit_flag) {
+        report_recorder_violation(&project, violation);
+        return Err(Error::Other(format!(
+            "Trace does not follow the CFS after {} step(s): {}",
+            trace.len(),
+            violation
+        )));
+    }
+    if let Some(StepRecord::TileFailed(failed)) = trace.last() {
+        println!(
+            "Tile `{}` failed ({:?}) at {:?}; the trace ends with the failure",
+            failed.tile_id, failed.failure_kind, failed.coordinates
+        );
+    }
+
+    // The program always runs natively; the RISC0 backend re-executes the
+    // recorded tile invocations to measure their zkVM cost.
+    if backend_type == BackendType::Risc0 && violation.is_none() {
+        estimate_tile_cycles(
+            &project,
+            &trace,
+            &trace_recorder,
+            cycle_samples,
+            &profile_path,
+            &artifacts.run_id,
+        )?;
+    }
 
     if commit_flag.is_some() {
         let commit_path = commit_flag.expect("Commitment path was provided");
         let fraud_proof_config = fraud_proof_config
             .expect("--fraud-proof-window-size is required alongside --commit");
+        let manifest = read_json_source(input_manifest, "input manifest")?;
+        let manifest_commitment = Sha256::digest(&manifest).to_vec();
+        let seed = match seed {
+            Some(seed) => seed.to_vec(),
+            None => default_commitment_seed(&manifest, &artifacts.run_id),
+        };
+        println!("Commitment seed: {}", hex::encode(&seed));
+        let commitment_run = CommitmentRun {
+            project_name: &project.name,
+            manifest_commitment,
+            seed,
+        };
 
         // TODO: temprorary way to generate "fraud" trace commitment
         // prefix file with fraud_{NAME}
         //
         if commit_path.starts_with("fraud_") {
-            fraud(&mut trace, commit_path, fraud_proof_config)?;
+            fraud(
+                &mut trace,
+                commit_path,
+                &cfs,
+                fraud_proof_config,
+                &commitment_run,
+            )?;
         } else {
-            commit(&trace, commit_path, fraud_proof_config)?;
+            commit(
+                &trace,
+                commit_path,
+                &cfs,
+                fraud_proof_config,
+                &commitment_run,
+            )?;
         }
     } else if audit_flag.is_some() {
         let commit_path = audit_flag.expect("Commitment path was provided");
+        if let Some(violation) = violation {
+            println!("Verification Failed: the trace does not follow the CFS");
+            report_recorder_violation(&project, &violation);
+            println!("  No fraud proof was generated");
+            return Err(Error::Other(format!(
+                "Audit of '{}' failed: the trace does not follow the CFS after {} step(s)",
+                commit_path,
+                trace.len()
+            )));
+        }
         let verification_result = verify(&trace, commit_path, &cfs)?;
 
         match verification_result {
             VerificationResult::Ok => println!("Verification Success"),
             VerificationResult::Fraud(fraud_evidence) => {
-                let backend = Risc0Backend::new(project.output_dir.clone())
-                    .with_user_crate(project.root_dir.clone());
-                let replayer = Replayer::new(&backend, &project);
-                let fraud_proof = prove(
-                    fraud_evidence,
-                    &trace,
-                    &cfs,
-                    &trace_recorder,
-                    &replayer,
-                    input_manifest,
-                );
-                let fraud_proof_path = write_fraud_proof(&fraud_proof, commit_path);
-                println!("Fraud proof generated: {}", fraud_proof_path.display());
+                let unreplayable_failure = match fraud_evidence.window.items.last() {
+                    Some(StepRecord::TileFailed(failed))
+                        if !failed.failure_kind.is_replayable() =>
+                    {
+                        Some(failed)
+                    }
+                    _ => None,
+                };
+                if let Some(failed) = unreplayable_failure {
+                    println!(
+                        "Verification Failed: the commitment diverges where tile `{}` failed at {:?}",
+                        failed.tile_id, failed.coordinates
+                    );
+                    println!(
+                        "  A {:?} failure cannot be reproduced in the zkVM, so no fraud proof was generated",
+                        failed.failure_kind
+                    );
+                } else {
+                    let backend = Risc0Backend::new(project.output_dir.clone())
+                        .with_user_crate(project.root_dir.clone());
+                    let replayer = Replayer::new(&backend, &project);
+                    let (fraud_proof, authorization_receipt) = prove(
+                        fraud_evidence,
+                        &trace,
+                        &cfs,
+                        &trace_recorder,
+                        &replayer,
+                        input_manifest,
+                    );
+                    let fraud_proof_path = write_fraud_proof(&fraud_proof, commit_path);
+                    if is_fake_receipt(&fraud_proof) {
+                        println!(
+                            "Fraud proof generated (dev-mode fake receipt): {}",
+                            fraud_proof_path.display()
+                        );
+                    } else {
+                        println!("Fraud proof generated: {}", fraud_proof_path.display());
+                    }
+                    let authorization_receipt_path =
+                        write_authorization_receipt(&authorization_receipt, commit_path);
+                    println!(
+                        "Authorization receipt: {}",
+                        authorization_receipt_path.display()
+                    );
+                }
             }
         }
     } else {
@@ -315,6 +473,16 @@ pub fn run(
                         sequence_end_record.coordinates
                     );
                 }
+                StepRecord::TileFailed(tile_failed_record) => {
+                    println!("\nexec_index: {}", tile_failed_record.exec_index);
+                    println!("sequence_id: {}", tile_failed_record.sequence_id);
+                    println!("tile_coordinates: {:?}", tile_failed_record.coordinates);
+
+                    println!(
+                        "tile_id: {} (failed: {:?})",
+                        tile_failed_record.tile_id, tile_failed_record.failure_kind
+                    );
+                }
             }
         }
     }
@@ -352,31 +520,67 @@ fn profiling_enabled(features: &[String], all_features: bool) -> bool {
         })
 }
 
-fn load_trace_from_file(
+/// Record the trace at `trace_path` against `cfs`.
+///
+/// Recording stops at the first event that does not follow the CFS; the
+/// trace up to that event is returned along with the violation.
+pub(crate) fn load_trace_from_file(
     trace_path: &PathBuf,
     trace_format: TraceFormat,
     cfs: &ControlFlowSchema,
-) -> Result<(Trace, TraceRecorder)> {
+) -> Result<(Trace, TraceRecorder, Option<RecorderViolation>)> {
     let mut trace = Trace::new();
-    let mut trace_recorder = TraceRecorder::new(cfs.clone());
+    let mut trace_recorder = TraceRecorder::new(cfs.clone())?;
 
-    match trace_format {
+    let violation = match trace_format {
         TraceFormat::Binary => {
             load_binary_trace_from_file(trace_path, &mut trace, &mut trace_recorder)?
         }
         TraceFormat::Json => {
             load_json_trace_from_file(trace_path, &mut trace, &mut trace_recorder)?
         }
-    }
+    };
+
+    Ok((trace, trace_recorder, violation))
+}
 
-    Ok((trace, trace_recorder))
+/// Print a CFS violation with the source location of the offending step: its
+/// call site in the enclosing sequence, or else its definition.
+fn report_recorder_violation(project: &Project, violation: &RecorderViolation) {
+    println!();
+    println!("Trace does not follow the CFS:");
+    println!("  {}", violation);
+
+    let functions = &project.ast.functions;
+    let call_site = functions
+        .iter()
+        .find(|function| function.id == violation.sequence_id)
+        .and_then(|sequence| {
+            sequence
+                .call_infos
+                .iter()
+                .find(|call| call.callee == violation.step)
+                .map(|call| (&sequence.path, call.location))
+        });
+    let definition = functions
+        .iter()
+        .find(|function| function.id == violation.step)
+        .map(|function| (&function.path, function.location));
+    if let Some((path, location)) = call_site.or(definition) {
+        println!(
+            "  at {}:{}:{}",
+            path.display(),
+            location.line,
+            location.column
+        );
+    }
 }
 
 fn load_binary_trace_from_file(
     trace_path: &PathBuf,
     trace: &mut Trace,
     trace_recorder: &mut TraceRecorder,
-) -> Result<()> {
+) -> Result<Option<RecorderViolation>> {
     let mut file = std::fs::File::open(trace_path).map_err(raster_core::Error::Io)?;
 
     loop {
@@ -419,17 +623,19 @@ fn load_binary_trace_from_file(
                 error
             ))
         })?;
-        record_trace_event(trace, trace_recorder, event);
+        if let Err(violation) = record_trace_event(trace, trace_recorder, event) {
+            return Ok(Some(violation));
+        }
     }
 
-    Ok(())
+    Ok(None)
 }
 
 fn load_json_trace_from_file(
     trace_path: &PathBuf,
     trace: &mut Trace,
     trace_recorder: &mut TraceRecorder,
-) -> Result<()> {
+) -> Result<Option<RecorderViolation>> {
     let file = std::fs::File::open(trace_path).map_err(raster_core::Error::Io)?;
     let reader = BufReader::new(file);
 
@@ -447,21 +653,30 @@ fn load_json_trace_from_file(
                 error
             ))
         })?;
-        record_trace_event(trace, trace_recorder, event);
+        if let Err(violation) = record_trace_event(trace, trace_recorder, event) {
+            return Ok(Some(violation));
+        }
     }
 
-    Ok(())
+    Ok(None)
 }
 
-fn record_trace_event(trace: &mut Trace, trace_recorder: &mut TraceRecorder, event: TraceEvent) {
-    let step_record = trace_recorder.record(event);
+fn record_trace_event(
+    trace: &mut Trace,
+    trace_recorder: &mut TraceRecorder,
+    event: TraceEvent,
+) -> std::result::Result<(), RecorderViolation> {
+    let step_record = trace_recorder.record(event)?;
     trace.push(step_record);
+    Ok(())
 }
 
 pub fn fraud(
     trace: &mut Trace,
     commit_path: &str,
+    cfs: &ControlFlowSchema,
     fraud_proof_config: FraudProofConfig,
+    commitment_run: &CommitmentRun,
 ) -> Result<()> {
     let mut rng = rand::rng();
     if let Some(fraud_step) = trace
@@ -478,7 +693,9 @@ pub fn fraud(
                     .external_input_commitment
                     .is_empty()
             }
-            StepRecord::SequenceStart(_) | StepRecord::SequenceEnd(_) => false,
+            StepRecord::SequenceStart(_)
+            | StepRecord::SequenceEnd(_)
+            | StepRecord::TileFailed(_) => false,
         })
         .choose(&mut rng)
     {
@@ -498,39 +715,59 @@ pub fn fraud(
             StepRecord::SequenceEnd(sequence_end_record) => {
                 sequence_end_record.output_commitment.push(0);
             }
+            StepRecord::TileFailed(tile_failed_record) => {
+                tile_failed_record.message_hash.push(0);
+            }
         }
     };
 
-    let trace_commitment = TraceCommitment::try_from(trace, &EMPTY_TRIE_NODES[0], fraud_proof_config)
-        .map_err(|e| Error::Other(e.to_string()))?;
-
-    let bytes = postcard::to_allocvec(&trace_commitment).unwrap();
+    write_commitment(trace, commit_path, cfs, fraud_proof_config, commitment_run)
+}
 
-    let mut commitment_file =
-        std::fs::File::create(commit_path).expect("Failed to create commitemt file");
-    commitment_file
-        .write_all(&bytes)
-        .expect("Failed to save commitment");
+pub fn commit(
+    trace: &Trace,
+    commit_path: &str,
+    cfs: &ControlFlowSchema,
+    fraud_proof_config: FraudProofConfig,
+    commitment_run: &CommitmentRun,
+) -> Result<()> {
+    write_commitment(trace, commit_path, cfs, fraud_proof_config, commitment_run)
+}
 
-    Ok(())
+/// Run facts recorded in a commitment file alongside the commitment.
+pub struct CommitmentRun<'a> {
+    pub project_name: &'a str,
+    /// SHA-256 of the input manifest bytes.
+    pub manifest_commitment: Vec<u8>,
+    pub seed: Vec<u8>,
 }
 
-pub fn commit(
+fn write_commitment(
     trace: &Trace,
     commit_path: &str,
+    cfs: &ControlFlowSchema,
     fraud_proof_config: FraudProofConfig,
+    commitment_run: &CommitmentRun,
 ) -> Result<()> {
-    let trace_commitment = TraceCommitment::try_from(trace, &EMPTY_TRIE_NODES[0], fraud_proof_config)
+    let trace_commitment =
+        TraceCommitment::try_from(trace, &commitment_run.seed, cfs, fraud_proof_config)
+            .map_err(|e| Error::Other(e.to_string()))?;
+    let commitment_file = CommitmentFile::new(
+        trace_commitment,
+        fraud_proof_config,
+        commitment_run.project_name,
+        commitment_run.manifest_commitment.clone(),
+    );
+    let bytes = commitment_file
+        .to_bytes()
         .map_err(|e| Error::Other(e.to_string()))?;
-    let bytes = postcard::to_allocvec(&trace_commitment).unwrap();
-
-    let mut commitment_file =
-        std::fs::File::create(commit_path).expect("Failed to create commitemt file");
-    commitment_file
-        .write_all(&bytes)
-        .expect("Failed to save commitment");
 
-    Ok(())
+    std::fs::write(commit_path, bytes).map_err(|e| {
+        Error::Other(format!(
+            "Failed to write commitment file '{}': {}",
+            commit_path, e
+        ))
+    })
 }
 
 pub fn verify(
@@ -539,20 +776,46 @@ pub fn verify(
     cfs: &ControlFlowSchema,
 ) -> Result<VerificationResult> {
     let trace_commitment = read_trace_commitment(commit_path)?;
+    let seed = trace_commitment.seed.clone();
 
-    let mut trace_verifier = TraceVerifier::new(trace_commitment, &EMPTY_TRIE_NODES[0], cfs)
+    let mut trace_verifier = TraceVerifier::new(trace_commitment, &seed, cfs)
         .map_err(|e| Error::Other(e.to_string()))?;
 
     Ok(trace_verifier.verify(trace))
 }
 
+/// Seed for a commitment made without --seed: a hash of the input manifest
+/// and the run ID, so that no two runs commit under the same seed.
+fn default_commitment_seed(manifest: &[u8], run_id: &str) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update((manifest.len() as u64).to_le_bytes());
+    hasher.update(manifest);
+    hasher.update(run_id.as_bytes());
+    hasher.finalize().to_vec()
+}
+
 pub fn fraud_proof_path(commit_path: &str) -> PathBuf {
+    commitment_sibling_path(commit_path, "fraud-proof")
+}
+
+pub fn authorization_receipt_path(commit_path: &str) -> PathBuf {
+    commitment_sibling_path(commit_path, "authorization")
+}
+
+pub fn fraud_proof_bundle_path(commit_path: &str) -> PathBuf {
+    commitment_sibling_path(commit_path, "bundle")
+}
+
+/// `<commit_path>.<extension>`, or `<extension>` when the path has no file
+/// name.
+fn commitment_sibling_path(commit_path: &str, extension: &str) -> PathBuf {
     let path = PathBuf::from(commit_path);
     let mut file_name = path
         .file_name()
         .map(|name| name.to_os_string())
-        .unwrap_or_else(|| std::ffi::OsString::from("fraud-proof"));
-    file_name.push(".fraud-proof");
+        .unwrap_or_else(|| std::ffi::OsString::from(extension));
+    file_name.push(".");
+    file_name.push(extension);
     path.with_file_name(file_name)
 }
 
@@ -569,6 +832,34 @@ pub fn write_fraud_proof(receipt: &risc0_zkvm::Receipt, commit_path: &str) -> Pa
     proof_path
 }
 
+pub fn write_authorization_receipt(receipt: &risc0_zkvm::Receipt, commit_path: &str) -> PathBuf {
+    let receipt_path = authorization_receipt_path(commit_path);
+    let bytes = postcard::to_allocvec(receipt).expect("Failed to serialize authorization receipt");
+    std::fs::write(&receipt_path, bytes).expect("Failed to save authorization receipt");
+
+    receipt_path
+}
+
+/// Read a `postcard`-encoded receipt written next to a commitment.
+pub fn read_receipt(path: &std::path::Path, what: &str) -> Result<risc0_zkvm::Receipt> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        Error::Other(format!(
+            "Failed to read {} '{}': {}",
+            what,
+            path.display(),
+            e
+        ))
+    })?;
+    postcard::from_bytes(&bytes).map_err(|e| {
+        Error::Other(format!(
+            "Failed to deserialize {} from '{}': {}",
+            what,
+            path.display(),
+            e
+        ))
+    })
+}
+
 #[derive(Debug, Clone)]
 struct ProofInternalStoreState {
     frontier: SerializableFrontier,
@@ -717,9 +1008,10 @@ pub fn prove(
     trace_recorder: &TraceRecorder,
     replayer: &Replayer,
     input_manifest: Option<&str>,
-) -> risc0_zkvm::Receipt {
+) -> (risc0_zkvm::Receipt, risc0_zkvm::Receipt) {
     let mode = ExecutionMode::prove_and_verify();
     let FraudEvidence {
+        seed,
         window: fraud_window,
         input_sources_witnesses,
     } = fraud_evidence;
@@ -738,6 +1030,7 @@ pub fn prove(
             Option<DraftTransitionWitness>,
         ),
     > = HashMap::new();
+    let mut branch_condition_witnesses: HashMap<StepRecord, Vec<u8>> = HashMap::new();
     let window_start_index = fraud_window
         .items
         .first()
@@ -845,10 +1138,27 @@ pub fn prove(
                 draft_transition_witness,
             ),
         );
+        let branch_condition_witness = trace_recorder
+            .branch_condition_witness_at(step_record.coordinates())
+            .unwrap_or_else(|error| {
+                panic!(
+                    "Failed to read branch condition witness at coordinates {:?}: {}",
+                    step_record.coordinates(),
+                    error
+                )
+            });
+        if let Some(witness) = branch_condition_witness {
+            branch_condition_witnesses.insert(step_record.clone(), witness);
+        }
 
-        if let StepRecord::TileExec(record) = step_record {
+        let replayed_tile = match step_record {
+            StepRecord::TileExec(record) => Some(&record.tile_id),
+            StepRecord::TileFailed(record) => Some(&record.tile_id),
+            _ => None,
+        };
+        if let Some(tile_id) = replayed_tile {
             let replay_input = input_witness.unwrap_or_default();
-            match replayer.replay(record, replay_input.as_slice(), mode) {
+            match replayer.replay(tile_id, replay_input.as_slice(), mode) {
                 Ok(replay_result) => {
                     replayed_results.insert(step_record.clone(), replay_result);
                 }
@@ -873,6 +1183,7 @@ pub fn prove(
         println!("Replaying transition frontier with transition guest...");
 
         let Some(receipt) = step_transitions(
+            &seed,
             &frontier,
             &initial_internal_store_state.frontier,
             &initial_internal_store_state.coordinate_index.root(),
@@ -881,6 +1192,7 @@ pub fn prove(
             &cfs,
             &input_sources_witnesses,
             &recorded_step_io,
+            &branch_condition_witnesses,
             &replayed_results,
             &authorization_journal,
             &authorization_receipt,
@@ -888,36 +1200,31 @@ pub fn prove(
             panic!("Failed to generate fraud proof");
         };
 
-        return receipt;
+        return (receipt, authorization_receipt);
     }
 
     panic!("Failed to generate fraud proof");
 }
 
 pub fn read_trace_commitment(commit_path: &str) -> Result<TraceCommitment> {
-    let mut file = std::fs::File::open(commit_path).map_err(|e| {
-        Error::Other(format!(
-            "Failed to open expected commitment file '{}': {}",
-            commit_path, e
-        ))
-    })?;
+    Ok(read_commitment_file(commit_path)?.commitment)
+}
 
-    let mut bytes = Vec::new();
-    file.read_to_end(&mut bytes).map_err(|e| {
+/// Read and validate the commitment file at `commit_path`.
+pub fn read_commitment_file(commit_path: &str) -> Result<CommitmentFile> {
+    let bytes = std::fs::read(commit_path).map_err(|e| {
         Error::Other(format!(
-            "Failed to read expected commitment file '{}': {}",
+            "Failed to read commitment file '{}': {}",
             commit_path, e
         ))
     })?;
 
-    let trace_commitment: TraceCommitment = postcard::from_bytes(&bytes).map_err(|e| {
+    CommitmentFile::from_bytes(&bytes).map_err(|e| {
         Error::Other(format!(
-            "Failed to deserialize trace commitment from '{}': {}",
+            "Failed to load commitment file '{}': {}",
             commit_path, e
         ))
-    })?;
-
-    Ok(trace_commitment)
+    })
 }
 
 #[cfg(test)]
@@ -1,11 +1,10 @@
-use crate::commands::project_path;
+use crate::commands::load_project;
 use raster_compiler::tile::TileDiscovery;
-use raster_compiler::Project;
 use raster_core::Result;
 
 /// List command: show all tiles discovered in the project.
-pub fn list_tiles() -> Result<()> {
-    let project = Project::new(project_path())?;
+pub fn list_tiles(package: Option<&str>) -> Result<()> {
+    let project = load_project(package)?;
     let tile_explorer = TileDiscovery::new(&project);
 
     for tile in tile_explorer.tiles.iter() {
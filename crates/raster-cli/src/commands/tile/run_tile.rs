@@ -1,13 +1,12 @@
 use raster_backend::{ExecutionFailure, ExecutionMode};
 use raster_compiler::builder::Builder;
 use raster_compiler::tile::TileDiscovery;
-use raster_compiler::Project;
 /// Run command: execute a tile with the specified backend.
 use raster_core::Result;
 
 use crate::utils::encode::{decode_execution_output, encode_input};
 use crate::{
-    commands::{create_backend, project_path},
+    commands::{create_backend, load_project},
     BackendType,
 };
 
@@ -17,6 +16,7 @@ pub fn run_tile(
     input: Option<&str>,
     prove: bool,
     verify: bool,
+    package: Option<&str>,
 ) -> Result<()> {
     // Determine execution mode
     let mode = match (prove, verify) {
@@ -25,12 +25,12 @@ pub fn run_tile(
         (false, false) => ExecutionMode::Estimate,
     };
 
-    let project = Project::new(project_path())?;
+    let project = load_project(package)?;
 
     let tile_discovery = TileDiscovery::new(&project);
     let tile = tile_discovery.get(tile_id).unwrap();
 
-    let backend = create_backend(backend_type)?;
+    let backend = create_backend(backend_type, &project)?;
 
     let builder = Builder::new(&project, &backend);
 
@@ -53,6 +53,12 @@ pub fn run_tile(
     if let Some(cycles) = result.cycles {
         println!("  Compute Cycles: {}", cycles);
     }
+    if let Some(wall_time_ns) = result.wall_time_ns {
+        println!(
+            "  Wall time: {:?}",
+            std::time::Duration::from_nanos(wall_time_ns)
+        );
+    }
     // Show proof cycles in estimate mode to help users understand proving cost
     if let Some(proof_cycles) = result.proof_cycles {
         if result.receipt.is_none() {
@@ -7,6 +7,7 @@ mod utils;
 
 use clap::{Parser, ValueEnum};
 use raster_core::Result;
+use raster_prover::precomputed::{H256, HASH_SIZE};
 use raster_prover::trace::FraudProofConfig;
 
 #[derive(Parser)]
@@ -15,8 +16,23 @@ use raster_prover::trace::FraudProofConfig;
 #[command(about = "Raster toolchain CLI", long_about = None)]
 #[command(version)]
 enum Cli {
+    Raster(RasterArgs),
+}
+
+#[derive(clap::Args)]
+struct RasterArgs {
+    /// Package to use as the project when running from a workspace root
+    #[arg(long, short, global = true)]
+    package: Option<String>,
+
+    /// Execute guests without proving and emit fake receipts (also selected
+    /// by RASTER_DEV_PROVER=1). Verifiers accept fake receipts only with the
+    /// dev prover selected.
+    #[arg(long, global = true)]
+    dev_prover: bool,
+
     #[command(subcommand)]
-    Raster(Commands),
+    command: Commands,
 }
 
 #[derive(Parser)]
@@ -118,6 +134,11 @@ enum Commands {
         /// Verify the generated proof (implies --prove)
         #[arg(long)]
         verify: bool,
+
+        /// Earlier `run` (by run id) whose recorded tile inputs supply the
+        /// inline and external arguments the CFS does not carry
+        #[arg(long, value_name = "RUN_ID")]
+        inputs_from: Option<String>,
     },
 
     /// Generate control flow schema (CFS)
@@ -125,14 +146,46 @@ enum Commands {
         /// Output file path (default: target/raster/cfs.json)
         #[arg(long, short)]
         output: Option<String>,
+
+        /// Instead of writing the CFS, report how it differs from a previous
+        /// cfs.json. With two paths, compare the first file against the second.
+        #[arg(long, num_args = 1..=2, value_names = ["OLD", "NEW"], conflicts_with = "output")]
+        diff: Option<Vec<String>>,
+
+        /// Output format of the --diff report
+        #[arg(long, value_enum, default_value = "text", requires = "diff")]
+        format: DiffFormat,
+
+        /// Instead of writing the CFS, render it as a graph. Printed to
+        /// stdout unless --output is given.
+        #[arg(long, value_enum, conflicts_with = "diff")]
+        graph: Option<GraphFormat>,
+
+        /// Execution profile whose per-tile timings are overlaid on the graph
+        #[arg(long, requires = "graph")]
+        profile: Option<String>,
+    },
+
+    /// Generate per-sequence schemas from the control flow schema
+    Schema {
+        /// Output directory (default: target/raster, next to cfs.json)
+        #[arg(long, short)]
+        output: Option<String>,
     },
 
     /// Run the user program
     Run {
-        /// Backend to use for execution
+        /// Backend to use for execution. The program always runs natively;
+        /// with risc0 its tile invocations are re-executed on RISC0 to
+        /// estimate their zkVM cycles.
         #[arg(long, short, value_enum, default_value = "native")]
         backend: BackendType,
 
+        /// Execute at most N randomly chosen invocations of each tile when
+        /// estimating cycles with --backend risc0 (all of them by default)
+        #[arg(long = "cycle-samples", value_name = "N")]
+        cycle_samples: Option<usize>,
+
         /// Input as path to a JSON file
         #[arg(long)]
         input: Option<String>,
@@ -157,6 +210,12 @@ enum Commands {
         )]
         fraud_proof_config: Option<FraudProofConfig>,
 
+        /// Hex-encoded 32-byte seed for the commitment's trace tree, such as a
+        /// challenger-supplied nonce. Defaults to a hash of the input manifest
+        /// and the run ID; audits read it from the commitment file.
+        #[arg(long, value_parser = parse_commitment_seed, requires = "commit")]
+        seed: Option<H256>,
+
         /// Read and verify trace from file (mutually exclusive with --commit)
         #[arg(long, conflicts_with = "commit")]
         audit: Option<String>,
@@ -180,6 +239,98 @@ enum Commands {
         /// Disable default Cargo features when building the target project
         #[arg(long)]
         no_default_features: bool,
+
+        /// Resume an interrupted run from its last checkpoint. Pass the same
+        /// input and flags as the original run: the program is replayed from
+        /// its start up to the checkpoint, skipping recorded tile bodies.
+        #[arg(long, value_name = "RUN_ID")]
+        resume: Option<String>,
+
+        /// Write a checkpoint every N tile executions (0 disables checkpoints)
+        #[arg(long = "checkpoint-every", value_name = "N", default_value_t = 1000)]
+        checkpoint_every: u64,
+
+        /// Run the program N times (2 when N is omitted) and report the first
+        /// step where the recorded traces differ, instead of a normal run
+        #[arg(
+            long = "check-determinism",
+            value_name = "N",
+            num_args = 0..=1,
+            default_missing_value = "2",
+            conflicts_with_all = ["commit", "audit", "resume"]
+        )]
+        check_determinism: Option<usize>,
+
+        /// Vary thread count, time zone, locale and environment size between
+        /// determinism check runs
+        #[arg(long, requires = "check_determinism")]
+        perturb: bool,
+    },
+
+    /// Verify a fraud proof against the commitment it disputes
+    VerifyFraudProof {
+        /// Commitment file written by `run --commit`
+        commitment: String,
+
+        /// Fraud proof file (default: the `.fraud-proof` file next to the
+        /// commitment, as written by `run --audit`)
+        #[arg(long)]
+        proof: Option<String>,
+    },
+
+    /// Work with trace commitment files
+    Commitment {
+        #[command(subcommand)]
+        command: CommitmentCommands,
+    },
+
+    /// Package fraud proofs into single files and verify them
+    Bundle {
+        #[command(subcommand)]
+        command: BundleCommands,
+    },
+}
+
+#[derive(Parser)]
+enum CommitmentCommands {
+    /// Print the header of a commitment file written by `run --commit`
+    Inspect {
+        /// Commitment file to inspect
+        path: String,
+    },
+}
+
+#[derive(Parser)]
+enum BundleCommands {
+    /// Bundle a fraud proof with the commitment, CFS, input manifest and
+    /// receipts needed to verify it
+    Create {
+        /// Commitment file the fraud proof disputes
+        commitment: String,
+
+        /// Input manifest the committed run was given
+        #[arg(long)]
+        input_manifest: Option<String>,
+
+        /// Fraud proof file (default: the `.fraud-proof` file next to the
+        /// commitment, as written by `run --audit`)
+        #[arg(long)]
+        proof: Option<String>,
+
+        /// Authorization receipt file (default: the `.authorization` file next
+        /// to the commitment, as written by `run --audit`)
+        #[arg(long)]
+        authorization: Option<String>,
+
+        /// Output path (default: the `.bundle` file next to the commitment)
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+
+    /// Verify a fraud proof bundle offline
+    Verify {
+        /// Bundle file written by `bundle create`
+        path: String,
     },
 }
 
@@ -194,6 +345,13 @@ fn parse_fraud_proof_config(
     FraudProofConfig::from_window_size(window_size).map_err(|e| e.to_string())
 }
 
+/// Parse the hex-encoded --seed argument.
+fn parse_commitment_seed(value: &str) -> std::result::Result<H256, String> {
+    let bytes = hex::decode(value).map_err(|e| format!("'{value}' is not valid hex: {e}"))?;
+    H256::try_from(bytes.as_slice())
+        .map_err(|_| format!("seed must be {HASH_SIZE} bytes, got {}", bytes.len()))
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
 pub enum AnalyzeFormat {
     Text,
@@ -209,6 +367,20 @@ pub enum BackendType {
     Risc0,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
 pub enum TraceFormat {
     /// Length-prefixed postcard-encoded TraceEvent frames
@@ -241,18 +413,33 @@ fn main() {
 }
 
 fn try_main() -> Result<()> {
-    let Cli::Raster(cmd) = Cli::parse();
+    let Cli::Raster(RasterArgs {
+        package,
+        dev_prover,
+        command,
+    }) = Cli::parse();
+    let package = package.as_deref();
+    if raster_prover::dev_mode::select_dev_prover(dev_prover) {
+        eprintln!("WARNING: dev prover selected: receipts are fake and prove nothing");
+    }
 
-    match cmd {
-        Commands::Build { backend, tile } => commands::build(backend, tile),
+    match command {
+        Commands::Build { backend, tile } => commands::build(backend, tile, package),
         Commands::RunTile {
             backend,
             tile,
             input,
             prove,
             verify,
-        } => commands::tile::run_tile::run_tile(backend, &tile, input.as_deref(), prove, verify),
-        Commands::List => commands::tile::list_tile::list_tiles(),
+        } => commands::tile::run_tile::run_tile(
+            backend,
+            &tile,
+            input.as_deref(),
+            prove,
+            verify,
+            package,
+        ),
+        Commands::List => commands::tile::list_tile::list_tiles(package),
         Commands::Analyze {
             profile_path,
             follow,
@@ -269,32 +456,91 @@ fn try_main() -> Result<()> {
             input,
             prove,
             verify,
-        } => commands::run_sequence(backend, &sequence, input.as_deref(), prove, verify),
-        Commands::Cfs { output } => commands::cfs(output),
+            inputs_from,
+        } => commands::run_sequence(
+            backend,
+            &sequence,
+            input.as_deref(),
+            prove,
+            verify,
+            inputs_from.as_deref(),
+            package,
+        ),
+        Commands::Cfs {
+            diff: Some(paths),
+            format,
+            ..
+        } => commands::cfs_diff(&paths, format, package),
+        Commands::Cfs {
+            output,
+            graph: Some(graph),
+            profile,
+            ..
+        } => commands::cfs_graph(graph, profile, output, package),
+        Commands::Cfs { output, .. } => commands::cfs(output, package),
+        Commands::Schema { output } => commands::schema(output, package),
         Commands::Run {
             backend,
             input,
             input_manifest,
             commit,
             fraud_proof_config,
+            seed,
             audit,
             verbose,
             trace_format,
             features,
             all_features,
             no_default_features,
+            cycle_samples,
+            resume,
+            checkpoint_every,
+            check_determinism,
+            perturb,
         } => commands::run::run(
             backend,
             input.as_deref(),
             input_manifest.as_deref(),
             commit.as_deref(),
             fraud_proof_config,
+            seed,
             audit.as_deref(),
             verbose,
             trace_format,
             &features,
             all_features,
             no_default_features,
+            package,
+            cycle_samples,
+            resume.as_deref(),
+            checkpoint_every,
+            check_determinism.map(|runs| commands::determinism::DeterminismCheck { runs, perturb }),
+        ),
+        Commands::VerifyFraudProof { commitment, proof } => {
+            commands::fraud_proof::verify_fraud_proof(&commitment, proof.as_deref())
+        }
+        Commands::Commitment {
+            command: CommitmentCommands::Inspect { path },
+        } => commands::commitment::inspect_commitment(&path),
+        Commands::Bundle {
+            command:
+                BundleCommands::Create {
+                    commitment,
+                    input_manifest,
+                    proof,
+                    authorization,
+                    output,
+                },
+        } => commands::bundle::create_bundle(
+            &commitment,
+            input_manifest.as_deref(),
+            proof.as_deref(),
+            authorization.as_deref(),
+            output.as_deref(),
+            package,
         ),
+        Commands::Bundle {
+            command: BundleCommands::Verify { path },
+        } => commands::bundle::verify_bundle(&path),
     }
 }
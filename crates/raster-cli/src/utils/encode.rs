@@ -17,6 +17,55 @@ pub fn encode_input(input: Option<&str>) -> Result<Vec<u8>> {
     Ok(input_bytes)
 }
 
+/// Encode `--input` as the arguments of a sequence with the given parameter
+/// types: the JSON value itself for one parameter, a JSON array of one value
+/// per parameter otherwise.
+pub fn encode_sequence_arguments(
+    input_types: &[String],
+    input: Option<&str>,
+) -> Result<Vec<Vec<u8>>> {
+    let values = match (input_types.len(), input) {
+        (0, None) => Vec::new(),
+        (0, Some(_)) => {
+            return Err(Error::Other(
+                "The sequence takes no arguments, but --input was provided".to_string(),
+            ))
+        }
+        (n, None) => {
+            return Err(Error::Other(format!(
+                "The sequence requires {} argument(s), but no --input provided.\n\
+                 Expected types: {}",
+                n,
+                input_types.join(", ")
+            )))
+        }
+        (n, Some(input_json)) => {
+            let value: serde_json::Value = serde_json::from_str(input_json)
+                .map_err(|e| Error::Other(format!("Failed to parse input JSON: {}", e)))?;
+            match value {
+                serde_json::Value::Array(values) if n > 1 && values.len() == n => values,
+                value if n == 1 => vec![value],
+                _ => {
+                    return Err(Error::Other(format!(
+                        "Expects {} arguments, provide them as a JSON array.\n\
+                         Expected types: ({})",
+                        n,
+                        input_types.join(", ")
+                    )))
+                }
+            }
+        }
+    };
+
+    values
+        .iter()
+        .map(|value| {
+            postcard::to_allocvec(value)
+                .map_err(|e| Error::Other(format!("Failed to serialize input: {}", e)))
+        })
+        .collect()
+}
+
 /// Decode tile output bytes based on the tile's return type.
 pub fn decode_output(output_type: &str, output: &[u8]) -> String {
     // Try to decode based on the type hint
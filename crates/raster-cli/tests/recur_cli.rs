@@ -314,3 +314,47 @@ fn hello_tiles_run_forwards_requested_build_features() {
     assert!(PathBuf::from(profile_path).exists());
     assert!(PathBuf::from(profile_stream_path).exists());
 }
+
+#[test]
+fn schema_keeps_linear_items_around_recur_sites() {
+    let output_dir = unique_artifact_dir();
+    let output = Command::new(cargo_raster_bin())
+        .current_dir(hello_tiles_dir())
+        .args(["raster", "schema", "--output"])
+        .arg(&output_dir)
+        .output()
+        .expect("schema command should execute");
+    assert!(
+        output.status.success(),
+        "schema should succeed\nstdout:\n{}\nstderr:\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr),
+    );
+
+    let schema: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(output_dir.join("main.schema.json"))
+            .expect("main schema should be written"),
+    )
+    .expect("main schema should be JSON");
+    let control_flow = schema["control_flow"]
+        .as_array()
+        .expect("control flow should be a list of nodes");
+    let node_types: Vec<&str> = control_flow
+        .iter()
+        .map(|node| node["type"].as_str().expect("node should be tagged"))
+        .collect();
+    assert_eq!(node_types.first(), Some(&"Linear"));
+    assert_eq!(node_types.last(), Some(&"Linear"));
+    assert!(node_types.contains(&"Loop"));
+
+    let first_steps = control_flow[0]["steps"]
+        .as_array()
+        .expect("linear node should list its steps");
+    assert_eq!(first_steps[0], "greet_sequence");
+    assert_eq!(first_steps[1], "personal_greet_with_seed");
+
+    // Tiles of nested sequence calls count too, e.g. `greet` from `greet_sequence`.
+    let tiles = schema["tiles"].as_array().expect("tiles should be listed");
+    assert!(tiles.iter().any(|tile| tile == "greet"));
+    assert!(tiles.iter().any(|tile| tile == "collect_line_chunk"));
+}
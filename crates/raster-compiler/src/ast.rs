@@ -1,21 +1,35 @@
 use cargo_toml::Manifest;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use syn::{
     parse::{Parse, ParseStream},
     parse_file,
+    spanned::Spanned,
     visit::Visit,
-    Attribute, Expr, ExprLit, ExprMacro, FnArg, Lit, Local, Meta, Pat, StmtMacro, Token,
+    Attribute, Expr, ExprIf, ExprLit, ExprMacro, ExprMatch, FnArg, Lit, Local, Meta, Pat,
+    StmtMacro, Token,
 };
 use walkdir::WalkDir;
 
+use raster_core::cfs::BranchPattern;
+use raster_core::input::{SelectorPath, SelectorSegment};
 use raster_core::Result;
 
+use crate::workspace::CargoPackage;
+
 #[derive(Debug, Clone)]
 pub struct ProjectAst {
     pub name: String,
     pub root_path: PathBuf,
     pub functions: Vec<FunctionAstItem>,
+    /// Type names that stand for another type: `type` aliases and `use ... as`
+    /// renames anywhere in the parsed sources.
+    pub type_aliases: HashSet<String>,
+    /// Variant names of the enums declared in the parsed sources, by enum
+    /// name, in declaration order. A name declared with different variants
+    /// in several places maps to no variants.
+    pub enums: HashMap<String, Vec<String>>,
 }
 
 /// Indicates which canonical Raster call primitive was used.
@@ -29,6 +43,7 @@ pub enum CallKind {
     /// Invoked via `call_seq!(seq_fn, args...)` — caller declares this is a sequence call.
     Sequence,
     /// Invoked via `call_recur!` — caller declares this is a recursive tile step.
+    /// `call_map!` sites are recursive tile steps without state or output.
     RecursiveTile,
     /// Invoked via `call_recur_seq!(sequence_fn, args...)` — caller declares this is a recursive sequence step.
     RecursiveSequence,
@@ -45,7 +60,8 @@ pub enum CallArgumentKind {
 /// Captures detailed information about a function call within a function body.
 #[derive(Debug, Clone)]
 pub struct CallInfo {
-    /// The name of the function being called
+    /// The function being called: the path as written in the call macro, or the
+    /// callee's id once [`ProjectAst`] has resolved it.
     pub callee: String,
     /// The arguments passed to the function (as string representations)
     pub arguments: Vec<String>,
@@ -53,18 +69,93 @@ pub struct CallInfo {
     pub argument_kinds: Vec<CallArgumentKind>,
     /// The variable name this call result is bound to, if any (e.g., `let x = foo()` -> Some("x"))
     pub result_binding: Option<String>,
+    /// Names bound by destructuring the call result (e.g., `let (a, b) = foo()`),
+    /// each with its position inside the output.
+    pub field_bindings: Vec<FieldBinding>,
     /// Which canonical call primitive produced this call.
     pub call_kind: CallKind,
     /// Static chunk size from `call_recur! { ..., chunk = N }`, if declared.
     pub chunk: Option<u64>,
+    /// Where the call macro is invoked, for diagnostics.
+    pub location: SourceLocation,
+}
+
+/// A 1-based line and column in a source file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SourceLocation {
+    fn of(span: proc_macro2::Span) -> Self {
+        let start = span.start();
+        Self {
+            line: start.line,
+            column: start.column + 1,
+        }
+    }
+}
+
+/// A name bound to part of a call result by a destructuring `let`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldBinding {
+    pub name: String,
+    /// Path of the bound value inside the call output: tuple and tuple-struct
+    /// positions are `Index` segments, named struct fields are `Field` segments.
+    pub selector: SelectorPath,
+}
+
+/// The value an `if` / `match` branches on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BranchCondition {
+    /// An inline canonical call (`if call!(is_valid, x)`), as an index into `call_infos`.
+    Call(usize),
+    /// A plain binding (`if ok`), e.g. one bound by `let ok = call!(is_valid, x)`.
+    Binding(String),
+    /// Any other expression (as its string representation).
+    Other(String),
+}
+
+/// One arm of a captured `if` / `match`.
+#[derive(Debug, Clone)]
+pub struct BranchArmInfo {
+    /// The arm pattern, or `None` if it cannot be decided from the condition value alone
+    /// (guards, or-patterns, literals other than `bool`, ...).
+    pub pattern: Option<BranchPattern>,
+    /// The arm pattern as written, for diagnostics.
+    pub pattern_source: String,
+    /// Range of `call_infos` invoked inside this arm, nested branches included.
+    pub calls: Range<usize>,
+}
+
+/// An `if` / `match` in a function body with canonical calls in at least one arm.
+#[derive(Debug, Clone)]
+pub struct BranchInfo {
+    pub condition: BranchCondition,
+    pub arms: Vec<BranchArmInfo>,
 }
 
 #[derive(Debug, Clone)]
 pub struct FunctionAstItem {
     pub name: String,
+    /// Identifier of the function in the CFS: its name, or its module-qualified
+    /// path when another tile or sequence shares that name.
+    pub id: String,
     pub path: PathBuf,
+    /// Position of the function name in `path`.
+    pub location: SourceLocation,
+    /// Library name of the crate declaring the function when it is a local
+    /// dependency of the project; `None` for the project crate itself.
+    pub crate_name: Option<String>,
+    /// Manifest directory of that dependency crate.
+    pub crate_dir: Option<PathBuf>,
+    /// Modules enclosing the function, starting below the crate root.
+    pub module_path: Vec<String>,
     /// Detailed information about each function call in this function's body
     pub call_infos: Vec<CallInfo>,
+    /// Branches over those calls, outermost first, in source order
+    pub branches: Vec<BranchInfo>,
     pub macros: Vec<MacroAstItem>,
     /// Parameter names of the function
     pub input_names: Vec<String>,
@@ -72,6 +163,40 @@ pub struct FunctionAstItem {
     pub inputs: Vec<String>,
     pub output: Option<String>,
     pub signature: String,
+    /// Problems in the function body that keep it out of a CFS, such as an
+    /// unsupported destructured `call!` binding; the CFS build reports them.
+    pub errors: Vec<String>,
+}
+
+impl FunctionAstItem {
+    /// `module::name` path of the function within its crate.
+    pub fn qualified_name(&self) -> String {
+        let mut segments = self.module_path.clone();
+        segments.push(self.name.clone());
+        segments.join("::")
+    }
+
+    /// Path of the function as recorded in the CFS: [`Self::qualified_name`],
+    /// prefixed with the crate name for functions declared in a dependency.
+    pub fn cfs_path(&self) -> String {
+        self.cfs_segments().join("::")
+    }
+
+    fn cfs_segments(&self) -> Vec<String> {
+        let mut segments: Vec<String> = self.crate_name.iter().cloned().collect();
+        segments.extend(self.module_path.iter().cloned());
+        segments.push(self.name.clone());
+        segments
+    }
+
+    /// `"tile"` or `"sequence"` for functions carrying a Raster attribute.
+    fn raster_kind(&self) -> Option<&'static str> {
+        self.macros.iter().find_map(|m| match m.name.as_str() {
+            "tile" | "raster::tile" => Some("tile"),
+            "sequence" | "raster::sequence" => Some("sequence"),
+            _ => None,
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -79,7 +204,6 @@ pub struct MacroAstItem {
     pub name: String,
     pub args: HashMap<String, String>,
 }
-// TODO: Project AST Explorer should contain function with full path resulution (like mod's)
 impl ProjectAst {
     pub fn new(project_root: &Path) -> Result<Self> {
         let cargo_toml_path = project_root.join("Cargo.toml");
@@ -88,24 +212,159 @@ impl ProjectAst {
 
         let package = cargo_manifest.package.as_ref().unwrap();
 
-        let files_paths = Self::find_all_rs_files(project_root);
+        Self::discover(package.name.clone(), project_root, &[])
+    }
 
-        let functions = files_paths
-            .iter()
-            .flat_map(|path| Self::parse_file(path))
-            .collect::<Vec<FunctionAstItem>>();
+    /// Parse the project crate at `project_root` together with the library
+    /// sources of its local `dependencies`, so tiles and sequences declared
+    /// in a shared crate are discovered alongside the project's own.
+    pub fn discover(
+        name: String,
+        project_root: &Path,
+        dependencies: &[&CargoPackage],
+    ) -> Result<Self> {
+        let mut type_aliases = HashSet::new();
+        let mut enums = HashMap::new();
+        let mut functions = Self::parse_crate(project_root, None, &mut type_aliases, &mut enums);
+        for dependency in dependencies {
+            functions.extend(Self::parse_crate(
+                &dependency.manifest_dir,
+                Some(dependency),
+                &mut type_aliases,
+                &mut enums,
+            ));
+        }
+
+        let mut project = Self::from_functions(name, project_root.to_path_buf(), functions);
+        project.type_aliases = type_aliases;
+        project.enums = enums;
+        Ok(project)
+    }
+
+    /// Parse the sources under `crate_root/src`. Binary targets of
+    /// dependencies are skipped: only their library is linked into the project.
+    fn parse_crate(
+        crate_root: &Path,
+        dependency: Option<&CargoPackage>,
+        type_aliases: &mut HashSet<String>,
+        enums: &mut HashMap<String, Vec<String>>,
+    ) -> Vec<FunctionAstItem> {
+        let src_dir = crate_root.join("src");
+        let mut functions = Vec::new();
+        for path in Self::find_all_rs_files(crate_root) {
+            if dependency.is_some() && Self::is_binary_root(&src_dir, &path) {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path).unwrap();
+            let ast = parse_file(&content).unwrap();
+            functions.extend(Self::parse_functions(
+                &ast,
+                path.clone(),
+                &Self::file_module_path(&src_dir, &path),
+            ));
+            type_aliases.extend(Self::type_aliases(&ast));
+            Self::collect_enums(&ast, enums);
+        }
+
+        if let Some(dependency) = dependency {
+            for function in &mut functions {
+                function.crate_name = dependency.lib_name.clone();
+                function.crate_dir = Some(dependency.manifest_dir.clone());
+            }
+        }
+        functions
+    }
 
-        Ok(Self {
-            name: package.name.clone(),
-            root_path: project_root.to_path_buf(),
+    /// Assemble a project from parsed functions, assigning each tile and sequence
+    /// its CFS id and resolving call macro callees to those ids.
+    pub fn from_functions(
+        name: String,
+        root_path: PathBuf,
+        functions: Vec<FunctionAstItem>,
+    ) -> Self {
+        let mut functions = functions;
+        Self::assign_ids(&mut functions);
+        Self::resolve_callees(&name, &mut functions);
+
+        Self {
+            name,
+            root_path,
             functions,
-        })
+            type_aliases: HashSet::new(),
+            enums: HashMap::new(),
+        }
     }
 
-    fn parse_file(path: &Path) -> Vec<FunctionAstItem> {
-        let content = std::fs::read_to_string(path).unwrap();
-        let ast = parse_file(&content).unwrap();
-        Self::parse_functions(&ast, path.to_path_buf())
+    /// Names `ast` declares as `type` aliases or imports under another name
+    /// with `use ... as`, inline modules included.
+    pub(crate) fn type_aliases(ast: &syn::File) -> HashSet<String> {
+        fn use_tree(tree: &syn::UseTree, aliases: &mut HashSet<String>) {
+            match tree {
+                syn::UseTree::Path(path) => use_tree(&path.tree, aliases),
+                syn::UseTree::Rename(rename) => {
+                    aliases.insert(rename.rename.to_string());
+                }
+                syn::UseTree::Group(group) => {
+                    for tree in &group.items {
+                        use_tree(tree, aliases);
+                    }
+                }
+                syn::UseTree::Name(_) | syn::UseTree::Glob(_) => {}
+            }
+        }
+        fn items(list: &[syn::Item], aliases: &mut HashSet<String>) {
+            for item in list {
+                match item {
+                    syn::Item::Type(item_type) => {
+                        aliases.insert(item_type.ident.to_string());
+                    }
+                    syn::Item::Use(item_use) => use_tree(&item_use.tree, aliases),
+                    syn::Item::Mod(item_mod) => {
+                        if let Some((_, content)) = &item_mod.content {
+                            items(content, aliases);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut aliases = HashSet::new();
+        items(&ast.items, &mut aliases);
+        aliases
+    }
+
+    /// Add the enums `ast` declares, inline modules included, to `enums`.
+    pub(crate) fn collect_enums(ast: &syn::File, enums: &mut HashMap<String, Vec<String>>) {
+        fn items(list: &[syn::Item], enums: &mut HashMap<String, Vec<String>>) {
+            for item in list {
+                match item {
+                    syn::Item::Enum(item_enum) => {
+                        let variants: Vec<String> = item_enum
+                            .variants
+                            .iter()
+                            .map(|variant| variant.ident.to_string())
+                            .collect();
+                        enums
+                            .entry(item_enum.ident.to_string())
+                            .and_modify(|known| {
+                                if *known != variants {
+                                    known.clear();
+                                }
+                            })
+                            .or_insert(variants);
+                    }
+                    syn::Item::Mod(item_mod) => {
+                        if let Some((_, content)) = &item_mod.content {
+                            items(content, enums);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        items(&ast.items, enums);
     }
 
     fn find_all_rs_files(project_root: &Path) -> Vec<PathBuf> {
@@ -115,15 +374,65 @@ impl ProjectAst {
             .into_iter()
             .filter_map(|e| e.ok())
             .filter(|e| e.path().extension().is_some_and(|ext| ext == "rs"))
-            .filter(|e| e.path().file_name().unwrap_or_default() != "mod.rs")
             .map(|e| e.path().to_path_buf())
             .collect()
     }
 
-    fn parse_functions(ast: &syn::File, path: PathBuf) -> Vec<FunctionAstItem> {
+    /// Module path of a source file under `src/`, following Cargo's layout:
+    /// `lib.rs`, `main.rs` and `bin/*.rs` are crate roots, `a/mod.rs` and
+    /// `a.rs` are module `a`.
+    fn file_module_path(src_dir: &Path, file: &Path) -> Vec<String> {
+        let Ok(relative) = file.strip_prefix(src_dir) else {
+            return Vec::new();
+        };
+        let mut segments: Vec<String> = relative
+            .with_extension("")
+            .iter()
+            .map(|segment| segment.to_string_lossy().into_owned())
+            .collect();
+        match segments.as_slice() {
+            [root] if root == "lib" || root == "main" => Vec::new(),
+            [bin, ..] if bin == "bin" => Vec::new(),
+            _ => {
+                if segments.last().is_some_and(|last| last == "mod") {
+                    segments.pop();
+                }
+                segments
+            }
+        }
+    }
+
+    /// Whether a source file is the root of a binary target (`main.rs`,
+    /// `bin/*.rs`).
+    fn is_binary_root(src_dir: &Path, file: &Path) -> bool {
+        file.strip_prefix(src_dir)
+            .is_ok_and(|relative| relative == Path::new("main.rs") || relative.starts_with("bin"))
+    }
+
+    pub(crate) fn parse_functions(
+        ast: &syn::File,
+        path: PathBuf,
+        module_path: &[String],
+    ) -> Vec<FunctionAstItem> {
         let mut functions = Vec::new();
+        Self::parse_items(&ast.items, &path, module_path, &mut functions);
+        functions
+    }
 
-        for item in &ast.items {
+    fn parse_items(
+        items: &[syn::Item],
+        path: &Path,
+        module_path: &[String],
+        functions: &mut Vec<FunctionAstItem>,
+    ) {
+        for item in items {
+            if let syn::Item::Mod(item_mod) = item {
+                if let Some((_, items)) = &item_mod.content {
+                    let mut module_path = module_path.to_vec();
+                    module_path.push(item_mod.ident.to_string());
+                    Self::parse_items(items, path, &module_path, functions);
+                }
+            }
             if let syn::Item::Fn(func) = item {
                 let name = func.sig.ident.to_string();
 
@@ -161,20 +470,166 @@ impl ProjectAst {
                 let mut visitor = CallVisitor::new();
                 visitor.visit_item_fn(func);
                 let call_infos = visitor.get_call_infos();
+                let branches = visitor.get_branches();
+                let errors = visitor.get_errors();
                 let function_info = FunctionAstItem {
+                    id: name.clone(),
                     name,
-                    path: path.clone(),
+                    path: path.to_path_buf(),
+                    location: SourceLocation::of(func.sig.ident.span()),
+                    crate_name: None,
+                    crate_dir: None,
+                    module_path: module_path.to_vec(),
                     call_infos,
+                    branches,
                     macros,
                     input_names,
                     inputs,
                     output,
                     signature,
+                    errors,
                 };
                 functions.push(function_info);
             }
         }
-        functions
+    }
+
+    /// Tiles and sequences keep their name as id while no other function of the
+    /// same kind shares it; colliding names are qualified with their CFS path.
+    fn assign_ids(functions: &mut [FunctionAstItem]) {
+        let mut name_counts: HashMap<(Option<&'static str>, String), usize> = HashMap::new();
+        for function in functions.iter() {
+            *name_counts
+                .entry((function.raster_kind(), function.name.clone()))
+                .or_default() += 1;
+        }
+        for function in functions.iter_mut() {
+            let kind = function.raster_kind();
+            if kind.is_some() && name_counts[&(kind, function.name.clone())] > 1 {
+                function.id = function.cfs_path();
+            }
+        }
+    }
+
+    /// Rewrite every call macro callee to the id of the tile or sequence it names.
+    ///
+    /// Paths are compared as CFS paths, so a dependency's functions live under
+    /// its crate name while the project's own live at the root.
+    fn resolve_callees(project_name: &str, functions: &mut [FunctionAstItem]) {
+        let project_name = project_name.replace('-', "_");
+        let targets: Vec<(&'static str, Vec<String>, String)> = functions
+            .iter()
+            .filter_map(|function| {
+                Some((
+                    function.raster_kind()?,
+                    function.cfs_segments(),
+                    function.id.clone(),
+                ))
+            })
+            .collect();
+
+        for function in functions.iter_mut() {
+            let crate_name = function.crate_name.as_deref().unwrap_or(&project_name);
+            let mut module: Vec<String> = function.crate_name.iter().cloned().collect();
+            module.extend(function.module_path.iter().cloned());
+            for call in &mut function.call_infos {
+                let kind = match call.call_kind {
+                    CallKind::Tile | CallKind::RecursiveTile => "tile",
+                    CallKind::Sequence | CallKind::RecursiveSequence => "sequence",
+                };
+                let candidates: Vec<(&[String], &str)> = targets
+                    .iter()
+                    .filter(|(target_kind, _, _)| *target_kind == kind)
+                    .map(|(_, qualified, id)| (qualified.as_slice(), id.as_str()))
+                    .collect();
+                match Self::resolve_callee(
+                    crate_name,
+                    function.crate_name.as_slice(),
+                    &module,
+                    &call.callee,
+                    &candidates,
+                ) {
+                    Ok(Some(id)) => call.callee = id.to_string(),
+                    // Unknown callees are diagnosed by tile and sequence discovery.
+                    Ok(None) => {}
+                    // The CFS build rejects the sequence with this error.
+                    Err(ids) => function.errors.push(format!(
+                        "{} call `{}` is ambiguous; it may refer to `{}`. \
+                         Call it by its module path.",
+                        kind,
+                        call.callee,
+                        ids.join("`, `")
+                    )),
+                }
+            }
+        }
+    }
+
+    /// Resolve a callee path written inside `module` against the CFS paths of
+    /// `candidates`: `crate::`, `self::` and `super::` paths, and paths through
+    /// the library crate from a binary, are taken as written; other paths are
+    /// looked up relative to `module`, then from the crate root at `crate_root`,
+    /// then from the project root, then by unique suffix.
+    fn resolve_callee<'a>(
+        crate_name: &str,
+        crate_root: &[String],
+        module: &[String],
+        callee: &str,
+        candidates: &[(&[String], &'a str)],
+    ) -> std::result::Result<Option<&'a str>, Vec<&'a str>> {
+        let segments: Vec<&str> = callee.split("::").filter(|s| !s.is_empty()).collect();
+        let mut base = module.to_vec();
+        let mut relative = segments.as_slice();
+        let lookups: Vec<Vec<String>> = match segments.first().copied() {
+            Some(root) if root == "crate" || root == crate_name => {
+                let mut path = crate_root.to_vec();
+                path.extend(segments[1..].iter().map(|s| s.to_string()));
+                vec![path]
+            }
+            Some("self") | Some("super") => {
+                if segments[0] == "self" {
+                    relative = &relative[1..];
+                }
+                while relative.first() == Some(&"super") {
+                    base.pop();
+                    relative = &relative[1..];
+                }
+                base.extend(relative.iter().map(|s| s.to_string()));
+                vec![base]
+            }
+            _ => {
+                let segments: Vec<String> = segments.iter().map(|s| s.to_string()).collect();
+                base.extend(segments.iter().cloned());
+                let mut from_crate_root = crate_root.to_vec();
+                from_crate_root.extend(segments.iter().cloned());
+                vec![base, from_crate_root, segments]
+            }
+        };
+
+        for lookup in &lookups {
+            if let Some((_, id)) = candidates
+                .iter()
+                .find(|(path, _)| *path == lookup.as_slice())
+            {
+                return Ok(Some(id));
+            }
+        }
+        if lookups.len() == 1 {
+            return Ok(None);
+        }
+
+        let written = &lookups[lookups.len() - 1];
+
+        let matches: Vec<&'a str> = candidates
+            .iter()
+            .filter(|(path, _)| path.ends_with(written))
+            .map(|(_, id)| *id)
+            .collect();
+        match matches.as_slice() {
+            [] => Ok(None),
+            [id] => Ok(Some(id)),
+            _ => Err(matches),
+        }
     }
 
     fn parse_macro(attr: &Attribute) -> MacroAstItem {
@@ -236,15 +691,26 @@ impl ProjectAst {
 
 pub struct CallVisitor {
     call_infos: Vec<CallInfo>,
+    branches: Vec<BranchInfo>,
     /// Tracks the current let binding name when visiting let statements
     current_binding: Option<String>,
+    /// Tracks the names bound by a destructuring let statement
+    current_field_bindings: Vec<FieldBinding>,
+    /// Why the current let statement cannot be destructured, reported if its
+    /// initializer turns out to be a call
+    current_binding_error: Option<String>,
+    errors: Vec<String>,
 }
 
 impl CallVisitor {
     fn new() -> Self {
         Self {
             call_infos: Vec::new(),
+            branches: Vec::new(),
             current_binding: None,
+            current_field_bindings: Vec::new(),
+            current_binding_error: None,
+            errors: Vec::new(),
         }
     }
 
@@ -252,6 +718,91 @@ impl CallVisitor {
         self.call_infos.clone()
     }
 
+    fn get_branches(&self) -> Vec<BranchInfo> {
+        self.branches.clone()
+    }
+
+    fn get_errors(&self) -> Vec<String> {
+        self.errors.clone()
+    }
+
+    /// Visits a branch condition (`if` condition or `match` scrutinee).
+    fn visit_branch_condition(&mut self, expr: &Expr) -> BranchCondition {
+        match expr {
+            Expr::Paren(paren) => return self.visit_branch_condition(&paren.expr),
+            Expr::Group(group) => return self.visit_branch_condition(&group.expr),
+            Expr::Path(path) => {
+                if let Some(ident) = path.path.get_ident() {
+                    return BranchCondition::Binding(ident.to_string());
+                }
+            }
+            _ => {}
+        }
+
+        let calls_before = self.call_infos.len();
+        self.visit_expr(expr);
+        if matches!(expr, Expr::Macro(_)) && self.call_infos.len() == calls_before + 1 {
+            BranchCondition::Call(calls_before)
+        } else {
+            BranchCondition::Other(Self::expr_to_string(expr))
+        }
+    }
+
+    /// Visits an arm body, returning the range of calls made inside it.
+    fn visit_branch_arm(&mut self, visit: impl FnOnce(&mut Self)) -> Range<usize> {
+        let start = self.call_infos.len();
+        visit(self);
+        start..self.call_infos.len()
+    }
+
+    /// Records a branch whose condition was visited when `slot` branches were known.
+    ///
+    /// Inner branches are recorded while their arms are visited, so the outer branch is
+    /// inserted in front of them.
+    fn record_branch(&mut self, slot: usize, condition: BranchCondition, arms: Vec<BranchArmInfo>) {
+        if arms.iter().all(|arm| arm.calls.is_empty()) {
+            return;
+        }
+        self.branches.insert(slot, BranchInfo { condition, arms });
+    }
+
+    /// Maps an arm pattern onto the condition value it is taken for.
+    fn branch_pattern(pat: &Pat) -> Option<BranchPattern> {
+        let variant = |path: &syn::Path| {
+            let name = path.segments.last()?.ident.to_string();
+            // `Option` is encoded untagged (`None` as unit, `Some(v)` as `v`),
+            // so its variants cannot be told apart from the committed output.
+            if name == "Some" || name == "None" {
+                return None;
+            }
+            Some(BranchPattern::Variant(name))
+        };
+
+        match pat {
+            Pat::Lit(ExprLit {
+                lit: Lit::Bool(b), ..
+            }) => Some(BranchPattern::Bool(b.value)),
+            Pat::Wild(_) => Some(BranchPattern::Wildcard),
+            Pat::Ident(pat_ident) if pat_ident.subpat.is_none() => {
+                let name = pat_ident.ident.to_string();
+                if name.starts_with(char::is_uppercase) {
+                    variant(&pat_ident.ident.clone().into())
+                } else {
+                    Some(BranchPattern::Wildcard)
+                }
+            }
+            Pat::Path(pat_path) => variant(&pat_path.path),
+            Pat::TupleStruct(pat_tuple_struct) => variant(&pat_tuple_struct.path),
+            Pat::Struct(pat_struct) => variant(&pat_struct.path),
+            Pat::Paren(pat_paren) => Self::branch_pattern(&pat_paren.pat),
+            _ => None,
+        }
+    }
+
+    fn pat_to_string(pat: &Pat) -> String {
+        quote::quote!(#pat).to_string()
+    }
+
     /// Extracts the binding name from a pattern (e.g., `x` from `let x = ...`)
     fn extract_binding_name(pat: &Pat) -> Option<String> {
         match pat {
@@ -261,6 +812,93 @@ impl CallVisitor {
         }
     }
 
+    /// Extracts the names bound by a destructuring pattern (e.g., `a` and `b` from
+    /// `let (a, b) = ...`), each with its selector into the destructured value.
+    ///
+    /// Names whose position cannot be derived from the pattern alone (after a `..`
+    /// in a tuple, or under slice / or-patterns) are left out, so uses of them stay
+    /// unresolved instead of pointing at the wrong value.
+    fn extract_field_bindings(pat: &Pat) -> std::result::Result<Vec<FieldBinding>, String> {
+        fn collect(
+            pat: &Pat,
+            segments: &mut Vec<SelectorSegment>,
+            out: &mut Vec<FieldBinding>,
+        ) -> std::result::Result<(), String> {
+            let mut collect_positional =
+                |elems: &mut dyn Iterator<Item = &Pat>| -> std::result::Result<(), String> {
+                    let mut elems = elems.enumerate();
+                    for (index, elem) in elems.by_ref() {
+                        if matches!(elem, Pat::Rest(_)) {
+                            break;
+                        }
+                        segments.push(SelectorSegment::Index(index as u64));
+                        collect(elem, segments, out)?;
+                        segments.pop();
+                    }
+                    // Same rule as the `#[sequence]` macro: positions after `..`
+                    // depend on the output's arity.
+                    if elems.any(|(_, elem)| !matches!(elem, Pat::Wild(_))) {
+                        return Err("destructured `call!` bindings after `..` are not supported"
+                            .to_string());
+                    }
+                    Ok(())
+                };
+            match pat {
+                Pat::Ident(pat_ident) => {
+                    if let Some((_, subpat)) = &pat_ident.subpat {
+                        collect(subpat, segments, out)?;
+                    }
+                    if !segments.is_empty() {
+                        out.push(FieldBinding {
+                            name: pat_ident.ident.to_string(),
+                            selector: SelectorPath::new(segments.clone()),
+                        });
+                    }
+                    Ok(())
+                }
+                Pat::Type(pat_type) => collect(&pat_type.pat, segments, out),
+                Pat::Paren(pat_paren) => collect(&pat_paren.pat, segments, out),
+                Pat::Reference(pat_ref) => collect(&pat_ref.pat, segments, out),
+                Pat::Wild(_) => Ok(()),
+                Pat::Tuple(pat_tuple) => collect_positional(&mut pat_tuple.elems.iter()),
+                Pat::TupleStruct(pat_tuple_struct) => {
+                    collect_positional(&mut pat_tuple_struct.elems.iter())
+                }
+                Pat::Struct(pat_struct) => {
+                    for field in &pat_struct.fields {
+                        segments.push(match &field.member {
+                            syn::Member::Named(name) => SelectorSegment::Field(name.to_string()),
+                            syn::Member::Unnamed(index) => {
+                                SelectorSegment::Index(u64::from(index.index))
+                            }
+                        });
+                        collect(&field.pat, segments, out)?;
+                        segments.pop();
+                    }
+                    Ok(())
+                }
+                _ => Err("unsupported pattern in a destructured `call!` binding".to_string()),
+            }
+        }
+
+        let mut out = Vec::new();
+        collect(pat, &mut Vec::new(), &mut out)?;
+        Ok(out)
+    }
+
+    /// Whether `expr` is a macro invocation, possibly behind parentheses.
+    ///
+    /// `call!(...)?` is excluded: a fallible call stores its whole `Result`, and
+    /// selectors cannot reach through the `Ok` variant.
+    fn is_direct_macro(expr: &Expr) -> bool {
+        match expr {
+            Expr::Macro(_) => true,
+            Expr::Paren(paren) => Self::is_direct_macro(&paren.expr),
+            Expr::Group(group) => Self::is_direct_macro(&group.expr),
+            _ => false,
+        }
+    }
+
     /// Converts an expression to its string representation for argument capture
     fn expr_to_string(expr: &Expr) -> String {
         quote::quote!(#expr).to_string()
@@ -291,10 +929,10 @@ impl CallVisitor {
 
         let mut iter = args.iter();
 
-        // First argument must be a plain identifier (the callee function name).
+        // First argument must be a path (the callee function).
         let callee_expr = iter.next()?;
         let callee = match callee_expr {
-            Expr::Path(path) => path.path.get_ident()?.to_string(),
+            Expr::Path(path) => Self::path_to_string(&path.path),
             _ => return None,
         };
 
@@ -313,7 +951,7 @@ impl CallVisitor {
         mac: &syn::Macro,
     ) -> Option<(String, Vec<String>, Vec<CallArgumentKind>, Option<u64>)> {
         struct RecurCallInput {
-            tile: syn::Ident,
+            tile: syn::Path,
             input: Expr,
             // Chunk size is a call-site control parameter, not a tile input:
             // it groups iterations but binds no data source, so it is pinned
@@ -357,7 +995,7 @@ impl CallVisitor {
         impl Parse for RecurCallInput {
             fn parse(input: ParseStream) -> syn::Result<Self> {
                 parse_named_key(input, "tile")?;
-                let tile: syn::Ident = input.parse()?;
+                let tile: syn::Path = input.parse()?;
                 input.parse::<Token![,]>()?;
 
                 parse_named_key(input, "input")?;
@@ -419,14 +1057,19 @@ impl CallVisitor {
             argument_kinds.push(Self::classify_argument(&expr));
         }
 
-        Some((parsed.tile.to_string(), arguments, argument_kinds, chunk))
+        Some((
+            Self::path_to_string(&parsed.tile),
+            arguments,
+            argument_kinds,
+            chunk,
+        ))
     }
 
     fn parse_recur_sequence_call_macro_args(
         mac: &syn::Macro,
     ) -> Option<(String, Vec<String>, Vec<CallArgumentKind>)> {
         struct RecurSequenceCallInput {
-            sequence: syn::Ident,
+            sequence: syn::Path,
             input: Expr,
             state: Option<Expr>,
             output: Option<Expr>,
@@ -436,7 +1079,7 @@ impl CallVisitor {
         impl Parse for RecurSequenceCallInput {
             fn parse(input: ParseStream) -> syn::Result<Self> {
                 CallVisitor::parse_named_recur_key(input, "sequence")?;
-                let sequence: syn::Ident = input.parse()?;
+                let sequence: syn::Path = input.parse()?;
                 input.parse::<Token![,]>()?;
 
                 CallVisitor::parse_named_recur_key(input, "input")?;
@@ -508,7 +1151,11 @@ impl CallVisitor {
             argument_kinds.push(Self::classify_argument(&expr));
         }
 
-        Some((parsed.sequence.to_string(), arguments, argument_kinds))
+        Some((
+            Self::path_to_string(&parsed.sequence),
+            arguments,
+            argument_kinds,
+        ))
     }
 
     fn parse_named_recur_key(input: ParseStream, expected: &str) -> syn::Result<()> {
@@ -523,6 +1170,15 @@ impl CallVisitor {
         Ok(())
     }
 
+    /// `a::b::c` for a callee path, ignoring generic arguments.
+    fn path_to_string(path: &syn::Path) -> String {
+        path.segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+
     fn classify_argument(expr: &Expr) -> CallArgumentKind {
         match expr {
             Expr::Path(path) if path.path.get_ident().is_some() => CallArgumentKind::Identifier,
@@ -571,6 +1227,10 @@ impl CallVisitor {
             [prefix, name] if prefix == "raster" && name == "call_recur" => {
                 Some(CallKind::RecursiveTile)
             }
+            [name] if name == "call_map" => Some(CallKind::RecursiveTile),
+            [prefix, name] if prefix == "raster" && name == "call_map" => {
+                Some(CallKind::RecursiveTile)
+            }
             [name] if name == "call_recur_seq" => Some(CallKind::RecursiveSequence),
             [prefix, name] if prefix == "raster" && name == "call_recur_seq" => {
                 Some(CallKind::RecursiveSequence)
@@ -586,6 +1246,17 @@ impl<'ast> Visit<'ast> for CallVisitor {
 
         // Set the current binding context before visiting the initializer
         self.current_binding = binding_name;
+        // Only a call result itself can be destructured into selections of its output.
+        if node
+            .init
+            .as_ref()
+            .is_some_and(|init| Self::is_direct_macro(&init.expr))
+        {
+            match Self::extract_field_bindings(&node.pat) {
+                Ok(bindings) => self.current_field_bindings = bindings,
+                Err(error) => self.current_binding_error = Some(error),
+            }
+        }
 
         // Visit the initializer expression (this will trigger visit_expr_call /
         // visit_expr_macro if there's a call or macro invocation)
@@ -595,6 +1266,8 @@ impl<'ast> Visit<'ast> for CallVisitor {
 
         // Clear the binding context after processing
         self.current_binding = None;
+        self.current_field_bindings.clear();
+        self.current_binding_error = None;
     }
 
     fn visit_expr_macro(&mut self, node: &'ast ExprMacro) {
@@ -603,13 +1276,19 @@ impl<'ast> Visit<'ast> for CallVisitor {
                 Self::parse_call_macro_args(&node.mac)
             {
                 let result_binding = self.current_binding.take();
+                let field_bindings = std::mem::take(&mut self.current_field_bindings);
+                if let Some(error) = self.current_binding_error.take() {
+                    self.errors.push(error);
+                }
                 self.call_infos.push(CallInfo {
                     callee,
                     arguments,
                     argument_kinds,
                     result_binding,
+                    field_bindings,
                     call_kind,
                     chunk,
+                    location: SourceLocation::of(node.mac.span()),
                 });
                 // Do not recurse into the macro body — arguments are already captured above.
                 return;
@@ -635,8 +1314,10 @@ impl<'ast> Visit<'ast> for CallVisitor {
                     arguments,
                     argument_kinds,
                     result_binding: None,
+                    field_bindings: Vec::new(),
                     call_kind,
                     chunk,
+                    location: SourceLocation::of(node.mac.span()),
                 });
                 return;
             }
@@ -644,6 +1325,89 @@ impl<'ast> Visit<'ast> for CallVisitor {
         syn::visit::visit_stmt_macro(self, node);
     }
 
+    fn visit_expr_if(&mut self, node: &'ast ExprIf) {
+        // An `if` value is not a call result, even when bound by `let`.
+        self.current_binding = None;
+        self.current_field_bindings.clear();
+        self.current_binding_error = None;
+        let slot = self.branches.len();
+
+        let (condition, then_pattern, then_source, else_pattern) = match node.cond.as_ref() {
+            Expr::Let(expr_let) => (
+                self.visit_branch_condition(&expr_let.expr),
+                Self::branch_pattern(&expr_let.pat),
+                Self::pat_to_string(&expr_let.pat),
+                BranchPattern::Wildcard,
+            ),
+            cond => (
+                self.visit_branch_condition(cond),
+                Some(BranchPattern::Bool(true)),
+                "true".to_string(),
+                BranchPattern::Bool(false),
+            ),
+        };
+
+        let then_calls = self.visit_branch_arm(|visitor| visitor.visit_block(&node.then_branch));
+        let else_calls = self.visit_branch_arm(|visitor| {
+            if let Some((_, else_branch)) = &node.else_branch {
+                visitor.visit_expr(else_branch);
+            }
+        });
+        let else_source = match else_pattern {
+            BranchPattern::Bool(false) => "false",
+            _ => "_",
+        };
+
+        self.record_branch(
+            slot,
+            condition,
+            vec![
+                BranchArmInfo {
+                    pattern: then_pattern,
+                    pattern_source: then_source,
+                    calls: then_calls,
+                },
+                BranchArmInfo {
+                    pattern: Some(else_pattern),
+                    pattern_source: else_source.to_string(),
+                    calls: else_calls,
+                },
+            ],
+        );
+    }
+
+    fn visit_expr_match(&mut self, node: &'ast ExprMatch) {
+        self.current_binding = None;
+        self.current_field_bindings.clear();
+        self.current_binding_error = None;
+        let slot = self.branches.len();
+
+        let condition = self.visit_branch_condition(&node.expr);
+        let arms = node
+            .arms
+            .iter()
+            .map(|arm| {
+                let pattern = match arm.guard {
+                    Some(_) => None,
+                    None => Self::branch_pattern(&arm.pat),
+                };
+                let calls = self.visit_branch_arm(|visitor| {
+                    if let Some((_, guard)) = &arm.guard {
+                        visitor.visit_expr(guard);
+                    }
+                    visitor.visit_expr(&arm.body);
+                });
+                BranchArmInfo {
+                    pattern,
+                    pattern_source: Self::pat_to_string(&arm.pat),
+                    calls,
+                }
+            })
+            .collect();
+
+        self.record_branch(slot, condition, arms);
+    }
+
     // Note: visit_expr_call is intentionally NOT overridden. Bare function calls
     // (e.g. `greet(name)`) are not extracted — only canonical `call!` and `call_seq!`
     // macro invocations are recognized as step boundaries in sequences.
@@ -661,6 +1425,13 @@ mod tests {
         visitor.get_call_infos()
     }
 
+    fn parse_branches(code: &str) -> (Vec<CallInfo>, Vec<BranchInfo>) {
+        let file: syn::File = syn::parse_str(code).expect("Failed to parse test code");
+        let mut visitor = CallVisitor::new();
+        visitor.visit_file(&file);
+        (visitor.get_call_infos(), visitor.get_branches())
+    }
+
     #[test]
     fn test_bare_call_not_extracted() {
         // Bare function calls (without call!/call_seq!) must NOT be extracted.
@@ -714,6 +1485,18 @@ mod tests {
         assert_eq!(calls[0].arguments.len(), 3);
     }
 
+    #[test]
+    fn test_call_map_macro_extraction() {
+        let calls = parse_calls(
+            "fn seq() { let lengths = call_map!(tile = line_len, input = lines, args = (offset,)); }",
+        );
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].callee, "line_len");
+        assert_eq!(calls[0].call_kind, CallKind::RecursiveTile);
+        assert_eq!(calls[0].result_binding.as_deref(), Some("lengths"));
+        assert_eq!(calls[0].arguments, vec!["lines", "offset"]);
+    }
+
     #[test]
     fn test_call_recur_chunked_macro_extraction() {
         // `chunk = N` is a control parameter: the call must still be
@@ -773,6 +1556,77 @@ mod tests {
         );
     }
 
+    fn field(name: &str, segments: Vec<SelectorSegment>) -> FieldBinding {
+        FieldBinding {
+            name: name.to_string(),
+            selector: SelectorPath::new(segments),
+        }
+    }
+
+    #[test]
+    fn test_tuple_destructuring_binds_indexed_selectors() {
+        let calls = parse_calls("fn seq() { let (a, (b, _), ..) = call!(split, x); }");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].result_binding, None);
+        assert_eq!(
+            calls[0].field_bindings,
+            vec![
+                field("a", vec![SelectorSegment::Index(0)]),
+                field(
+                    "b",
+                    vec![SelectorSegment::Index(1), SelectorSegment::Index(0)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_struct_destructuring_binds_field_selectors() {
+        let calls = parse_calls(
+            "fn seq() { let Split { head, tail: Tail { rest, .. } } = call!(split, x); }",
+        );
+        assert_eq!(
+            calls[0].field_bindings,
+            vec![
+                field("head", vec![SelectorSegment::Field("head".into())]),
+                field(
+                    "rest",
+                    vec![
+                        SelectorSegment::Field("tail".into()),
+                        SelectorSegment::Field("rest".into()),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_destructuring_non_call_value_binds_nothing() {
+        let calls = parse_calls(
+            "fn seq() { let (a, b) = (call!(left, x), y); let (c, d) = call!(split, x)?; }",
+        );
+        assert_eq!(calls.len(), 2);
+        assert!(calls.iter().all(|call| call.field_bindings.is_empty()));
+    }
+
+    #[test]
+    fn test_destructuring_rejects_names_after_rest() {
+        let file: syn::File = syn::parse_str(
+            "fn seq() { let (a, .., _) = call!(split, x); let (b, .., c) = call!(split, x); \
+             let [d, e] = call!(split, x); let [f] = vec![x]; }",
+        )
+        .expect("Failed to parse test code");
+        let mut visitor = CallVisitor::new();
+        visitor.visit_file(&file);
+        assert_eq!(
+            visitor.get_errors(),
+            vec![
+                "destructured `call!` bindings after `..` are not supported".to_string(),
+                "unsupported pattern in a destructured `call!` binding".to_string(),
+            ]
+        );
+    }
+
     #[test]
     fn test_external_and_inline_argument_classification() {
         let calls = parse_calls(
@@ -863,4 +1717,321 @@ mod tests {
         assert_eq!(calls[0].call_kind, CallKind::Sequence);
         assert_eq!(calls[0].result_binding.as_deref(), Some("x"));
     }
+
+    #[test]
+    fn test_if_else_branch_extraction() {
+        let (calls, branches) = parse_branches(
+            r#"
+            fn seq(x: u64) {
+                if call!(is_valid, x) {
+                    call!(accept, x);
+                } else {
+                    call!(reject, x);
+                    call!(log, x);
+                }
+            }
+            "#,
+        );
+        assert_eq!(calls.len(), 4);
+        assert_eq!(branches.len(), 1);
+        assert_eq!(branches[0].condition, BranchCondition::Call(0));
+        let arms = &branches[0].arms;
+        assert_eq!(arms[0].pattern, Some(BranchPattern::Bool(true)));
+        assert_eq!(arms[0].calls, 1..2);
+        assert_eq!(arms[1].pattern, Some(BranchPattern::Bool(false)));
+        assert_eq!(arms[1].calls, 2..4);
+    }
+
+    #[test]
+    fn test_if_without_else_has_empty_else_arm() {
+        let (calls, branches) = parse_branches(
+            "fn seq(x: u64) { let ok = call!(is_valid, x); if ok { call!(accept, x); } }",
+        );
+        assert_eq!(calls[0].result_binding.as_deref(), Some("ok"));
+        assert_eq!(branches.len(), 1);
+        assert_eq!(branches[0].condition, BranchCondition::Binding("ok".into()));
+        assert_eq!(branches[0].arms[1].calls, 2..2);
+    }
+
+    #[test]
+    fn test_else_if_nests_inside_else_arm() {
+        let (_, branches) = parse_branches(
+            r#"
+            fn seq(x: u64) {
+                if call!(is_small, x) {
+                    call!(a, x);
+                } else if call!(is_large, x) {
+                    call!(b, x);
+                }
+            }
+            "#,
+        );
+        assert_eq!(branches.len(), 2);
+        assert_eq!(branches[0].condition, BranchCondition::Call(0));
+        assert_eq!(branches[0].arms[1].calls, 2..4);
+        assert_eq!(branches[1].condition, BranchCondition::Call(2));
+        assert_eq!(branches[1].arms[0].calls, 3..4);
+    }
+
+    #[test]
+    fn test_match_branch_patterns() {
+        let (_, branches) = parse_branches(
+            r#"
+            fn seq(x: u64) {
+                let _value = match call!(classify, x) {
+                    Verdict::Accept => call!(accept, x),
+                    Verdict::Retry { .. } => call!(retry, x),
+                    Verdict::Reject(n) if n > 3 => call!(reject, x),
+                    other => call!(fallback, x),
+                };
+            }
+            "#,
+        );
+        assert_eq!(branches.len(), 1);
+        let patterns: Vec<_> = branches[0]
+            .arms
+            .iter()
+            .map(|arm| arm.pattern.clone())
+            .collect();
+        assert_eq!(
+            patterns,
+            vec![
+                Some(BranchPattern::Variant("Accept".into())),
+                Some(BranchPattern::Variant("Retry".into())),
+                None,
+                Some(BranchPattern::Wildcard),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_branch_without_calls_is_not_recorded() {
+        let (calls, branches) = parse_branches(
+            "fn seq(x: u64) { let y = call!(double, x); let z = if flag { 1 } else { 2 }; }",
+        );
+        assert_eq!(calls.len(), 1);
+        assert!(branches.is_empty());
+    }
+
+    #[test]
+    fn test_if_value_is_not_a_call_binding() {
+        let (calls, _) = parse_branches(
+            "fn seq(x: u64) { let r = if call!(is_valid, x) { call!(a, x) } else { call!(b, x) }; }",
+        );
+        assert!(calls.iter().all(|call| call.result_binding.is_none()));
+    }
+
+    fn parse_project(code: &str) -> ProjectAst {
+        let file: syn::File = syn::parse_str(code).expect("Failed to parse test code");
+        ProjectAst::from_functions(
+            "test".to_string(),
+            PathBuf::from("/test"),
+            ProjectAst::parse_functions(&file, PathBuf::from("src/main.rs"), &[]),
+        )
+    }
+
+    fn function<'a>(project: &'a ProjectAst, qualified_name: &str) -> &'a FunctionAstItem {
+        project
+            .functions
+            .iter()
+            .find(|f| f.qualified_name() == qualified_name)
+            .expect("function exists")
+    }
+
+    #[test]
+    fn test_file_module_path_follows_cargo_layout() {
+        let src = Path::new("/p/src");
+        let module_path = |file: &str| ProjectAst::file_module_path(src, &src.join(file));
+
+        assert!(module_path("lib.rs").is_empty());
+        assert!(module_path("main.rs").is_empty());
+        assert!(module_path("bin/tool.rs").is_empty());
+        assert_eq!(module_path("text.rs"), vec!["text"]);
+        assert_eq!(module_path("text/mod.rs"), vec!["text"]);
+        assert_eq!(module_path("text/clean.rs"), vec!["text", "clean"]);
+    }
+
+    #[test]
+    fn test_colliding_tile_names_get_qualified_ids() {
+        let project = parse_project(
+            r#"
+            mod text { #[tile] pub fn normalize(s: String) -> String { s } }
+            mod audio { #[tile] pub fn normalize(s: String) -> String { s } }
+            #[tile] fn greet(s: String) -> String { s }
+            "#,
+        );
+
+        assert_eq!(function(&project, "text::normalize").id, "text::normalize");
+        assert_eq!(
+            function(&project, "audio::normalize").id,
+            "audio::normalize"
+        );
+        assert_eq!(function(&project, "greet").id, "greet");
+    }
+
+    #[test]
+    fn test_callees_resolve_to_ids() {
+        let project = parse_project(
+            r#"
+            mod text {
+                #[tile] pub fn normalize(s: String) -> String { s }
+                #[tile] pub fn trim(s: String) -> String { s }
+                #[sequence] pub fn clean(s: String) -> String {
+                    let a = call!(normalize, s);
+                    call!(super::audio::normalize, a)
+                }
+            }
+            mod audio { #[tile] pub fn normalize(s: String) -> String { s } }
+            #[sequence]
+            fn main() {
+                let s = call!(text::normalize, external!(String, "s"));
+                let t = call!(test::audio::normalize, s);
+                let u = call!(trim, t);
+                let v = call!(normalize, u);
+            }
+            "#,
+        );
+
+        let callees = |name: &str| -> Vec<String> {
+            function(&project, name)
+                .call_infos
+                .iter()
+                .map(|call| call.callee.clone())
+                .collect()
+        };
+        assert_eq!(
+            callees("text::clean"),
+            vec!["text::normalize", "audio::normalize"]
+        );
+        // `normalize` is ambiguous from the crate root and fails the build.
+        assert_eq!(
+            callees("main"),
+            vec!["text::normalize", "audio::normalize", "trim", "normalize"]
+        );
+        assert!(function(&project, "text::clean").errors.is_empty());
+        assert_eq!(
+            function(&project, "main").errors,
+            vec![
+                "tile call `normalize` is ambiguous; it may refer to `text::normalize`, \
+                 `audio::normalize`. Call it by its module path."
+            ]
+        );
+
+        let project = crate::Project {
+            name: "test".to_string(),
+            ast: project,
+            root_dir: PathBuf::from("/test"),
+            output_dir: PathBuf::from("/test/target/raster"),
+            target_dir: PathBuf::from("/test/target/"),
+        };
+        let Err(raster_core::Error::InvalidSequence(message)) =
+            crate::CfsBuilder::new(&project).build()
+        else {
+            panic!("ambiguous callee should not build");
+        };
+        assert!(message.starts_with("sequence `main`: tile call `normalize` is ambiguous"));
+    }
+
+    #[test]
+    fn test_match_branch_records_declared_variants() {
+        let code = r#"
+            pub enum Parity { Even, Odd }
+            #[tile] fn classify(x: u64) -> Parity { Parity::Even }
+            #[tile] fn halve(x: u64) -> u64 { x / 2 }
+            #[tile] fn triple(x: u64) -> u64 { x * 3 }
+            #[sequence]
+            fn main(x: u64) -> u64 {
+                match call!(classify, x) {
+                    Parity::Odd => call!(triple, x),
+                    Parity::Even => call!(halve, x),
+                }
+            }
+        "#;
+        let file: syn::File = syn::parse_str(code).expect("Failed to parse test code");
+        let mut ast = parse_project(code);
+        ProjectAst::collect_enums(&file, &mut ast.enums);
+        let project = crate::Project {
+            name: "test".to_string(),
+            ast,
+            root_dir: PathBuf::from("/test"),
+            output_dir: PathBuf::from("/test/target/raster"),
+            target_dir: PathBuf::from("/test/target/"),
+        };
+
+        let cfs = crate::CfsBuilder::new(&project)
+            .build()
+            .expect("sequence should build");
+        let sequence = cfs
+            .sequences
+            .iter()
+            .find(|sequence| sequence.id == "main")
+            .expect("main sequence");
+        assert_eq!(sequence.branches.len(), 1);
+        assert_eq!(sequence.branches[0].variants, vec!["Even", "Odd"]);
+    }
+
+    #[test]
+    fn test_dependency_functions_are_qualified_by_crate() {
+        let parse = |code: &str, crate_name: Option<&str>| {
+            let file: syn::File = syn::parse_str(code).expect("Failed to parse test code");
+            let mut functions =
+                ProjectAst::parse_functions(&file, PathBuf::from("src/lib.rs"), &[]);
+            for function in &mut functions {
+                function.crate_name = crate_name.map(str::to_string);
+            }
+            functions
+        };
+        let mut functions = parse(
+            r#"
+            #[tile] fn normalize(s: String) -> String { s }
+            #[sequence]
+            fn main() {
+                let s = call!(normalize, external!(String, "s"));
+                let t = call!(shared_tiles::normalize, s);
+                call_seq!(shared_tiles::clean, t);
+            }
+            "#,
+            None,
+        );
+        functions.extend(parse(
+            r#"
+            #[tile] pub fn normalize(s: String) -> String { s }
+            #[sequence] pub fn clean(s: String) -> String { call!(crate::normalize, s) }
+            "#,
+            Some("shared_tiles"),
+        ));
+        let project =
+            ProjectAst::from_functions("app".to_string(), PathBuf::from("/app"), functions);
+
+        let ids: Vec<(String, &str)> = project
+            .functions
+            .iter()
+            .map(|f| (f.cfs_path(), f.id.as_str()))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                ("normalize".to_string(), "normalize"),
+                ("main".to_string(), "main"),
+                (
+                    "shared_tiles::normalize".to_string(),
+                    "shared_tiles::normalize"
+                ),
+                ("shared_tiles::clean".to_string(), "clean"),
+            ]
+        );
+
+        let callees = |index: usize| -> Vec<&str> {
+            project.functions[index]
+                .call_infos
+                .iter()
+                .map(|call| call.callee.as_str())
+                .collect()
+        };
+        assert_eq!(
+            callees(1),
+            vec!["normalize", "shared_tiles::normalize", "clean"]
+        );
+        assert_eq!(callees(3), vec!["shared_tiles::normalize"]);
+    }
 }
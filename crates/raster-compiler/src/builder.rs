@@ -3,11 +3,12 @@ use raster_core::Error;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::Arc;
 
-use crate::tile::Tile;
+use crate::sequence_runner::{reachable_tiles, SequenceRunner, TileExecutor};
+use crate::tile::{Tile, TileDiscovery};
 use crate::{backend::BackendImpl, Project};
 use raster_backend::{Backend, CompilationArtifact, ExecutionMode, TileExecutionResult};
+use raster_core::cfs::{ControlFlowSchema, TileDef};
 use raster_core::Result;
 /// Orchestrates the build process for a Raster project.
 pub struct Builder<'ast, 'b> {
@@ -146,38 +147,27 @@ impl<'ast, 'b> Builder<'ast, 'b> {
         })
     }
 
-    /// Build a sequence runner from an entrypoint sequence name.
+    /// Build a sequence runner for `sequence_id` of `cfs`.
     ///
-    /// This creates a SequenceRunner that can execute a full sequence of tiles.
-    /// The sequence is expanded (resolving nested sequence calls) and all tiles
-    /// are pre-built before returning the runner.
-    ///
-    /// # Arguments
-    /// * `sequence_name` - The name of the sequence to build
-    ///
-    /// # Returns
-    /// A SequenceRunner ready to execute the sequence.
-    // pub fn build_sequence_runner<'ast>(
-    //     &self,
-    //     seq: &'ast Sequence<'ast>,
-    //     seq_discovery: &'ast SequenceDiscovery<'ast>,
-    // ) -> Result<SequenceRunner<'ast>> {
-    //     // Get the project path
-    //     // Build all tile runners (filter for tiles only from the flattened steps)
-    //     let tile_runners = seq.flatten(seq_discovery)
-    //         .filter_map(|step| match step {
-    //             FlattenedStep::Tile(tile) => Some(tile),
-    //             FlattenedStep::Sequence(_) => None,
-    //         })
-    //         .map(|tile| self.build_tile_runner(tile))
-    //         .collect::<Result<Vec<TileRunner>>>()?;
-
-    //     Ok(SequenceRunner {
-    //         backend: self.backend.clone(),
-    //         tile_runners,
-    //         sequence_name: seq.function.name.clone(),
-    //     })
-    // }
+    /// Every tile reachable from the sequence is built before the runner is
+    /// returned, so compilation failures surface before anything executes.
+    pub fn build_sequence_runner<'a>(
+        &self,
+        cfs: ControlFlowSchema,
+        sequence_id: &str,
+        tiles: &'a TileDiscovery<'a>,
+        mode: ExecutionMode,
+    ) -> Result<SequenceRunner<CompiledTiles<'a, 'b>>> {
+        let mut runners = HashMap::new();
+        for tile_id in reachable_tiles(&cfs, sequence_id)? {
+            let tile = tiles.get(&tile_id).ok_or_else(|| {
+                Error::Other(format!("Tile '{}' not found in the project", tile_id))
+            })?;
+            runners.insert(tile_id, self.build_tile_runner(tile)?);
+        }
+
+        SequenceRunner::new(cfs, sequence_id, CompiledTiles { runners, mode })
+    }
 
     /// Get the output directory.
     pub fn output_dir(&self) -> &PathBuf {
@@ -397,73 +387,17 @@ impl<'ast, 'b> TileRunner<'ast, 'b> {
     }
 }
 
-/// A compiled sequence ready to run.
-///
-/// SequenceRunner encapsulates a sequence of pre-built TileRunners that can
-/// be executed in order, with each tile's output becoming the next tile's input.
-pub struct SequenceRunner<'ast, 'b> {
-    /// The backend used for execution (shared).
-    backend: Arc<dyn Backend>,
-    /// Pre-built TileRunners in execution order.
-    step_runners: Vec<StepRunner<'ast, 'b>>,
-    /// The sequence name.
-    sequence_name: String,
+/// The compiled tiles of a sequence, executed in one [`ExecutionMode`].
+pub struct CompiledTiles<'ast, 'b> {
+    runners: HashMap<String, TileRunner<'ast, 'b>>,
+    mode: ExecutionMode,
 }
 
-pub enum StepRunner<'ast, 'b> {
-    Tile(TileRunner<'ast, 'b>),
-    Sequence(SequenceRunner<'ast, 'b>),
+impl TileExecutor for CompiledTiles<'_, '_> {
+    fn execute(&mut self, tile: &TileDef, input: &[u8]) -> Result<TileExecutionResult> {
+        self.runners
+            .get(&tile.id)
+            .ok_or_else(|| Error::Other(format!("Tile '{}' was not built", tile.id)))?
+            .run(input, self.mode)
+    }
 }
-
-// impl<'ast> SequenceRunner<'ast> {
-//     /// Execute the full sequence, chaining outputs to inputs.
-//     ///
-//     /// The initial input is passed to the first tile, and each subsequent tile
-//     /// receives the output from the previous tile as its input.
-//     pub fn run(&self, input: &[u8], mode: ExecutionMode) -> Result<SequenceExecutionResult> {
-//         let mut current_input = input.to_vec();
-//         let mut step_results = Vec::new();
-
-//         for runner in &self.step_runners {
-//             let result = match runner {
-//                 StepRunner::Tile(tile_runner) => tile_runner.run(&current_input, mode)?,
-//                 StepRunner::Sequence(sequence_runner) => sequence_runner.run(&current_input, mode)?,
-//             };
-
-//             step_results.push(StepResult::Tile(TileResult {
-//                 output: result.output,
-//             }));
-
-//             // Output becomes input for next tile
-//             current_input = result.output;
-//         }
-
-//         Ok(SequenceExecutionResult {
-//             output: current_input,
-//             step_results,
-//         })
-//     }
-
-//     /// Get the sequence name.
-//     pub fn sequence_name(&self) -> &str {
-//         &self.sequence_name
-//     }
-
-//     /// Get the number of tiles in this sequence.
-//     pub fn tile_count(&self) -> usize {
-//         self.tile_runners.len()
-//     }
-
-//     /// Get the tile IDs in execution order.
-//     pub fn tile_ids(&self) -> Vec<&str> {
-//         self.tile_runners
-//             .iter()
-//             .map(|r| r.tile.function.name.as_str())
-//             .collect()
-//     }
-
-//     /// Get the backend name.
-//     pub fn backend_name(&self) -> &'static str {
-//         self.backend.name()
-//     }
-// }
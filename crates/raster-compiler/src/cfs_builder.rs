@@ -1,18 +1,21 @@
 //! Control Flow Schema (CFS) builder.
 //!
 //! This module orchestrates the generation of a CFS from a Raster project
-//! by combining tile discovery, sequence discovery, and data flow resolution.
+//! by combining tile discovery, sequence discovery, and data flow resolution,
+//! and type checks the resolved data flow.
 
 use raster_core::cfs::{
-    CfsCoordinate, CfsCoordinates, ControlFlowSchema, InputBinding, SequenceChildId,
-    SequenceChildItem, SequenceDef, SequenceId, SequenceItem, TileDef,
+    BranchPattern, CfsCoordinate, CfsCoordinates, ControlFlowSchema, InputBinding, SequenceChildId,
+    SequenceChildItem, SequenceDef, SequenceId, SequenceItem, TileDef, CFS_VERSION,
 };
 
+use crate::ast::FunctionAstItem;
 use crate::flow_resolver::FlowResolver;
 use crate::sequence::{Sequence, SequenceDiscovery};
 use crate::tile::TileDiscovery;
+use crate::type_check::{self, compact_type, TypeMismatch};
 use crate::Project;
-use raster_core::Result;
+use raster_core::{Error, Result};
 
 /// Builds a control flow schema from a Raster project.
 pub struct CfsBuilder<'a> {
@@ -42,28 +45,64 @@ impl<'a> CfsBuilder<'a> {
             .map(|t| {
                 let input_count = t.function.inputs.len();
                 let output_count = if t.function.output.is_some() { 1 } else { 0 };
-                TileDef::new(&t.function.name, &t.tile_type, input_count, output_count)
+                let (input_types, output_type) = signature_types(t.function);
+                TileDef::new(t.id(), &t.tile_type, input_count, output_count)
+                    .with_types(input_types, output_type)
+                    .with_path(t.function.cfs_path())
             })
             .collect();
 
         // Build sequence definitions with resolved data flow
         let mut sequences = Vec::new();
         for seq in &sequence_discovery.sequences {
-            let seq_def = self.build_sequence_def(seq)?;
+            let mut seq_def = self.build_sequence_def(seq)?;
+            self.record_branch_variants(&tiles, &mut seq_def);
             sequences.push(seq_def);
         }
 
-        Ok(ControlFlowSchema {
-            version: "1.0".to_string(),
+        let cfs = ControlFlowSchema {
+            version: CFS_VERSION.to_string(),
             project: self.project.name.clone(),
             encoding: "postcard".to_string(),
             tiles,
             sequences,
-        })
+        };
+
+        // Reject data flow between mismatched types before anything runs
+        let type_aliases = &self.project.ast.type_aliases;
+        let mismatches: Vec<TypeMismatch> = sequence_discovery
+            .sequences
+            .iter()
+            .zip(&cfs.sequences)
+            .flat_map(|(seq, seq_def)| type_check::check_sequence(&cfs, seq_def, seq, type_aliases))
+            .collect();
+        if !mismatches.is_empty() {
+            let mut message = format!(
+                "{} argument(s) bound to a value of a different type",
+                mismatches.len()
+            );
+            for mut mismatch in mismatches {
+                if let Ok(relative) = mismatch.path.strip_prefix(&self.project.root_dir) {
+                    mismatch.path = relative.to_path_buf();
+                }
+                message.push_str(&format!("\n{}", mismatch));
+            }
+            return Err(Error::InvalidSequence(message));
+        }
+
+        Ok(cfs)
     }
 
     /// Build a sequence definition from a discovered sequence.
     fn build_sequence_def(&self, seq: &Sequence<'_>) -> Result<SequenceDef> {
+        if let Some(error) = seq.function.errors.first() {
+            return Err(Error::InvalidSequence(format!(
+                "sequence `{}`: {}",
+                seq.id(),
+                error
+            )));
+        }
+
         // Create input sources for the sequence's parameters
         // All sequence inputs come from external sources
         let input_count = seq.function.inputs.len();
@@ -73,11 +112,66 @@ impl<'a> CfsBuilder<'a> {
         // Resolve data flow for the sequence items
         let mut resolver = FlowResolver::new();
         let items = resolver.resolve(seq);
+        let branches = resolver.resolve_branches(seq)?;
+
+        let (input_types, output_type) = signature_types(seq.function);
 
         Ok(SequenceDef {
-            id: seq.function.name.clone(),
+            id: seq.id().to_string(),
             input_sources,
             items,
+            branches,
+            input_types,
+            output_type,
+            path: seq.function.cfs_path(),
         })
     }
+
+    /// Record the variant order of the condition type of every branch that
+    /// matches enum variants, for runners that only see the variant index.
+    /// Enums not declared in the project sources are left unrecorded.
+    fn record_branch_variants(&self, tiles: &[TileDef], sequence: &mut SequenceDef) {
+        for branch in &mut sequence.branches {
+            let matches_variants = branch
+                .arms
+                .iter()
+                .any(|arm| matches!(arm.pattern, BranchPattern::Variant(_)));
+            if !matches_variants {
+                continue;
+            }
+            let Some(SequenceChildItem::Tile(condition)) = sequence.items.get(branch.condition)
+            else {
+                continue;
+            };
+            let enum_name = tiles
+                .iter()
+                .find(|tile| tile.id == condition.id)
+                .and_then(|tile| tile.output_type.as_deref())
+                .and_then(|ty| syn::parse_str::<syn::TypePath>(ty).ok())
+                .and_then(|ty| {
+                    ty.path
+                        .segments
+                        .last()
+                        .map(|segment| segment.ident.to_string())
+                });
+            branch.variants = match enum_name.as_deref() {
+                Some("Result") => vec!["Ok".to_string(), "Err".to_string()],
+                Some(name) => self
+                    .project
+                    .ast
+                    .enums
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_default(),
+                None => Vec::new(),
+            };
+        }
+    }
+}
+
+/// Parameter and return types of a function signature, as recorded in the CFS.
+fn signature_types(function: &FunctionAstItem) -> (Vec<String>, Option<String>) {
+    let input_types = function.inputs.iter().map(|ty| compact_type(ty)).collect();
+    let output_type = function.output.as_deref().map(compact_type);
+    (input_types, output_type)
 }
@@ -0,0 +1,874 @@
+//! Compatibility diff between two control flow schemas.
+//!
+//! Trace records address sequence items by CFS coordinates (intra-sequence
+//! item indices) and are verified against the items' input bindings, so a
+//! refactor keeps previously committed traces auditable only if every item
+//! that existed before keeps its position and shape. [`CfsDiff`] lists what
+//! changed between two schemas and classifies each change as
+//! coordinate-compatible or breaking.
+//!
+//! Trace commitments also carry the CFS digest, so any change at all stops
+//! old commitments from auditing against the new CFS; the diff reports that
+//! separately from coordinate compatibility.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use raster_core::cfs::{ControlFlowSchema, InputBinding, SequenceChildItem, SequenceDef, TileDef};
+use serde::Serialize;
+
+/// Whether traces committed under the old CFS still line up with the new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compatibility {
+    /// Every old coordinate still names the same item with the same bindings,
+    /// followed by the same items.
+    CoordinateCompatible,
+    /// At least one old coordinate moved, disappeared or changed meaning.
+    Breaking,
+}
+
+/// A sequence item, identified the way the CFS identifies it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ItemRef {
+    /// `tile`, `recur_tile`, `sequence` or `recur_sequence`.
+    pub kind: &'static str,
+    pub id: String,
+}
+
+impl ItemRef {
+    fn of(item: &SequenceChildItem) -> Self {
+        let (kind, id) = match item {
+            SequenceChildItem::Tile(item) => ("tile", &item.id),
+            SequenceChildItem::RecurTile(item) => ("recur_tile", &item.id),
+            SequenceChildItem::Sequence(item) => ("sequence", &item.id),
+            SequenceChildItem::RecurSequence(item) => ("recur_sequence", &item.id),
+        };
+        Self {
+            kind,
+            id: id.clone(),
+        }
+    }
+}
+
+impl fmt::Display for ItemRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} '{}'", self.kind, self.id)
+    }
+}
+
+/// One difference between the old and the new CFS.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum CfsChange {
+    TileAdded {
+        tile: String,
+    },
+    TileRemoved {
+        tile: String,
+    },
+    TileArityChanged {
+        tile: String,
+        old_inputs: usize,
+        new_inputs: usize,
+        old_outputs: usize,
+        new_outputs: usize,
+    },
+    TileTypesChanged {
+        tile: String,
+        old_inputs: Vec<String>,
+        new_inputs: Vec<String>,
+        old_output: Option<String>,
+        new_output: Option<String>,
+    },
+    /// The same function got another id, e.g. a module-qualified one after
+    /// another tile started sharing its name.
+    TileRenamed {
+        path: String,
+        old_id: String,
+        new_id: String,
+    },
+    TilePathChanged {
+        tile: String,
+        old_path: String,
+        new_path: String,
+    },
+    SequenceAdded {
+        sequence: String,
+    },
+    SequenceRemoved {
+        sequence: String,
+    },
+    SequenceTypesChanged {
+        sequence: String,
+        old_inputs: Vec<String>,
+        new_inputs: Vec<String>,
+        old_output: Option<String>,
+        new_output: Option<String>,
+    },
+    SequenceRenamed {
+        path: String,
+        old_id: String,
+        new_id: String,
+    },
+    SequencePathChanged {
+        sequence: String,
+        old_path: String,
+        new_path: String,
+    },
+    SequenceInputsChanged {
+        sequence: String,
+        old: Vec<InputBinding>,
+        new: Vec<InputBinding>,
+    },
+    BranchesChanged {
+        sequence: String,
+    },
+    /// An item that did not exist before. Inserting it before an old item
+    /// shifts that item's coordinates; appending it changes what follows the
+    /// old last item, so an old trace ending there no longer matches the CFS.
+    ItemAdded {
+        sequence: String,
+        index: usize,
+        item: ItemRef,
+        appended: bool,
+    },
+    ItemRemoved {
+        sequence: String,
+        index: usize,
+        item: ItemRef,
+    },
+    ItemReordered {
+        sequence: String,
+        item: ItemRef,
+        old_index: usize,
+        new_index: usize,
+    },
+    InputBindingChanged {
+        sequence: String,
+        index: usize,
+        item: ItemRef,
+        input: usize,
+        old: Option<InputBinding>,
+        new: Option<InputBinding>,
+    },
+    ChunkChanged {
+        sequence: String,
+        index: usize,
+        item: ItemRef,
+        old: Option<u64>,
+        new: Option<u64>,
+    },
+}
+
+impl CfsChange {
+    /// Whether the change invalidates coordinates or bindings of old traces.
+    ///
+    /// Every change breaks old commitments; see [`CfsDiff::commitments_compatible`].
+    pub fn is_breaking(&self) -> bool {
+        !matches!(
+            self,
+            CfsChange::TileAdded { .. }
+                | CfsChange::SequenceAdded { .. }
+                | CfsChange::TilePathChanged { .. }
+                | CfsChange::SequencePathChanged { .. }
+        )
+    }
+}
+
+impl fmt::Display for CfsChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CfsChange::TileAdded { tile } => write!(f, "tile '{}' added", tile),
+            CfsChange::TileRemoved { tile } => write!(f, "tile '{}' removed", tile),
+            CfsChange::TileArityChanged {
+                tile,
+                old_inputs,
+                new_inputs,
+                old_outputs,
+                new_outputs,
+            } => write!(
+                f,
+                "tile '{}' arity changed: inputs {} -> {}, outputs {} -> {}",
+                tile, old_inputs, new_inputs, old_outputs, new_outputs
+            ),
+            CfsChange::TileTypesChanged {
+                tile,
+                old_inputs,
+                new_inputs,
+                old_output,
+                new_output,
+            } => write!(
+                f,
+                "tile '{}' types changed: ({}) -> {:?} to ({}) -> {:?}",
+                tile,
+                old_inputs.join(", "),
+                old_output,
+                new_inputs.join(", "),
+                new_output
+            ),
+            CfsChange::TileRenamed {
+                path,
+                old_id,
+                new_id,
+            } => write!(f, "tile '{}' renamed to '{}' ({})", old_id, new_id, path),
+            CfsChange::TilePathChanged {
+                tile,
+                old_path,
+                new_path,
+            } => write!(
+                f,
+                "tile '{}' moved from '{}' to '{}'",
+                tile, old_path, new_path
+            ),
+            CfsChange::SequenceAdded { sequence } => write!(f, "sequence '{}' added", sequence),
+            CfsChange::SequenceRemoved { sequence } => {
+                write!(f, "sequence '{}' removed", sequence)
+            }
+            CfsChange::SequenceTypesChanged {
+                sequence,
+                old_inputs,
+                new_inputs,
+                old_output,
+                new_output,
+            } => write!(
+                f,
+                "sequence '{}' types changed: ({}) -> {:?} to ({}) -> {:?}",
+                sequence,
+                old_inputs.join(", "),
+                old_output,
+                new_inputs.join(", "),
+                new_output
+            ),
+            CfsChange::SequenceRenamed {
+                path,
+                old_id,
+                new_id,
+            } => write!(
+                f,
+                "sequence '{}' renamed to '{}' ({})",
+                old_id, new_id, path
+            ),
+            CfsChange::SequencePathChanged {
+                sequence,
+                old_path,
+                new_path,
+            } => write!(
+                f,
+                "sequence '{}' moved from '{}' to '{}'",
+                sequence, old_path, new_path
+            ),
+            CfsChange::SequenceInputsChanged { sequence, old, new } => write!(
+                f,
+                "sequence '{}' inputs changed: {:?} -> {:?}",
+                sequence, old, new
+            ),
+            CfsChange::BranchesChanged { sequence } => {
+                write!(f, "sequence '{}' branches changed", sequence)
+            }
+            CfsChange::ItemAdded {
+                sequence,
+                index,
+                item,
+                appended,
+            } => write!(
+                f,
+                "{}[{}]: {} added{}",
+                sequence,
+                index,
+                item,
+                if *appended {
+                    " (after the old last item)"
+                } else {
+                    " (shifts later items)"
+                }
+            ),
+            CfsChange::ItemRemoved {
+                sequence,
+                index,
+                item,
+            } => write!(f, "{}[{}]: {} removed", sequence, index, item),
+            CfsChange::ItemReordered {
+                sequence,
+                item,
+                old_index,
+                new_index,
+            } => write!(
+                f,
+                "{}: {} moved from [{}] to [{}]",
+                sequence, item, old_index, new_index
+            ),
+            CfsChange::InputBindingChanged {
+                sequence,
+                index,
+                item,
+                input,
+                old,
+                new,
+            } => write!(
+                f,
+                "{}[{}]: {} input {} binding changed: {:?} -> {:?}",
+                sequence, index, item, input, old, new
+            ),
+            CfsChange::ChunkChanged {
+                sequence,
+                index,
+                item,
+                old,
+                new,
+            } => write!(
+                f,
+                "{}[{}]: {} chunk changed: {:?} -> {:?}",
+                sequence, index, item, old, new
+            ),
+        }
+    }
+}
+
+/// Differences between an old and a new CFS and their overall compatibility.
+#[derive(Debug, Clone, Serialize)]
+pub struct CfsDiff {
+    pub compatibility: Compatibility,
+    /// Whether commitments made under the old CFS still audit against the new
+    /// one. They are bound to the CFS digest, so this only holds when the two
+    /// schemas are identical; any change makes `--audit` fail with
+    /// `CfsMismatch`.
+    pub commitments_compatible: bool,
+    pub changes: Vec<CfsChange>,
+}
+
+impl CfsDiff {
+    /// Compare `old` against `new`.
+    pub fn between(old: &ControlFlowSchema, new: &ControlFlowSchema) -> Self {
+        let mut changes = Vec::new();
+        diff_tiles(&old.tiles, &new.tiles, &mut changes);
+        diff_sequences(&old.sequences, &new.sequences, &mut changes);
+
+        let compatibility = if changes.iter().any(CfsChange::is_breaking) {
+            Compatibility::Breaking
+        } else {
+            Compatibility::CoordinateCompatible
+        };
+
+        Self {
+            compatibility,
+            commitments_compatible: old.digest() == new.digest(),
+            changes,
+        }
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.compatibility == Compatibility::Breaking
+    }
+}
+
+impl fmt::Display for CfsDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.changes.is_empty() {
+            writeln!(f, "No changes")?;
+        }
+        for change in &self.changes {
+            let marker = if change.is_breaking() {
+                "breaking"
+            } else {
+                "ok"
+            };
+            writeln!(f, "  [{:>8}] {}", marker, change)?;
+        }
+        match self.compatibility {
+            Compatibility::CoordinateCompatible => writeln!(f, "Result: coordinate-compatible")?,
+            Compatibility::Breaking => writeln!(
+                f,
+                "Result: breaking ({} of {} changes)",
+                self.changes.iter().filter(|c| c.is_breaking()).count(),
+                self.changes.len()
+            )?,
+        }
+        if self.commitments_compatible {
+            write!(f, "Commitments: existing commitments still audit")
+        } else {
+            write!(
+                f,
+                "Commitments: existing commitments no longer audit (CFS digest changed)"
+            )
+        }
+    }
+}
+
+fn diff_tiles(old: &[TileDef], new: &[TileDef], changes: &mut Vec<CfsChange>) {
+    let old_by_id: BTreeMap<_, _> = old.iter().map(|tile| (&tile.id, tile)).collect();
+    let new_by_id: BTreeMap<_, _> = new.iter().map(|tile| (&tile.id, tile)).collect();
+
+    let mut added: Vec<&TileDef> = new_by_id
+        .iter()
+        .filter(|(id, _)| !old_by_id.contains_key(*id))
+        .map(|(_, tile)| *tile)
+        .collect();
+
+    for (id, old_tile) in &old_by_id {
+        let new_tile = match new_by_id.get(id) {
+            Some(new_tile) => *new_tile,
+            None => match take_same_path(&mut added, &old_tile.path, |tile| &tile.path) {
+                Some(new_tile) => {
+                    changes.push(CfsChange::TileRenamed {
+                        path: old_tile.path.clone(),
+                        old_id: id.to_string(),
+                        new_id: new_tile.id.clone(),
+                    });
+                    new_tile
+                }
+                None => {
+                    changes.push(CfsChange::TileRemoved {
+                        tile: id.to_string(),
+                    });
+                    continue;
+                }
+            },
+        };
+        let tile = &new_tile.id;
+        if new_tile.inputs != old_tile.inputs || new_tile.outputs != old_tile.outputs {
+            changes.push(CfsChange::TileArityChanged {
+                tile: tile.clone(),
+                old_inputs: old_tile.inputs,
+                new_inputs: new_tile.inputs,
+                old_outputs: old_tile.outputs,
+                new_outputs: new_tile.outputs,
+            });
+        } else if new_tile.input_types != old_tile.input_types
+            || new_tile.output_type != old_tile.output_type
+        {
+            changes.push(CfsChange::TileTypesChanged {
+                tile: tile.clone(),
+                old_inputs: old_tile.input_types.clone(),
+                new_inputs: new_tile.input_types.clone(),
+                old_output: old_tile.output_type.clone(),
+                new_output: new_tile.output_type.clone(),
+            });
+        }
+        if new_tile.path != old_tile.path {
+            changes.push(CfsChange::TilePathChanged {
+                tile: tile.clone(),
+                old_path: old_tile.path.clone(),
+                new_path: new_tile.path.clone(),
+            });
+        }
+    }
+    for tile in added {
+        changes.push(CfsChange::TileAdded {
+            tile: tile.id.clone(),
+        });
+    }
+}
+
+fn diff_sequences(old: &[SequenceDef], new: &[SequenceDef], changes: &mut Vec<CfsChange>) {
+    let new_by_id: BTreeMap<_, _> = new.iter().map(|seq| (&seq.id, seq)).collect();
+
+    let mut added: Vec<&SequenceDef> = new
+        .iter()
+        .filter(|new_seq| !old.iter().any(|old_seq| old_seq.id == new_seq.id))
+        .collect();
+
+    for old_seq in old {
+        match new_by_id.get(&old_seq.id) {
+            Some(new_seq) => diff_sequence(old_seq, new_seq, changes),
+            None => match take_same_path(&mut added, &old_seq.path, |seq| &seq.path) {
+                Some(new_seq) => {
+                    changes.push(CfsChange::SequenceRenamed {
+                        path: old_seq.path.clone(),
+                        old_id: old_seq.id.clone(),
+                        new_id: new_seq.id.clone(),
+                    });
+                    diff_sequence(old_seq, new_seq, changes);
+                }
+                None => changes.push(CfsChange::SequenceRemoved {
+                    sequence: old_seq.id.clone(),
+                }),
+            },
+        }
+    }
+    for new_seq in added {
+        changes.push(CfsChange::SequenceAdded {
+            sequence: new_seq.id.clone(),
+        });
+    }
+}
+
+/// Remove and return the definition in `added` declared at `path`, if any.
+/// Paths are empty in schemas written before they were recorded, and match
+/// nothing then.
+fn take_same_path<'a, T>(
+    added: &mut Vec<&'a T>,
+    path: &str,
+    path_of: impl Fn(&T) -> &String,
+) -> Option<&'a T> {
+    if path.is_empty() {
+        return None;
+    }
+    let position = added.iter().position(|def| path_of(def) == path)?;
+    Some(added.remove(position))
+}
+
+fn diff_sequence(old: &SequenceDef, new: &SequenceDef, changes: &mut Vec<CfsChange>) {
+    let sequence = &new.id;
+
+    if old.input_types != new.input_types || old.output_type != new.output_type {
+        changes.push(CfsChange::SequenceTypesChanged {
+            sequence: sequence.clone(),
+            old_inputs: old.input_types.clone(),
+            new_inputs: new.input_types.clone(),
+            old_output: old.output_type.clone(),
+            new_output: new.output_type.clone(),
+        });
+    }
+    if old.path != new.path {
+        changes.push(CfsChange::SequencePathChanged {
+            sequence: sequence.clone(),
+            old_path: old.path.clone(),
+            new_path: new.path.clone(),
+        });
+    }
+
+    if old.input_sources != new.input_sources {
+        changes.push(CfsChange::SequenceInputsChanged {
+            sequence: sequence.clone(),
+            old: old.input_sources.clone(),
+            new: new.input_sources.clone(),
+        });
+    }
+
+    let old_refs: Vec<ItemRef> = old.items.iter().map(ItemRef::of).collect();
+    let new_refs: Vec<ItemRef> = new.items.iter().map(ItemRef::of).collect();
+    let matched = longest_common_subsequence(&old_refs, &new_refs);
+
+    let mut removed: Vec<usize> = (0..old_refs.len())
+        .filter(|index| !matched.iter().any(|(old_index, _)| old_index == index))
+        .collect();
+    let mut added: Vec<usize> = (0..new_refs.len())
+        .filter(|index| !matched.iter().any(|(_, new_index)| new_index == index))
+        .collect();
+
+    // An item that left one position and reappeared at another was moved.
+    let mut reordered = Vec::new();
+    removed.retain(|&old_index| {
+        let Some(position) = added
+            .iter()
+            .position(|&new_index| new_refs[new_index] == old_refs[old_index])
+        else {
+            return true;
+        };
+        reordered.push((old_index, added.remove(position)));
+        false
+    });
+
+    for &old_index in &removed {
+        changes.push(CfsChange::ItemRemoved {
+            sequence: sequence.clone(),
+            index: old_index,
+            item: old_refs[old_index].clone(),
+        });
+    }
+    for &(old_index, new_index) in &reordered {
+        changes.push(CfsChange::ItemReordered {
+            sequence: sequence.clone(),
+            item: old_refs[old_index].clone(),
+            old_index,
+            new_index,
+        });
+    }
+    for &new_index in &added {
+        changes.push(CfsChange::ItemAdded {
+            sequence: sequence.clone(),
+            index: new_index,
+            item: new_refs[new_index].clone(),
+            appended: new_index >= old_refs.len(),
+        });
+    }
+
+    for &(old_index, new_index) in matched.iter().chain(&reordered) {
+        diff_item(
+            sequence,
+            new_index,
+            &old.items[old_index],
+            &new.items[new_index],
+            changes,
+        );
+    }
+
+    if old.branches != new.branches {
+        changes.push(CfsChange::BranchesChanged {
+            sequence: sequence.clone(),
+        });
+    }
+}
+
+fn diff_item(
+    sequence: &str,
+    index: usize,
+    old: &SequenceChildItem,
+    new: &SequenceChildItem,
+    changes: &mut Vec<CfsChange>,
+) {
+    let item = ItemRef::of(new);
+    let (old_inputs, new_inputs) = (old.inputs(), new.inputs());
+
+    for input in 0..old_inputs.len().max(new_inputs.len()) {
+        let (old_binding, new_binding) = (old_inputs.get(input), new_inputs.get(input));
+        if old_binding != new_binding {
+            changes.push(CfsChange::InputBindingChanged {
+                sequence: sequence.to_string(),
+                index,
+                item: item.clone(),
+                input,
+                old: old_binding.cloned(),
+                new: new_binding.cloned(),
+            });
+        }
+    }
+
+    if let (SequenceChildItem::RecurTile(old), SequenceChildItem::RecurTile(new)) = (old, new) {
+        if old.chunk != new.chunk {
+            changes.push(CfsChange::ChunkChanged {
+                sequence: sequence.to_string(),
+                index,
+                item,
+                old: old.chunk,
+                new: new.chunk,
+            });
+        }
+    }
+}
+
+/// Index pairs of a longest common subsequence of `old` and `new`, in order.
+fn longest_common_subsequence(old: &[ItemRef], new: &[ItemRef]) -> Vec<(usize, usize)> {
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut pairs = Vec::new();
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raster_core::cfs::{RecurTileItem, TileItem};
+
+    fn tile(id: &str, sources: Vec<InputBinding>) -> SequenceChildItem {
+        SequenceChildItem::Tile(TileItem {
+            id: id.to_string(),
+            sources,
+        })
+    }
+
+    fn cfs_with_main(items: Vec<SequenceChildItem>) -> ControlFlowSchema {
+        let mut cfs = ControlFlowSchema::new("test");
+        cfs.tiles = vec![TileDef::iter("a", 1, 1), TileDef::iter("b", 1, 1)];
+        let mut main = SequenceDef::new("main");
+        main.items = items;
+        cfs.sequences.push(main);
+        cfs
+    }
+
+    #[test]
+    fn identical_schemas_have_no_changes() {
+        let cfs = cfs_with_main(vec![tile("a", vec![InputBinding::external()])]);
+        let diff = CfsDiff::between(&cfs, &cfs);
+
+        assert!(diff.changes.is_empty());
+        assert_eq!(diff.compatibility, Compatibility::CoordinateCompatible);
+        assert!(diff.commitments_compatible);
+    }
+
+    #[test]
+    fn added_tiles_keep_coordinates_but_break_commitments() {
+        let old = cfs_with_main(vec![tile("a", vec![])]);
+        let mut new = old.clone();
+        new.tiles.push(TileDef::iter("c", 0, 1));
+
+        let diff = CfsDiff::between(&old, &new);
+
+        assert_eq!(
+            diff.changes,
+            vec![CfsChange::TileAdded {
+                tile: "c".to_string()
+            }]
+        );
+        assert!(!diff.is_breaking());
+        assert!(!diff.commitments_compatible);
+    }
+
+    #[test]
+    fn appended_items_are_breaking() {
+        let old = cfs_with_main(vec![tile("a", vec![])]);
+        let new = cfs_with_main(vec![tile("a", vec![]), tile("b", vec![])]);
+
+        let diff = CfsDiff::between(&old, &new);
+
+        assert_eq!(
+            diff.changes,
+            vec![CfsChange::ItemAdded {
+                sequence: "main".to_string(),
+                index: 1,
+                item: ItemRef {
+                    kind: "tile",
+                    id: "b".to_string()
+                },
+                appended: true,
+            }]
+        );
+        assert!(diff.is_breaking());
+    }
+
+    #[test]
+    fn type_changes_and_module_qualified_ids_are_reported() {
+        let mut old = cfs_with_main(vec![tile("a", vec![])]);
+        old.tiles[0] = TileDef::iter("a", 1, 1)
+            .with_types(vec!["u64".to_string()], Some("String".to_string()))
+            .with_path("text::a");
+        old.tiles[1] = TileDef::iter("b", 1, 1).with_path("text::b");
+
+        let mut new = old.clone();
+        new.tiles[0] = TileDef::iter("a", 1, 1)
+            .with_types(vec!["u32".to_string()], Some("String".to_string()))
+            .with_path("text::a");
+        new.tiles[1] = TileDef::iter("text::b", 1, 1).with_path("text::b");
+        new.tiles
+            .push(TileDef::iter("audio::b", 1, 1).with_path("audio::b"));
+
+        let diff = CfsDiff::between(&old, &new);
+
+        assert_eq!(
+            diff.changes,
+            vec![
+                CfsChange::TileTypesChanged {
+                    tile: "a".to_string(),
+                    old_inputs: vec!["u64".to_string()],
+                    new_inputs: vec!["u32".to_string()],
+                    old_output: Some("String".to_string()),
+                    new_output: Some("String".to_string()),
+                },
+                CfsChange::TileRenamed {
+                    path: "text::b".to_string(),
+                    old_id: "b".to_string(),
+                    new_id: "text::b".to_string(),
+                },
+                CfsChange::TileAdded {
+                    tile: "audio::b".to_string()
+                },
+            ]
+        );
+        assert!(diff.is_breaking());
+    }
+
+    #[test]
+    fn inserted_removed_and_reordered_items_are_breaking() {
+        let old = cfs_with_main(vec![tile("a", vec![]), tile("b", vec![])]);
+
+        let inserted = cfs_with_main(vec![
+            tile("b", vec![]),
+            tile("a", vec![]),
+            tile("b", vec![]),
+        ]);
+        let diff = CfsDiff::between(&old, &inserted);
+        assert!(matches!(
+            diff.changes.as_slice(),
+            [CfsChange::ItemAdded {
+                index: 0,
+                appended: false,
+                ..
+            }]
+        ));
+        assert!(diff.is_breaking());
+
+        let removed = cfs_with_main(vec![tile("b", vec![])]);
+        let diff = CfsDiff::between(&old, &removed);
+        assert!(matches!(
+            diff.changes.as_slice(),
+            [CfsChange::ItemRemoved { index: 0, .. }]
+        ));
+
+        let reordered = cfs_with_main(vec![tile("b", vec![]), tile("a", vec![])]);
+        let diff = CfsDiff::between(&old, &reordered);
+        assert!(matches!(
+            diff.changes.as_slice(),
+            [CfsChange::ItemReordered {
+                old_index: 0,
+                new_index: 1,
+                ..
+            }]
+        ));
+        assert!(diff.is_breaking());
+    }
+
+    #[test]
+    fn binding_arity_and_chunk_changes_are_breaking() {
+        let mut old = cfs_with_main(vec![
+            tile("a", vec![InputBinding::external()]),
+            SequenceChildItem::RecurTile(RecurTileItem {
+                id: "b".to_string(),
+                sources: vec![InputBinding::prior_item_output(0)],
+                chunk: None,
+            }),
+        ]);
+        old.tiles[0].inputs = 1;
+
+        let mut new = cfs_with_main(vec![
+            tile("a", vec![InputBinding::inline()]),
+            SequenceChildItem::RecurTile(RecurTileItem {
+                id: "b".to_string(),
+                sources: vec![InputBinding::prior_item_output(0)],
+                chunk: Some(4),
+            }),
+        ]);
+        new.tiles[0].inputs = 2;
+
+        let diff = CfsDiff::between(&old, &new);
+
+        assert_eq!(diff.changes.len(), 3);
+        assert!(matches!(
+            diff.changes[0],
+            CfsChange::TileArityChanged {
+                old_inputs: 1,
+                new_inputs: 2,
+                ..
+            }
+        ));
+        assert!(matches!(
+            diff.changes[1],
+            CfsChange::InputBindingChanged {
+                index: 0,
+                input: 0,
+                ..
+            }
+        ));
+        assert!(matches!(
+            diff.changes[2],
+            CfsChange::ChunkChanged {
+                index: 1,
+                old: None,
+                new: Some(4),
+                ..
+            }
+        ));
+        assert!(diff.changes.iter().all(CfsChange::is_breaking));
+    }
+}
@@ -0,0 +1,454 @@
+//! Graph rendering of a control flow schema as Graphviz DOT or Mermaid.
+//!
+//! Every sequence becomes a cluster of its items in execution order. Input
+//! edges follow the items' `InputBinding`s: sequence inputs and the external,
+//! inline and internal sources get their own nodes inside the cluster, prior
+//! item outputs are drawn item to item (labelled with the selector, if any),
+//! and sequence items point at the cluster of the sequence they call.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use raster_core::cfs::{
+    ControlFlowSchema, InputBinding, InputSource, SequenceChildItem, SequenceDef,
+};
+use raster_core::input::{SelectorPath, SelectorSegment};
+
+/// Measured cost of a tile, overlaid on its items.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TileTiming {
+    pub invocations: u64,
+    pub total_duration_ns: u64,
+}
+
+impl TileTiming {
+    fn label(&self) -> String {
+        let avg = self.total_duration_ns / self.invocations.max(1);
+        format!("{} avg x{}", format_duration(avg), self.invocations)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeShape {
+    Tile,
+    Recur,
+    Call,
+    Source,
+}
+
+struct Node {
+    id: String,
+    label: String,
+    shape: NodeShape,
+}
+
+struct Edge {
+    from: String,
+    to: String,
+    label: Option<String>,
+    /// Call edges into another sequence's cluster.
+    call: bool,
+}
+
+struct Cluster {
+    id: String,
+    label: String,
+    nodes: Vec<Node>,
+}
+
+/// Renders a [`ControlFlowSchema`] as a data-flow graph.
+pub struct CfsGraph<'a> {
+    cfs: &'a ControlFlowSchema,
+    tile_timings: BTreeMap<String, TileTiming>,
+}
+
+impl<'a> CfsGraph<'a> {
+    pub fn new(cfs: &'a ControlFlowSchema) -> Self {
+        Self {
+            cfs,
+            tile_timings: BTreeMap::new(),
+        }
+    }
+
+    /// Overlay per-tile timings (keyed by tile id) on tile and recur items.
+    pub fn with_tile_timings(mut self, tile_timings: BTreeMap<String, TileTiming>) -> Self {
+        self.tile_timings = tile_timings;
+        self
+    }
+
+    /// Render as a Graphviz `digraph`.
+    pub fn to_dot(&self) -> String {
+        let (clusters, edges) = self.layout();
+        let mut out = String::new();
+
+        let _ = writeln!(out, "digraph cfs {{");
+        let _ = writeln!(out, "  label={};", dot_quote(&self.cfs.project));
+        let _ = writeln!(out, "  compound=true;");
+        let _ = writeln!(out, "  rankdir=TB;");
+        let _ = writeln!(out, "  node [fontname=\"Helvetica\"];");
+        for cluster in &clusters {
+            let _ = writeln!(
+                out,
+                "  subgraph {} {{",
+                dot_quote(&format!("cluster_{}", cluster.id))
+            );
+            let _ = writeln!(out, "    label={};", dot_quote(&cluster.label));
+            // Anchor for call edges, which Graphviz can only draw node to node.
+            let _ = writeln!(
+                out,
+                "    {} [shape=point, style=invis];",
+                dot_quote(&anchor_id(&cluster.id))
+            );
+            for node in &cluster.nodes {
+                let shape = match node.shape {
+                    NodeShape::Tile => "box",
+                    NodeShape::Recur => "box3d",
+                    NodeShape::Call => "component",
+                    NodeShape::Source => "ellipse",
+                };
+                let _ = writeln!(
+                    out,
+                    "    {} [label={}, shape={}];",
+                    dot_quote(&node.id),
+                    dot_quote(&node.label),
+                    shape
+                );
+            }
+            let _ = writeln!(out, "  }}");
+        }
+        for edge in &edges {
+            let mut attrs = Vec::new();
+            if let Some(label) = &edge.label {
+                attrs.push(format!("label={}", dot_quote(label)));
+            }
+            let to = if edge.call {
+                attrs.push("style=dashed".to_string());
+                attrs.push(format!(
+                    "lhead={}",
+                    dot_quote(&format!("cluster_{}", edge.to))
+                ));
+                anchor_id(&edge.to)
+            } else {
+                edge.to.clone()
+            };
+            let (from, to) = (dot_quote(&edge.from), dot_quote(&to));
+            if attrs.is_empty() {
+                let _ = writeln!(out, "  {} -> {};", from, to);
+            } else {
+                let _ = writeln!(out, "  {} -> {} [{}];", from, to, attrs.join(", "));
+            }
+        }
+        let _ = writeln!(out, "}}");
+        out
+    }
+
+    /// Render as a Mermaid `flowchart`.
+    pub fn to_mermaid(&self) -> String {
+        let (clusters, edges) = self.layout();
+        let mut out = String::new();
+
+        let _ = writeln!(out, "flowchart TB");
+        for cluster in &clusters {
+            let _ = writeln!(
+                out,
+                "  subgraph {}[{}]",
+                mermaid_id(&cluster.id),
+                mermaid_quote(&cluster.label)
+            );
+            for node in &cluster.nodes {
+                let (id, label) = (mermaid_id(&node.id), mermaid_quote(&node.label));
+                let _ = match node.shape {
+                    NodeShape::Tile => writeln!(out, "    {}[{}]", id, label),
+                    NodeShape::Recur => writeln!(out, "    {}[[{}]]", id, label),
+                    NodeShape::Call => writeln!(out, "    {}[/{}/]", id, label),
+                    NodeShape::Source => writeln!(out, "    {}([{}])", id, label),
+                };
+            }
+            let _ = writeln!(out, "  end");
+        }
+        for edge in &edges {
+            let arrow = if edge.call { "-.->" } else { "-->" };
+            let (from, to) = (mermaid_id(&edge.from), mermaid_id(&edge.to));
+            match &edge.label {
+                Some(label) => {
+                    let _ = writeln!(out, "  {} {}|{}| {}", from, arrow, mermaid_quote(label), to);
+                }
+                None => {
+                    let _ = writeln!(out, "  {} {} {}", from, arrow, to);
+                }
+            }
+        }
+        out
+    }
+
+    fn layout(&self) -> (Vec<Cluster>, Vec<Edge>) {
+        let mut clusters = Vec::new();
+        let mut edges = Vec::new();
+        for sequence in &self.cfs.sequences {
+            clusters.push(self.layout_sequence(sequence, &mut edges));
+        }
+        (clusters, edges)
+    }
+
+    fn layout_sequence(&self, sequence: &SequenceDef, edges: &mut Vec<Edge>) -> Cluster {
+        let cluster_id = format!("seq_{}", sequence.id);
+        let mut nodes = Vec::new();
+        let mut sources: BTreeMap<String, Node> = BTreeMap::new();
+
+        for (index, item) in sequence.items.iter().enumerate() {
+            let node_id = item_id(&sequence.id, index);
+            let (shape, kind, id) = match item {
+                SequenceChildItem::Tile(item) => (NodeShape::Tile, "tile", &item.id),
+                SequenceChildItem::RecurTile(item) => (NodeShape::Recur, "recur tile", &item.id),
+                SequenceChildItem::Sequence(item) => (NodeShape::Call, "sequence", &item.id),
+                SequenceChildItem::RecurSequence(item) => {
+                    (NodeShape::Call, "recur sequence", &item.id)
+                }
+            };
+
+            let mut label = format!("[{}] {} {}", index, kind, id);
+            if let SequenceChildItem::RecurTile(recur) = item {
+                if let Some(chunk) = recur.chunk {
+                    let _ = write!(label, " (chunk {})", chunk);
+                }
+            }
+            if matches!(shape, NodeShape::Tile | NodeShape::Recur) {
+                if let Some(timing) = self.tile_timings.get(id) {
+                    let _ = write!(label, "\n{}", timing.label());
+                }
+            }
+
+            if shape == NodeShape::Call && self.cfs.sequences.iter().any(|seq| &seq.id == id) {
+                edges.push(Edge {
+                    from: node_id.clone(),
+                    to: format!("seq_{}", id),
+                    label: Some("calls".to_string()),
+                    call: true,
+                });
+            }
+
+            for (input, binding) in item.inputs().iter().enumerate() {
+                let (from, selector) = match binding {
+                    InputBinding::PriorItemOutput {
+                        intra_sequence_item_index,
+                        selector,
+                    } => (
+                        item_id(&sequence.id, *intra_sequence_item_index),
+                        Some(selector),
+                    ),
+                    InputBinding::SequenceScope { input_index } => {
+                        let source_id = format!("{}_in{}", sequence.id, input_index);
+                        sources.entry(source_id.clone()).or_insert_with(|| Node {
+                            id: source_id.clone(),
+                            label: format!("seq input {}", input_index),
+                            shape: NodeShape::Source,
+                        });
+                        (source_id, None)
+                    }
+                    InputBinding::Direct(source) => {
+                        let name = match source {
+                            InputSource::External => "external",
+                            InputSource::Inline => "inline",
+                            InputSource::Internal => "internal",
+                        };
+                        let source_id = format!("{}_{}", sequence.id, name);
+                        sources.entry(source_id.clone()).or_insert_with(|| Node {
+                            id: source_id.clone(),
+                            label: name.to_string(),
+                            shape: NodeShape::Source,
+                        });
+                        (source_id, None)
+                    }
+                };
+
+                let mut edge_label = format!("arg {}", input);
+                if let Some(selector) = selector.filter(|selector| !selector.is_empty()) {
+                    let _ = write!(edge_label, " {}", format_selector(selector));
+                }
+                edges.push(Edge {
+                    from,
+                    to: node_id.clone(),
+                    label: Some(edge_label),
+                    call: false,
+                });
+            }
+
+            nodes.push(Node {
+                id: node_id,
+                label,
+                shape,
+            });
+        }
+
+        nodes.extend(sources.into_values());
+        Cluster {
+            id: cluster_id,
+            label: format!("sequence {}", sequence.id),
+            nodes,
+        }
+    }
+}
+
+fn item_id(sequence_id: &str, index: usize) -> String {
+    format!("{}_{}", sequence_id, index)
+}
+
+fn anchor_id(cluster_id: &str) -> String {
+    format!("{}_anchor", cluster_id)
+}
+
+fn format_selector(selector: &SelectorPath) -> String {
+    selector
+        .segments
+        .iter()
+        .map(|segment| match segment {
+            SelectorSegment::Field(name) => format!(".{}", name),
+            SelectorSegment::Index(index) => format!("[{}]", index),
+            SelectorSegment::Range { start, end } => format!("[{}..{}]", start, end),
+        })
+        .collect()
+}
+
+fn format_duration(duration_ns: u64) -> String {
+    if duration_ns >= 1_000_000_000 {
+        format!("{:.3} s", duration_ns as f64 / 1_000_000_000.0)
+    } else if duration_ns >= 1_000_000 {
+        format!("{:.3} ms", duration_ns as f64 / 1_000_000.0)
+    } else if duration_ns >= 1_000 {
+        format!("{:.3} us", duration_ns as f64 / 1_000.0)
+    } else {
+        format!("{} ns", duration_ns)
+    }
+}
+
+fn dot_quote(label: &str) -> String {
+    format!(
+        "\"{}\"",
+        label
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+/// Mermaid node ids cannot be quoted, so module-qualified ids such as
+/// `text::normalize` are reduced to `[A-Za-z0-9_]`; labels keep the raw id.
+fn mermaid_id(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn mermaid_quote(label: &str) -> String {
+    format!(
+        "\"{}\"",
+        label.replace('"', "#quot;").replace('\n', "<br/>")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raster_core::cfs::{RecurTileItem, SequenceItem, TileDef, TileItem};
+
+    fn sample_cfs() -> ControlFlowSchema {
+        let mut cfs = ControlFlowSchema::new("demo");
+        cfs.tiles = vec![TileDef::iter("load", 1, 1), TileDef::iter("step", 1, 1)];
+
+        let mut inner = SequenceDef::new("inner");
+        inner.input_sources = vec![InputBinding::prior_item_output(0)];
+        inner.items = vec![SequenceChildItem::RecurTile(RecurTileItem {
+            id: "step".to_string(),
+            sources: vec![InputBinding::seq_input(0)],
+            chunk: Some(4),
+        })];
+
+        let mut main = SequenceDef::new("main");
+        main.items = vec![
+            SequenceChildItem::Tile(TileItem {
+                id: "load".to_string(),
+                sources: vec![InputBinding::external()],
+            }),
+            SequenceChildItem::Sequence(SequenceItem {
+                id: "inner".to_string(),
+                sources: vec![InputBinding::prior_item_output_selection(
+                    0,
+                    SelectorPath::new(vec![SelectorSegment::Field("items".to_string())]),
+                )],
+            }),
+        ];
+
+        cfs.sequences = vec![main, inner];
+        cfs
+    }
+
+    #[test]
+    fn dot_renders_items_sources_and_calls() {
+        let dot = CfsGraph::new(&sample_cfs()).to_dot();
+
+        assert!(dot.starts_with("digraph cfs {"));
+        assert!(dot.contains("subgraph \"cluster_seq_main\" {"));
+        assert!(dot.contains("\"main_0\" [label=\"[0] tile load\", shape=box];"));
+        assert!(dot.contains("\"inner_0\" [label=\"[0] recur tile step (chunk 4)\", shape=box3d];"));
+        assert!(dot.contains("\"main_external\" -> \"main_0\" [label=\"arg 0\"];"));
+        assert!(dot.contains("\"main_0\" -> \"main_1\" [label=\"arg 0 .items\"];"));
+        assert!(dot.contains("\"inner_in0\" -> \"inner_0\" [label=\"arg 0\"];"));
+        assert!(dot.contains(
+            "\"main_1\" -> \"seq_inner_anchor\" [label=\"calls\", style=dashed, lhead=\"cluster_seq_inner\"];"
+        ));
+    }
+
+    #[test]
+    fn mermaid_renders_subgraphs_and_timings() {
+        let timings = BTreeMap::from([(
+            "load".to_string(),
+            TileTiming {
+                invocations: 2,
+                total_duration_ns: 3_000_000,
+            },
+        )]);
+        let mermaid = CfsGraph::new(&sample_cfs())
+            .with_tile_timings(timings)
+            .to_mermaid();
+
+        assert!(mermaid.starts_with("flowchart TB\n"));
+        assert!(mermaid.contains("  subgraph seq_main[\"sequence main\"]"));
+        assert!(mermaid.contains("    main_0[\"[0] tile load<br/>1.500 ms avg x2\"]"));
+        assert!(mermaid.contains("    inner_0[[\"[0] recur tile step (chunk 4)\"]]"));
+        assert!(mermaid.contains("  main_1 -.->|\"calls\"| seq_inner"));
+    }
+
+    #[test]
+    fn module_qualified_ids_stay_valid() {
+        let mut cfs = ControlFlowSchema::new("demo");
+        cfs.tiles = vec![TileDef::iter("text::normalize", 1, 1)];
+        let mut inner = SequenceDef::new("text::clean");
+        inner.items = vec![SequenceChildItem::Tile(TileItem {
+            id: "text::normalize".to_string(),
+            sources: vec![InputBinding::seq_input(0)],
+        })];
+        let mut main = SequenceDef::new("main");
+        main.items = vec![SequenceChildItem::Sequence(SequenceItem {
+            id: "text::clean".to_string(),
+            sources: vec![InputBinding::external()],
+        })];
+        cfs.sequences = vec![main, inner];
+        let graph = CfsGraph::new(&cfs);
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("subgraph \"cluster_seq_text::clean\" {"));
+        assert!(dot.contains("\"text::clean_0\" [label=\"[0] tile text::normalize\", shape=box];"));
+        assert!(dot.contains("\"text::clean_in0\" -> \"text::clean_0\" [label=\"arg 0\"];"));
+        assert!(dot.contains("lhead=\"cluster_seq_text::clean\""));
+
+        let mermaid = graph.to_mermaid();
+        assert!(mermaid.contains("  subgraph seq_text__clean[\"sequence text::clean\"]"));
+        assert!(mermaid.contains("    text__clean_0[\"[0] tile text::normalize\"]"));
+        assert!(mermaid.contains("  main_0 -.->|\"calls\"| seq_text__clean"));
+        // Outside quoted labels, no `::` is left to break Mermaid's syntax.
+        for line in mermaid.lines() {
+            let unquoted: String = line.split('"').step_by(2).collect();
+            assert!(!unquoted.contains("::"), "unsanitized id in {:?}", line);
+        }
+    }
+}
@@ -4,19 +4,23 @@
 //! variable bindings and mapping them to `InputSource` references.
 
 use raster_core::cfs::{
-    InputBinding, RecurSequenceItem, RecurTileItem, SequenceChildItem, SequenceItem, TileItem,
+    BranchArm, BranchItem, InputBinding, RecurSequenceItem, RecurTileItem, SequenceChildItem,
+    SequenceItem, TileItem,
 };
+use raster_core::input::SelectorPath;
+use raster_core::{Error, Result};
 use std::collections::HashMap;
 
-use crate::ast::{CallArgumentKind, CallInfo, CallKind};
+use crate::ast::{BranchCondition, BranchInfo, CallArgumentKind, CallInfo, CallKind};
 use crate::sequence::Sequence;
 
 /// Resolves data flow within a sequence, producing `SequenceItem`s with
 /// correctly bound input sources.
 #[derive(Default)]
 pub struct FlowResolver {
-    /// Map of variable names to the item index that produced them.
-    bindings: HashMap<String, usize>,
+    /// Map of variable names to the item index that produced them, and the part
+    /// of that item's output they are bound to.
+    bindings: HashMap<String, (usize, SelectorPath)>,
     /// Sequence parameter names mapped to their input index.
     param_indices: HashMap<String, usize>,
 }
@@ -40,29 +44,7 @@ impl FlowResolver {
 
         let mut items = Vec::new();
 
-        // Collect call_infos that correspond to validated sequence steps.
-        // Unknown callees are already rejected and diagnosed by SequenceDiscovery::extract_sequence
-        // (in sequence.rs) — only validated calls survive into sequence.steps and reach the resolver.
-        // We filter by step membership here to stay in sync with what discovery accepted.
-        let step_callees: Vec<&str> = sequence
-            .steps
-            .iter()
-            .map(|step| match step {
-                crate::sequence::SequenceStep::Tile(tile) => tile.function.name.as_str(),
-                crate::sequence::SequenceStep::RecurTile(tile) => tile.function.name.as_str(),
-                crate::sequence::SequenceStep::RecurSequence(name) => name.as_str(),
-                crate::sequence::SequenceStep::Sequence(name) => name.as_str(),
-            })
-            .collect();
-
-        let relevant_calls: Vec<&CallInfo> = sequence
-            .function
-            .call_infos
-            .iter()
-            .filter(|call| step_callees.contains(&call.callee.as_str()))
-            .collect();
-
-        for (item_index, call) in relevant_calls.iter().enumerate() {
+        for (item_index, call) in Self::item_calls(sequence).into_iter().enumerate() {
             let input_sources = self.resolve_call_inputs(call);
 
             // Call kind directly determines item type — no name-matching needed.
@@ -92,13 +74,138 @@ impl FlowResolver {
 
             // If this call has a result binding, record it
             if let Some(ref binding_name) = call.result_binding {
-                self.bindings.insert(binding_name.clone(), item_index);
+                self.bindings
+                    .insert(binding_name.clone(), (item_index, SelectorPath::default()));
+            }
+            // Destructured names bind selections of the same output
+            for field in &call.field_bindings {
+                self.bindings
+                    .insert(field.name.clone(), (item_index, field.selector.clone()));
             }
         }
 
         items
     }
 
+    /// Resolve the `if` / `match` branches of a sequence into `BranchItem`s over the
+    /// items produced by [`Self::resolve`].
+    ///
+    /// A branch must be selected by the output of the tile call right before its
+    /// arms. The `#[sequence]` macro skips arm items at runtime whatever the CFS
+    /// says, so a branch that does not fit the CFS model is an error rather than
+    /// something to leave out.
+    pub fn resolve_branches(&self, sequence: &Sequence<'_>) -> Result<Vec<BranchItem>> {
+        let item_indices = Self::item_indices(sequence);
+        let call_infos = &sequence.function.call_infos;
+        let sequence_name = &sequence.function.name;
+        // Item index of the first call at or after each call index.
+        let items_before = |call_index: usize| {
+            item_indices[..call_index]
+                .iter()
+                .filter(|item_index| item_index.is_some())
+                .count()
+        };
+
+        let mut branches = Vec::with_capacity(sequence.function.branches.len());
+        for BranchInfo { condition, arms } in &sequence.function.branches {
+            let Some(first_arm) = arms.first() else {
+                continue;
+            };
+            let start = items_before(first_arm.calls.start);
+
+            let condition_call = match condition {
+                BranchCondition::Call(call_index) => Some(*call_index),
+                BranchCondition::Binding(name) => call_infos[..first_arm.calls.start]
+                    .iter()
+                    .rposition(|call| call.result_binding.as_deref() == Some(name.as_str())),
+                BranchCondition::Other(expr) => {
+                    return Err(Error::InvalidSequence(format!(
+                        "branch in sequence `{}` is conditioned on `{}`; a sequence may only \
+                         branch on the output of a `call!`",
+                        sequence_name, expr
+                    )));
+                }
+            };
+            let condition_item = condition_call.and_then(|call_index| {
+                item_indices[call_index]
+                    .filter(|&item_index| item_index + 1 == start)
+                    .filter(|_| call_infos[call_index].call_kind == CallKind::Tile)
+            });
+            let Some(condition_item) = condition_item else {
+                return Err(Error::InvalidSequence(format!(
+                    "branch condition in sequence `{}` is not the tile call made right before \
+                     the branch; branch on `call!(...)` directly, or on a binding from the \
+                     immediately preceding `call!`",
+                    sequence_name
+                )));
+            };
+
+            let mut branch_arms = Vec::with_capacity(arms.len());
+            for arm in arms {
+                let Some(pattern) = arm.pattern.clone() else {
+                    return Err(Error::InvalidSequence(format!(
+                        "branch arm `{}` in sequence `{}` cannot be decided from the condition \
+                         output; use `true` / `false`, enum variant patterns or `_`, without \
+                         guards",
+                        arm.pattern_source, sequence_name
+                    )));
+                };
+                let len = items_before(arm.calls.end) - items_before(arm.calls.start);
+                branch_arms.push(BranchArm { pattern, len });
+            }
+
+            branches.push(BranchItem {
+                condition: condition_item,
+                start,
+                arms: branch_arms,
+                variants: Vec::new(),
+            });
+        }
+
+        Ok(branches)
+    }
+
+    /// The calls that become the sequence's items, in item order.
+    pub(crate) fn item_calls<'a>(sequence: &Sequence<'a>) -> Vec<&'a CallInfo> {
+        Self::item_indices(sequence)
+            .into_iter()
+            .zip(sequence.function.call_infos.iter())
+            .filter(|(item_index, _)| item_index.is_some())
+            .map(|(_, call)| call)
+            .collect()
+    }
+
+    /// Maps each call in the sequence body to its item index, or `None` when the call is
+    /// not a validated sequence step.
+    fn item_indices(sequence: &Sequence<'_>) -> Vec<Option<usize>> {
+        // Unknown callees are already rejected and diagnosed by SequenceDiscovery::extract_sequence
+        // (in sequence.rs) — only validated calls survive into sequence.steps and reach the resolver.
+        // We filter by step membership here to stay in sync with what discovery accepted.
+        let step_callees: Vec<&str> = sequence
+            .steps
+            .iter()
+            .map(|step| match step {
+                crate::sequence::SequenceStep::Tile(tile) => tile.id(),
+                crate::sequence::SequenceStep::RecurTile(tile) => tile.id(),
+                crate::sequence::SequenceStep::RecurSequence(name) => name.as_str(),
+                crate::sequence::SequenceStep::Sequence(name) => name.as_str(),
+            })
+            .collect();
+
+        let mut next_item_index = 0;
+        sequence
+            .function
+            .call_infos
+            .iter()
+            .map(|call| {
+                step_callees.contains(&call.callee.as_str()).then(|| {
+                    next_item_index += 1;
+                    next_item_index - 1
+                })
+            })
+            .collect()
+    }
+
     /// Resolve input sources for a function call's arguments.
     fn resolve_call_inputs(&self, call: &CallInfo) -> Vec<InputBinding> {
         call.arguments
@@ -118,8 +225,8 @@ impl FlowResolver {
         }
 
         // Check if it's a bound variable from a previous item
-        if let Some(&item_index) = self.bindings.get(arg) {
-            return InputBinding::prior_item_output(item_index);
+        if let Some((item_index, selector)) = self.bindings.get(arg) {
+            return InputBinding::prior_item_output_selection(*item_index, selector.clone());
         }
 
         match kind {
@@ -133,13 +240,16 @@ impl FlowResolver {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::FieldBinding;
     use crate::ast::FunctionAstItem;
     use crate::ast::MacroAstItem;
     use crate::ast::ProjectAst;
+    use crate::ast::SourceLocation;
     use crate::sequence::SequenceStep;
     use crate::tile::{Tile, TileDiscovery};
     use crate::Project;
-    use raster_core::cfs::InputSource;
+    use raster_core::cfs::{BranchPattern, InputSource};
+    use raster_core::input::SelectorSegment;
     use std::collections::HashMap;
     use std::path::PathBuf;
 
@@ -150,6 +260,8 @@ mod tests {
                 name: "test".to_string(),
                 root_path: PathBuf::from("/test"),
                 functions: vec![],
+                type_aliases: Default::default(),
+                enums: Default::default(),
             },
             root_dir: PathBuf::from("/test"),
             output_dir: PathBuf::from("/test/target/raster"),
@@ -160,8 +272,14 @@ mod tests {
     fn make_tile_function(name: &str, input_names: Vec<&str>, has_output: bool) -> FunctionAstItem {
         FunctionAstItem {
             name: name.to_string(),
+            id: name.to_string(),
             path: PathBuf::from("test.rs"),
+            location: SourceLocation::default(),
+            crate_name: None,
+            crate_dir: None,
+            module_path: vec![],
             call_infos: vec![],
+            branches: vec![],
             macros: vec![MacroAstItem {
                 name: "tile".to_string(),
                 args: HashMap::new(),
@@ -174,6 +292,7 @@ mod tests {
                 None
             },
             signature: format!("fn {}()", name),
+            errors: Vec::new(),
         }
     }
 
@@ -184,8 +303,14 @@ mod tests {
     ) -> FunctionAstItem {
         FunctionAstItem {
             name: name.to_string(),
+            id: name.to_string(),
             path: PathBuf::from("test.rs"),
+            location: SourceLocation::default(),
+            crate_name: None,
+            crate_dir: None,
+            module_path: vec![],
             call_infos,
+            branches: vec![],
             macros: vec![MacroAstItem {
                 name: "sequence".to_string(),
                 args: HashMap::new(),
@@ -194,6 +319,7 @@ mod tests {
             inputs: input_names.iter().map(|_| "String".to_string()).collect(),
             output: Some("String".to_string()),
             signature: format!("fn {}()", name),
+            errors: Vec::new(),
         }
     }
 
@@ -235,18 +361,22 @@ mod tests {
                 CallInfo {
                     callee: "greet".to_string(),
                     result_binding: Some("greeting".to_string()),
+                    field_bindings: vec![],
                     arguments: vec!["name".to_string()],
                     argument_kinds: vec![CallArgumentKind::Identifier],
                     call_kind: CallKind::Tile,
                     chunk: None,
+                    location: SourceLocation::default(),
                 },
                 CallInfo {
                     callee: "exclaim".to_string(),
                     result_binding: None,
+                    field_bindings: vec![],
                     arguments: vec!["greeting".to_string()],
                     argument_kinds: vec![CallArgumentKind::Identifier],
                     call_kind: CallKind::Tile,
                     chunk: None,
+                    location: SourceLocation::default(),
                 },
             ],
         );
@@ -286,8 +416,10 @@ mod tests {
                 match &tile_item.sources[0] {
                     InputBinding::PriorItemOutput {
                         intra_sequence_item_index,
+                        selector,
                     } => {
                         assert_eq!(*intra_sequence_item_index, 0);
+                        assert!(selector.is_empty());
                     }
                     _ => panic!("Expected PriorItemOutput"),
                 }
@@ -296,6 +428,99 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_resolve_destructured_bindings_as_output_selections() {
+        let project = make_mock_project();
+        let split_func = make_tile_function("split", vec!["input"], true);
+        let join_func = make_tile_function("join", vec!["tail", "head"], true);
+        let tile = |function| Tile {
+            function,
+            tile_type: "iter".to_string(),
+            estimated_cycles: None,
+            max_memory: None,
+            description: None,
+        };
+        let tile_discovery = TileDiscovery {
+            project: &project,
+            tiles: vec![tile(&split_func), tile(&join_func)],
+        };
+
+        // let (head, Rest { tail, .. }) = call!(split, name); call!(join, tail, head)
+        let seq_func = make_sequence_function(
+            "main",
+            vec!["name"],
+            vec![
+                CallInfo {
+                    callee: "split".to_string(),
+                    result_binding: None,
+                    field_bindings: vec![
+                        FieldBinding {
+                            name: "head".to_string(),
+                            selector: SelectorPath::new(vec![SelectorSegment::Index(0)]),
+                        },
+                        FieldBinding {
+                            name: "tail".to_string(),
+                            selector: SelectorPath::new(vec![
+                                SelectorSegment::Index(1),
+                                SelectorSegment::Field("tail".to_string()),
+                            ]),
+                        },
+                    ],
+                    arguments: vec!["name".to_string()],
+                    argument_kinds: vec![CallArgumentKind::Identifier],
+                    call_kind: CallKind::Tile,
+                    chunk: None,
+                    location: SourceLocation::default(),
+                },
+                CallInfo {
+                    callee: "join".to_string(),
+                    result_binding: None,
+                    field_bindings: vec![],
+                    arguments: vec!["tail".to_string(), "head".to_string()],
+                    argument_kinds: vec![CallArgumentKind::Identifier; 2],
+                    call_kind: CallKind::Tile,
+                    chunk: None,
+                    location: SourceLocation::default(),
+                },
+            ],
+        );
+        let sequence = Sequence {
+            function: &seq_func,
+            steps: vec![
+                SequenceStep::Tile(&tile_discovery.tiles[0]),
+                SequenceStep::Tile(&tile_discovery.tiles[1]),
+            ],
+            description: None,
+        };
+
+        let items = FlowResolver::new().resolve(&sequence);
+
+        let SequenceChildItem::Tile(join) = &items[1] else {
+            panic!("Expected Tile item");
+        };
+        let selections: Vec<_> = join
+            .sources
+            .iter()
+            .map(|source| match source {
+                InputBinding::PriorItemOutput {
+                    intra_sequence_item_index: 0,
+                    selector,
+                } => selector.segments.clone(),
+                other => panic!("Expected PriorItemOutput of item 0, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            selections,
+            vec![
+                vec![
+                    SelectorSegment::Index(1),
+                    SelectorSegment::Field("tail".to_string())
+                ],
+                vec![SelectorSegment::Index(0)],
+            ]
+        );
+    }
+
     #[test]
     fn test_resolve_inline_argument_as_inline_source() {
         let project = make_mock_project();
@@ -318,10 +543,12 @@ mod tests {
             vec![CallInfo {
                 callee: "greet".to_string(),
                 result_binding: None,
+                field_bindings: vec![],
                 arguments: vec!["\"Raster\".to_string()".to_string()],
                 argument_kinds: vec![CallArgumentKind::Other],
                 call_kind: CallKind::Tile,
                 chunk: None,
+                location: SourceLocation::default(),
             }],
         );
 
@@ -342,4 +569,143 @@ mod tests {
             _ => panic!("Expected Tile item"),
         }
     }
+
+    #[test]
+    fn test_resolve_branches_maps_calls_to_items() {
+        let project = make_mock_project();
+        let funcs: Vec<FunctionAstItem> = ["is_valid", "accept", "reject"]
+            .iter()
+            .map(|name| make_tile_function(name, vec!["x"], true))
+            .collect();
+        let tile_discovery = TileDiscovery {
+            project: &project,
+            tiles: funcs
+                .iter()
+                .map(|function| Tile {
+                    function,
+                    tile_type: "iter".to_string(),
+                    estimated_cycles: None,
+                    max_memory: None,
+                    description: None,
+                })
+                .collect(),
+        };
+
+        let call = |callee: &str, result_binding: Option<&str>| CallInfo {
+            callee: callee.to_string(),
+            result_binding: result_binding.map(str::to_string),
+            field_bindings: vec![],
+            arguments: vec!["x".to_string()],
+            argument_kinds: vec![CallArgumentKind::Identifier],
+            call_kind: CallKind::Tile,
+            chunk: None,
+            location: SourceLocation::default(),
+        };
+        // let ok = call!(is_valid, x); if ok { call!(accept, x) } else { unknown; call!(reject, x) }
+        let mut seq_func = make_sequence_function(
+            "main",
+            vec!["x"],
+            vec![
+                call("is_valid", Some("ok")),
+                call("accept", None),
+                call("unknown", None),
+                call("reject", None),
+            ],
+        );
+        seq_func.branches = vec![BranchInfo {
+            condition: BranchCondition::Binding("ok".to_string()),
+            arms: vec![
+                crate::ast::BranchArmInfo {
+                    pattern: Some(BranchPattern::Bool(true)),
+                    pattern_source: "true".to_string(),
+                    calls: 1..2,
+                },
+                crate::ast::BranchArmInfo {
+                    pattern: Some(BranchPattern::Bool(false)),
+                    pattern_source: "false".to_string(),
+                    calls: 2..4,
+                },
+            ],
+        }];
+
+        let sequence = Sequence {
+            function: &seq_func,
+            steps: tile_discovery
+                .tiles
+                .iter()
+                .map(SequenceStep::Tile)
+                .collect(),
+            description: None,
+        };
+
+        let resolver = FlowResolver::new();
+        assert_eq!(
+            resolver
+                .resolve_branches(&sequence)
+                .expect("supported branches"),
+            vec![BranchItem {
+                condition: 0,
+                start: 1,
+                arms: vec![
+                    BranchArm {
+                        pattern: BranchPattern::Bool(true),
+                        len: 1
+                    },
+                    BranchArm {
+                        pattern: BranchPattern::Bool(false),
+                        len: 1
+                    },
+                ],
+                variants: Vec::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_resolve_branches_rejects_non_call_conditions() {
+        let project = make_mock_project();
+        let accept_func = make_tile_function("accept", vec!["x"], true);
+        let tile_discovery = TileDiscovery {
+            project: &project,
+            tiles: vec![Tile {
+                function: &accept_func,
+                tile_type: "iter".to_string(),
+                estimated_cycles: None,
+                max_memory: None,
+                description: None,
+            }],
+        };
+        let mut seq_func = make_sequence_function(
+            "main",
+            vec!["x"],
+            vec![CallInfo {
+                callee: "accept".to_string(),
+                result_binding: None,
+                field_bindings: vec![],
+                arguments: vec!["x".to_string()],
+                argument_kinds: vec![CallArgumentKind::Identifier],
+                call_kind: CallKind::Tile,
+                chunk: None,
+                location: SourceLocation::default(),
+            }],
+        );
+        seq_func.branches = vec![BranchInfo {
+            condition: BranchCondition::Binding("x".to_string()),
+            arms: vec![crate::ast::BranchArmInfo {
+                pattern: Some(BranchPattern::Bool(true)),
+                pattern_source: "true".to_string(),
+                calls: 0..1,
+            }],
+        }];
+        let sequence = Sequence {
+            function: &seq_func,
+            steps: vec![SequenceStep::Tile(&tile_discovery.tiles[0])],
+            description: None,
+        };
+
+        assert!(matches!(
+            FlowResolver::new().resolve_branches(&sequence),
+            Err(Error::InvalidSequence(_))
+        ));
+    }
 }
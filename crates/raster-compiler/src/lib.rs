@@ -4,20 +4,40 @@
 //! - Compiling tiles into standalone binaries
 //! - Generating sequence schemas
 //! - Managing build artifacts
-//! - Source-based tile discovery
+//! - Source-based tile discovery, across the local crates of a workspace
 //! - Control flow schema (CFS) generation
+//! - CFS compatibility diffs
+//! - CFS graph export (DOT and Mermaid)
+//! - Type checking of sequence data flow
+//! - Running sequences tile by tile on a backend, following the CFS
 
 pub mod ast;
 pub mod backend;
 pub mod builder;
 pub mod cfs_builder;
+pub mod cfs_diff;
+pub mod cfs_graph;
 pub mod flow_resolver;
 pub mod project;
+pub mod schema_gen;
 pub mod sequence;
+pub mod sequence_runner;
 pub mod tile;
+pub mod type_check;
+pub mod value_store;
+pub mod workspace;
 
 pub use ast::ProjectAst;
-pub use builder::{BuildOutput, Builder, SequenceRunner, TileArtifact, TileManifest, TileRunner};
+pub use builder::{BuildOutput, Builder, CompiledTiles, TileArtifact, TileManifest, TileRunner};
 pub use cfs_builder::CfsBuilder;
+pub use cfs_diff::{CfsChange, CfsDiff, Compatibility};
+pub use cfs_graph::{CfsGraph, TileTiming};
 pub use flow_resolver::FlowResolver;
 pub use project::Project;
+pub use schema_gen::SchemaGenerator;
+pub use sequence_runner::{
+    BindingResolver, RecordedInputs, SequenceRun, SequenceRunner, TileCycles, TileExecutor,
+};
+pub use type_check::TypeMismatch;
+pub use value_store::{StoredValue, ValueStore};
+pub use workspace::{CargoPackage, CargoWorkspace};
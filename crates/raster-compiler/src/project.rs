@@ -1,17 +1,15 @@
-use std::path::Path;
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
 
 use crate::ast::ProjectAst;
-use cargo_toml::Manifest;
-use raster_core::{Error, Result};
+use crate::workspace::{CargoPackage, CargoWorkspace};
+use raster_core::Result;
 
 #[derive(Debug, Clone)]
 pub struct Project {
     pub name: String,
     pub ast: ProjectAst,
 
-    /// Path to the project root.
+    /// Path to the project crate (the directory of its `Cargo.toml`).
     /// TODO: Change to &Path
     pub root_dir: PathBuf,
     pub output_dir: PathBuf,
@@ -19,52 +17,48 @@ pub struct Project {
 }
 
 impl Project {
+    /// Load the project crate in `root_dir`. See [`Project::for_package`].
     pub fn new(root_dir: PathBuf) -> Result<Self> {
-        let name = Self::project_name(&root_dir);
-        let target_dir =
-            Self::find_target_path(&root_dir).unwrap_or_else(|| root_dir.join("target"));
-
-        let ast = ProjectAst::new(&root_dir)?;
+        Self::for_package(root_dir, None)
+    }
 
-        let output_dir = root_dir.join("target").join("raster");
+    /// Load a project from the Cargo workspace containing `dir`.
+    ///
+    /// The project crate is the workspace member named `package`, or, when
+    /// none is given, the member whose manifest is in `dir` (or the only
+    /// member). Tiles and sequences are discovered in that crate and in its
+    /// local dependencies that use `raster`. Artifacts are written to
+    /// `dir/target/raster`, or to `dir/target/raster/<package>` when the
+    /// project crate is not the one in `dir`.
+    pub fn for_package(dir: PathBuf, package: Option<&str>) -> Result<Self> {
+        let workspace = CargoWorkspace::load(&dir)?;
+        let project = workspace.select_package(&dir, package)?;
+        let dependencies = workspace.tile_dependencies(project);
+
+        let ast = ProjectAst::discover(project.name.clone(), &project.manifest_dir, &dependencies)?;
 
         Ok(Self {
-            name,
+            name: project.name.clone(),
             ast,
-            root_dir,
-            output_dir,
-            target_dir,
+            root_dir: project.manifest_dir.clone(),
+            output_dir: Self::output_dir(&dir, project),
+            target_dir: workspace.target_dir,
         })
     }
 
-    fn project_name(project_root: &Path) -> String {
-        let manifest =
-            Manifest::from_path(project_root.join("Cargo.toml")).expect("No manifest found");
-
-        manifest
-            .package
-            .map(|p| p.name)
-            .expect("No [package] found")
-    }
-
-    fn find_target_path(project_path: &std::path::Path) -> Option<PathBuf> {
-        // Run cargo metadata to get the target directory
-        let output = Command::new("cargo")
-            .current_dir(project_path)
-            .args(["metadata", "--format-version", "1", "--no-deps"])
-            .output()
-            .ok()?;
-
-        if !output.status.success() {
-            return None;
+    /// Artifact directory for `package` selected from `dir`. Packages
+    /// selected from a workspace root each get their own directory.
+    fn output_dir(dir: &Path, package: &CargoPackage) -> PathBuf {
+        let output_dir = dir.join("target").join("raster");
+        let same_dir = |a: &Path, b: &Path| match (a.canonicalize(), b.canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => a == b,
+        };
+        if same_dir(dir, &package.manifest_dir) {
+            output_dir
+        } else {
+            output_dir.join(&package.name)
         }
-
-        let stdout = String::from_utf8(output.stdout).ok()?;
-        let meta: serde_json::Value = serde_json::from_str(&stdout).ok()?;
-
-        meta.get("target_directory")
-            .and_then(|v| v.as_str())
-            .map(PathBuf::from)
     }
 }
 
@@ -0,0 +1,542 @@
+//! Sequence schema generation.
+//!
+//! Derives a [`SequenceSchema`] per sequence from a built control flow schema,
+//! so consumers get the tile ordering and control-flow shape of each sequence
+//! without walking CFS items, branch spans and recur sites themselves.
+
+use core::ops::Range;
+
+use raster_core::cfs::{
+    BranchItem, BranchPattern, ControlFlowSchema, InputBinding, SequenceChildItem, SequenceDef,
+};
+use raster_core::input::SelectorSegment;
+use raster_core::schema::{Branch, ControlFlow, SequenceSchema};
+use raster_core::tile::TileId;
+use raster_core::{Error, Result};
+
+/// Generates sequence schemas from a control flow schema.
+pub struct SchemaGenerator<'a> {
+    cfs: &'a ControlFlowSchema,
+}
+
+impl<'a> SchemaGenerator<'a> {
+    /// Create a generator over the given CFS.
+    pub fn new(cfs: &'a ControlFlowSchema) -> Self {
+        Self { cfs }
+    }
+
+    /// Generate one schema per sequence, in CFS order.
+    ///
+    /// Fails when a sequence's branches do not match its items.
+    pub fn generate(&self) -> Result<Vec<SequenceSchema>> {
+        self.cfs
+            .sequences
+            .iter()
+            .map(|sequence| self.generate_sequence(sequence))
+            .collect()
+    }
+
+    /// Generate the schema of a single sequence.
+    ///
+    /// Runs of plain items become `Linear` nodes, each branch a `Conditional`
+    /// node whose arms hold the nodes of their items, and each recur site a
+    /// `Loop` node.
+    pub fn generate_sequence(&self, sequence: &SequenceDef) -> Result<SequenceSchema> {
+        let mut tiles = Vec::new();
+        self.collect_tiles(sequence, &mut vec![sequence.id.as_str()], &mut tiles);
+
+        let mut steps = StepBuilder {
+            cfs: self.cfs,
+            sequence,
+            next_branch: 0,
+        };
+
+        Ok(SequenceSchema {
+            name: sequence.id.clone(),
+            version: self.cfs.version.clone(),
+            tiles,
+            control_flow: steps.build(0..sequence.items.len())?,
+        })
+    }
+
+    /// Tiles of `sequence` in item order, descending into nested sequence calls.
+    fn collect_tiles<'s>(
+        &'s self,
+        sequence: &'s SequenceDef,
+        visited: &mut Vec<&'s str>,
+        tiles: &mut Vec<TileId>,
+    ) {
+        for item in &sequence.items {
+            let nested_id = match item {
+                SequenceChildItem::Sequence(nested) => &nested.id,
+                SequenceChildItem::RecurSequence(nested) => &nested.id,
+                SequenceChildItem::Tile(_) | SequenceChildItem::RecurTile(_) => {
+                    push_unique(tiles, item_id(item));
+                    continue;
+                }
+            };
+            let Some(nested) = self.cfs.sequences.iter().find(|def| def.id == *nested_id) else {
+                continue;
+            };
+            if !visited.contains(&nested.id.as_str()) {
+                visited.push(&nested.id);
+                self.collect_tiles(nested, visited, tiles);
+            }
+        }
+    }
+}
+
+/// Builds the control flow nodes of one sequence. Branches are stored
+/// outermost first in source order, which is the order a walk over the items
+/// meets them in, so `next_branch` only ever moves forward.
+struct StepBuilder<'a> {
+    cfs: &'a ControlFlowSchema,
+    sequence: &'a SequenceDef,
+    next_branch: usize,
+}
+
+impl StepBuilder<'_> {
+    fn build(&mut self, range: Range<usize>) -> Result<Vec<ControlFlow>> {
+        let sequence = self.sequence;
+        let mut steps = Vec::new();
+        let mut linear = Vec::new();
+        let mut index = range.start;
+        loop {
+            if let Some(branch) = sequence
+                .branches
+                .get(self.next_branch)
+                .filter(|branch| branch.start == index && branch.end() <= range.end)
+            {
+                self.next_branch += 1;
+                flush_linear(&mut steps, &mut linear);
+                steps.push(self.conditional(branch)?);
+                index = branch.end();
+                continue;
+            }
+            let Some(item) = sequence.items.get(index).filter(|_| index < range.end) else {
+                break;
+            };
+            if is_recur_site(item) {
+                flush_linear(&mut steps, &mut linear);
+                steps.push(ControlFlow::Loop {
+                    body: vec![ControlFlow::Linear {
+                        steps: vec![item_id(item)],
+                    }],
+                    max_iterations: self.max_iterations(item),
+                });
+            } else {
+                linear.push(item_id(item));
+            }
+            index += 1;
+        }
+        flush_linear(&mut steps, &mut linear);
+        Ok(steps)
+    }
+
+    fn conditional(&mut self, branch: &BranchItem) -> Result<ControlFlow> {
+        let condition = self
+            .sequence
+            .items
+            .get(branch.condition)
+            .map(item_id)
+            .ok_or_else(|| {
+                Error::InvalidSequence(format!(
+                    "Branch at item {} of sequence '{}' has no condition item {}",
+                    branch.start, self.sequence.id, branch.condition
+                ))
+            })?;
+
+        let mut branches = Vec::new();
+        for (arm_index, arm) in branch.arms.iter().enumerate() {
+            let Some(arm_range) = branch.arm_range(arm_index) else {
+                continue;
+            };
+            let pattern = match &arm.pattern {
+                BranchPattern::Bool(value) => value.to_string(),
+                BranchPattern::Variant(name) => name.clone(),
+                BranchPattern::Wildcard => "_".to_string(),
+            };
+            branches.push(Branch {
+                condition: format!("matches!({}, {})", condition.0, pattern),
+                steps: self.build(arm_range)?,
+            });
+        }
+
+        Ok(ControlFlow::Conditional {
+            condition,
+            branches,
+        })
+    }
+
+    /// Iteration bound of a recur site, known when the length of its iterated
+    /// input is: a range selection, or a fixed-size array output or sequence
+    /// parameter. A `chunk = N` site iterates once per group of N elements.
+    fn max_iterations(&self, item: &SequenceChildItem) -> Option<u64> {
+        let len = match item.inputs().first()? {
+            InputBinding::PriorItemOutput {
+                intra_sequence_item_index,
+                selector,
+            } => match selector.segments.last() {
+                Some(SelectorSegment::Range { start, end }) => end.saturating_sub(*start),
+                Some(_) => return None,
+                None => self
+                    .output_type(*intra_sequence_item_index)
+                    .and_then(fixed_array_len)?,
+            },
+            InputBinding::SequenceScope { input_index } => self
+                .sequence
+                .input_types
+                .get(*input_index)
+                .and_then(|ty| fixed_array_len(ty))?,
+            InputBinding::Direct(_) => return None,
+        };
+
+        match item {
+            SequenceChildItem::RecurTile(recur) => match recur.chunk {
+                Some(chunk) if chunk > 0 => Some(len.div_ceil(chunk)),
+                _ => Some(len),
+            },
+            _ => Some(len),
+        }
+    }
+
+    /// Declared output type of the plain tile or sequence call at `index`.
+    fn output_type(&self, index: usize) -> Option<&str> {
+        match self.sequence.items.get(index)? {
+            SequenceChildItem::Tile(tile) => self
+                .cfs
+                .tiles
+                .iter()
+                .find(|def| def.id == tile.id)?
+                .output_type
+                .as_deref(),
+            SequenceChildItem::Sequence(nested) => self
+                .cfs
+                .sequences
+                .iter()
+                .find(|def| def.id == nested.id)?
+                .output_type
+                .as_deref(),
+            _ => None,
+        }
+    }
+}
+
+fn flush_linear(steps: &mut Vec<ControlFlow>, linear: &mut Vec<TileId>) {
+    if !linear.is_empty() {
+        steps.push(ControlFlow::Linear {
+            steps: std::mem::take(linear),
+        });
+    }
+}
+
+/// Length `N` of a CFS type string of the form `[T;N]`.
+fn fixed_array_len(ty: &str) -> Option<u64> {
+    let (_, len) = ty.strip_prefix('[')?.strip_suffix(']')?.rsplit_once(';')?;
+    len.trim().parse().ok()
+}
+
+fn push_unique(tiles: &mut Vec<TileId>, tile: TileId) {
+    if !tiles.contains(&tile) {
+        tiles.push(tile);
+    }
+}
+
+fn item_id(item: &SequenceChildItem) -> TileId {
+    match item {
+        SequenceChildItem::Sequence(item) => TileId::new(&item.id),
+        SequenceChildItem::Tile(item) => TileId::new(&item.id),
+        SequenceChildItem::RecurTile(item) => TileId::new(&item.id),
+        SequenceChildItem::RecurSequence(item) => TileId::new(&item.id),
+    }
+}
+
+fn is_recur_site(item: &SequenceChildItem) -> bool {
+    matches!(
+        item,
+        SequenceChildItem::RecurTile(_) | SequenceChildItem::RecurSequence(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raster_core::cfs::{
+        BranchArm, RecurTileItem, SequenceItem, TileDef, TileItem, CFS_VERSION,
+    };
+    use raster_core::input::SelectorPath;
+
+    fn tile(id: &str) -> SequenceChildItem {
+        SequenceChildItem::Tile(TileItem {
+            id: id.to_string(),
+            sources: vec![],
+        })
+    }
+
+    fn recur_tile(id: &str, source: InputBinding, chunk: Option<u64>) -> SequenceChildItem {
+        SequenceChildItem::RecurTile(RecurTileItem {
+            id: id.to_string(),
+            sources: vec![source],
+            chunk,
+        })
+    }
+
+    fn schema_of(sequence: SequenceDef) -> SequenceSchema {
+        let mut cfs = ControlFlowSchema::new("test");
+        cfs.sequences.push(sequence);
+        SchemaGenerator::new(&cfs).generate().unwrap().remove(0)
+    }
+
+    fn ids(ids: &[&str]) -> Vec<TileId> {
+        ids.iter().copied().map(TileId::from).collect()
+    }
+
+    fn linear_steps(node: &ControlFlow) -> &[TileId] {
+        let ControlFlow::Linear { steps } = node else {
+            panic!("expected linear control flow, found {:?}", node);
+        };
+        steps
+    }
+
+    fn loop_node(node: &ControlFlow) -> (&[TileId], Option<u64>) {
+        let ControlFlow::Loop {
+            body,
+            max_iterations,
+        } = node
+        else {
+            panic!("expected loop control flow, found {:?}", node);
+        };
+        assert_eq!(body.len(), 1);
+        (linear_steps(&body[0]), *max_iterations)
+    }
+
+    #[test]
+    fn plain_sequence_is_linear() {
+        let mut sequence = SequenceDef::new("main");
+        sequence.items = vec![tile("greet"), tile("exclaim"), tile("greet")];
+
+        let schema = schema_of(sequence);
+
+        assert_eq!(schema.name, "main");
+        assert_eq!(schema.version, CFS_VERSION);
+        assert_eq!(schema.tiles, ids(&["greet", "exclaim"]));
+        assert_eq!(schema.control_flow.len(), 1);
+        assert_eq!(
+            linear_steps(&schema.control_flow[0]),
+            ids(&["greet", "exclaim", "greet"])
+        );
+    }
+
+    #[test]
+    fn recur_site_is_loop_bounded_by_range_and_chunk() {
+        let mut sequence = SequenceDef::new("main");
+        sequence.items = vec![
+            tile("load"),
+            recur_tile(
+                "fold",
+                InputBinding::prior_item_output_selection(
+                    0,
+                    SelectorPath::new(vec![SelectorSegment::Range { start: 2, end: 12 }]),
+                ),
+                Some(4),
+            ),
+            tile("report"),
+        ];
+
+        let control_flow = schema_of(sequence).control_flow;
+
+        assert_eq!(control_flow.len(), 3);
+        assert_eq!(linear_steps(&control_flow[0]), ids(&["load"]));
+        assert_eq!(loop_node(&control_flow[1]), (&ids(&["fold"])[..], Some(3)));
+        assert_eq!(linear_steps(&control_flow[2]), ids(&["report"]));
+    }
+
+    #[test]
+    fn loop_bound_comes_from_fixed_size_arrays() {
+        let mut cfs = ControlFlowSchema::new("test");
+        cfs.tiles = vec![
+            TileDef::iter("load", 0, 1).with_types(vec![], Some("[u64;6]".to_string())),
+            TileDef::iter("fold", 1, 1),
+        ];
+        let mut sequence = SequenceDef::new("main");
+        sequence.input_types = vec!["[String;5]".to_string()];
+        sequence.items = vec![
+            tile("load"),
+            recur_tile(
+                "fold",
+                InputBinding::prior_item_output_selection(0, SelectorPath::default()),
+                None,
+            ),
+            recur_tile(
+                "fold",
+                InputBinding::SequenceScope { input_index: 0 },
+                Some(2),
+            ),
+        ];
+        cfs.sequences.push(sequence);
+
+        let control_flow = SchemaGenerator::new(&cfs).generate().unwrap().remove(0).control_flow;
+
+        assert_eq!(loop_node(&control_flow[1]).1, Some(6));
+        assert_eq!(loop_node(&control_flow[2]).1, Some(3));
+    }
+
+    #[test]
+    fn branches_keep_condition_surrounding_items_and_nested_loops() {
+        let mut sequence = SequenceDef::new("main");
+        sequence.items = vec![
+            tile("load"),
+            tile("is_even"),
+            tile("halve"),
+            recur_tile("fold", InputBinding::external(), None),
+            tile("triple"),
+            tile("label"),
+        ];
+        sequence.branches = vec![BranchItem {
+            condition: 1,
+            start: 2,
+            arms: vec![
+                BranchArm {
+                    pattern: BranchPattern::Bool(true),
+                    len: 2,
+                },
+                BranchArm {
+                    pattern: BranchPattern::Wildcard,
+                    len: 1,
+                },
+            ],
+            variants: Vec::new(),
+        }];
+
+        let control_flow = schema_of(sequence).control_flow;
+
+        assert_eq!(control_flow.len(), 3);
+        assert_eq!(linear_steps(&control_flow[0]), ids(&["load", "is_even"]));
+        let ControlFlow::Conditional {
+            condition,
+            branches,
+        } = &control_flow[1]
+        else {
+            panic!("expected conditional control flow");
+        };
+        assert_eq!(condition, &TileId::from("is_even"));
+        assert_eq!(branches.len(), 2);
+        assert_eq!(branches[0].condition, "matches!(is_even, true)");
+        assert_eq!(branches[0].steps.len(), 2);
+        assert_eq!(linear_steps(&branches[0].steps[0]), ids(&["halve"]));
+        assert_eq!(
+            loop_node(&branches[0].steps[1]),
+            (&ids(&["fold"])[..], None)
+        );
+        assert_eq!(branches[1].condition, "matches!(is_even, _)");
+        assert_eq!(linear_steps(&branches[1].steps[0]), ids(&["triple"]));
+        assert_eq!(linear_steps(&control_flow[2]), ids(&["label"]));
+    }
+
+    #[test]
+    fn branch_without_condition_item_is_an_error() {
+        let mut sequence = SequenceDef::new("main");
+        sequence.items = vec![tile("halve"), tile("triple")];
+        sequence.branches = vec![BranchItem {
+            condition: 5,
+            start: 0,
+            arms: vec![
+                BranchArm {
+                    pattern: BranchPattern::Bool(true),
+                    len: 1,
+                },
+                BranchArm {
+                    pattern: BranchPattern::Wildcard,
+                    len: 1,
+                },
+            ],
+            variants: Vec::new(),
+        }];
+        let mut cfs = ControlFlowSchema::new("test");
+        cfs.sequences.push(sequence);
+
+        let Err(Error::InvalidSequence(message)) = SchemaGenerator::new(&cfs).generate() else {
+            panic!("expected an invalid sequence error");
+        };
+        assert!(message.contains("no condition item 5"), "{}", message);
+    }
+
+    #[test]
+    fn nested_branches_nest_in_their_arm() {
+        let mut sequence = SequenceDef::new("main");
+        sequence.items = vec![
+            tile("outer"),
+            tile("inner"),
+            tile("both"),
+            tile("outer_only"),
+        ];
+        sequence.branches = vec![
+            BranchItem {
+                condition: 0,
+                start: 1,
+                arms: vec![
+                    BranchArm {
+                        pattern: BranchPattern::Bool(true),
+                        len: 2,
+                    },
+                    BranchArm {
+                        pattern: BranchPattern::Bool(false),
+                        len: 1,
+                    },
+                ],
+                variants: Vec::new(),
+            },
+            BranchItem {
+                condition: 1,
+                start: 2,
+                arms: vec![BranchArm {
+                    pattern: BranchPattern::Bool(true),
+                    len: 1,
+                }],
+                variants: Vec::new(),
+            },
+        ];
+
+        let control_flow = schema_of(sequence).control_flow;
+
+        let ControlFlow::Conditional { branches, .. } = &control_flow[1] else {
+            panic!("expected conditional control flow");
+        };
+        assert_eq!(linear_steps(&branches[0].steps[0]), ids(&["inner"]));
+        let ControlFlow::Conditional {
+            condition,
+            branches: inner,
+        } = &branches[0].steps[1]
+        else {
+            panic!("expected nested conditional control flow");
+        };
+        assert_eq!(condition, &TileId::from("inner"));
+        assert_eq!(linear_steps(&inner[0].steps[0]), ids(&["both"]));
+        assert_eq!(linear_steps(&branches[1].steps[0]), ids(&["outer_only"]));
+    }
+
+    #[test]
+    fn tiles_include_nested_sequence_calls() {
+        let mut cfs = ControlFlowSchema::new("test");
+        let mut inner = SequenceDef::new("inner");
+        inner.items = vec![tile("shout"), tile("greet")];
+        let mut main = SequenceDef::new("main");
+        main.items = vec![
+            tile("greet"),
+            SequenceChildItem::Sequence(SequenceItem {
+                id: "inner".to_string(),
+                sources: vec![],
+            }),
+            tile("sign"),
+        ];
+        cfs.sequences = vec![inner, main];
+
+        let schema = SchemaGenerator::new(&cfs).generate().unwrap().remove(1);
+
+        assert_eq!(schema.tiles, ids(&["greet", "shout", "sign"]));
+        assert_eq!(
+            linear_steps(&schema.control_flow[0]),
+            ids(&["greet", "inner", "sign"])
+        );
+    }
+}
@@ -57,14 +57,14 @@ impl<'ast> SequenceDiscovery<'ast> {
                     .iter()
                     .any(|m| m.name == "sequence" || m.name == "raster::sequence")
             })
-            .map(|f| f.name.clone())
+            .map(|f| f.id.clone())
             .collect();
 
         let sequences = project
             .ast
             .functions
             .iter()
-            .filter(|f| sequence_names.contains(&f.name))
+            .filter(|f| sequence_names.contains(&f.id))
             .map(|f| Self::extract_sequence(f, &tile_discovery, &sequence_names))
             .collect();
 
@@ -152,15 +152,15 @@ impl<'ast> SequenceDiscovery<'ast> {
         }
     }
 
-    /// Get a sequence by name.
-    pub fn get(&self, name: &str) -> Option<&Sequence<'ast>> {
-        self.sequences.iter().find(|s| s.function.name == name)
+    /// Get a sequence by id.
+    pub fn get(&self, id: &str) -> Option<&Sequence<'ast>> {
+        self.sequences.iter().find(|s| s.id() == id)
     }
 }
 
 impl<'ast> Sequence<'ast> {
     pub fn id(&self) -> &str {
-        &self.function.name
+        &self.function.id
     }
 
     pub fn source_file(&self) -> &Path {
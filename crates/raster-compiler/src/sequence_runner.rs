@@ -0,0 +1,1280 @@
+//! CFS-driven execution of whole sequences.
+//!
+//! [`SequenceRunner`] walks the control flow schema from an entry sequence
+//! with a [`CfsCursor`], runs every tile it reaches through a
+//! [`TileExecutor`] and feeds each argument from where the CFS says it comes
+//! from: a sequence parameter or a prior item's output, both kept in a
+//! [`ValueStore`]. Branches take the arm their condition's output selects.
+//! Recur tiles iterate their list source, in chunks when the site declares
+//! one, threading the state until the list ends or the tile returns
+//! `RecurControl::Break`; recur sequences run their body once per element.
+//!
+//! Values the CFS does not carry (inline literals, external and internal
+//! inputs) are asked from a [`BindingResolver`], such as [`RecordedInputs`],
+//! which cuts them out of the inputs a recorded run passed to each item.
+//! Draft values are bound to
+//! the live runtime and cannot be passed between separately executed tiles,
+//! so items taking or returning a `Draft` or `RecurOutput` are rejected.
+
+use std::collections::{BTreeMap, HashSet};
+
+use raster_backend::{ExecutionFailure, TileExecutionResult};
+use raster_core::branching::{self, BranchDiscriminant};
+use raster_core::cfs::{
+    BranchItem, BranchPattern, CfsCoordinates, CfsCursor, ControlFlowSchema, InputBinding,
+    InputSource, RecurSequenceItem, RecurTileItem, SequenceChildItem, SequenceDef, TileDef,
+};
+use raster_core::input::{SelectorPath, SelectorSegment};
+use raster_core::postcard;
+use raster_core::{Error, Result};
+
+use crate::value_store::{encode_varint, path_parts, read_varint, StoredValue, ValueStore};
+
+/// Executes a single tile on its postcard-encoded ABI input.
+pub trait TileExecutor {
+    fn execute(&mut self, tile: &TileDef, input: &[u8]) -> Result<TileExecutionResult>;
+}
+
+/// An item argument whose value the CFS does not carry.
+#[derive(Debug, Clone, Copy)]
+pub struct BindingSite<'a> {
+    /// Coordinates of the item taking the argument.
+    pub coordinates: &'a CfsCoordinates,
+    /// Id of the tile or sequence the item calls.
+    pub item_id: &'a str,
+    pub argument_index: usize,
+    /// Parameter type as written in the callee's signature, if known.
+    pub ty: Option<&'a str>,
+    /// Number of arguments the item takes.
+    pub inputs: usize,
+    /// Parameter types of the callee's signature, as far as known.
+    pub input_types: &'a [String],
+}
+
+/// Supplies the values of inline, external and internal arguments.
+pub trait BindingResolver {
+    fn resolve(&mut self, site: &BindingSite<'_>, source: &InputSource) -> Result<Vec<u8>>;
+}
+
+/// Resolver backed by a store of the inputs items were called with in a
+/// recorded run, by item coordinates: an argument is cut out of the input
+/// recorded for the item taking it. Arguments without a recorded input are
+/// an error naming the call site.
+#[derive(Debug, Clone, Default)]
+pub struct RecordedInputs {
+    inputs: BTreeMap<CfsCoordinates, Vec<u8>>,
+}
+
+impl RecordedInputs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the postcard-encoded arguments the item at `coordinates` was
+    /// called with, concatenated in parameter order.
+    pub fn insert(&mut self, coordinates: CfsCoordinates, input: Vec<u8>) {
+        self.inputs.insert(coordinates, input);
+    }
+}
+
+impl BindingResolver for RecordedInputs {
+    fn resolve(&mut self, site: &BindingSite<'_>, source: &InputSource) -> Result<Vec<u8>> {
+        let Some(input) = self.inputs.get(site.coordinates) else {
+            let kind = match source {
+                InputSource::External => "an external input",
+                InputSource::Inline => "an inline value",
+                InputSource::Internal => "an internal store value",
+            };
+            return Err(Error::Other(format!(
+                "Argument {} of `{}` at {:?} is {}, which the CFS does not record, and no \
+                 input was recorded for that item",
+                site.argument_index, site.item_id, site.coordinates.0, kind
+            )));
+        };
+        if site.input_types.len() != site.inputs {
+            return Err(Error::Other(format!(
+                "Cannot split the recorded input of `{}` at {:?}: its parameter types are \
+                 not known",
+                site.item_id, site.coordinates.0
+            )));
+        }
+        let arguments = StoredValue::new(
+            input.clone(),
+            Some(format!("({},)", site.input_types.join(", "))),
+        );
+        let selector = SelectorPath {
+            segments: vec![SelectorSegment::Index(site.argument_index as u64)],
+        };
+        Ok(arguments.select(&selector)?.bytes)
+    }
+}
+
+/// One tile execution of a sequence run.
+#[derive(Debug, Clone)]
+pub struct TileRun {
+    pub tile_id: String,
+    /// Item coordinates, extended by the iteration index for recur tiles.
+    pub coordinates: CfsCoordinates,
+    pub cycles: Option<u64>,
+    pub proof_cycles: Option<u64>,
+    /// Wall-clock nanoseconds, reported by the native backend.
+    pub wall_time_ns: Option<u64>,
+}
+
+/// Cycles spent in one tile across a sequence run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileCycles {
+    pub tile_id: String,
+    /// Number of executions, recur iterations included.
+    pub runs: usize,
+    pub cycles: u64,
+    pub proof_cycles: u64,
+    pub wall_time_ns: u64,
+}
+
+/// Result of a sequence run.
+#[derive(Debug, Clone)]
+pub struct SequenceRun {
+    /// Value returned by the entry sequence: the output of the last item it
+    /// executed. `None` if it executed nothing or a tile failed.
+    pub output: Option<StoredValue>,
+    /// Error value returned by a fallible tile, which ends the run the way
+    /// `?` in the sequence would.
+    pub user_error: Option<String>,
+    /// Every tile execution, in order.
+    pub tile_runs: Vec<TileRun>,
+}
+
+impl SequenceRun {
+    /// Cycle totals per tile, in the order tiles first ran.
+    pub fn cycles_by_tile(&self) -> Vec<TileCycles> {
+        let mut totals: Vec<TileCycles> = Vec::new();
+        for run in &self.tile_runs {
+            let index = match totals.iter().position(|total| total.tile_id == run.tile_id) {
+                Some(index) => index,
+                None => {
+                    totals.push(TileCycles {
+                        tile_id: run.tile_id.clone(),
+                        runs: 0,
+                        cycles: 0,
+                        proof_cycles: 0,
+                        wall_time_ns: 0,
+                    });
+                    totals.len() - 1
+                }
+            };
+            let total = &mut totals[index];
+            total.runs += 1;
+            total.cycles += run.cycles.unwrap_or(0);
+            total.proof_cycles += run.proof_cycles.unwrap_or(0);
+            total.wall_time_ns += run.wall_time_ns.unwrap_or(0);
+        }
+        totals
+    }
+}
+
+/// Ids of the tiles reachable from the sequence `sequence_id`, each listed once.
+pub fn reachable_tiles(cfs: &ControlFlowSchema, sequence_id: &str) -> Result<Vec<String>> {
+    let mut tiles = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![sequence_id.to_string()];
+
+    while let Some(id) = pending.pop() {
+        if !visited.insert(id.clone()) {
+            continue;
+        }
+        let sequence = cfs
+            .sequences
+            .iter()
+            .find(|sequence| sequence.id == id)
+            .ok_or_else(|| Error::Other(format!("Sequence '{}' not found in the CFS", id)))?;
+        let mut nested = Vec::new();
+        for item in &sequence.items {
+            match item {
+                SequenceChildItem::Tile(tile) if !tiles.contains(&tile.id) => {
+                    tiles.push(tile.id.clone())
+                }
+                SequenceChildItem::RecurTile(tile) if !tiles.contains(&tile.id) => {
+                    tiles.push(tile.id.clone())
+                }
+                SequenceChildItem::Sequence(sequence) => nested.push(sequence.id.clone()),
+                SequenceChildItem::RecurSequence(sequence) => nested.push(sequence.id.clone()),
+                _ => {}
+            }
+        }
+        pending.extend(nested.into_iter().rev());
+    }
+
+    Ok(tiles)
+}
+
+type Step<T> = std::result::Result<T, ExecutionFailure<String>>;
+
+/// Runs a sequence by following its control flow schema.
+pub struct SequenceRunner<E> {
+    cursor: CfsCursor,
+    executor: E,
+}
+
+impl<E: TileExecutor> SequenceRunner<E> {
+    /// A runner for the sequence `sequence_id` of `cfs`.
+    pub fn new(cfs: ControlFlowSchema, sequence_id: &str, executor: E) -> Result<Self> {
+        let cursor = CfsCursor::with_entrypoint(cfs, sequence_id).ok_or_else(|| {
+            Error::Other(format!("Sequence '{}' not found in the CFS", sequence_id))
+        })?;
+        Ok(Self { cursor, executor })
+    }
+
+    /// Get the sequence name.
+    pub fn sequence_name(&self) -> &str {
+        &self.cursor.entrypoint().id
+    }
+
+    /// Run the sequence on its postcard-encoded arguments.
+    pub fn run(
+        &mut self,
+        arguments: Vec<Vec<u8>>,
+        resolver: &mut dyn BindingResolver,
+    ) -> Result<SequenceRun> {
+        let entrypoint = self.cursor.entrypoint().clone();
+        if arguments.len() != entrypoint.input_sources.len() {
+            return Err(Error::Other(format!(
+                "Sequence '{}' takes {} argument(s), got {}",
+                entrypoint.id,
+                entrypoint.input_sources.len(),
+                arguments.len()
+            )));
+        }
+        let arguments = arguments
+            .into_iter()
+            .enumerate()
+            .map(|(index, bytes)| {
+                StoredValue::new(bytes, entrypoint.input_types.get(index).cloned())
+            })
+            .collect();
+
+        let mut frame = Frame {
+            cursor: &self.cursor,
+            executor: &mut self.executor,
+            resolver,
+            store: ValueStore::new(),
+            tile_runs: Vec::new(),
+        };
+        let root = CfsCoordinates::new();
+        frame.store.insert_arguments(root.clone(), arguments);
+        let outcome = frame.run_sequence(&root, &entrypoint);
+        let tile_runs = frame.tile_runs;
+
+        match outcome {
+            Ok(output) => Ok(SequenceRun {
+                output,
+                user_error: None,
+                tile_runs,
+            }),
+            Err(ExecutionFailure::User(error)) => Ok(SequenceRun {
+                output: None,
+                user_error: Some(error),
+                tile_runs,
+            }),
+            Err(ExecutionFailure::Runtime(error)) => Err(error),
+        }
+    }
+}
+
+/// State of one run while the runner walks the schema.
+struct Frame<'r> {
+    cursor: &'r CfsCursor,
+    executor: &'r mut dyn TileExecutor,
+    resolver: &'r mut dyn BindingResolver,
+    store: ValueStore,
+    tile_runs: Vec<TileRun>,
+}
+
+impl Frame<'_> {
+    /// Run the items of `sequence`, whose arguments are stored under
+    /// `coordinates`, and return the output of the last item executed.
+    fn run_sequence(
+        &mut self,
+        coordinates: &CfsCoordinates,
+        sequence: &SequenceDef,
+    ) -> Step<Option<StoredValue>> {
+        let mut output = None;
+        let mut position = (!sequence.items.is_empty()).then_some(0);
+
+        while let Some(index) = position {
+            let mut item_coordinates = coordinates.clone();
+            item_coordinates.push(index);
+            let item = self
+                .cursor
+                .try_get_item(&item_coordinates)
+                .cloned()
+                .ok_or_else(|| runtime(format!("No CFS item at {:?}", item_coordinates.0)))?;
+
+            let value = match &item {
+                SequenceChildItem::Tile(tile_item) => {
+                    let tile = self.tile(&tile_item.id)?;
+                    self.run_tile(&item_coordinates, coordinates, &tile, &tile_item.sources)?
+                }
+                SequenceChildItem::Sequence(sequence_item) => {
+                    let nested = self.sequence(&sequence_item.id)?;
+                    let arguments = self.resolve_all(
+                        coordinates,
+                        &item_coordinates,
+                        &nested.id,
+                        &sequence_item.sources,
+                        &nested.input_types,
+                    )?;
+                    self.store
+                        .insert_arguments(item_coordinates.clone(), arguments);
+                    self.run_sequence(&item_coordinates, &nested)?
+                }
+                SequenceChildItem::RecurTile(recur_item) => {
+                    Some(self.run_recur_tile(&item_coordinates, coordinates, recur_item)?)
+                }
+                SequenceChildItem::RecurSequence(recur_item) => {
+                    Some(self.run_recur_sequence(&item_coordinates, coordinates, recur_item)?)
+                }
+            };
+
+            let positions = match self.cursor.try_get_branch(&item_coordinates) {
+                Some(branch) => {
+                    let condition = value.as_ref().ok_or_else(|| {
+                        runtime(format!(
+                            "Branch condition at {:?} produced no value",
+                            item_coordinates.0
+                        ))
+                    })?;
+                    let arm = select_arm(branch, condition).map_err(ExecutionFailure::Runtime)?;
+                    sequence
+                        .arm_entry_positions(branch, arm)
+                        .ok_or_else(|| runtime(format!("Branch arm {} does not exist", arm)))?
+                }
+                None => sequence.successor_positions(index),
+            };
+            position = match positions.as_slice() {
+                [position] => *position,
+                _ => {
+                    return Err(runtime(format!(
+                        "Execution after {:?} in sequence '{}' is ambiguous",
+                        item_coordinates.0, sequence.id
+                    )))
+                }
+            };
+
+            if let Some(value) = &value {
+                self.store
+                    .insert_output(item_coordinates.clone(), value.clone());
+            }
+            output = value;
+        }
+
+        Ok(output)
+    }
+
+    fn run_tile(
+        &mut self,
+        item_coordinates: &CfsCoordinates,
+        frame: &CfsCoordinates,
+        tile: &TileDef,
+        sources: &[InputBinding],
+    ) -> Step<Option<StoredValue>> {
+        reject_drafts(&tile.id, &tile.input_types, tile.output_type.as_deref())?;
+        let arguments = self.resolve_all(
+            frame,
+            item_coordinates,
+            &tile.id,
+            sources,
+            &tile.input_types,
+        )?;
+        let input: Vec<u8> = arguments
+            .into_iter()
+            .flat_map(|value| value.bytes)
+            .collect();
+        let output = self.execute(item_coordinates.clone(), tile, &input)?;
+
+        let Some(output_type) = tile.output_type.as_deref() else {
+            return Ok(None);
+        };
+        match split_type(output_type) {
+            Some((name, args)) if name == "Result" && !args.is_empty() => {
+                let (variant, offset) = read_varint(&output).map_err(ExecutionFailure::Runtime)?;
+                let payload = output[offset..].to_vec();
+                match variant {
+                    0 => Ok(Some(StoredValue::new(payload, Some(args[0].clone())))),
+                    _ => Err(ExecutionFailure::User(
+                        postcard::from_bytes::<String>(&payload).unwrap_or_else(|_| {
+                            format!("`{}` failed with a {}-byte error", tile.id, payload.len())
+                        }),
+                    )),
+                }
+            }
+            _ => Ok(Some(StoredValue::new(
+                output,
+                Some(output_type.to_string()),
+            ))),
+        }
+    }
+
+    /// Iterate a recur tile over its list source and return the final state.
+    fn run_recur_tile(
+        &mut self,
+        site: &CfsCoordinates,
+        frame: &CfsCoordinates,
+        item: &RecurTileItem,
+    ) -> Step<StoredValue> {
+        let tile = self.tile(&item.id)?;
+        reject_drafts(&tile.id, &tile.input_types, tile.output_type.as_deref())?;
+        let element = tile
+            .input_types
+            .first()
+            .and_then(|ty| inner_type(ty, "RecurInput"))
+            .ok_or_else(|| {
+                runtime(format!(
+                    "Recur tile `{}` must take `RecurInput<T>` first",
+                    tile.id
+                ))
+            })?;
+        let element = match item.chunk {
+            Some(_) => inner_type(&element, "Vec").ok_or_else(|| {
+                runtime(format!(
+                    "Chunked recur tile `{}` must take `RecurInput<Vec<T>>`",
+                    tile.id
+                ))
+            })?,
+            None => element,
+        };
+        let state_type = tile
+            .input_types
+            .get(1)
+            .and_then(|ty| inner_type(ty, "RecurState"))
+            .ok_or_else(|| {
+                runtime(format!(
+                    "Recur tile `{}` must take `RecurState<S>` after its input",
+                    tile.id
+                ))
+            })?;
+
+        let mut arguments =
+            self.resolve_all(frame, site, &tile.id, &item.sources, &tile.input_types)?;
+        if arguments.len() < 2 {
+            return Err(runtime(format!(
+                "Recur tile `{}` needs a list source and an initial state",
+                tile.id
+            )));
+        }
+        let extra: Vec<u8> = arguments.drain(2..).flat_map(|value| value.bytes).collect();
+        let mut state = arguments.pop().expect("state argument").bytes;
+        let list = arguments.pop().expect("list argument");
+        let elements = StoredValue::new(list.bytes, Some(format!("Vec<{}>", element)))
+            .split_list(item.chunk)
+            .map_err(ExecutionFailure::Runtime)?;
+
+        let breaks = tile
+            .output_type
+            .as_deref()
+            .and_then(split_type)
+            .is_some_and(|(name, _)| name == "RecurControl");
+        let len = elements.len() as u64;
+        for (index, element) in elements.into_iter().enumerate() {
+            let mut input = element.bytes;
+            input.extend(encode_varint(index as u64).map_err(ExecutionFailure::Runtime)?);
+            input.extend(encode_varint(len).map_err(ExecutionFailure::Runtime)?);
+            input.extend_from_slice(&state);
+            input.extend_from_slice(&extra);
+
+            let mut iteration = site.clone();
+            iteration.push(index as u32);
+            let output = self.execute(iteration, &tile, &input)?;
+
+            if !breaks {
+                state = output;
+                continue;
+            }
+            let (control, offset) = read_varint(&output).map_err(ExecutionFailure::Runtime)?;
+            state = output[offset..].to_vec();
+            if control == 1 {
+                break;
+            }
+        }
+
+        Ok(StoredValue::new(state, Some(state_type)))
+    }
+
+    /// Run a recur sequence body once per element of its list source and
+    /// return the final state.
+    fn run_recur_sequence(
+        &mut self,
+        site: &CfsCoordinates,
+        frame: &CfsCoordinates,
+        item: &RecurSequenceItem,
+    ) -> Step<StoredValue> {
+        let sequence = self.sequence(&item.id)?;
+        let element = sequence
+            .input_types
+            .first()
+            .and_then(|ty| inner_type(ty, "RecurSequenceInput"))
+            .ok_or_else(|| {
+                runtime(format!(
+                    "Recur sequence `{}` must take `RecurSequenceInput<T>` first",
+                    sequence.id
+                ))
+            })?;
+        let state_type = sequence
+            .input_types
+            .get(1)
+            .and_then(|ty| inner_type(ty, "RecurSequenceState"))
+            .ok_or_else(|| {
+                runtime(format!(
+                    "Recur sequence `{}` must take `RecurSequenceState<S>` after its input; \
+                     draft outputs cannot be threaded between separately executed tiles",
+                    sequence.id
+                ))
+            })?;
+
+        let mut arguments = self.resolve_all(
+            frame,
+            site,
+            &sequence.id,
+            &item.sources,
+            &sequence.input_types,
+        )?;
+        if arguments.len() < 2 {
+            return Err(runtime(format!(
+                "Recur sequence `{}` needs a list source and an initial state",
+                sequence.id
+            )));
+        }
+        let extra: Vec<StoredValue> = arguments.drain(2..).collect();
+        let mut state = arguments.pop().expect("state argument");
+        state.ty = Some(state_type.clone());
+        let list = arguments.pop().expect("list argument");
+        let elements = StoredValue::new(list.bytes, Some(format!("Vec<{}>", element)))
+            .split_list(None)
+            .map_err(ExecutionFailure::Runtime)?;
+
+        for (index, element) in elements.into_iter().enumerate() {
+            let mut iteration = site.clone();
+            iteration.push(index as u32);
+            let mut body_arguments = vec![element, state];
+            body_arguments.extend(extra.iter().cloned());
+            self.store
+                .insert_arguments(iteration.clone(), body_arguments);
+
+            let output = self.run_sequence(&iteration, &sequence)?.ok_or_else(|| {
+                runtime(format!(
+                    "Recur sequence `{}` returned no state in iteration {}",
+                    sequence.id, index
+                ))
+            })?;
+            state = StoredValue::new(output.bytes, Some(state_type.clone()));
+        }
+
+        Ok(state)
+    }
+
+    fn execute(
+        &mut self,
+        coordinates: CfsCoordinates,
+        tile: &TileDef,
+        input: &[u8],
+    ) -> Step<Vec<u8>> {
+        let result = self
+            .executor
+            .execute(tile, input)
+            .map_err(ExecutionFailure::Runtime)?;
+        self.tile_runs.push(TileRun {
+            tile_id: tile.id.clone(),
+            coordinates,
+            cycles: result.cycles,
+            proof_cycles: result.proof_cycles,
+            wall_time_ns: result.wall_time_ns,
+        });
+        Ok(result.output)
+    }
+
+    /// Values of the arguments `sources` of the item at `item_coordinates`,
+    /// which sits in the sequence whose arguments are stored under `frame`.
+    fn resolve_all(
+        &mut self,
+        frame: &CfsCoordinates,
+        item_coordinates: &CfsCoordinates,
+        item_id: &str,
+        sources: &[InputBinding],
+        types: &[String],
+    ) -> Step<Vec<StoredValue>> {
+        sources
+            .iter()
+            .enumerate()
+            .map(|(index, binding)| {
+                let site = BindingSite {
+                    coordinates: item_coordinates,
+                    item_id,
+                    argument_index: index,
+                    ty: types.get(index).map(String::as_str),
+                    inputs: sources.len(),
+                    input_types: types,
+                };
+                self.resolve(frame, &site, binding)
+                    .map_err(ExecutionFailure::Runtime)
+            })
+            .collect()
+    }
+
+    fn resolve(
+        &mut self,
+        frame: &CfsCoordinates,
+        site: &BindingSite<'_>,
+        binding: &InputBinding,
+    ) -> Result<StoredValue> {
+        let BindingSite {
+            item_id,
+            argument_index,
+            ..
+        } = *site;
+        match binding {
+            InputBinding::Direct(source) => {
+                let bytes = self.resolver.resolve(site, source)?;
+                Ok(StoredValue::new(bytes, site.ty.map(str::to_string)))
+            }
+            InputBinding::SequenceScope { input_index } => self
+                .store
+                .argument(frame, *input_index)
+                .cloned()
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "Argument {} of `{}` reads sequence argument {}, which was not supplied",
+                        argument_index, item_id, input_index
+                    ))
+                }),
+            InputBinding::PriorItemOutput {
+                intra_sequence_item_index,
+                selector,
+            } => {
+                let mut source = frame.clone();
+                source.push(*intra_sequence_item_index as u32);
+                self.store
+                    .output(&source)
+                    .ok_or_else(|| {
+                        Error::Other(format!(
+                            "Argument {} of `{}` reads the output of item {}, which did not run \
+                             or returned nothing",
+                            argument_index, item_id, intra_sequence_item_index
+                        ))
+                    })?
+                    .select(selector)
+            }
+        }
+    }
+
+    fn tile(&self, id: &str) -> Step<TileDef> {
+        self.cursor
+            .cfs()
+            .tiles
+            .iter()
+            .find(|tile| tile.id == id)
+            .cloned()
+            .ok_or_else(|| runtime(format!("Tile '{}' not found in the CFS", id)))
+    }
+
+    fn sequence(&self, id: &str) -> Step<SequenceDef> {
+        self.cursor
+            .cfs()
+            .sequences
+            .iter()
+            .find(|sequence| sequence.id == id)
+            .cloned()
+            .ok_or_else(|| runtime(format!("Sequence '{}' not found in the CFS", id)))
+    }
+}
+
+/// Index of the first arm of `branch` whose pattern matches `condition`.
+///
+/// The condition value is postcard-encoded, which carries an enum variant by
+/// index, so `Variant` patterns are matched through the variant order the
+/// CFS records for the branch.
+fn select_arm(branch: &BranchItem, condition: &StoredValue) -> Result<usize> {
+    let patterns = || branch.arms.iter().map(|arm| &arm.pattern);
+    let discriminant = if patterns().any(|pattern| matches!(pattern, BranchPattern::Variant(_))) {
+        let (index, _) = read_varint(&condition.bytes)?;
+        let name = branch.variants.get(index as usize).ok_or_else(|| {
+            Error::Other(format!(
+                "Cannot match variant {} of `{}`: the CFS does not record the variants of the \
+                 condition type",
+                index,
+                condition.ty.as_deref().unwrap_or("the condition type")
+            ))
+        })?;
+        BranchDiscriminant::Variant(name.clone())
+    } else if patterns().any(|pattern| matches!(pattern, BranchPattern::Bool(_))) {
+        let value: bool = postcard::from_bytes(&condition.bytes)
+            .map_err(|e| Error::Serialization(format!("Branch condition is not a bool: {}", e)))?;
+        BranchDiscriminant::Leaf(vec![u8::from(value)])
+    } else {
+        // Only wildcard arms: any value takes the first.
+        BranchDiscriminant::Leaf(Vec::new())
+    };
+
+    branching::select_discriminant_arm(&branch.arms, discriminant)
+        .map_err(|violation| Error::Other(violation.to_string()))
+}
+
+fn reject_drafts(id: &str, input_types: &[String], output_type: Option<&str>) -> Step<()> {
+    let mentions_draft = |ty: &str| syn::parse_str(ty).is_ok_and(|ty| is_draft(&ty));
+    if input_types.iter().any(|ty| mentions_draft(ty)) || output_type.is_some_and(mentions_draft) {
+        return Err(runtime(format!(
+            "Tile `{}` works on a draft, which is bound to the live runtime and cannot be \
+             passed between separately executed tiles",
+            id
+        )));
+    }
+    Ok(())
+}
+
+/// Whether `ty` is or contains a `Draft` or `RecurOutput`.
+fn is_draft(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Tuple(tuple) => tuple.elems.iter().any(is_draft),
+        ty => path_parts(ty).is_some_and(|(name, args)| {
+            name == "Draft" || name == "RecurOutput" || args.iter().any(is_draft)
+        }),
+    }
+}
+
+/// Last path segment of a type and its type arguments, as strings.
+fn split_type(ty: &str) -> Option<(String, Vec<String>)> {
+    let parsed: syn::Type = syn::parse_str(ty).ok()?;
+    let (name, args) = path_parts(&parsed)?;
+    Some((
+        name,
+        args.iter()
+            .map(|arg| crate::type_check::compact_type(&quote::quote!(#arg).to_string()))
+            .collect(),
+    ))
+}
+
+/// `T` of `ty` when `ty` is `wrapper<T>`.
+fn inner_type(ty: &str, wrapper: &str) -> Option<String> {
+    match split_type(ty)? {
+        (name, args) if name == wrapper && args.len() == 1 => args.into_iter().next(),
+        _ => None,
+    }
+}
+
+fn runtime(message: String) -> ExecutionFailure<String> {
+    ExecutionFailure::Runtime(Error::Other(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raster_core::cfs::{BranchArm, SequenceItem, TileItem, CFS_VERSION};
+    use raster_core::input::{SelectorPath, SelectorSegment};
+    use std::collections::HashMap;
+
+    type TileFn = Box<dyn Fn(&[u8]) -> Vec<u8>>;
+
+    /// Tiles implemented in Rust, each costing 100 cycles.
+    struct FakeTiles(HashMap<&'static str, TileFn>);
+
+    impl TileExecutor for FakeTiles {
+        fn execute(&mut self, tile: &TileDef, input: &[u8]) -> Result<TileExecutionResult> {
+            Ok(TileExecutionResult::estimate(
+                (self.0[tile.id.as_str()])(input),
+                100,
+            ))
+        }
+    }
+
+    /// Resolves every inline value to the same bytes.
+    struct Inline(Vec<u8>);
+
+    impl BindingResolver for Inline {
+        fn resolve(&mut self, _site: &BindingSite<'_>, _source: &InputSource) -> Result<Vec<u8>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn encode<T: serde::Serialize>(value: &T) -> Vec<u8> {
+        postcard::to_allocvec(value).unwrap()
+    }
+
+    fn tile_def(id: &str, inputs: &[&str], output: &str) -> TileDef {
+        TileDef::iter(id, inputs.len(), 1).with_types(
+            inputs.iter().map(|ty| ty.to_string()).collect(),
+            Some(output.to_string()),
+        )
+    }
+
+    fn item(id: &str, sources: Vec<InputBinding>) -> SequenceChildItem {
+        SequenceChildItem::Tile(TileItem {
+            id: id.to_string(),
+            sources,
+        })
+    }
+
+    fn sequence(id: &str, inputs: &[&str], items: Vec<SequenceChildItem>) -> SequenceDef {
+        let mut def = SequenceDef::new(id);
+        def.input_sources = inputs.iter().map(|_| InputBinding::external()).collect();
+        def.input_types = inputs.iter().map(|ty| ty.to_string()).collect();
+        def.items = items;
+        def
+    }
+
+    fn cfs(tiles: Vec<TileDef>, sequences: Vec<SequenceDef>) -> ControlFlowSchema {
+        let mut cfs = ControlFlowSchema::new("test");
+        cfs.version = CFS_VERSION.to_string();
+        cfs.tiles = tiles;
+        cfs.sequences = sequences;
+        cfs
+    }
+
+    fn tiles(entries: Vec<(&'static str, TileFn)>) -> FakeTiles {
+        FakeTiles(entries.into_iter().collect())
+    }
+
+    #[test]
+    fn outputs_flow_through_nested_sequences_and_tuple_selections() {
+        let schema = cfs(
+            vec![
+                tile_def("split", &["u64"], "(u64,(String,bool))"),
+                tile_def("shout", &["String"], "String"),
+            ],
+            vec![
+                sequence(
+                    "main",
+                    &["u64"],
+                    vec![
+                        item("split", vec![InputBinding::seq_input(0)]),
+                        SequenceChildItem::Sequence(SequenceItem {
+                            id: "inner".to_string(),
+                            sources: vec![InputBinding::prior_item_output_selection(
+                                0,
+                                SelectorPath::new(vec![
+                                    SelectorSegment::Index(1),
+                                    SelectorSegment::Index(0),
+                                ]),
+                            )],
+                        }),
+                    ],
+                ),
+                sequence(
+                    "inner",
+                    &["String"],
+                    vec![item("shout", vec![InputBinding::seq_input(0)])],
+                ),
+            ],
+        );
+        let executor = tiles(vec![
+            (
+                "split",
+                Box::new(|input| {
+                    let n: u64 = postcard::from_bytes(input).unwrap();
+                    encode(&(n, (n.to_string(), true)))
+                }),
+            ),
+            (
+                "shout",
+                Box::new(|input| {
+                    let text: String = postcard::from_bytes(input).unwrap();
+                    encode(&text.to_uppercase())
+                }),
+            ),
+        ]);
+
+        let run = SequenceRunner::new(schema, "main", executor)
+            .unwrap()
+            .run(vec![encode(&300u64)], &mut RecordedInputs::new())
+            .unwrap();
+
+        let output = run.output.unwrap();
+        assert_eq!(
+            postcard::from_bytes::<String>(&output.bytes).unwrap(),
+            "300"
+        );
+        assert_eq!(output.ty.as_deref(), Some("String"));
+        let coordinates: Vec<_> = run
+            .tile_runs
+            .iter()
+            .map(|run| run.coordinates.0.clone())
+            .collect();
+        assert_eq!(coordinates, vec![vec![0], vec![1, 0]]);
+    }
+
+    #[test]
+    fn recur_tile_iterates_chunks_until_break() {
+        let schema = cfs(
+            vec![tile_def(
+                "sum_chunk",
+                &["RecurInput<Vec<u64>>", "RecurState<u64>", "u64"],
+                "RecurControl<RecurState<u64>>",
+            )
+            .with_path("sum_chunk")],
+            vec![sequence(
+                "main",
+                &["Vec<u64>", "u64"],
+                vec![SequenceChildItem::RecurTile(RecurTileItem {
+                    id: "sum_chunk".to_string(),
+                    sources: vec![
+                        InputBinding::seq_input(0),
+                        InputBinding::inline(),
+                        InputBinding::seq_input(1),
+                    ],
+                    chunk: Some(2),
+                })],
+            )],
+        );
+        let executor = tiles(vec![(
+            "sum_chunk",
+            Box::new(|input| {
+                let ((chunk, _index, _len), state, limit): ((Vec<u64>, u64, u64), u64, u64) =
+                    postcard::from_bytes(input).unwrap();
+                let state = state + chunk.iter().sum::<u64>();
+                // RecurControl::{Continue, Break} as postcard variants 0 and 1.
+                encode(&((state >= limit) as u32, state))
+            }),
+        )]);
+
+        let run = SequenceRunner::new(schema, "main", executor)
+            .unwrap()
+            .run(
+                vec![encode(&vec![1u64, 2, 3, 4, 5, 6, 7]), encode(&10u64)],
+                &mut Inline(encode(&0u64)),
+            )
+            .unwrap();
+
+        // Chunks [1, 2], [3, 4] reach the limit; [5, 6] and [7] never run.
+        assert_eq!(
+            postcard::from_bytes::<u64>(&run.output.as_ref().unwrap().bytes).unwrap(),
+            10
+        );
+        let coordinates: Vec<_> = run
+            .tile_runs
+            .iter()
+            .map(|run| run.coordinates.0.clone())
+            .collect();
+        assert_eq!(coordinates, vec![vec![0, 0], vec![0, 1]]);
+        assert_eq!(
+            run.cycles_by_tile(),
+            vec![TileCycles {
+                tile_id: "sum_chunk".to_string(),
+                runs: 2,
+                cycles: 200,
+                proof_cycles: 2 * raster_backend::MIN_PROOF_SEGMENT_CYCLES,
+                wall_time_ns: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn recur_sequence_threads_state_through_its_body() {
+        let schema = cfs(
+            vec![tile_def(
+                "add",
+                &["u64", "RecurState<u64>"],
+                "RecurState<u64>",
+            )],
+            vec![
+                sequence(
+                    "main",
+                    &["Vec<u64>"],
+                    vec![SequenceChildItem::RecurSequence(RecurSequenceItem {
+                        id: "body".to_string(),
+                        sources: vec![InputBinding::seq_input(0), InputBinding::inline()],
+                    })],
+                ),
+                sequence(
+                    "body",
+                    &["RecurSequenceInput<u64>", "RecurSequenceState<u64>"],
+                    vec![item(
+                        "add",
+                        vec![InputBinding::seq_input(0), InputBinding::seq_input(1)],
+                    )],
+                ),
+            ],
+        );
+        let executor = tiles(vec![(
+            "add",
+            Box::new(|input| {
+                let (value, state): (u64, u64) = postcard::from_bytes(input).unwrap();
+                encode(&(value + state))
+            }),
+        )]);
+
+        let run = SequenceRunner::new(schema, "main", executor)
+            .unwrap()
+            .run(
+                vec![encode(&vec![1u64, 2, 3])],
+                &mut Inline(encode(&100u64)),
+            )
+            .unwrap();
+
+        assert_eq!(
+            postcard::from_bytes::<u64>(&run.output.unwrap().bytes).unwrap(),
+            106
+        );
+        assert_eq!(run.tile_runs.len(), 3);
+        assert_eq!(run.tile_runs[2].coordinates.0, vec![0, 2, 0]);
+    }
+
+    #[test]
+    fn branch_runs_the_selected_arm_only() {
+        let mut main = sequence(
+            "main",
+            &["u64"],
+            vec![
+                item("is_even", vec![InputBinding::seq_input(0)]),
+                item("halve", vec![InputBinding::seq_input(0)]),
+                item("triple", vec![InputBinding::seq_input(0)]),
+            ],
+        );
+        main.branches = vec![BranchItem {
+            condition: 0,
+            start: 1,
+            arms: vec![
+                BranchArm {
+                    pattern: BranchPattern::Bool(true),
+                    len: 1,
+                },
+                BranchArm {
+                    pattern: BranchPattern::Wildcard,
+                    len: 1,
+                },
+            ],
+            variants: Vec::new(),
+        }];
+        let schema = cfs(
+            vec![
+                tile_def("is_even", &["u64"], "bool"),
+                tile_def("halve", &["u64"], "u64"),
+                tile_def("triple", &["u64"], "u64"),
+            ],
+            vec![main],
+        );
+        let executor = || {
+            tiles(vec![
+                (
+                    "is_even",
+                    Box::new(|input: &[u8]| {
+                        encode(
+                            &postcard::from_bytes::<u64>(input)
+                                .unwrap()
+                                .is_multiple_of(2),
+                        )
+                    }) as TileFn,
+                ),
+                (
+                    "halve",
+                    Box::new(|input: &[u8]| {
+                        encode(&(postcard::from_bytes::<u64>(input).unwrap() / 2))
+                    }),
+                ),
+                (
+                    "triple",
+                    Box::new(|input: &[u8]| {
+                        encode(&(postcard::from_bytes::<u64>(input).unwrap() * 3))
+                    }),
+                ),
+            ])
+        };
+
+        for (input, expected, tile) in [(8u64, 4u64, "halve"), (5, 15, "triple")] {
+            let run = SequenceRunner::new(schema.clone(), "main", executor())
+                .unwrap()
+                .run(vec![encode(&input)], &mut RecordedInputs::new())
+                .unwrap();
+            assert_eq!(
+                postcard::from_bytes::<u64>(&run.output.unwrap().bytes).unwrap(),
+                expected
+            );
+            let ran: Vec<_> = run
+                .tile_runs
+                .iter()
+                .map(|run| run.tile_id.as_str())
+                .collect();
+            assert_eq!(ran, vec!["is_even", tile]);
+        }
+    }
+
+    #[test]
+    fn tile_error_ends_the_run_and_inline_values_need_a_resolver() {
+        let schema = cfs(
+            vec![
+                tile_def("check", &["String"], "Result<String>"),
+                tile_def("greet", &["String"], "String"),
+            ],
+            vec![sequence(
+                "main",
+                &["String"],
+                vec![
+                    item("check", vec![InputBinding::seq_input(0)]),
+                    item("greet", vec![InputBinding::prior_item_output(0)]),
+                ],
+            )],
+        );
+        let executor = || {
+            tiles(vec![
+                (
+                    "check",
+                    Box::new(|input: &[u8]| {
+                        let name: String = postcard::from_bytes(input).unwrap();
+                        let result: std::result::Result<String, String> = if name.is_empty() {
+                            Err("MissingName".to_string())
+                        } else {
+                            Ok(name)
+                        };
+                        encode(&result)
+                    }) as TileFn,
+                ),
+                ("greet", Box::new(|input: &[u8]| input.to_vec())),
+            ])
+        };
+
+        let run = SequenceRunner::new(schema.clone(), "main", executor())
+            .unwrap()
+            .run(vec![encode(&"")], &mut RecordedInputs::new())
+            .unwrap();
+        assert_eq!(run.user_error.as_deref(), Some("MissingName"));
+        assert_eq!(run.tile_runs.len(), 1);
+
+        let run = SequenceRunner::new(schema, "main", executor())
+            .unwrap()
+            .run(vec![encode(&"Ada")], &mut RecordedInputs::new())
+            .unwrap();
+        assert_eq!(
+            postcard::from_bytes::<String>(&run.output.unwrap().bytes).unwrap(),
+            "Ada"
+        );
+
+        let inline = cfs(
+            vec![tile_def("greet", &["String"], "String")],
+            vec![sequence(
+                "main",
+                &[],
+                vec![item("greet", vec![InputBinding::inline()])],
+            )],
+        );
+        let err = SequenceRunner::new(inline, "main", executor())
+            .unwrap()
+            .run(vec![], &mut RecordedInputs::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("inline value"), "{}", err);
+    }
+
+    #[test]
+    fn recorded_inputs_supply_direct_arguments() {
+        let schema = cfs(
+            vec![tile_def("repeat", &["u64", "String"], "String")],
+            vec![sequence(
+                "main",
+                &["u64"],
+                vec![item(
+                    "repeat",
+                    vec![InputBinding::seq_input(0), InputBinding::inline()],
+                )],
+            )],
+        );
+        let executor = tiles(vec![(
+            "repeat",
+            Box::new(|input: &[u8]| {
+                let (count, text): (u64, String) = postcard::from_bytes(input).unwrap();
+                encode(&text.repeat(count as usize))
+            }),
+        )]);
+        let mut recorded = RecordedInputs::new();
+        // Recorded with a different count: only the inline argument is taken.
+        recorded.insert(CfsCoordinates(vec![0]), encode(&(1u64, "ab")));
+
+        let run = SequenceRunner::new(schema, "main", executor)
+            .unwrap()
+            .run(vec![encode(&3u64)], &mut recorded)
+            .unwrap();
+
+        assert_eq!(
+            postcard::from_bytes::<String>(&run.output.unwrap().bytes).unwrap(),
+            "ababab"
+        );
+    }
+
+    #[test]
+    fn variant_branch_matches_through_the_recorded_variant_order() {
+        let mut main = sequence(
+            "main",
+            &["u64"],
+            vec![
+                item("classify", vec![InputBinding::seq_input(0)]),
+                item("halve", vec![InputBinding::seq_input(0)]),
+                item("triple", vec![InputBinding::seq_input(0)]),
+            ],
+        );
+        main.branches = vec![BranchItem {
+            condition: 0,
+            start: 1,
+            arms: vec![
+                BranchArm {
+                    pattern: BranchPattern::Variant("Odd".to_string()),
+                    len: 1,
+                },
+                BranchArm {
+                    pattern: BranchPattern::Variant("Even".to_string()),
+                    len: 1,
+                },
+            ],
+            variants: Vec::new(),
+        }];
+        let executor = || {
+            tiles(vec![
+                (
+                    "classify",
+                    // `Parity::{Even, Odd}` as postcard variants 0 and 1.
+                    Box::new(|input: &[u8]| {
+                        encode(&(postcard::from_bytes::<u64>(input).unwrap() % 2 == 1))
+                    }) as TileFn,
+                ),
+                (
+                    "halve",
+                    Box::new(|input: &[u8]| {
+                        encode(&(postcard::from_bytes::<u64>(input).unwrap() / 2))
+                    }),
+                ),
+                (
+                    "triple",
+                    Box::new(|input: &[u8]| {
+                        encode(&(postcard::from_bytes::<u64>(input).unwrap() * 3))
+                    }),
+                ),
+            ])
+        };
+        let schema = |main: SequenceDef| {
+            cfs(
+                vec![
+                    tile_def("classify", &["u64"], "Parity"),
+                    tile_def("halve", &["u64"], "u64"),
+                    tile_def("triple", &["u64"], "u64"),
+                ],
+                vec![main],
+            )
+        };
+
+        let err = SequenceRunner::new(schema(main.clone()), "main", executor())
+            .unwrap()
+            .run(vec![encode(&8u64)], &mut RecordedInputs::new())
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("does not record the variants"),
+            "{}",
+            err
+        );
+
+        main.branches[0].variants = vec!["Even".to_string(), "Odd".to_string()];
+        for (input, expected) in [(8u64, 24u64), (5, 2)] {
+            let run = SequenceRunner::new(schema(main.clone()), "main", executor())
+                .unwrap()
+                .run(vec![encode(&input)], &mut RecordedInputs::new())
+                .unwrap();
+            assert_eq!(
+                postcard::from_bytes::<u64>(&run.output.unwrap().bytes).unwrap(),
+                expected
+            );
+        }
+    }
+}
@@ -23,7 +23,7 @@ pub struct Tile<'ast> {
 
 impl<'ast> Tile<'ast> {
     pub fn id(&self) -> &str {
-        &self.function.name
+        &self.function.id
     }
 
     pub fn source_file(&self) -> PathBuf {
@@ -38,6 +38,12 @@ impl<'ast> Tile<'ast> {
         TileMetadata {
             id: TileId::new(self.id()),
             name: self.function.name.clone(),
+            path: self.function.qualified_name(),
+            crate_dir: self
+                .function
+                .crate_dir
+                .as_ref()
+                .map(|dir| dir.display().to_string()),
             description: self.description.clone(),
             estimated_cycles: self.estimated_cycles,
             max_memory: self.max_memory,
@@ -114,13 +120,13 @@ impl<'ast> TileDiscovery<'ast> {
         }
     }
 
-    /// Find a tile by name
-    pub fn get(&self, name: &str) -> Option<&Tile<'ast>> {
-        self.tiles.iter().find(|t| t.function.name == name)
+    /// Find a tile by id
+    pub fn get(&self, id: &str) -> Option<&Tile<'ast>> {
+        self.tiles.iter().find(|t| t.id() == id)
     }
 
-    /// Check if a function name is a tile
-    pub fn contains(&self, name: &str) -> bool {
-        self.tiles.iter().any(|t| t.function.name == name)
+    /// Check if an id names a tile
+    pub fn contains(&self, id: &str) -> bool {
+        self.tiles.iter().any(|t| t.id() == id)
     }
 }
@@ -0,0 +1,441 @@
+//! Type checking of the data flow between sequence items.
+//!
+//! Tile and sequence signatures are recorded in the CFS as written. Every
+//! argument bound to a sequence parameter or to a prior item's output is
+//! checked against the parameter type of the tile or sequence it feeds, so a
+//! mismatch is reported at the `call!` site at build time rather than as a
+//! decode failure at runtime.
+//!
+//! Types are compared syntactically: paths by their last segment (so
+//! `alloc::vec::Vec<String>` matches `Vec<String>`), references and lifetimes
+//! are ignored, and a fallible producer's `Result<T, _>` yields `T`. Types that
+//! cannot be compared this way (`impl Trait`, `_`, qualified paths, macros),
+//! types naming a `type` alias or a `use ... as` rename of the project, whose
+//! target is not resolved, selections that do not walk into a tuple, and recur
+//! items are left unchecked.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+
+use raster_core::cfs::{ControlFlowSchema, InputBinding, SequenceChildItem, SequenceDef};
+use raster_core::input::{SelectorPath, SelectorSegment};
+use syn::{GenericArgument, PathArguments, Type};
+
+use crate::ast::SourceLocation;
+use crate::flow_resolver::FlowResolver;
+use crate::sequence::Sequence;
+
+/// An item argument whose source has a different type than the parameter it feeds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeMismatch {
+    pub sequence: String,
+    /// Source file of the sequence.
+    pub path: PathBuf,
+    /// Location of the call macro.
+    pub location: SourceLocation,
+    /// The call as written, e.g. `call!(exclaim, ..)`.
+    pub call: String,
+    pub argument_index: usize,
+    pub argument: String,
+    pub expected: String,
+    pub found: String,
+    /// Where the argument comes from, e.g. the output of `count` (item 0).
+    pub origin: String,
+}
+
+impl fmt::Display for TypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "mismatched types in sequence `{}`: argument #{} (`{}`) of `{}` \
+             expects `{}`, found `{}` from {}",
+            self.sequence,
+            self.argument_index + 1,
+            self.argument,
+            self.call,
+            self.expected,
+            self.found,
+            self.origin
+        )?;
+        write!(
+            f,
+            "  --> {}:{}:{}",
+            self.path.display(),
+            self.location.line,
+            self.location.column
+        )
+    }
+}
+
+/// Check the arguments of every tile and sequence item of `def` against the
+/// signatures recorded in `cfs`. Types naming one of `type_aliases` are not
+/// checked.
+pub fn check_sequence(
+    cfs: &ControlFlowSchema,
+    def: &SequenceDef,
+    sequence: &Sequence<'_>,
+    type_aliases: &HashSet<String>,
+) -> Vec<TypeMismatch> {
+    let mut mismatches = Vec::new();
+
+    for (item, call) in def.items.iter().zip(FlowResolver::item_calls(sequence)) {
+        let (macro_name, param_types) = match item {
+            SequenceChildItem::Tile(tile) => (
+                "call!",
+                cfs.tiles
+                    .iter()
+                    .find(|def| def.id == tile.id)
+                    .map(|def| &def.input_types),
+            ),
+            SequenceChildItem::Sequence(seq) => (
+                "call_seq!",
+                cfs.sequences
+                    .iter()
+                    .find(|def| def.id == seq.id)
+                    .map(|def| &def.input_types),
+            ),
+            // Recur items hand their callee one element (or chunk) of the
+            // input per iteration, not the bound value itself.
+            SequenceChildItem::RecurTile(_) | SequenceChildItem::RecurSequence(_) => continue,
+        };
+        let Some(param_types) = param_types.filter(|types| types.len() == item.inputs().len())
+        else {
+            continue;
+        };
+
+        for (index, (binding, expected)) in item.inputs().iter().zip(param_types).enumerate() {
+            let Some((found, origin)) = source_type(cfs, def, binding) else {
+                continue;
+            };
+            let expected_canonical = parse_type(expected)
+                .as_ref()
+                .and_then(|ty| canonical(ty, type_aliases));
+            let (Some(expected_canonical), Some(found_canonical)) =
+                (expected_canonical, canonical(&found, type_aliases))
+            else {
+                continue;
+            };
+            if expected_canonical != found_canonical {
+                mismatches.push(TypeMismatch {
+                    sequence: def.id.clone(),
+                    path: sequence.function.path.clone(),
+                    location: call.location,
+                    call: format!("{}({}, ..)", macro_name, call.callee),
+                    argument_index: index,
+                    argument: call.arguments.get(index).cloned().unwrap_or_default(),
+                    expected: expected.clone(),
+                    found: compact_type(&quote::quote!(#found).to_string()),
+                    origin,
+                });
+            }
+        }
+    }
+
+    mismatches
+}
+
+/// Type of the value behind `binding`, with a description of where it comes from.
+fn source_type(
+    cfs: &ControlFlowSchema,
+    def: &SequenceDef,
+    binding: &InputBinding,
+) -> Option<(Type, String)> {
+    match binding {
+        InputBinding::Direct(_) => None,
+        InputBinding::SequenceScope { input_index } => {
+            let ty = parse_type(def.input_types.get(*input_index)?)?;
+            Some((ty, format!("a parameter of `{}`", def.id)))
+        }
+        InputBinding::PriorItemOutput {
+            intra_sequence_item_index,
+            selector,
+        } => {
+            let (callee, output_type) = match def.items.get(*intra_sequence_item_index)? {
+                SequenceChildItem::Tile(tile) => {
+                    let tile_def = cfs.tiles.iter().find(|tile_def| tile_def.id == tile.id)?;
+                    (&tile.id, tile_def.output_type.as_ref()?)
+                }
+                SequenceChildItem::Sequence(seq) => {
+                    let seq_def = cfs.sequences.iter().find(|seq_def| seq_def.id == seq.id)?;
+                    (&seq.id, seq_def.output_type.as_ref()?)
+                }
+                SequenceChildItem::RecurTile(_) | SequenceChildItem::RecurSequence(_) => {
+                    return None
+                }
+            };
+            let output = ok_type(parse_type(output_type)?);
+            let ty = select(&output, selector)?.clone();
+            Some((
+                ty,
+                format!(
+                    "the output of `{}` (item {})",
+                    callee, intra_sequence_item_index
+                ),
+            ))
+        }
+    }
+}
+
+fn parse_type(ty: &str) -> Option<Type> {
+    syn::parse_str(ty).ok()
+}
+
+/// The success type of a fallible `Result<T, _>` return, otherwise `ty` itself.
+fn ok_type(ty: Type) -> Type {
+    if let Type::Path(type_path) = &ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            if segment.ident == "Result" {
+                if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                    if let Some(GenericArgument::Type(ok)) = args.args.first() {
+                        return ok.clone();
+                    }
+                }
+            }
+        }
+    }
+    ty
+}
+
+/// The part of `ty` a selector points at, as far as it can be read off tuple types.
+fn select<'t>(ty: &'t Type, selector: &SelectorPath) -> Option<&'t Type> {
+    selector
+        .segments
+        .iter()
+        .try_fold(ty, |ty, segment| match (peel(ty), segment) {
+            (Type::Tuple(tuple), SelectorSegment::Index(index)) => {
+                tuple.elems.iter().nth(usize::try_from(*index).ok()?)
+            }
+            _ => None,
+        })
+}
+
+fn peel(ty: &Type) -> &Type {
+    match ty {
+        Type::Paren(paren) => peel(&paren.elem),
+        Type::Group(group) => peel(&group.elem),
+        Type::Reference(reference) => peel(&reference.elem),
+        _ => ty,
+    }
+}
+
+/// Comparable form of a type, or `None` if it cannot be compared syntactically
+/// or names one of `aliases`.
+fn canonical(ty: &Type, aliases: &HashSet<String>) -> Option<String> {
+    match peel(ty) {
+        Type::Path(type_path) if type_path.qself.is_none() => {
+            let segment = type_path.path.segments.last()?;
+            if aliases.contains(&segment.ident.to_string()) {
+                return None;
+            }
+            let mut out = segment.ident.to_string();
+            match &segment.arguments {
+                PathArguments::None => {}
+                PathArguments::AngleBracketed(args) => {
+                    let args = args
+                        .args
+                        .iter()
+                        .filter_map(|arg| match arg {
+                            GenericArgument::Lifetime(_) => None,
+                            GenericArgument::Type(ty) => Some(canonical(ty, aliases)),
+                            other => Some(Some(compact_type(&quote::quote!(#other).to_string()))),
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+                    if !args.is_empty() {
+                        out.push('<');
+                        out.push_str(&args.join(","));
+                        out.push('>');
+                    }
+                }
+                PathArguments::Parenthesized(_) => return None,
+            }
+            Some(out)
+        }
+        Type::Tuple(tuple) => {
+            let elems = tuple
+                .elems
+                .iter()
+                .map(|elem| canonical(elem, aliases))
+                .collect::<Option<Vec<_>>>()?;
+            Some(format!("({})", elems.join(",")))
+        }
+        Type::Slice(slice) => Some(format!("[{}]", canonical(&slice.elem, aliases)?)),
+        Type::Array(array) => {
+            let len = &array.len;
+            Some(format!(
+                "[{};{}]",
+                canonical(&array.elem, aliases)?,
+                compact_type(&quote::quote!(#len).to_string())
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Drop the token spacing `quote` puts into a type, keeping only the spaces
+/// that separate two words (`&'a str`, `dyn Trait`).
+pub(crate) fn compact_type(ty: &str) -> String {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '\'';
+    let chars: Vec<char> = ty.chars().collect();
+    let mut out = String::with_capacity(ty.len());
+    for (index, &c) in chars.iter().enumerate() {
+        if c.is_whitespace() {
+            let before = out.chars().last();
+            let after = chars[index + 1..].iter().find(|c| !c.is_whitespace());
+            if before.is_some_and(is_word) && after.is_some_and(|&c| is_word(c)) {
+                out.push(' ');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::ProjectAst;
+    use crate::{CfsBuilder, Project};
+    use raster_core::Error;
+
+    fn project(source: &str) -> Project {
+        let file = syn::parse_file(source).expect("valid test source");
+        let mut ast = ProjectAst::from_functions(
+            "test".to_string(),
+            PathBuf::from("/test"),
+            ProjectAst::parse_functions(&file, PathBuf::from("src/main.rs"), &[]),
+        );
+        ast.type_aliases = ProjectAst::type_aliases(&file);
+        Project {
+            name: "test".to_string(),
+            ast,
+            root_dir: PathBuf::from("/test"),
+            output_dir: PathBuf::from("/test/target/raster"),
+            target_dir: PathBuf::from("/test/target/"),
+        }
+    }
+
+    const TILES: &str = r#"
+#[tile]
+fn count(text: String) -> Result<u64> { Ok(text.len() as u64) }
+
+#[tile]
+fn split(n: u64) -> (u64, (alloc::string::String, bool)) { (n, (n.to_string(), true)) }
+
+#[tile]
+fn shout(text: String) -> String { text.to_uppercase() }
+"#;
+
+    #[test]
+    fn compact_type_keeps_only_word_separators() {
+        assert_eq!(
+            compact_type("alloc :: vec :: Vec < String >"),
+            "alloc::vec::Vec<String>"
+        );
+        assert_eq!(compact_type("(& 'a str , [u8 ; 32])"), "(&'a str,[u8;32])");
+    }
+
+    #[test]
+    fn matching_types_pass_through_results_and_tuple_selections() {
+        let source = format!(
+            "{TILES}{}",
+            r#"
+#[sequence]
+fn main(text: String) -> String {
+    let n = call!(count, text);
+    let (_, (label, _)) = call!(split, n);
+    call!(shout, label)
+}
+"#
+        );
+        let cfs = CfsBuilder::new(&project(&source))
+            .build()
+            .expect("well-typed sequence builds");
+
+        let split = cfs.tiles.iter().find(|tile| tile.id == "split").unwrap();
+        assert_eq!(split.input_types, vec!["u64"]);
+        assert_eq!(
+            split.output_type.as_deref(),
+            Some("(u64,(alloc::string::String,bool))")
+        );
+        assert_eq!(cfs.sequences[0].input_types, vec!["String"]);
+    }
+
+    #[test]
+    fn mismatched_argument_is_reported_at_the_call_site() {
+        let source = format!(
+            "{TILES}{}",
+            r#"
+#[sequence]
+fn main(text: String) -> String {
+    let n = call!(count, text);
+    call!(shout, n)
+}
+"#
+        );
+        let line = source
+            .lines()
+            .position(|line| line.contains("call!(shout, n)"))
+            .unwrap()
+            + 1;
+
+        let Err(Error::InvalidSequence(message)) = CfsBuilder::new(&project(&source)).build()
+        else {
+            panic!("mismatched sequence should not build");
+        };
+        assert_eq!(
+            message,
+            format!(
+                "1 argument(s) bound to a value of a different type\n\
+                 mismatched types in sequence `main`: argument #1 (`n`) of `call!(shout, ..)` \
+                 expects `String`, found `u64` from the output of `count` (item 0)\n  \
+                 --> src/main.rs:{}:5",
+                line
+            )
+        );
+    }
+
+    #[test]
+    fn aliased_and_renamed_types_are_not_checked() {
+        let source = r#"
+use std::string::String as Text;
+type Bytes = Vec<u8>;
+
+#[tile]
+fn encode(text: Text) -> Vec<u8> { text.into_bytes() }
+
+#[tile]
+fn checksum(bytes: Bytes) -> u64 { bytes.len() as u64 }
+
+#[sequence]
+fn main(text: String) -> u64 {
+    let bytes = call!(encode, text);
+    call!(checksum, bytes)
+}
+"#;
+
+        CfsBuilder::new(&project(source))
+            .build()
+            .expect("aliases are left unchecked");
+    }
+
+    #[test]
+    fn build_rejects_mismatched_prior_output() {
+        let source = format!(
+            "{TILES}{}",
+            r#"
+#[sequence]
+fn main(text: String) -> String {
+    let n = call!(count, text);
+    call!(shout, n)
+}
+"#
+        );
+
+        assert!(matches!(
+            CfsBuilder::new(&project(&source)).build(),
+            Err(Error::InvalidSequence(_))
+        ));
+    }
+}
@@ -0,0 +1,556 @@
+//! Values produced while a sequence runs.
+//!
+//! The sequence runner keeps item outputs and sequence arguments as the
+//! postcard bytes the tile ABI reads and writes, keyed by CFS coordinates.
+//! When a binding needs only part of a value (a tuple element, a list item or
+//! slice, the elements of a recur list), that part is cut out of the bytes by
+//! walking the value's type as written in the signature. Types whose layout
+//! cannot be read off the signature, such as user structs walked by field,
+//! are reported as errors rather than guessed.
+
+use std::collections::HashMap;
+
+use raster_core::cfs::CfsCoordinates;
+use raster_core::input::{SelectorPath, SelectorSegment};
+use raster_core::postcard;
+use raster_core::{Error, Result};
+use syn::{Expr, ExprLit, GenericArgument, Lit, PathArguments, Type};
+
+use crate::type_check::compact_type;
+
+/// A postcard-encoded value and the type it was declared with, if known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredValue {
+    pub bytes: Vec<u8>,
+    pub ty: Option<String>,
+}
+
+impl StoredValue {
+    pub fn new(bytes: Vec<u8>, ty: Option<String>) -> Self {
+        Self { bytes, ty }
+    }
+
+    /// The part of the value `selector` points at: tuple elements and list
+    /// items by `Index`, list slices by `Range`.
+    pub fn select(&self, selector: &SelectorPath) -> Result<StoredValue> {
+        if selector.segments.is_empty() {
+            return Ok(self.clone());
+        }
+
+        let mut ty = self.parsed_type()?;
+        let mut bytes = self.bytes.as_slice();
+        let mut slice = None;
+        for segment in &selector.segments {
+            match (segment, layout(&ty)) {
+                (SelectorSegment::Index(index), Layout::Tuple(elems)) => {
+                    let index = *index as usize;
+                    let elem = elems.get(index).ok_or_else(|| {
+                        Error::Other(format!(
+                            "Selector index {} is out of bounds for `{}`",
+                            index,
+                            type_name(&ty)
+                        ))
+                    })?;
+                    let offset = skip_all(&elems[..index], bytes)?;
+                    bytes = &bytes[offset..];
+                    ty = elem.clone();
+                }
+                (SelectorSegment::Index(index), Layout::List(elem, fixed_len)) => {
+                    let (len, mut offset) = list_len(fixed_len, bytes)?;
+                    if *index >= len {
+                        return Err(Error::Other(format!(
+                            "Selector index {} is out of bounds for a list of {} elements",
+                            index, len
+                        )));
+                    }
+                    for _ in 0..*index {
+                        offset += encoded_len(&elem, &bytes[offset..])?;
+                    }
+                    bytes = &bytes[offset..];
+                    ty = *elem;
+                }
+                (SelectorSegment::Range { start, end }, Layout::List(elem, fixed_len)) => {
+                    let elements = split_elements(&elem, fixed_len, bytes)?;
+                    if start > end || *end as usize > elements.len() {
+                        return Err(Error::Other(format!(
+                            "Selector range {}..{} is out of bounds for a list of {} elements",
+                            start,
+                            end,
+                            elements.len()
+                        )));
+                    }
+                    slice = Some(encode_list(&elements[*start as usize..*end as usize])?);
+                    ty = syn::parse_quote!(Vec<#elem>);
+                }
+                (segment, _) => {
+                    return Err(Error::Other(format!(
+                        "Cannot select {:?} from `{}`: only tuple and list types can be \
+                         walked without the value's definition",
+                        segment,
+                        type_name(&ty)
+                    )))
+                }
+            }
+        }
+
+        let bytes = match slice {
+            Some(slice) => slice,
+            None => bytes[..encoded_len(&ty, bytes)?].to_vec(),
+        };
+        Ok(StoredValue::new(bytes, Some(type_name(&ty))))
+    }
+
+    /// The elements of a list value, each as its own value. With `chunk`,
+    /// consecutive elements are grouped into lists of up to `chunk` elements.
+    pub fn split_list(&self, chunk: Option<u64>) -> Result<Vec<StoredValue>> {
+        let ty = self.parsed_type()?;
+        let Layout::List(elem, fixed_len) = layout(&ty) else {
+            return Err(Error::Other(format!(
+                "Expected a list value, found `{}`",
+                type_name(&ty)
+            )));
+        };
+        let elements = split_elements(&elem, fixed_len, &self.bytes)?;
+
+        match chunk {
+            None => {
+                let elem_name = type_name(&elem);
+                Ok(elements
+                    .into_iter()
+                    .map(|bytes| StoredValue::new(bytes.to_vec(), Some(elem_name.clone())))
+                    .collect())
+            }
+            Some(chunk) => {
+                let chunk_name = type_name(&syn::parse_quote!(Vec<#elem>));
+                elements
+                    .chunks(chunk.max(1) as usize)
+                    .map(|group| {
+                        Ok(StoredValue::new(
+                            encode_list(group)?,
+                            Some(chunk_name.clone()),
+                        ))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// The value written out for people: decoded along its type where the
+    /// signature spells out the layout, hex bytes otherwise.
+    pub fn describe(&self) -> String {
+        let decoded = self.parsed_type().and_then(|ty| render(&ty, &self.bytes));
+        match decoded {
+            Ok((text, len)) if len == self.bytes.len() => text,
+            _ => format!("0x{}", hex::encode(&self.bytes)),
+        }
+    }
+
+    fn parsed_type(&self) -> Result<Type> {
+        let ty = self
+            .ty
+            .as_deref()
+            .ok_or_else(|| Error::Other("Cannot walk a value whose type is unknown".to_string()))?;
+        syn::parse_str(ty).map_err(|e| Error::Other(format!("Invalid type `{}`: {}", ty, e)))
+    }
+}
+
+/// Item outputs and sequence arguments of one run, by CFS coordinates.
+///
+/// Outputs are keyed by the coordinates of the item that produced them, and
+/// arguments by the coordinates of the sequence frame that received them
+/// (empty for the entry sequence, the iteration coordinates for a recur
+/// sequence body).
+#[derive(Debug, Default)]
+pub struct ValueStore {
+    outputs: HashMap<CfsCoordinates, StoredValue>,
+    arguments: HashMap<CfsCoordinates, Vec<StoredValue>>,
+}
+
+impl ValueStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert_output(&mut self, coordinates: CfsCoordinates, value: StoredValue) {
+        self.outputs.insert(coordinates, value);
+    }
+
+    pub fn output(&self, coordinates: &CfsCoordinates) -> Option<&StoredValue> {
+        self.outputs.get(coordinates)
+    }
+
+    pub fn insert_arguments(&mut self, frame: CfsCoordinates, arguments: Vec<StoredValue>) {
+        self.arguments.insert(frame, arguments);
+    }
+
+    pub fn argument(&self, frame: &CfsCoordinates, index: usize) -> Option<&StoredValue> {
+        self.arguments.get(frame)?.get(index)
+    }
+}
+
+/// How a type is laid out in postcard, as far as the runner walks it.
+enum Layout {
+    Tuple(Vec<Type>),
+    /// Element type, and the length for fixed-size arrays.
+    List(Box<Type>, Option<u64>),
+    Other,
+}
+
+fn layout(ty: &Type) -> Layout {
+    match peel(ty) {
+        Type::Tuple(tuple) => Layout::Tuple(tuple.elems.iter().cloned().collect()),
+        Type::Slice(slice) => Layout::List(slice.elem.clone(), None),
+        Type::Array(array) => match array_len(&array.len) {
+            Some(len) => Layout::List(array.elem.clone(), Some(len)),
+            None => Layout::Other,
+        },
+        ty => match path_parts(ty) {
+            Some((name, args)) => match (name.as_str(), args.as_slice()) {
+                ("Vec" | "VecDeque", [elem]) => Layout::List(Box::new(elem.clone()), None),
+                (wrapper, [inner]) if is_transparent(wrapper) => layout(inner),
+                _ => Layout::Other,
+            },
+            None => Layout::Other,
+        },
+    }
+}
+
+/// Wrappers encoded exactly like the value they hold.
+fn is_transparent(name: &str) -> bool {
+    matches!(
+        name,
+        "Box" | "Rc" | "Arc" | "RecurState" | "RecurSequenceState" | "AuthValue"
+    )
+}
+
+/// Number of bytes the postcard encoding of a `ty` value takes at the start of `bytes`.
+fn encoded_len(ty: &Type, bytes: &[u8]) -> Result<usize> {
+    match layout(ty) {
+        Layout::Tuple(elems) => return skip_all(&elems, bytes),
+        Layout::List(elem, fixed_len) => {
+            let (len, mut offset) = list_len(fixed_len, bytes)?;
+            for _ in 0..len {
+                offset += encoded_len(&elem, &bytes[offset..])?;
+            }
+            return Ok(offset);
+        }
+        Layout::Other => {}
+    }
+
+    let unsupported = || {
+        Error::Other(format!(
+            "Cannot read the postcard layout of `{}` from its signature",
+            type_name(ty)
+        ))
+    };
+    let (name, args) = path_parts(ty).ok_or_else(unsupported)?;
+    let len = match (name.as_str(), args.as_slice()) {
+        ("u8" | "i8" | "bool", []) => 1,
+        ("f32", []) => 4,
+        ("f64", []) => 8,
+        (
+            "u16" | "u32" | "u64" | "u128" | "usize" | "i16" | "i32" | "i64" | "i128" | "isize",
+            [],
+        ) => varint_len(bytes)?,
+        ("String" | "str" | "char", []) => {
+            let (len, offset) = read_varint(bytes)?;
+            offset + len as usize
+        }
+        ("Option", [inner]) => match bytes.first() {
+            Some(0) => 1,
+            Some(1) => 1 + encoded_len(inner, &bytes[1..])?,
+            _ => return Err(truncated()),
+        },
+        ("Result" | "RecurControl", [..]) => {
+            let (variant, offset) = read_varint(bytes)?;
+            let payload = match (name.as_str(), variant) {
+                ("RecurControl", 0 | 1) => args.first(),
+                ("Result", variant) => args.get(variant as usize),
+                _ => None,
+            }
+            .ok_or_else(unsupported)?;
+            offset + encoded_len(payload, &bytes[offset..])?
+        }
+        ("RecurInput", [inner]) => {
+            let mut offset = encoded_len(inner, bytes)?;
+            offset += varint_len(&bytes[offset..])?;
+            offset + varint_len(&bytes[offset..])?
+        }
+        _ => return Err(unsupported()),
+    };
+
+    if len > bytes.len() {
+        return Err(truncated());
+    }
+    Ok(len)
+}
+
+/// A `ty` value at the start of `bytes` as text, and the number of bytes it takes.
+fn render(ty: &Type, bytes: &[u8]) -> Result<(String, usize)> {
+    match layout(ty) {
+        Layout::Tuple(elems) => {
+            let (items, len) = render_all(elems.iter(), bytes)?;
+            let trailing = if items.len() == 1 { "," } else { "" };
+            return Ok((format!("({}{})", items.join(", "), trailing), len));
+        }
+        Layout::List(elem, fixed_len) => {
+            let (count, offset) = list_len(fixed_len, bytes)?;
+            let elems = std::iter::repeat_n(elem.as_ref(), count as usize);
+            let (items, len) = render_all(elems, &bytes[offset..])?;
+            return Ok((format!("[{}]", items.join(", ")), offset + len));
+        }
+        Layout::Other => {}
+    }
+
+    let unsupported = || {
+        Error::Other(format!(
+            "Cannot read the postcard layout of `{}` from its signature",
+            type_name(ty)
+        ))
+    };
+    let (name, args) = path_parts(ty).ok_or_else(unsupported)?;
+    match (name.as_str(), args.as_slice()) {
+        ("bool", []) => take::<bool>(bytes),
+        ("u8", []) => take::<u8>(bytes),
+        ("i8", []) => take::<i8>(bytes),
+        ("u16" | "u32" | "u64" | "usize", []) => take::<u64>(bytes),
+        ("i16" | "i32" | "i64" | "isize", []) => take::<i64>(bytes),
+        ("u128", []) => take::<u128>(bytes),
+        ("i128", []) => take::<i128>(bytes),
+        ("f32", []) => take::<f32>(bytes),
+        ("f64", []) => take::<f64>(bytes),
+        ("char", []) => take::<char>(bytes),
+        ("String" | "str", []) => take::<String>(bytes),
+        ("Option", [inner]) => match bytes.first() {
+            Some(0) => Ok(("None".to_string(), 1)),
+            Some(1) => {
+                let (text, len) = render(inner, &bytes[1..])?;
+                Ok((format!("Some({})", text), 1 + len))
+            }
+            _ => Err(truncated()),
+        },
+        ("Result", [ok, err]) => {
+            let (variant, offset) = read_varint(bytes)?;
+            let (label, payload) = match variant {
+                0 => ("Ok", ok),
+                1 => ("Err", err),
+                _ => return Err(unsupported()),
+            };
+            let (text, len) = render(payload, &bytes[offset..])?;
+            Ok((format!("{}({})", label, text), offset + len))
+        }
+        _ => Err(unsupported()),
+    }
+}
+
+/// A `T` at the start of `bytes` in its `Debug` form.
+fn take<T: serde::de::DeserializeOwned + std::fmt::Debug>(bytes: &[u8]) -> Result<(String, usize)> {
+    let (value, rest) = postcard::take_from_bytes::<T>(bytes).map_err(|_| truncated())?;
+    Ok((format!("{:?}", value), bytes.len() - rest.len()))
+}
+
+fn render_all<'a>(
+    types: impl Iterator<Item = &'a Type>,
+    bytes: &[u8],
+) -> Result<(Vec<String>, usize)> {
+    let mut items = Vec::new();
+    let mut offset = 0;
+    for ty in types {
+        let (text, len) = render(ty, bytes.get(offset..).ok_or_else(truncated)?)?;
+        items.push(text);
+        offset += len;
+    }
+    Ok((items, offset))
+}
+
+fn skip_all(types: &[Type], bytes: &[u8]) -> Result<usize> {
+    types.iter().try_fold(0, |offset, ty| {
+        Ok(offset + encoded_len(ty, bytes.get(offset..).ok_or_else(truncated)?)?)
+    })
+}
+
+fn split_elements<'a>(
+    elem: &Type,
+    fixed_len: Option<u64>,
+    bytes: &'a [u8],
+) -> Result<Vec<&'a [u8]>> {
+    let (len, mut offset) = list_len(fixed_len, bytes)?;
+    let mut elements = Vec::with_capacity(len as usize);
+    for _ in 0..len {
+        let elem_len = encoded_len(elem, &bytes[offset..])?;
+        elements.push(&bytes[offset..offset + elem_len]);
+        offset += elem_len;
+    }
+    Ok(elements)
+}
+
+/// Element count of a list and the offset of its first element.
+fn list_len(fixed_len: Option<u64>, bytes: &[u8]) -> Result<(u64, usize)> {
+    match fixed_len {
+        Some(len) => Ok((len, 0)),
+        None => read_varint(bytes),
+    }
+}
+
+/// Postcard encoding of a `Vec` holding the already encoded `elements`.
+pub(crate) fn encode_list(elements: &[&[u8]]) -> Result<Vec<u8>> {
+    let mut bytes = encode_varint(elements.len() as u64)?;
+    for element in elements {
+        bytes.extend_from_slice(element);
+    }
+    Ok(bytes)
+}
+
+pub(crate) fn encode_varint(value: u64) -> Result<Vec<u8>> {
+    postcard::to_allocvec(&value)
+        .map_err(|e| Error::Serialization(format!("Failed to encode length: {}", e)))
+}
+
+/// A varint at the start of `bytes` and the number of bytes it takes.
+pub(crate) fn read_varint(bytes: &[u8]) -> Result<(u64, usize)> {
+    let (value, rest) = postcard::take_from_bytes::<u64>(bytes).map_err(|_| truncated())?;
+    Ok((value, bytes.len() - rest.len()))
+}
+
+fn varint_len(bytes: &[u8]) -> Result<usize> {
+    bytes
+        .iter()
+        .position(|byte| byte & 0x80 == 0)
+        .map(|last| last + 1)
+        .ok_or_else(truncated)
+}
+
+fn truncated() -> Error {
+    Error::Serialization("Value bytes end before the value their type describes".to_string())
+}
+
+fn peel(ty: &Type) -> &Type {
+    match ty {
+        Type::Paren(paren) => peel(&paren.elem),
+        Type::Group(group) => peel(&group.elem),
+        Type::Reference(reference) => peel(&reference.elem),
+        _ => ty,
+    }
+}
+
+/// Last path segment of `ty` and its type arguments.
+pub(crate) fn path_parts(ty: &Type) -> Option<(String, Vec<Type>)> {
+    let Type::Path(type_path) = peel(ty) else {
+        return None;
+    };
+    if type_path.qself.is_some() {
+        return None;
+    }
+    let segment = type_path.path.segments.last()?;
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    Some((segment.ident.to_string(), args))
+}
+
+fn array_len(len: &Expr) -> Option<u64> {
+    match len {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(int), ..
+        }) => int.base10_parse().ok(),
+        _ => None,
+    }
+}
+
+fn type_name(ty: &Type) -> String {
+    compact_type(&quote::quote!(#ty).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value<T: serde::Serialize>(value: &T, ty: &str) -> StoredValue {
+        StoredValue::new(postcard::to_allocvec(value).unwrap(), Some(ty.to_string()))
+    }
+
+    fn selector(segments: Vec<SelectorSegment>) -> SelectorPath {
+        SelectorPath::new(segments)
+    }
+
+    #[test]
+    fn select_walks_tuples_and_lists() {
+        let stored = value(
+            &(
+                300u64,
+                vec!["a".to_string(), "bc".to_string(), "d".to_string()],
+                -2i32,
+            ),
+            "(u64, Vec<String>, i32)",
+        );
+
+        let item = stored
+            .select(&selector(vec![
+                SelectorSegment::Index(1),
+                SelectorSegment::Index(1),
+            ]))
+            .unwrap();
+        assert_eq!(item, value(&"bc".to_string(), "String"));
+
+        let slice = stored
+            .select(&selector(vec![
+                SelectorSegment::Index(1),
+                SelectorSegment::Range { start: 1, end: 3 },
+            ]))
+            .unwrap();
+        assert_eq!(
+            slice,
+            value(&vec!["bc".to_string(), "d".to_string()], "Vec<String>")
+        );
+
+        let last = stored
+            .select(&selector(vec![SelectorSegment::Index(2)]))
+            .unwrap();
+        assert_eq!(last, value(&-2i32, "i32"));
+
+        let err = stored
+            .select(&selector(vec![SelectorSegment::Field("name".to_string())]))
+            .unwrap_err();
+        assert!(err.to_string().contains("Cannot select"), "{}", err);
+    }
+
+    #[test]
+    fn describe_decodes_values_along_their_type() {
+        let stored = value(
+            &(200u8, vec![Some(-3i64), None], "hi".to_string()),
+            "(u8, Vec<Option<i64>>, String)",
+        );
+        assert_eq!(stored.describe(), r#"(200, [Some(-3), None], "hi")"#);
+
+        let unknown = StoredValue::new(vec![1, 2, 255], Some("Point".to_string()));
+        assert_eq!(unknown.describe(), "0x0102ff");
+
+        let untyped = StoredValue::new(vec![0xab], None);
+        assert_eq!(untyped.describe(), "0xab");
+    }
+
+    #[test]
+    fn split_list_groups_chunks() {
+        let stored = value(&vec![Some(1u16), None, Some(300)], "Vec<Option<u16>>");
+
+        let elements = stored.split_list(None).unwrap();
+        assert_eq!(elements[2], value(&Some(300u16), "Option<u16>"));
+
+        let chunks = stored.split_list(Some(2)).unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                value(&vec![Some(1u16), None], "Vec<Option<u16>>"),
+                value(&vec![Some(300u16)], "Vec<Option<u16>>"),
+            ]
+        );
+    }
+}
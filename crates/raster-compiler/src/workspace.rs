@@ -0,0 +1,462 @@
+//! Cargo workspace discovery.
+//!
+//! Tiles often live in a library crate shared by several program crates. The
+//! workspace layout reported by `cargo metadata` tells which package is the
+//! project and which of its local dependencies declare tiles and sequences.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use cargo_toml::Manifest;
+use serde::Deserialize;
+
+use raster_core::{Error, Result};
+
+/// A local package (workspace member or path dependency) of a Cargo workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CargoPackage {
+    pub name: String,
+    /// Name of the library target as used in paths (`my_tiles` for package
+    /// `my-tiles`), if the package has one.
+    pub lib_name: Option<String>,
+    /// Directory containing the package's `Cargo.toml`.
+    pub manifest_dir: PathBuf,
+    /// Names of the package's normal (non-dev, non-build) dependencies.
+    pub dependencies: Vec<String>,
+}
+
+impl CargoPackage {
+    /// Whether the package depends on `raster` and so may declare tiles.
+    pub fn uses_raster(&self) -> bool {
+        self.dependencies.iter().any(|dep| dep == "raster")
+    }
+}
+
+/// The local packages of a Cargo workspace.
+#[derive(Debug, Clone)]
+pub struct CargoWorkspace {
+    pub root_dir: PathBuf,
+    pub target_dir: PathBuf,
+    /// Names of the workspace members.
+    pub members: Vec<String>,
+    /// Workspace members and path dependencies. Registry and git packages
+    /// are left out.
+    pub packages: Vec<CargoPackage>,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    workspace_root: PathBuf,
+    target_directory: PathBuf,
+    workspace_members: Vec<String>,
+    packages: Vec<MetadataPackage>,
+}
+
+#[derive(Deserialize)]
+struct MetadataPackage {
+    id: String,
+    name: String,
+    source: Option<String>,
+    manifest_path: PathBuf,
+    targets: Vec<MetadataTarget>,
+    dependencies: Vec<MetadataDependency>,
+}
+
+#[derive(Deserialize)]
+struct MetadataTarget {
+    name: String,
+    kind: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct MetadataDependency {
+    name: String,
+    kind: Option<String>,
+    /// Directory of a path dependency.
+    path: Option<PathBuf>,
+}
+
+impl MetadataPackage {
+    fn manifest_dir(&self) -> PathBuf {
+        self.manifest_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    }
+
+    /// Directories of the package's normal path dependencies.
+    fn path_dependencies(&self) -> Vec<PathBuf> {
+        self.dependencies
+            .iter()
+            .filter(|dep| dep.kind.is_none())
+            .filter_map(|dep| dep.path.clone())
+            .collect()
+    }
+}
+
+impl CargoWorkspace {
+    /// Run `cargo metadata --no-deps` for the workspace containing `dir`.
+    ///
+    /// `--no-deps` lists only the workspace members, so path dependencies
+    /// outside the workspace are read from their own manifests. When cargo
+    /// metadata fails, `dir` is taken as a single-crate project.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut metadata = match Self::metadata(dir) {
+            Ok(metadata) => metadata,
+            Err(error) => return Self::single_crate(dir).map_err(|_| error),
+        };
+
+        let mut loaded: HashSet<PathBuf> = metadata
+            .packages
+            .iter()
+            .map(MetadataPackage::manifest_dir)
+            .collect();
+        let mut pending: Vec<PathBuf> = metadata
+            .packages
+            .iter()
+            .flat_map(MetadataPackage::path_dependencies)
+            .collect();
+        while let Some(path) = pending.pop() {
+            if loaded.contains(&path) {
+                continue;
+            }
+            // A dependency that cannot be read fails the cargo build instead.
+            let Ok(dependency) = Self::metadata(&path) else {
+                continue;
+            };
+            for package in dependency.packages {
+                if loaded.insert(package.manifest_dir()) {
+                    pending.extend(package.path_dependencies());
+                    metadata.packages.push(package);
+                }
+            }
+        }
+
+        Ok(Self::from_parsed(metadata))
+    }
+
+    fn metadata(dir: &Path) -> Result<Metadata> {
+        let output = Command::new("cargo")
+            .current_dir(dir)
+            .args(["metadata", "--format-version", "1", "--no-deps"])
+            .output()
+            .map_err(|e| Error::Other(format!("Failed to run cargo metadata: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(Error::Other(format!(
+                "cargo metadata failed in {}:\n{}",
+                dir.display(),
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+
+        Ok(serde_json::from_slice(&output.stdout)?)
+    }
+
+    /// The crate in `dir` on its own, read from its `Cargo.toml`.
+    fn single_crate(dir: &Path) -> Result<Self> {
+        let manifest = Manifest::from_path(dir.join("Cargo.toml"))
+            .map_err(|e| Error::Other(format!("Failed to read Cargo.toml: {}", e)))?;
+        let package = manifest
+            .package
+            .as_ref()
+            .ok_or_else(|| Error::Other(format!("No [package] in {}", dir.display())))?;
+        let lib_name = manifest
+            .lib
+            .as_ref()
+            .and_then(|lib| lib.name.clone())
+            .or_else(|| {
+                dir.join("src/lib.rs")
+                    .exists()
+                    .then(|| package.name.clone())
+            })
+            .map(|name| name.replace('-', "_"));
+
+        Ok(Self {
+            root_dir: dir.to_path_buf(),
+            target_dir: dir.join("target"),
+            members: vec![package.name.clone()],
+            packages: vec![CargoPackage {
+                name: package.name.clone(),
+                lib_name,
+                manifest_dir: dir.to_path_buf(),
+                dependencies: manifest.dependencies.keys().cloned().collect(),
+            }],
+        })
+    }
+
+    /// Parse the JSON output of `cargo metadata --format-version 1`.
+    pub fn from_metadata(json: &str) -> Result<Self> {
+        Ok(Self::from_parsed(serde_json::from_str(json)?))
+    }
+
+    fn from_parsed(metadata: Metadata) -> Self {
+        let members = metadata
+            .packages
+            .iter()
+            .filter(|package| metadata.workspace_members.contains(&package.id))
+            .map(|package| package.name.clone())
+            .collect();
+
+        let packages = metadata
+            .packages
+            .into_iter()
+            .filter(|package| package.source.is_none())
+            .map(|package| CargoPackage {
+                lib_name: package
+                    .targets
+                    .iter()
+                    .find(|target| target.kind.iter().any(|kind| kind.ends_with("lib")))
+                    .map(|target| target.name.replace('-', "_")),
+                manifest_dir: package.manifest_dir(),
+                dependencies: package
+                    .dependencies
+                    .into_iter()
+                    .filter(|dep| dep.kind.is_none())
+                    .map(|dep| dep.name)
+                    .collect(),
+                name: package.name,
+            })
+            .collect();
+
+        Self {
+            root_dir: metadata.workspace_root,
+            target_dir: metadata.target_directory,
+            members,
+            packages,
+        }
+    }
+
+    pub fn package(&self, name: &str) -> Option<&CargoPackage> {
+        self.packages.iter().find(|package| package.name == name)
+    }
+
+    /// Select the project package: the member named `name` when given,
+    /// otherwise the member whose manifest is in `dir`, or the only member.
+    pub fn select_package(&self, dir: &Path, name: Option<&str>) -> Result<&CargoPackage> {
+        let members: Vec<&CargoPackage> = self
+            .packages
+            .iter()
+            .filter(|package| self.members.contains(&package.name))
+            .collect();
+
+        if let Some(name) = name {
+            return members
+                .iter()
+                .find(|package| package.name == name)
+                .copied()
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "Package '{}' is not a member of the workspace at {} (members: {})",
+                        name,
+                        self.root_dir.display(),
+                        self.members.join(", ")
+                    ))
+                });
+        }
+
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        let in_dir = members.iter().find(|package| {
+            package
+                .manifest_dir
+                .canonicalize()
+                .is_ok_and(|manifest_dir| manifest_dir == dir)
+        });
+        match (in_dir, members.as_slice()) {
+            (Some(package), _) => Ok(package),
+            (None, [package]) => Ok(package),
+            _ => Err(Error::Other(format!(
+                "{} is a workspace with several packages; select the project with \
+                 --package (one of: {})",
+                self.root_dir.display(),
+                self.members.join(", ")
+            ))),
+        }
+    }
+
+    /// Local crates reachable from `package` through its dependencies that
+    /// depend on `raster` themselves, in discovery order.
+    pub fn tile_dependencies(&self, package: &CargoPackage) -> Vec<&CargoPackage> {
+        let mut visited: HashSet<&str> = HashSet::from([package.name.as_str()]);
+        let mut pending: Vec<&CargoPackage> = vec![package];
+        let mut dependencies = Vec::new();
+
+        while let Some(current) = pending.pop() {
+            for name in &current.dependencies {
+                let Some(dependency) = self.package(name) else {
+                    continue;
+                };
+                if !visited.insert(dependency.name.as_str()) {
+                    continue;
+                }
+                if dependency.uses_raster() && dependency.lib_name.is_some() {
+                    dependencies.push(dependency);
+                }
+                pending.push(dependency);
+            }
+        }
+
+        dependencies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn package(name: &str, dir: &str, lib: bool, deps: &[&str]) -> String {
+        let targets = if lib {
+            format!(r#"[{{"name": "{name}", "kind": ["lib"]}}]"#)
+        } else {
+            format!(r#"[{{"name": "{name}", "kind": ["bin"]}}]"#)
+        };
+        let deps: Vec<String> = deps
+            .iter()
+            .map(|dep| format!(r#"{{"name": "{dep}", "kind": null}}"#))
+            .collect();
+        format!(
+            r#"{{"id": "{name}", "name": "{name}", "source": null,
+                "manifest_path": "{dir}/Cargo.toml", "targets": {targets},
+                "dependencies": [{deps}]}}"#,
+            deps = deps.join(",")
+        )
+    }
+
+    fn workspace() -> CargoWorkspace {
+        let packages = [
+            package("app", "/ws/app", false, &["shared-tiles", "serde"]),
+            package("other-app", "/ws/other-app", false, &["shared-tiles"]),
+            package("shared-tiles", "/ws/shared-tiles", true, &["raster", "util"]),
+            package("util", "/ws/util", true, &["more-tiles"]),
+            package("more-tiles", "/vendor/more-tiles", true, &["raster"]),
+            package("raster", "/raster/crates/raster", true, &["raster-core"]),
+            package("raster-core", "/raster/crates/raster-core", true, &[]),
+            r#"{"id": "serde", "name": "serde", "source": "registry+https://github.com/rust-lang/crates.io-index",
+                "manifest_path": "/registry/serde/Cargo.toml",
+                "targets": [{"name": "serde", "kind": ["lib"]}], "dependencies": []}"#
+                .to_string(),
+        ];
+        CargoWorkspace::from_metadata(&format!(
+            r#"{{"workspace_root": "/ws", "target_directory": "/ws/target",
+                "workspace_members": ["app", "other-app", "shared-tiles", "util"],
+                "packages": [{}]}}"#,
+            packages.join(",")
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_registry_packages_are_left_out() {
+        let workspace = workspace();
+        assert!(workspace.package("serde").is_none());
+        assert_eq!(
+            workspace
+                .package("shared-tiles")
+                .unwrap()
+                .lib_name
+                .as_deref(),
+            Some("shared_tiles")
+        );
+        assert_eq!(workspace.members.len(), 4);
+    }
+
+    #[test]
+    fn test_select_package_by_name_or_requires_one() {
+        let workspace = workspace();
+        let selected = workspace
+            .select_package(Path::new("/ws"), Some("other-app"))
+            .unwrap();
+        assert_eq!(selected.name, "other-app");
+
+        assert!(workspace
+            .select_package(Path::new("/ws"), Some("raster"))
+            .is_err());
+        let err = workspace
+            .select_package(Path::new("/ws"), None)
+            .unwrap_err();
+        assert!(err.to_string().contains("--package"));
+    }
+
+    /// Write a crate named `name` in `dir` with the given `[dependencies]`.
+    fn write_crate(dir: &Path, name: &str, dependencies: &str) {
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+                 [dependencies]\n{dependencies}\n"
+            ),
+        )
+        .unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "").unwrap();
+    }
+
+    fn unique_dir() -> PathBuf {
+        static UNIQUE_DIR_COUNTER: AtomicU64 = AtomicU64::new(0);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let counter = UNIQUE_DIR_COUNTER.fetch_add(1, Ordering::Relaxed);
+        std::env::temp_dir().join(format!("raster-workspace-test-{}-{}", nanos, counter))
+    }
+
+    #[test]
+    fn test_load_follows_path_dependencies_outside_the_workspace() {
+        let root = unique_dir();
+        write_crate(&root.join("raster"), "raster", "");
+        write_crate(
+            &root.join("tiles"),
+            "tiles",
+            r#"raster = { path = "../raster" }"#,
+        );
+        write_crate(&root.join("app"), "app", r#"tiles = { path = "../tiles" }"#);
+
+        let workspace = CargoWorkspace::load(&root.join("app")).unwrap();
+        assert_eq!(workspace.members, vec!["app"]);
+        let app = workspace.package("app").unwrap();
+        let names: Vec<&str> = workspace
+            .tile_dependencies(app)
+            .iter()
+            .map(|package| package.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["tiles"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_load_falls_back_to_the_crate_when_metadata_fails() {
+        let root = unique_dir();
+        // A crate inside a workspace that does not list it makes
+        // `cargo metadata` fail.
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("Cargo.toml"), "[workspace]\nmembers = []\n").unwrap();
+        let dir = root.join("app");
+        write_crate(&dir, "app", r#"tiles = { path = "../tiles" }"#);
+
+        let workspace = CargoWorkspace::load(&dir).unwrap();
+        assert_eq!(workspace.members, vec!["app"]);
+        assert_eq!(workspace.target_dir, dir.join("target"));
+        let app = workspace.select_package(&dir, None).unwrap();
+        assert_eq!(app.lib_name.as_deref(), Some("app"));
+        assert_eq!(app.dependencies, vec!["tiles"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_tile_dependencies_are_transitive_raster_users() {
+        let workspace = workspace();
+        let app = workspace.package("app").unwrap();
+        let names: Vec<&str> = workspace
+            .tile_dependencies(app)
+            .iter()
+            .map(|package| package.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["shared-tiles", "more-tiles"]);
+    }
+}
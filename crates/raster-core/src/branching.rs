@@ -0,0 +1,218 @@
+//! Selection of sequence branch arms (`BranchItem`) from the committed
+//! output of their condition tile.
+//!
+//! The condition output is read from its raster payload — the bytes whose
+//! subtree root is the tile's `output_commitment` — so the native recorder and
+//! the transition guest decide the taken arm from the same committed value.
+//! Only the top-level node is inspected: a `bool` leaf or an enum variant name.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::cfs::{BranchArm, BranchPattern};
+use crate::input::{verify_selection_proof, SelectionProof, SelectorPath};
+
+/// The part of a condition output that arm patterns are matched against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BranchDiscriminant {
+    /// A scalar leaf, carrying its canonical leaf bytes (`[0]` / `[1]` for `bool`).
+    Leaf(Vec<u8>),
+    /// An enum value, carrying its variant name.
+    Variant(String),
+}
+
+/// A violation of the CFS-declared branch structure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BranchViolation {
+    /// The condition output is not a raster payload with a matchable root.
+    Undecodable,
+    /// The condition output does not hash to the committed output root.
+    CommitmentMismatch,
+    /// No arm pattern matches the condition output.
+    NoMatchingArm(BranchDiscriminant),
+    /// An item was executed outside the arm selected by the condition.
+    OutsideTakenArm { arm_index: usize, item_index: usize },
+}
+
+impl fmt::Display for BranchViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Undecodable => write!(f, "branch condition output is not decodable"),
+            Self::CommitmentMismatch => write!(
+                f,
+                "branch condition output does not match its output commitment"
+            ),
+            Self::NoMatchingArm(discriminant) => write!(
+                f,
+                "no branch arm matches condition value {:?}",
+                discriminant
+            ),
+            Self::OutsideTakenArm {
+                arm_index,
+                item_index,
+            } => write!(
+                f,
+                "sequence item {} executed outside taken branch arm {}",
+                item_index, arm_index
+            ),
+        }
+    }
+}
+
+fn read_u64(bytes: &[u8], offset: &mut usize) -> Option<u64> {
+    let end = offset.checked_add(8)?;
+    let value = u64::from_le_bytes(bytes.get(*offset..end)?.try_into().ok()?);
+    *offset = end;
+    Some(value)
+}
+
+fn read_len_prefixed<'a>(bytes: &'a [u8], offset: &mut usize) -> Option<&'a [u8]> {
+    let len = usize::try_from(read_u64(bytes, offset)?).ok()?;
+    let end = offset.checked_add(len)?;
+    let slice = bytes.get(*offset..end)?;
+    *offset = end;
+    Some(slice)
+}
+
+/// Decode the discriminant of a raster payload's root node.
+pub fn branch_discriminant(raster_bytes: &[u8]) -> Option<BranchDiscriminant> {
+    let mut offset = 1;
+    match *raster_bytes.first()? {
+        // Leaf
+        0x00 => read_len_prefixed(raster_bytes, &mut offset)
+            .map(|leaf| BranchDiscriminant::Leaf(leaf.to_vec())),
+        // Unit, newtype, tuple and struct enum variants
+        0x05..=0x08 => {
+            let name = read_len_prefixed(raster_bytes, &mut offset)?;
+            let name = core::str::from_utf8(name).ok()?;
+            Some(BranchDiscriminant::Variant(String::from(name)))
+        }
+        _ => None,
+    }
+}
+
+/// Whether an arm pattern accepts a condition value.
+pub fn pattern_matches(pattern: &BranchPattern, discriminant: &BranchDiscriminant) -> bool {
+    match (pattern, discriminant) {
+        (BranchPattern::Wildcard, _) => true,
+        (BranchPattern::Bool(expected), BranchDiscriminant::Leaf(bytes)) => {
+            bytes.as_slice() == [u8::from(*expected)]
+        }
+        (BranchPattern::Variant(expected), BranchDiscriminant::Variant(name)) => expected == name,
+        _ => false,
+    }
+}
+
+/// Index of the first arm whose pattern accepts the condition output.
+pub fn select_arm(arms: &[BranchArm], raster_bytes: &[u8]) -> Result<usize, BranchViolation> {
+    let discriminant = branch_discriminant(raster_bytes).ok_or(BranchViolation::Undecodable)?;
+    select_discriminant_arm(arms, discriminant)
+}
+
+/// Index of the first arm whose pattern accepts an already decoded condition value.
+pub fn select_discriminant_arm(
+    arms: &[BranchArm],
+    discriminant: BranchDiscriminant,
+) -> Result<usize, BranchViolation> {
+    arms.iter()
+        .position(|arm| pattern_matches(&arm.pattern, &discriminant))
+        .ok_or(BranchViolation::NoMatchingArm(discriminant))
+}
+
+/// Like [`select_arm`], but first checks that `raster_bytes` is the full raster
+/// payload committed to by `output_commitment`.
+pub fn select_committed_arm(
+    arms: &[BranchArm],
+    raster_bytes: &[u8],
+    output_commitment: &[u8],
+) -> Result<usize, BranchViolation> {
+    let root_hash = output_commitment
+        .try_into()
+        .map_err(|_| BranchViolation::CommitmentMismatch)?;
+    let proof = SelectionProof {
+        path: SelectorPath::default(),
+        root_hash,
+        steps: Vec::new(),
+    };
+    if !verify_selection_proof(raster_bytes, &proof) {
+        return Err(BranchViolation::CommitmentMismatch);
+    }
+    select_arm(arms, raster_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn leaf(bytes: &[u8]) -> Vec<u8> {
+        let mut payload = vec![0x00];
+        payload.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+        payload.extend_from_slice(bytes);
+        payload
+    }
+
+    fn enum_unit(name: &str) -> Vec<u8> {
+        let mut payload = vec![0x05];
+        payload.extend_from_slice(&(name.len() as u64).to_le_bytes());
+        payload.extend_from_slice(name.as_bytes());
+        payload
+    }
+
+    fn arm(pattern: BranchPattern) -> BranchArm {
+        BranchArm { pattern, len: 1 }
+    }
+
+    #[test]
+    fn bool_condition_selects_then_or_else_arm() {
+        let arms = [arm(BranchPattern::Bool(true)), arm(BranchPattern::Wildcard)];
+
+        assert_eq!(select_arm(&arms, &leaf(&[1])), Ok(0));
+        assert_eq!(select_arm(&arms, &leaf(&[0])), Ok(1));
+    }
+
+    #[test]
+    fn variant_condition_selects_first_matching_arm() {
+        let arms = [
+            arm(BranchPattern::Variant(String::from("Accept"))),
+            arm(BranchPattern::Variant(String::from("Reject"))),
+            arm(BranchPattern::Wildcard),
+        ];
+
+        assert_eq!(select_arm(&arms, &enum_unit("Reject")), Ok(1));
+        assert_eq!(select_arm(&arms, &enum_unit("Defer")), Ok(2));
+    }
+
+    #[test]
+    fn unmatched_or_undecodable_condition_is_a_violation() {
+        let arms = [arm(BranchPattern::Bool(true))];
+
+        assert_eq!(
+            select_arm(&arms, &leaf(&[0])),
+            Err(BranchViolation::NoMatchingArm(BranchDiscriminant::Leaf(
+                vec![0]
+            )))
+        );
+        assert_eq!(
+            select_arm(&arms, &[0x03]),
+            Err(BranchViolation::Undecodable)
+        );
+    }
+
+    #[test]
+    fn committed_arm_requires_matching_output_root() {
+        let arms = [
+            arm(BranchPattern::Bool(true)),
+            arm(BranchPattern::Bool(false)),
+        ];
+        let payload = leaf(&[1]);
+        let root = crate::input::selection_hash(&[b"leaf", &[1]]);
+
+        assert_eq!(select_committed_arm(&arms, &payload, &root), Ok(0));
+        assert_eq!(
+            select_committed_arm(&arms, &leaf(&[0]), &root),
+            Err(BranchViolation::CommitmentMismatch)
+        );
+    }
+}
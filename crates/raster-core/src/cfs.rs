@@ -10,6 +10,10 @@ use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::ops::{Deref, DerefMut};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+use crate::input::SelectorPath;
 
 pub type CfsCoordinate = u32;
 
@@ -58,26 +62,45 @@ pub struct CfsCursor {
 
 impl CfsCursor {
     pub fn new(cfs: ControlFlowSchema) -> Self {
+        Self::with_entrypoint(cfs, "main").expect("Missing main entrypoint")
+    }
+
+    /// A cursor whose empty coordinates address the sequence `sequence_id`
+    /// instead of `main`. `None` if the schema has no such sequence.
+    pub fn with_entrypoint(cfs: ControlFlowSchema, sequence_id: &str) -> Option<Self> {
         let entrypoint_coordinate: u32 = cfs
             .sequences
             .iter()
-            .position(|s| s.id == "main")
-            .expect("Missing main entrypoint")
+            .position(|s| s.id == sequence_id)?
             .try_into()
             .expect("Sequence definitions out of bounds");
 
-        Self {
+        Some(Self {
             cfs,
             entrypoint_coordinate,
 
             coordinates: CfsCoordinates::new(),
-        }
+        })
+    }
+
+    pub fn cfs(&self) -> &ControlFlowSchema {
+        &self.cfs
+    }
+
+    /// The sequence addressed by the empty coordinates.
+    pub fn entrypoint(&self) -> &SequenceDef {
+        &self.cfs.sequences[self.entrypoint_coordinate as usize]
     }
 
     pub fn coordinates(&self) -> CfsCoordinates {
         self.coordinates.clone()
     }
 
+    /// See [`ControlFlowSchema::resolve_id`].
+    pub fn resolve_id<'a>(&'a self, module_path: &str, fn_name: &'a str) -> &'a str {
+        self.cfs.resolve_id(module_path, fn_name)
+    }
+
     pub fn set_coordinates(&mut self, coordinates: CfsCoordinates) {
         self.coordinates = coordinates;
     }
@@ -103,113 +126,132 @@ impl CfsCursor {
             return Some(Vec::from([next_iteration_coordinates, site_coordinates]));
         }
 
-        let mut current_coordinates = coordinates.clone();
-        loop {
-            let (current_sequence, current_item_coordinate) =
-                self.get_sequence(&current_coordinates);
-            let sequence_last_coordinate = current_sequence.items.len() - 1;
+        let (current_sequence, current_item_coordinate) = self.get_sequence(coordinates);
 
-            // if the item is not the sequence itself we can try find next item within that
-            // sequence (last coordinate, is a sequence coordinate)
-            match current_item_coordinate {
-                Some(current_item_coordinate) => {
-                    let next_item_coordinate = current_item_coordinate + 1;
+        // if the item is not the sequence itself we can try find next item within that
+        // sequence (last coordinate, is a sequence coordinate)
+        match current_item_coordinate {
+            Some(current_item_coordinate) => self.next_coordinates_from_positions(
+                coordinates,
+                current_sequence.successor_positions(current_item_coordinate),
+            ),
+            None => {
+                let mut next_coordinates_options: Vec<CfsCoordinates> = Vec::new();
 
-                    if current_sequence
-                        .items
-                        .get(next_item_coordinate as usize)
-                        .is_some()
-                    {
-                        let mut next_coordinates = current_coordinates.clone();
-
-                        match next_coordinates.last_mut() {
-                            Some(coordinate) => {
-                                *coordinate = next_item_coordinate;
-                            }
-                            // TODO: is this actually possible?
-                            None => {
-                                return None;
-                            }
-                        }
+                next_coordinates_options.push(coordinates.clone());
+
+                let mut next_coordinates = coordinates.clone();
+
+                next_coordinates.push(0);
+                if self.try_get_item(&next_coordinates).is_some() {
+                    next_coordinates_options
+                        .extend(self.expand_recur_entry_coordinates(next_coordinates));
+                }
+
+                let Some((current_sequence_coordinate, parent_sequence_coordinates)) =
+                    coordinates.split_last()
+                else {
+                    // Entrypoint start
+                    next_coordinates_options.push(coordinates.clone());
 
-                        return Some(self.expand_recur_entry_coordinates(next_coordinates));
-                    } else if current_item_coordinate as usize == sequence_last_coordinate {
-                        let (current_sequence_coordinate, parent_sequence_coordinates) =
-                            current_coordinates.split_last().expect("Empty coordinates");
-                        let parent_sequence_coordinates =
-                            CfsCoordinates(parent_sequence_coordinates.to_vec());
+                    return Some(next_coordinates_options);
+                };
 
-                        let (parent_sequence, _) = self.get_sequence(&parent_sequence_coordinates);
+                let parent_sequence_coordinates =
+                    CfsCoordinates(parent_sequence_coordinates.to_vec());
+                let (parent_sequence, _) = self.get_sequence(&parent_sequence_coordinates);
 
-                        if let Some(_next_item) = parent_sequence
-                            .items
-                            .get((*current_sequence_coordinate + 1) as usize)
-                        {
+                for position in parent_sequence.successor_positions(*current_sequence_coordinate) {
+                    match position {
+                        Some(position) => {
                             let mut next_coordinates = parent_sequence_coordinates.clone();
-                            next_coordinates.push(*current_sequence_coordinate + 1);
+                            next_coordinates.push(position);
 
                             if self.try_get_item(&next_coordinates).is_some() {
-                                return Some(self.expand_recur_entry_coordinates(next_coordinates));
+                                next_coordinates_options
+                                    .extend(self.expand_recur_entry_coordinates(next_coordinates));
                             }
-
-                            return None;
                         }
-
-                        if parent_sequence_coordinates.is_empty() {
-                            return None;
+                        None => {
+                            let mut next_coordinates = coordinates.clone();
+                            next_coordinates.pop();
+                            next_coordinates_options.push(next_coordinates);
                         }
-                        current_coordinates = parent_sequence_coordinates;
-                    } else {
-                        return None;
                     }
                 }
-                None => {
-                    let mut next_coordinates_options: Vec<CfsCoordinates> = Vec::new();
-
-                    next_coordinates_options.push(current_coordinates.clone());
 
-                    let mut next_coordinates = current_coordinates.clone();
+                Some(next_coordinates_options)
+            }
+        }
+    }
 
-                    next_coordinates.push(0);
-                    if self.try_get_item(&next_coordinates).is_some() {
-                        next_coordinates_options
-                            .extend(self.expand_recur_entry_coordinates(next_coordinates));
-                    }
+    /// The branch whose arm is selected by the output of the item at
+    /// `condition_coordinates`, if any.
+    pub fn try_get_branch(&self, condition_coordinates: &CfsCoordinates) -> Option<&BranchItem> {
+        if self
+            .try_get_recur_iteration_coordinates(condition_coordinates)
+            .is_some()
+        {
+            return None;
+        }
+        let (sequence, item_coordinate) = self.get_sequence(condition_coordinates);
+        let item_coordinate = item_coordinate? as usize;
+        sequence
+            .branches
+            .iter()
+            .find(|branch| branch.condition == item_coordinate)
+    }
 
-                    let Some((current_sequence_coordinate, parent_sequence_coordinates)) =
-                        current_coordinates.split_last()
-                    else {
-                        // Entrypoint start
-                        next_coordinates_options.push(current_coordinates.clone());
+    /// Next coordinates after the branch condition at `condition_coordinates`
+    /// when its output selected `arm_index`. This is the per-arm subset of
+    /// [`Self::try_get_next_coordinates`] for the same coordinates.
+    pub fn try_get_branch_arm_next_coordinates(
+        &self,
+        condition_coordinates: &CfsCoordinates,
+        arm_index: usize,
+    ) -> Option<Vec<CfsCoordinates>> {
+        let (sequence, _) = self.get_sequence(condition_coordinates);
+        let branch = self.try_get_branch(condition_coordinates)?;
+        let positions = sequence.arm_entry_positions(branch, arm_index)?;
 
-                        return Some(next_coordinates_options);
-                    };
+        self.next_coordinates_from_positions(condition_coordinates, positions)
+    }
 
-                    let parent_sequence_coordinates =
-                        CfsCoordinates(parent_sequence_coordinates.to_vec());
-                    let (parent_sequence, _) = self.get_sequence(&parent_sequence_coordinates);
+    /// Turn successor positions of the item at `coordinates` (within its own
+    /// sequence) into next coordinates, walking up to the parent sequence when
+    /// a position leaves the sequence.
+    fn next_coordinates_from_positions(
+        &self,
+        coordinates: &CfsCoordinates,
+        positions: Vec<Option<CfsCoordinate>>,
+    ) -> Option<Vec<CfsCoordinates>> {
+        let mut next_coordinates_options: Vec<CfsCoordinates> = Vec::new();
+        let mut exits_sequence = false;
+
+        for position in positions {
+            let Some(position) = position else {
+                exits_sequence = true;
+                continue;
+            };
+            let mut next_coordinates = coordinates.clone();
+            *next_coordinates.last_mut()? = position;
+            next_coordinates_options.extend(self.expand_recur_entry_coordinates(next_coordinates));
+        }
 
-                    if let Some(_next_item) = parent_sequence
-                        .items
-                        .get((*current_sequence_coordinate + 1) as usize)
-                    {
-                        let mut next_coordinates = parent_sequence_coordinates.clone();
-                        next_coordinates.push(*current_sequence_coordinate + 1);
-
-                        if self.try_get_item(&next_coordinates).is_some() {
-                            next_coordinates_options
-                                .extend(self.expand_recur_entry_coordinates(next_coordinates));
-                        }
-                    } else {
-                        let mut next_coordinates = current_coordinates.clone();
-                        next_coordinates.pop();
-                        next_coordinates_options.push(next_coordinates);
-                    }
+        if !exits_sequence {
+            return Some(next_coordinates_options);
+        }
 
-                    return Some(next_coordinates_options);
-                }
-            }
+        let (_, parent_sequence_coordinates) = coordinates.split_last()?;
+        let parent_sequence_coordinates = CfsCoordinates(parent_sequence_coordinates.to_vec());
+        if parent_sequence_coordinates.is_empty() {
+            return (!next_coordinates_options.is_empty()).then_some(next_coordinates_options);
         }
+
+        let mut parent_options = self.try_get_next_coordinates(&parent_sequence_coordinates)?;
+        next_coordinates_options.append(&mut parent_options);
+
+        Some(next_coordinates_options)
     }
 
     fn sequence_by_id(&self, id: &str) -> &SequenceDef {
@@ -322,35 +364,9 @@ impl CfsCursor {
 
         child_id: SequenceChildId,
     ) -> CfsCoordinates {
-        if parent_coords.is_empty() && child_id == SequenceChildId::Sequence("main".to_string()) {
-            return parent_coords.clone();
-        }
-
-        let (parent_sequence, _sequence_item_coord) = self.get_sequence(parent_coords);
-
-        let child_coord = parent_sequence
-            .items
-            .iter()
-            .enumerate()
-            .position(|(index, item)| {
-                let id = match item {
-                    SequenceChildItem::Sequence(sequence_item) => {
-                        SequenceChildId::Sequence(sequence_item.id.clone())
-                    }
-                    SequenceChildItem::Tile(tile_item) => {
-                        SequenceChildId::Tile(tile_item.id.clone())
-                    }
-                    SequenceChildItem::RecurTile(recur_item) => {
-                        SequenceChildId::RecurTile(recur_item.id.clone())
-                    }
-                    SequenceChildItem::RecurSequence(recur_sequence_item) => {
-                        SequenceChildId::RecurSequence(recur_sequence_item.id.clone())
-                    }
-                };
-
-                id == child_id && index >= parent_current_index as usize
-            })
+        self.try_get_child_coordinates(parent_coords, parent_current_index, &child_id)
             .unwrap_or_else(|| {
+                let (parent_sequence, _) = self.get_sequence(parent_coords);
                 panic!(
                     "Wrong coordinates for sequence child '{:?}[index: {}]': [{} [{:?}] {:?}]",
                     child_id,
@@ -360,16 +376,34 @@ impl CfsCursor {
                     parent_sequence
                         .items
                         .iter()
-                        .cloned()
-                        .map(|item| match item {
-                            SequenceChildItem::Sequence(item) => item.id,
-                            SequenceChildItem::Tile(item) => item.id,
-                            SequenceChildItem::RecurTile(item) => item.id,
-                            SequenceChildItem::RecurSequence(item) => item.id,
-                        })
+                        .map(|item| SequenceChildId::of(item).id().to_string())
                         .collect::<Vec<_>>()
                 )
-            });
+            })
+    }
+
+    /// Coordinates of the first `child_id` item of the sequence at
+    /// `parent_coords` at or after `parent_current_index`, or `None` when the
+    /// sequence has no such item left.
+    pub fn try_get_child_coordinates(
+        &self,
+        parent_coords: &CfsCoordinates,
+        parent_current_index: CfsCoordinate,
+        child_id: &SequenceChildId,
+    ) -> Option<CfsCoordinates> {
+        if parent_coords.is_empty() && *child_id == SequenceChildId::Sequence("main".to_string()) {
+            return Some(parent_coords.clone());
+        }
+
+        let (parent_sequence, _sequence_item_coord) = self.get_sequence(parent_coords);
+
+        let child_coord = parent_sequence
+            .items
+            .iter()
+            .enumerate()
+            .position(|(index, item)| {
+                SequenceChildId::of(item) == *child_id && index >= parent_current_index as usize
+            })?;
 
         let mut current_coords = parent_coords.clone();
         current_coords.push(
@@ -378,7 +412,23 @@ impl CfsCursor {
                 .expect("Sequence coordinate out ouf bound u8"),
         );
 
-        current_coords
+        Some(current_coords)
+    }
+
+    /// Items of the sequence at `parent_coords` from `from_index` on: the
+    /// children it can still run.
+    pub fn remaining_children(
+        &self,
+        parent_coords: &CfsCoordinates,
+        from_index: CfsCoordinate,
+    ) -> Vec<SequenceChildId> {
+        let (parent_sequence, _) = self.get_sequence(parent_coords);
+        parent_sequence
+            .items
+            .iter()
+            .skip(from_index as usize)
+            .map(SequenceChildId::of)
+            .collect()
     }
 
     pub fn try_get_recur_iteration_coordinates(
@@ -408,6 +458,20 @@ impl CfsCursor {
     }
 }
 
+/// Format version of [`ControlFlowSchema`] written by this build.
+///
+/// `1.2` adds the argument and return types of tiles and sequences. `1.1`
+/// added `RecurTileItem::chunk`, `SequenceDef::branches` and the
+/// `PriorItemOutput` selector. Older documents are upgraded by
+/// [`crate::cfs_migration`].
+pub const CFS_VERSION: &str = "1.4";
+
+/// Version of the [`ControlFlowSchema::digest`] encoding. Bump it whenever the
+/// digest preimage changes so digests from different encodings never collide.
+pub const CFS_DIGEST_VERSION: u8 = 1;
+
+const CFS_DIGEST_DOMAIN: &[u8] = b"raster.cfs.digest";
+
 /// The root control flow schema structure for a Raster project.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ControlFlowSchema {
@@ -427,19 +491,82 @@ impl ControlFlowSchema {
     /// Create a new CFS with the given project name.
     pub fn new(project: impl Into<String>) -> Self {
         Self {
-            version: "1.0".to_string(),
+            version: CFS_VERSION.to_string(),
             project: project.into(),
             encoding: "postcard".to_string(),
             tiles: Vec::new(),
             sequences: Vec::new(),
         }
     }
+
+    /// Reject a schema whose `version` this build does not understand.
+    pub fn check_version(&self) -> Result<()> {
+        if self.version != CFS_VERSION {
+            return Err(Error::UnsupportedVersion {
+                format: "CFS",
+                found: self.version.clone(),
+                supported: CFS_VERSION,
+            });
+        }
+        Ok(())
+    }
+
+    /// Parse CFS JSON, upgrading documents written by older format versions.
+    #[cfg(feature = "std")]
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let cfs: Self = serde_json::from_value(crate::cfs_migration::migrate(value)?)?;
+        cfs.check_version()?;
+        Ok(cfs)
+    }
+
+    /// Canonical digest of the schema: SHA-256 over a domain tag, the
+    /// [`CFS_DIGEST_VERSION`] byte and the postcard encoding of the schema.
+    ///
+    /// Trace commitments and transition journals carry this digest so a
+    /// commitment or proof can only be checked against the CFS it was built for.
+    pub fn digest(&self) -> Vec<u8> {
+        let encoded = postcard::to_allocvec(self).expect("CFS is always postcard-serializable");
+        let mut hasher = Sha256::new();
+        hasher.update(CFS_DIGEST_DOMAIN);
+        hasher.update([CFS_DIGEST_VERSION]);
+        hasher.update(&encoded);
+        hasher.finalize().to_vec()
+    }
+
+    /// Resolve a function, named as at runtime by its `module_path!()` and
+    /// bare name, to the id of its tile or sequence definition.
+    ///
+    /// Definitions are matched on their module-qualified `path`. A name no
+    /// path claims resolves to itself, which is its id whenever it is unique.
+    pub fn resolve_id<'a>(&'a self, module_path: &str, fn_name: &'a str) -> &'a str {
+        let matches = |path: &str, module: &str| match path.strip_suffix(fn_name) {
+            Some(prefix) if module.is_empty() => prefix.is_empty(),
+            Some(prefix) => prefix.strip_suffix("::") == Some(module),
+            None => false,
+        };
+        let find = |module: &str| {
+            self.tiles
+                .iter()
+                .map(|tile| (&tile.path, &tile.id))
+                .chain(self.sequences.iter().map(|seq| (&seq.path, &seq.id)))
+                .find(|(path, _)| matches(path, module))
+                .map(|(_, id)| id.as_str())
+        };
+        // Paths of functions declared in dependency crates keep the crate
+        // name; the project crate's own paths omit it.
+        let crate_relative = module_path.split_once("::").map_or("", |(_, rest)| rest);
+        find(module_path)
+            .or_else(|| find(crate_relative))
+            .unwrap_or(fn_name)
+    }
 }
 
 /// Definition of a tile in the CFS.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TileDef {
-    /// Unique identifier for the tile (function name).
+    /// Unique identifier for the tile: the function name, or its module
+    /// path when several tiles share that name.
     pub id: String,
     /// Tile type (e.g., "iter" for iterator-style tiles).
     #[serde(rename = "type")]
@@ -448,6 +575,19 @@ pub struct TileDef {
     pub inputs: usize,
     /// Number of output values.
     pub outputs: usize,
+    /// Argument types as written in the tile signature. Empty when unknown.
+    #[serde(default)]
+    pub input_types: Vec<String>,
+    /// Return type as written in the tile signature. `None` for tiles without
+    /// a return value, or when unknown.
+    #[serde(default)]
+    pub output_type: Option<String>,
+    /// Module-qualified path of the tile function (`module::name`). The
+    /// crate root is omitted for the project crate and kept for tiles
+    /// declared in a dependency (`shared_tiles::module::name`). Empty when
+    /// unknown.
+    #[serde(default)]
+    pub path: String,
 }
 
 impl TileDef {
@@ -463,9 +603,25 @@ impl TileDef {
             tile_type: tile_type.into(),
             inputs,
             outputs,
+            input_types: Vec::new(),
+            output_type: None,
+            path: String::new(),
         }
     }
 
+    /// Record the argument and return types of the tile signature.
+    pub fn with_types(mut self, input_types: Vec<String>, output_type: Option<String>) -> Self {
+        self.input_types = input_types;
+        self.output_type = output_type;
+        self
+    }
+
+    /// Record the module-qualified path of the tile function.
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
     /// Create a new tile definition with the default "iter" type.
     pub fn iter(id: impl Into<String>, inputs: usize, outputs: usize) -> Self {
         Self::new(id, "iter", inputs, outputs)
@@ -475,7 +631,7 @@ impl TileDef {
 pub type SequenceId = String;
 pub type TileId = String;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum SequenceChildId {
     Sequence(SequenceId),
     Tile(TileId),
@@ -483,11 +639,56 @@ pub enum SequenceChildId {
     RecurSequence(SequenceId),
 }
 
+impl SequenceChildId {
+    pub fn of(item: &SequenceChildItem) -> Self {
+        match item {
+            SequenceChildItem::Sequence(item) => Self::Sequence(item.id.clone()),
+            SequenceChildItem::Tile(item) => Self::Tile(item.id.clone()),
+            SequenceChildItem::RecurTile(item) => Self::RecurTile(item.id.clone()),
+            SequenceChildItem::RecurSequence(item) => Self::RecurSequence(item.id.clone()),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Sequence(id) | Self::Tile(id) | Self::RecurTile(id) | Self::RecurSequence(id) => {
+                id
+            }
+        }
+    }
+}
+
+impl core::fmt::Display for SequenceChildId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Sequence(id) => write!(f, "sequence `{}`", id),
+            Self::Tile(id) => write!(f, "tile `{}`", id),
+            Self::RecurTile(id) => write!(f, "recur tile `{}`", id),
+            Self::RecurSequence(id) => write!(f, "recur sequence `{}`", id),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SequenceDef {
     pub id: SequenceId,
     pub input_sources: Vec<InputBinding>,
     pub items: Vec<SequenceChildItem>,
+    /// Conditional regions over `items`. Branches never own coordinates: their
+    /// arms are contiguous runs of ordinary items, so a linear sequence is
+    /// simply one without branches.
+    #[serde(default)]
+    pub branches: Vec<BranchItem>,
+    /// Parameter types as written in the sequence signature. Empty when unknown.
+    #[serde(default)]
+    pub input_types: Vec<String>,
+    /// Return type as written in the sequence signature, if any.
+    #[serde(default)]
+    pub output_type: Option<String>,
+    /// Module-qualified path of the sequence function, following the same
+    /// crate convention as [`TileDef::path`]. Empty when unknown.
+    #[serde(default)]
+    pub path: String,
 }
 
 impl SequenceDef {
@@ -497,7 +698,114 @@ impl SequenceDef {
             id: id.into(),
             input_sources: Vec::new(),
             items: Vec::new(),
+            branches: Vec::new(),
+            input_types: Vec::new(),
+            output_type: None,
+            path: String::new(),
+        }
+    }
+
+    /// Positions reachable right after the item at `index` finished, with
+    /// branch arms resolved. `None` means execution leaves the sequence.
+    pub fn successor_positions(&self, index: CfsCoordinate) -> Vec<Option<CfsCoordinate>> {
+        let index = index as usize;
+        let position = self.position_after_span(index, index + 1, None);
+        self.entry_positions(position)
+    }
+
+    /// Entry positions of one arm of `branch`: the arm's first item, or
+    /// whatever follows the branch when the arm is empty.
+    pub fn arm_entry_positions(
+        &self,
+        branch: &BranchItem,
+        arm_index: usize,
+    ) -> Option<Vec<Option<CfsCoordinate>>> {
+        let arm_range = branch.arm_range(arm_index)?;
+        if arm_range.is_empty() {
+            let branch_index = self.branch_index(branch)?;
+            let position = self.position_after_span(branch.start, branch.end(), Some(branch_index));
+            return Some(self.entry_positions(position));
+        }
+
+        Some(Vec::from([Some(arm_range.start as CfsCoordinate)]))
+    }
+
+    fn branch_index(&self, branch: &BranchItem) -> Option<usize> {
+        self.branches
+            .iter()
+            .position(|candidate| candidate.condition == branch.condition)
+    }
+
+    /// The position after the span `[start, end)` has executed, leaving every
+    /// enclosing arm that the span completes. `exited_branch` is the branch the
+    /// span represents, if any, so it is not mistaken for its own enclosing arm.
+    fn position_after_span(
+        &self,
+        mut start: usize,
+        mut end: usize,
+        mut exited_branch: Option<usize>,
+    ) -> usize {
+        loop {
+            let enclosing = self
+                .branches
+                .iter()
+                .enumerate()
+                .filter(|(branch_index, _)| Some(*branch_index) != exited_branch)
+                .flat_map(|(branch_index, branch)| {
+                    (0..branch.arms.len())
+                        .filter_map(move |arm_index| branch.arm_range(arm_index))
+                        .map(move |arm_range| (branch_index, arm_range))
+                })
+                .filter(|(_, arm_range)| arm_range.start <= start && end <= arm_range.end)
+                .min_by_key(|(_, arm_range)| arm_range.len());
+
+            match enclosing {
+                Some((branch_index, arm_range)) if arm_range.end == end => {
+                    let branch = &self.branches[branch_index];
+                    start = branch.start;
+                    end = branch.end();
+                    exited_branch = Some(branch_index);
+                }
+                _ => return end,
+            }
+        }
+    }
+
+    /// Expand a position into the items execution can actually enter there:
+    /// a branch start fans out into the entries of each of its arms.
+    fn entry_positions(&self, position: usize) -> Vec<Option<CfsCoordinate>> {
+        if position >= self.items.len() {
+            return Vec::from([None]);
+        }
+        let Some((branch_index, branch)) = self
+            .branches
+            .iter()
+            .enumerate()
+            .find(|(_, branch)| branch.start == position)
+        else {
+            return Vec::from([Some(position as CfsCoordinate)]);
+        };
+
+        let mut positions = Vec::new();
+        for arm_index in 0..branch.arms.len() {
+            let arm_positions = match branch.arm_range(arm_index) {
+                Some(arm_range) if !arm_range.is_empty() => {
+                    Vec::from([Some(arm_range.start as CfsCoordinate)])
+                }
+                _ => self.entry_positions(self.position_after_span(
+                    branch.start,
+                    branch.end(),
+                    Some(branch_index),
+                )),
+            };
+            for arm_position in arm_positions {
+                if !positions.contains(&arm_position) {
+                    positions.push(arm_position);
+                }
+            }
         }
+
+        positions
     }
 
     pub fn sequences(&self) -> Vec<SequenceItem> {
@@ -532,6 +840,64 @@ impl SequenceChildItem {
     }
 }
 
+/// A conditional region of a sequence, selected by the output of the item
+/// right before it (the `if` condition or `match` scrutinee call).
+///
+/// The arms cover `items[start..end()]` in source order: arm `i` spans the
+/// `arms[i].len` items following arm `i - 1`. Nested branches live entirely
+/// inside one arm of their parent.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BranchItem {
+    /// Intra-sequence index of the tile whose output selects the arm.
+    pub condition: usize,
+    /// Index of the first item covered by the arms (`condition + 1`).
+    pub start: usize,
+    pub arms: Vec<BranchArm>,
+    /// Variant names of the condition's enum type in declaration order, for
+    /// matching `Variant` patterns against an encoding that only carries the
+    /// variant index (postcard). Empty when no arm names a variant or the enum
+    /// is not declared in the project sources.
+    pub variants: Vec<String>,
+}
+
+impl BranchItem {
+    /// Index one past the last item covered by the arms.
+    pub fn end(&self) -> usize {
+        self.start + self.arms.iter().map(|arm| arm.len).sum::<usize>()
+    }
+
+    /// Item index range covered by the arm at `arm_index`.
+    pub fn arm_range(&self, arm_index: usize) -> Option<core::ops::Range<usize>> {
+        let arm = self.arms.get(arm_index)?;
+        let start = self.start
+            + self.arms[..arm_index]
+                .iter()
+                .map(|arm| arm.len)
+                .sum::<usize>();
+
+        Some(start..start + arm.len)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BranchArm {
+    pub pattern: BranchPattern,
+    /// Number of sequence items in this arm, nested branch arms included.
+    pub len: usize,
+}
+
+/// Condition value an arm is taken for. Arms are tried in order, so a
+/// `Wildcard` arm only catches what the arms before it did not.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BranchPattern {
+    /// `if` / `else` on a `bool` output, or a `true` / `false` match arm.
+    Bool(bool),
+    /// A `match` arm on an enum variant, by variant name.
+    Variant(String),
+    /// `_`, a binding pattern, or an implicit empty `else`.
+    Wildcard,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SequenceItem {
     pub id: SequenceId,
@@ -560,7 +926,7 @@ pub struct RecurTileItem {
     /// Static chunk size from `call_recur! { ..., chunk = N }`: each iteration
     /// consumes a contiguous group of N source elements (the final group may be
     /// shorter). `None` means per-element iteration.
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
     pub chunk: Option<u64>,
 }
 
@@ -570,7 +936,7 @@ pub struct RecurSequenceItem {
     pub sources: Vec<InputBinding>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum InputBinding {
     Direct(InputSource),
     SequenceScope {
@@ -578,6 +944,10 @@ pub enum InputBinding {
     },
     PriorItemOutput {
         intra_sequence_item_index: usize,
+        /// Part of the prior item's output the input is bound to, e.g. one
+        /// element of a destructured tuple. Empty for the whole output.
+        #[serde(default)]
+        selector: SelectorPath,
     },
 }
 
@@ -609,14 +979,23 @@ impl InputBinding {
 
     /// Create a binding sourced from a prior item's committed output.
     pub fn prior_item_output(intra_sequence_item_index: usize) -> Self {
+        Self::prior_item_output_selection(intra_sequence_item_index, SelectorPath::default())
+    }
+
+    /// Create a binding sourced from a selection inside a prior item's committed output.
+    pub fn prior_item_output_selection(
+        intra_sequence_item_index: usize,
+        selector: SelectorPath,
+    ) -> Self {
         Self::PriorItemOutput {
             intra_sequence_item_index,
+            selector,
         }
     }
 }
 
 /// Semantic source of an input value in the data flow schema.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum InputSource {
     /// Input comes from outside the sequence (runtime-provided).
     External,
@@ -635,7 +1014,7 @@ mod tests {
 
     fn recur_cursor() -> CfsCursor {
         CfsCursor::new(ControlFlowSchema {
-            version: "1.0".to_string(),
+            version: CFS_VERSION.to_string(),
             project: "test".to_string(),
             encoding: "postcard".to_string(),
             tiles: vec![
@@ -661,6 +1040,54 @@ mod tests {
                         sources: vec![],
                     }),
                 ],
+                branches: vec![],
+                input_types: vec![],
+                output_type: None,
+                path: String::new(),
+            }],
+        })
+    }
+
+    fn tile(id: &str) -> SequenceChildItem {
+        SequenceChildItem::Tile(TileItem {
+            id: id.to_string(),
+            sources: vec![],
+        })
+    }
+
+    /// `check; if .. { accept } else { reject }; [nested if] ..; after`
+    fn branch_cursor(else_len: usize) -> CfsCursor {
+        let mut items = vec![tile("check"), tile("accept")];
+        items.extend((0..else_len).map(|_| tile("reject")));
+        items.push(tile("after"));
+
+        CfsCursor::new(ControlFlowSchema {
+            version: CFS_VERSION.to_string(),
+            project: "test".to_string(),
+            encoding: "postcard".to_string(),
+            tiles: vec![],
+            sequences: vec![SequenceDef {
+                id: "main".to_string(),
+                input_sources: vec![],
+                items,
+                branches: vec![BranchItem {
+                    condition: 0,
+                    start: 1,
+                    arms: vec![
+                        BranchArm {
+                            pattern: BranchPattern::Bool(true),
+                            len: 1,
+                        },
+                        BranchArm {
+                            pattern: BranchPattern::Wildcard,
+                            len: else_len,
+                        },
+                    ],
+                    variants: Vec::new(),
+                }],
+                input_types: vec![],
+                output_type: None,
+                path: String::new(),
             }],
         })
     }
@@ -706,4 +1133,148 @@ mod tests {
 
         assert_eq!(next, vec![CfsCoordinates(vec![2])]);
     }
+
+    #[test]
+    fn branch_condition_offers_every_arm_entry() {
+        let cursor = branch_cursor(1);
+        let next = cursor
+            .try_get_next_coordinates(&CfsCoordinates(vec![0]))
+            .expect("next coordinates should exist");
+
+        assert_eq!(next, vec![CfsCoordinates(vec![1]), CfsCoordinates(vec![2])]);
+        assert_eq!(
+            cursor.try_get_branch_arm_next_coordinates(&CfsCoordinates(vec![0]), 1),
+            Some(vec![CfsCoordinates(vec![2])])
+        );
+    }
+
+    #[test]
+    fn branch_arm_completion_skips_remaining_arms() {
+        let cursor = branch_cursor(1);
+
+        for arm_item in [1, 2] {
+            let next = cursor
+                .try_get_next_coordinates(&CfsCoordinates(vec![arm_item]))
+                .expect("next coordinates should exist");
+            assert_eq!(next, vec![CfsCoordinates(vec![3])]);
+        }
+    }
+
+    #[test]
+    fn empty_branch_arm_falls_through_to_branch_successor() {
+        let cursor = branch_cursor(0);
+        let next = cursor
+            .try_get_next_coordinates(&CfsCoordinates(vec![0]))
+            .expect("next coordinates should exist");
+
+        assert_eq!(next, vec![CfsCoordinates(vec![1]), CfsCoordinates(vec![2])]);
+        assert_eq!(
+            cursor.try_get_branch_arm_next_coordinates(&CfsCoordinates(vec![0]), 1),
+            Some(vec![CfsCoordinates(vec![2])])
+        );
+        assert!(cursor.try_get_branch(&CfsCoordinates(vec![1])).is_none());
+    }
+
+    #[test]
+    fn nested_branch_arm_exits_every_completed_branch() {
+        // check; if { inner_check; if { inner } else {} } else { reject }; after
+        let sequence = SequenceDef {
+            id: "main".to_string(),
+            input_sources: vec![],
+            items: vec![
+                tile("check"),
+                tile("inner_check"),
+                tile("inner"),
+                tile("reject"),
+                tile("after"),
+            ],
+            branches: vec![
+                BranchItem {
+                    condition: 0,
+                    start: 1,
+                    arms: vec![
+                        BranchArm {
+                            pattern: BranchPattern::Bool(true),
+                            len: 2,
+                        },
+                        BranchArm {
+                            pattern: BranchPattern::Wildcard,
+                            len: 1,
+                        },
+                    ],
+                    variants: Vec::new(),
+                },
+                BranchItem {
+                    condition: 1,
+                    start: 2,
+                    arms: vec![
+                        BranchArm {
+                            pattern: BranchPattern::Bool(true),
+                            len: 1,
+                        },
+                        BranchArm {
+                            pattern: BranchPattern::Wildcard,
+                            len: 0,
+                        },
+                    ],
+                    variants: Vec::new(),
+                },
+            ],
+            input_types: vec![],
+            output_type: None,
+            path: String::new(),
+        };
+
+        assert_eq!(sequence.successor_positions(1), vec![Some(2), Some(4)]);
+        assert_eq!(sequence.successor_positions(2), vec![Some(4)]);
+        assert_eq!(sequence.successor_positions(3), vec![Some(4)]);
+        assert_eq!(sequence.successor_positions(4), vec![None]);
+    }
+
+    #[test]
+    fn cfs_digest_is_stable_and_tracks_schema_changes() {
+        let mut cfs = ControlFlowSchema::new("test");
+        cfs.tiles.push(TileDef::iter("a", 1, 1));
+        let digest = cfs.digest();
+
+        assert_eq!(digest.len(), 32);
+        assert_eq!(digest, cfs.clone().digest());
+
+        let mut renamed = cfs.clone();
+        renamed.tiles[0].id = "b".to_string();
+        assert_ne!(digest, renamed.digest());
+
+        let mut extended = cfs.clone();
+        extended.sequences.push(SequenceDef::new("main"));
+        assert_ne!(digest, extended.digest());
+    }
+
+    #[test]
+    fn resolve_id_prefers_module_qualified_paths() {
+        let mut cfs = ControlFlowSchema::new("test");
+        cfs.tiles
+            .push(TileDef::iter("text::normalize", 1, 1).with_path("text::normalize"));
+        cfs.tiles
+            .push(TileDef::iter("audio::normalize", 1, 1).with_path("audio::normalize"));
+        cfs.tiles
+            .push(TileDef::iter("greet", 1, 1).with_path("greet"));
+        cfs.tiles.push(
+            TileDef::iter("shared_tiles::normalize", 1, 1).with_path("shared_tiles::normalize"),
+        );
+
+        assert_eq!(
+            cfs.resolve_id("app::audio", "normalize"),
+            "audio::normalize"
+        );
+        assert_eq!(cfs.resolve_id("app::text", "normalize"), "text::normalize");
+        assert_eq!(cfs.resolve_id("app", "greet"), "greet");
+        // Dependency crates keep their name in CFS paths.
+        assert_eq!(
+            cfs.resolve_id("shared_tiles", "normalize"),
+            "shared_tiles::normalize"
+        );
+        // Unmatched names, e.g. from traces without a module path, stay bare.
+        assert_eq!(cfs.resolve_id("", "greet"), "greet");
+        assert_eq!(cfs.resolve_id("app::text", "main"), "main");
+    }
 }
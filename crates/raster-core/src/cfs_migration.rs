@@ -0,0 +1,248 @@
+//! Upgrades persisted CFS JSON to the current format version (requires std).
+//!
+//! Each step rewrites the JSON of one version into the shape of the next, so
+//! a document of any known version is walked forward to [`CFS_VERSION`]
+//! before it is deserialized. Unknown versions are rejected with
+//! [`Error::UnsupportedVersion`].
+
+use serde_json::{Map, Value};
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+use crate::cfs::CFS_VERSION;
+use crate::error::{Error, Result};
+use crate::input::SelectorPath;
+
+/// Upgrade a CFS JSON document to [`CFS_VERSION`].
+pub fn migrate(mut cfs: Value) -> Result<Value> {
+    loop {
+        let version = cfs
+            .get("version")
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::Serialization("CFS JSON has no string `version`".into()))?;
+
+        match version {
+            CFS_VERSION => return Ok(cfs),
+            "1.0" => migrate_1_0_to_1_1(&mut cfs)?,
+            "1.1" => migrate_1_1_to_1_2(&mut cfs)?,
+            "1.2" => migrate_1_2_to_1_3(&mut cfs)?,
+            "1.3" => migrate_1_3_to_1_4(&mut cfs)?,
+            other => {
+                return Err(Error::UnsupportedVersion {
+                    format: "CFS",
+                    found: other.to_string(),
+                    supported: CFS_VERSION,
+                })
+            }
+        }
+    }
+}
+
+/// `1.0` documents may predate recur chunking, branches and producer
+/// selectors; write their defaults out explicitly.
+fn migrate_1_0_to_1_1(cfs: &mut Value) -> Result<()> {
+    let empty_selector = serde_json::to_value(SelectorPath::default())?;
+
+    let Some(cfs) = cfs.as_object_mut() else {
+        return Err(Error::Serialization("CFS JSON is not an object".into()));
+    };
+    for sequence in array_mut(cfs, "sequences") {
+        let Some(sequence) = sequence.as_object_mut() else {
+            continue;
+        };
+        sequence
+            .entry("branches")
+            .or_insert_with(|| Value::Array(Vec::new()));
+
+        for binding in array_mut(sequence, "input_sources") {
+            fill_prior_item_selector(binding, &empty_selector);
+        }
+        for item in array_mut(sequence, "items") {
+            let Some((kind, item)) = item.as_object_mut().and_then(single_variant) else {
+                continue;
+            };
+            if kind == "RecurTile" {
+                item.entry("chunk").or_insert(Value::Null);
+            }
+            for binding in array_mut(item, "sources") {
+                fill_prior_item_selector(binding, &empty_selector);
+            }
+        }
+    }
+
+    cfs.insert("version".into(), Value::String("1.1".into()));
+    Ok(())
+}
+
+/// `1.1` documents carry no signature types; record them as unknown.
+fn migrate_1_1_to_1_2(cfs: &mut Value) -> Result<()> {
+    let Some(cfs) = cfs.as_object_mut() else {
+        return Err(Error::Serialization("CFS JSON is not an object".into()));
+    };
+    for key in ["tiles", "sequences"] {
+        for def in array_mut(cfs, key) {
+            let Some(def) = def.as_object_mut() else {
+                continue;
+            };
+            def.entry("input_types")
+                .or_insert_with(|| Value::Array(Vec::new()));
+            def.entry("output_type").or_insert(Value::Null);
+        }
+    }
+
+    cfs.insert("version".into(), Value::String("1.2".into()));
+    Ok(())
+}
+
+/// `1.2` ids are bare function names whose module is not recorded; leave
+/// their paths unknown so ids keep resolving by name.
+fn migrate_1_2_to_1_3(cfs: &mut Value) -> Result<()> {
+    let Some(cfs) = cfs.as_object_mut() else {
+        return Err(Error::Serialization("CFS JSON is not an object".into()));
+    };
+    for key in ["tiles", "sequences"] {
+        for def in array_mut(cfs, key) {
+            if let Some(def) = def.as_object_mut() {
+                def.entry("path")
+                    .or_insert_with(|| Value::String(String::new()));
+            }
+        }
+    }
+
+    cfs.insert("version".into(), Value::String("1.3".into()));
+    Ok(())
+}
+
+/// `1.3` branches do not record the variant names of their condition's enum;
+/// leave them unknown, as for an enum declared outside the project.
+fn migrate_1_3_to_1_4(cfs: &mut Value) -> Result<()> {
+    let Some(cfs) = cfs.as_object_mut() else {
+        return Err(Error::Serialization("CFS JSON is not an object".into()));
+    };
+    for sequence in array_mut(cfs, "sequences") {
+        let Some(sequence) = sequence.as_object_mut() else {
+            continue;
+        };
+        for branch in array_mut(sequence, "branches") {
+            if let Some(branch) = branch.as_object_mut() {
+                branch
+                    .entry("variants")
+                    .or_insert_with(|| Value::Array(Vec::new()));
+            }
+        }
+    }
+
+    cfs.insert("version".into(), Value::String("1.4".into()));
+    Ok(())
+}
+
+fn fill_prior_item_selector(binding: &mut Value, empty_selector: &Value) {
+    if let Some(prior) = binding
+        .get_mut("PriorItemOutput")
+        .and_then(Value::as_object_mut)
+    {
+        prior
+            .entry("selector")
+            .or_insert_with(|| empty_selector.clone());
+    }
+}
+
+/// The `(variant, fields)` of an externally tagged enum value.
+fn single_variant(value: &mut Map<String, Value>) -> Option<(&str, &mut Map<String, Value>)> {
+    if value.len() != 1 {
+        return None;
+    }
+    let (kind, fields) = value.iter_mut().next()?;
+    Some((kind.as_str(), fields.as_object_mut()?))
+}
+
+fn array_mut<'a>(
+    object: &'a mut Map<String, Value>,
+    key: &str,
+) -> impl Iterator<Item = &'a mut Value> {
+    object
+        .get_mut(key)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfs::{ControlFlowSchema, InputBinding, SequenceChildItem};
+    use serde_json::json;
+
+    fn pre_chunk_cfs(version: &str) -> Value {
+        json!({
+            "version": version,
+            "project": "legacy",
+            "encoding": "postcard",
+            "tiles": [
+                { "id": "produce", "type": "iter", "inputs": 1, "outputs": 1 },
+                { "id": "consume", "type": "iter", "inputs": 1, "outputs": 1 }
+            ],
+            "sequences": [{
+                "id": "main",
+                "input_sources": [],
+                "items": [
+                    { "Tile": { "id": "produce", "sources": [{ "Direct": "External" }] } },
+                    { "RecurTile": {
+                        "id": "consume",
+                        "sources": [{ "PriorItemOutput": { "intra_sequence_item_index": 0 } }]
+                    } }
+                ]
+            }]
+        })
+    }
+
+    #[test]
+    fn legacy_cfs_json_is_upgraded_to_current_shape() {
+        let json = pre_chunk_cfs("1.0").to_string();
+        let cfs = ControlFlowSchema::from_json(&json).expect("1.0 CFS upgrades");
+
+        assert_eq!(cfs.version, CFS_VERSION);
+        assert!(cfs.sequences[0].branches.is_empty());
+        assert!(cfs.tiles[0].input_types.is_empty());
+        assert_eq!(cfs.sequences[0].output_type, None);
+        assert!(cfs.tiles[1].path.is_empty());
+        let SequenceChildItem::RecurTile(recur) = &cfs.sequences[0].items[1] else {
+            panic!("expected a recur tile item");
+        };
+        assert_eq!(recur.chunk, None);
+        assert!(matches!(
+            &recur.sources[0],
+            InputBinding::PriorItemOutput { selector, .. } if selector.is_empty()
+        ));
+    }
+
+    #[test]
+    fn branches_of_a_1_3_cfs_get_unknown_variants() {
+        let mut cfs = pre_chunk_cfs("1.3");
+        cfs["tiles"][0]["path"] = json!("");
+        cfs["tiles"][1]["path"] = json!("");
+        cfs["sequences"][0]["branches"] = json!([{
+            "condition": 0,
+            "start": 1,
+            "arms": [{ "pattern": "Wildcard", "len": 1 }]
+        }]);
+        let json = cfs.to_string();
+
+        let cfs = ControlFlowSchema::from_json(&json).expect("1.3 CFS upgrades");
+
+        assert_eq!(cfs.version, CFS_VERSION);
+        let branch = &cfs.sequences[0].branches[0];
+        assert_eq!(branch.end(), 2);
+        assert!(branch.variants.is_empty());
+    }
+
+    #[test]
+    fn unknown_cfs_version_is_rejected() {
+        let json = pre_chunk_cfs("9.0").to_string();
+
+        assert!(matches!(
+            ControlFlowSchema::from_json(&json),
+            Err(Error::UnsupportedVersion { found, .. }) if found == "9.0"
+        ));
+    }
+}
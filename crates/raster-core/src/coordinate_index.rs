@@ -1,4 +1,5 @@
 use alloc::vec;
+use core::convert::Infallible;
 use sha2::{Digest, Sha256};
 use std::collections::{BTreeMap, HashMap};
 use std::vec::Vec;
@@ -19,25 +20,101 @@ struct NodeKey {
     prefix: [u8; 32],
 }
 
-#[derive(Debug, Clone)]
-pub struct IncrementalCoordinateIndex {
+/// Where an [`IncrementalCoordinateIndex`] keeps its entries and node hashes.
+///
+/// Entries are looked up by the index key of their coordinates, and nodes by
+/// their depth and the key prefix above them.
+pub trait CoordinateIndexStore {
+    type Error;
+
+    /// The coordinates and value of the entry with index key `key`.
+    fn entry(
+        &self,
+        key: &[u8; 32],
+    ) -> Result<Option<(CfsCoordinates, InternalStoreIndexValue)>, Self::Error>;
+
+    fn insert_entry(
+        &mut self,
+        key: [u8; 32],
+        coordinates: CfsCoordinates,
+        value: InternalStoreIndexValue,
+    ) -> Result<(), Self::Error>;
+
+    /// Hash of the node at `depth` under `prefix`, or `None` if no entry is
+    /// below it.
+    fn node(&self, depth: usize, prefix: &[u8; 32]) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    fn insert_node(
+        &mut self,
+        depth: usize,
+        prefix: [u8; 32],
+        hash: Vec<u8>,
+    ) -> Result<(), Self::Error>;
+}
+
+/// Keeps a coordinate index in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryCoordinateIndexStore {
     entries: BTreeMap<CfsCoordinates, InternalStoreIndexValue>,
     occupied_keys: HashMap<[u8; 32], CfsCoordinates>,
     node_hashes: HashMap<NodeKey, Vec<u8>>,
+}
+
+impl CoordinateIndexStore for MemoryCoordinateIndexStore {
+    type Error = Infallible;
+
+    fn entry(
+        &self,
+        key: &[u8; 32],
+    ) -> Result<Option<(CfsCoordinates, InternalStoreIndexValue)>, Infallible> {
+        Ok(self.occupied_keys.get(key).and_then(|coordinates| {
+            let value = self.entries.get(coordinates)?;
+            Some((coordinates.clone(), value.clone()))
+        }))
+    }
+
+    fn insert_entry(
+        &mut self,
+        key: [u8; 32],
+        coordinates: CfsCoordinates,
+        value: InternalStoreIndexValue,
+    ) -> Result<(), Infallible> {
+        self.occupied_keys.insert(key, coordinates.clone());
+        self.entries.insert(coordinates, value);
+        Ok(())
+    }
+
+    fn node(&self, depth: usize, prefix: &[u8; 32]) -> Result<Option<Vec<u8>>, Infallible> {
+        Ok(self
+            .node_hashes
+            .get(&NodeKey {
+                depth,
+                prefix: *prefix,
+            })
+            .cloned())
+    }
+
+    fn insert_node(
+        &mut self,
+        depth: usize,
+        prefix: [u8; 32],
+        hash: Vec<u8>,
+    ) -> Result<(), Infallible> {
+        self.node_hashes.insert(NodeKey { depth, prefix }, hash);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IncrementalCoordinateIndex<S = MemoryCoordinateIndexStore> {
+    store: S,
     empty_hashes: Vec<Vec<u8>>,
     root: Vec<u8>,
 }
 
 impl IncrementalCoordinateIndex {
     pub fn new() -> Self {
-        let empty_hashes = empty_hashes();
-        Self {
-            entries: BTreeMap::new(),
-            occupied_keys: HashMap::new(),
-            node_hashes: HashMap::new(),
-            root: empty_hashes[0].clone(),
-            empty_hashes,
-        }
+        Self::with_store(MemoryCoordinateIndexStore::default())
     }
 
     pub fn from_entries(entries: &BTreeMap<CfsCoordinates, InternalStoreIndexValue>) -> Self {
@@ -48,68 +125,27 @@ impl IncrementalCoordinateIndex {
         index
     }
 
-    pub fn root(&self) -> Vec<u8> {
-        self.root.clone()
-    }
-
     pub fn contains_key(&self, coordinates: &CfsCoordinates) -> bool {
-        self.entries.contains_key(coordinates)
+        self.store.entries.contains_key(coordinates)
     }
 
     pub fn get(&self, coordinates: &CfsCoordinates) -> Option<&InternalStoreIndexValue> {
-        self.entries.get(coordinates)
+        self.store.entries.get(coordinates)
     }
 
     pub fn insert(&mut self, coordinates: CfsCoordinates, value: InternalStoreIndexValue) {
-        assert!(
-            !self.entries.contains_key(&coordinates),
-            "Duplicate internal store write at coordinates {:?}",
-            coordinates
-        );
-
-        let key = coordinates_key(&coordinates);
-        if let Some(existing_coordinates) = self.occupied_keys.get(&key) {
-            assert_eq!(
-                existing_coordinates, &coordinates,
-                "Coordinate index hash collision for internal store entries",
-            );
-        }
-
-        let leaf = leaf_hash(&key, &value);
-        self.node_hashes
-            .insert(node_key(&key, INDEX_BITS), leaf.clone());
-        let previous = self.occupied_keys.insert(key, coordinates.clone());
-        assert!(
-            previous.is_none(),
-            "Coordinate index hash collision for internal store entries",
-        );
-
-        let mut current = leaf;
-        for depth in (0..INDEX_BITS).rev() {
-            let bit = bit_at(&key, depth);
-            let sibling = self.child_hash(&key, depth, !bit);
-            current = if bit {
-                combine_node_hash(depth, &sibling, &current)
-            } else {
-                combine_node_hash(depth, &current, &sibling)
-            };
-            self.node_hashes
-                .insert(node_key(&key, depth), current.clone());
-        }
-
-        self.root = current;
-        self.entries.insert(coordinates, value);
+        into_ok(self.try_insert(coordinates, value))
     }
 
     pub fn membership_proof(
         &self,
         coordinates: &CfsCoordinates,
     ) -> Option<CoordinateIndexMembershipProof> {
-        let value = self.entries.get(coordinates)?.clone();
+        let value = self.get(coordinates)?.clone();
         Some(CoordinateIndexMembershipProof {
             coordinates: coordinates.clone(),
             value,
-            siblings: self.sibling_path(&coordinates_key(coordinates)),
+            siblings: into_ok(self.sibling_path(&coordinates_key(coordinates))),
         })
     }
 
@@ -118,31 +154,98 @@ impl IncrementalCoordinateIndex {
         coordinates: &CfsCoordinates,
     ) -> CoordinateIndexNonMembershipProof {
         assert!(
-            !self.entries.contains_key(coordinates),
+            !self.contains_key(coordinates),
             "Coordinate index non-membership proof requested for existing coordinates {:?}",
             coordinates
         );
 
         CoordinateIndexNonMembershipProof {
             coordinates: coordinates.clone(),
-            siblings: self.sibling_path(&coordinates_key(coordinates)),
+            siblings: into_ok(self.sibling_path(&coordinates_key(coordinates))),
+        }
+    }
+}
+
+impl<S: CoordinateIndexStore> IncrementalCoordinateIndex<S> {
+    /// An empty index that keeps its entries and nodes in `store`, which
+    /// must be empty too.
+    pub fn with_store(store: S) -> Self {
+        let empty_hashes = empty_hashes();
+        Self {
+            store,
+            root: empty_hashes[0].clone(),
+            empty_hashes,
+        }
+    }
+
+    pub fn root(&self) -> Vec<u8> {
+        self.root.clone()
+    }
+
+    /// The value indexed at `coordinates`.
+    pub fn try_get(
+        &self,
+        coordinates: &CfsCoordinates,
+    ) -> Result<Option<InternalStoreIndexValue>, S::Error> {
+        let entry = self.store.entry(&coordinates_key(coordinates))?;
+        Ok(entry
+            .filter(|(indexed, _)| indexed == coordinates)
+            .map(|(_, value)| value))
+    }
+
+    pub fn try_insert(
+        &mut self,
+        coordinates: CfsCoordinates,
+        value: InternalStoreIndexValue,
+    ) -> Result<(), S::Error> {
+        let key = coordinates_key(&coordinates);
+        if let Some((existing_coordinates, _)) = self.store.entry(&key)? {
+            assert_ne!(
+                existing_coordinates, coordinates,
+                "Duplicate internal store write at coordinates {:?}",
+                coordinates
+            );
+            panic!("Coordinate index hash collision for internal store entries");
         }
+
+        let leaf = leaf_hash(&key, &value);
+        let leaf_key = node_key(&key, INDEX_BITS);
+        self.store
+            .insert_node(leaf_key.depth, leaf_key.prefix, leaf.clone())?;
+
+        let mut current = leaf;
+        for depth in (0..INDEX_BITS).rev() {
+            let bit = bit_at(&key, depth);
+            let sibling = self.child_hash(&key, depth, !bit)?;
+            current = if bit {
+                combine_node_hash(depth, &sibling, &current)
+            } else {
+                combine_node_hash(depth, &current, &sibling)
+            };
+            let node = node_key(&key, depth);
+            self.store
+                .insert_node(node.depth, node.prefix, current.clone())?;
+        }
+
+        self.root = current;
+        self.store.insert_entry(key, coordinates, value)
     }
 
-    fn child_hash(&self, key: &[u8; 32], depth: usize, bit: bool) -> Vec<u8> {
-        self.node_hashes
-            .get(&child_node_key(key, depth, bit))
-            .cloned()
-            .unwrap_or_else(|| self.empty_hashes[depth + 1].clone())
+    fn child_hash(&self, key: &[u8; 32], depth: usize, bit: bool) -> Result<Vec<u8>, S::Error> {
+        let child = child_node_key(key, depth, bit);
+        Ok(self
+            .store
+            .node(child.depth, &child.prefix)?
+            .unwrap_or_else(|| self.empty_hashes[depth + 1].clone()))
     }
 
-    fn sibling_path(&self, key: &[u8; 32]) -> Vec<Vec<u8>> {
+    fn sibling_path(&self, key: &[u8; 32]) -> Result<Vec<Vec<u8>>, S::Error> {
         let mut siblings = Vec::with_capacity(INDEX_BITS);
         for depth in 0..INDEX_BITS {
-            siblings.push(self.child_hash(key, depth, !bit_at(key, depth)));
+            siblings.push(self.child_hash(key, depth, !bit_at(key, depth))?);
         }
         siblings.reverse();
-        siblings
+        Ok(siblings)
     }
 }
 
@@ -152,6 +255,13 @@ impl Default for IncrementalCoordinateIndex {
     }
 }
 
+fn into_ok<T>(result: Result<T, Infallible>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(never) => match never {},
+    }
+}
+
 fn sha256(parts: &[&[u8]]) -> Vec<u8> {
     let mut hasher = Sha256::new();
     for part in parts {
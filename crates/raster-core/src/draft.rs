@@ -14,6 +14,7 @@ use serde::{Deserialize, Serialize, Serializer};
 use sha2::{Digest, Sha256};
 
 use crate::input::{Schema, SchemaField, SchemaFieldMode, SchemaNode};
+use crate::trace::{failure_message_hash, TileFailureKind};
 use crate::{Error, Result};
 
 pub type DraftId = [u8; 32];
@@ -38,6 +39,30 @@ pub struct DraftReplayTransition {
 pub struct TileReplayJournal {
     pub output_bytes: Vec<u8>,
     pub draft_transition: Option<DraftReplayTransition>,
+    /// The failure the replayed tile ended in instead of returning.
+    pub failure: Option<TileFailureAttestation>,
+}
+
+impl TileReplayJournal {
+    /// The journal of a replay that failed with `message`.
+    pub fn failed(kind: TileFailureKind, message: &str) -> Self {
+        Self {
+            output_bytes: Vec::new(),
+            draft_transition: None,
+            failure: Some(TileFailureAttestation {
+                kind,
+                message_hash: failure_message_hash(message),
+            }),
+        }
+    }
+}
+
+/// How a replayed tile failed, matched against a recorded `TileFailed` step.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct TileFailureAttestation {
+    pub kind: TileFailureKind,
+    /// SHA-256 of the failure message.
+    pub message_hash: Vec<u8>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -17,6 +17,16 @@ pub enum Error {
     /// Serialization/deserialization error.
     Serialization(String),
 
+    /// A persisted format version this build cannot read.
+    UnsupportedVersion {
+        /// What the versioned data is (e.g. `"CFS"`).
+        format: &'static str,
+        /// Version found in the data.
+        found: String,
+        /// Version this build reads and writes.
+        supported: &'static str,
+    },
+
     /// IO error (only available with std).
     #[cfg(feature = "std")]
     Io(std::io::Error),
@@ -31,6 +41,15 @@ impl fmt::Display for Error {
             Error::InvalidTileId(id) => write!(f, "Invalid tile ID: {}", id),
             Error::InvalidSequence(msg) => write!(f, "Invalid sequence: {}", msg),
             Error::Serialization(msg) => write!(f, "Serialization error: {}", msg),
+            Error::UnsupportedVersion {
+                format,
+                found,
+                supported,
+            } => write!(
+                f,
+                "Unsupported {} version '{}' (supported: '{}')",
+                format, found, supported
+            ),
             #[cfg(feature = "std")]
             Error::Io(e) => write!(f, "IO error: {}", e),
             Error::Other(msg) => write!(f, "{}", msg),
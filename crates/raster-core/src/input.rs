@@ -221,7 +221,7 @@ impl SelectedPayload {
     }
 }
 
-fn selection_hash(parts: &[&[u8]]) -> Hash32 {
+pub(crate) fn selection_hash(parts: &[&[u8]]) -> Hash32 {
     let mut hasher = Sha256::new();
     for part in parts {
         hasher.update(part);
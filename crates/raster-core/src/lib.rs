@@ -13,6 +13,7 @@ extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
 
+pub mod branching;
 pub mod cfs;
 pub mod chunking;
 pub mod draft;
@@ -26,6 +27,8 @@ pub mod trace;
 #[cfg(feature = "std")]
 pub mod authorization;
 #[cfg(feature = "std")]
+pub mod cfs_migration;
+#[cfg(feature = "std")]
 pub mod coordinate_index;
 #[cfg(feature = "std")]
 pub mod manifest;
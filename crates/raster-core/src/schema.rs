@@ -10,28 +10,37 @@ use std::vec::Vec;
 pub struct SequenceSchema {
     pub name: String,
     pub version: String,
+    /// Tiles the sequence can run, those of nested sequence calls included,
+    /// each listed once.
     pub tiles: Vec<TileId>,
-    pub control_flow: ControlFlow,
+    /// Body of the sequence, in execution order.
+    pub control_flow: Vec<ControlFlow>,
 }
 
-/// Control flow description for a sequence.
+/// One node of a sequence body. Branch arms and loop bodies hold nodes
+/// themselves, so control flow nests as it does in the CFS.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ControlFlow {
-    Linear {
-        steps: Vec<TileId>,
-    },
+    /// Items run one after another: tile ids, or sequence ids for nested
+    /// sequence calls.
+    Linear { steps: Vec<TileId> },
+    /// A branch on the output of `condition`, the item run right before it.
+    /// Only the first matching arm runs.
     Conditional {
+        condition: TileId,
         branches: Vec<Branch>,
     },
+    /// A recur site running `body` once per iteration.
     Loop {
-        body: Vec<TileId>,
+        body: Vec<ControlFlow>,
         max_iterations: Option<u64>,
     },
 }
 
+/// One arm of a [`ControlFlow::Conditional`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Branch {
     pub condition: String,
-    pub tiles: Vec<TileId>,
+    pub steps: Vec<ControlFlow>,
 }
@@ -25,6 +25,12 @@ impl From<&TileIdStatic> for TileId {
     }
 }
 
+/// Form of a tile id usable as a directory or package name: the module
+/// separators of qualified ids become `-`, which no Rust identifier contains.
+pub fn artifact_name(tile_id: &str) -> String {
+    tile_id.replace("::", "-")
+}
+
 /// Static tile identifier for use in const contexts (e.g., distributed slices).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TileIdStatic(pub &'static str);
@@ -45,6 +51,13 @@ impl TileIdStatic {
 pub struct TileMetadata {
     pub id: TileId,
     pub name: String,
+    /// Module-qualified path of the tile function within its crate.
+    #[serde(default)]
+    pub path: String,
+    /// Manifest directory of the crate declaring the tile when that crate is
+    /// a dependency of the project rather than the project itself.
+    #[serde(default)]
+    pub crate_dir: Option<String>,
     pub description: Option<String>,
 
     /// Expected cycle count (for cost estimation)
@@ -60,6 +73,8 @@ impl TileMetadata {
         Self {
             id: s.id.to_owned(),
             name: s.name.to_string(),
+            path: s.name.to_string(),
+            crate_dir: None,
             description: s.description.map(|d| d.to_string()),
             estimated_cycles: s.estimated_cycles,
             max_memory: s.max_memory,
@@ -1,9 +1,12 @@
 use alloc::collections::BTreeMap;
-use alloc::string::String;
+use alloc::format;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use core::any::Any;
 use core::hash::Hash;
 use core::ops::{Deref, DerefMut};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use crate::cfs::CfsCoordinates;
 use crate::draft::DraftTransitionWitness;
@@ -131,6 +134,10 @@ impl FnOutput {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct FnCallRecord {
     pub fn_name: String,
+    /// `module_path!()` of the function, used to resolve `fn_name` to its
+    /// CFS id when the same name is declared in several modules.
+    #[serde(default)]
+    pub module_path: String,
     pub input: Option<FnInput>,
     pub output: Option<FnOutput>,
     pub draft_transition_witness: Option<DraftTransitionWitness>,
@@ -190,6 +197,33 @@ pub struct RecurTileExecRecord {
     pub internal_store_index_root_after: Vec<u8>,
 }
 
+/// A tile invocation that failed instead of returning.
+///
+/// Nothing is written to the internal store, so both store roots are the
+/// roots the tile started from. A failure ends the trace: no step follows it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct TileFailedRecord {
+    pub exec_index: u64,
+
+    pub tile_id: String,
+
+    pub sequence_id: String,
+    pub intra_sequence_index: u32,
+
+    pub coordinates: CfsCoordinates,
+
+    pub input_commitment: Vec<u8>,
+    pub input_source_commitment: Vec<u8>,
+
+    pub external_input_commitment: Vec<u8>,
+    pub internal_store_root: Vec<u8>,
+    pub internal_store_index_root: Vec<u8>,
+
+    pub failure_kind: TileFailureKind,
+    /// SHA-256 of the failure message.
+    pub message_hash: Vec<u8>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct RecurSequenceExecRecord {
     pub exec_index: u64,
@@ -243,6 +277,7 @@ pub enum StepRecord {
     TileExec(TileExecRecord),
     RecurTileExec(RecurTileExecRecord),
     RecurSequenceExec(RecurSequenceExecRecord),
+    TileFailed(TileFailedRecord),
 }
 
 impl StepRecord {
@@ -255,6 +290,7 @@ impl StepRecord {
             }
             StepRecord::SequenceStart(sequence_start_record) => &sequence_start_record.coordinates,
             StepRecord::SequenceEnd(sequence_end_record) => &sequence_end_record.coordinates,
+            StepRecord::TileFailed(tile_failed_record) => &tile_failed_record.coordinates,
         }
     }
 
@@ -264,6 +300,7 @@ impl StepRecord {
             StepRecord::RecurTileExec(record) => Some(&record.input_commitment),
             StepRecord::RecurSequenceExec(record) => Some(&record.input_commitment),
             StepRecord::SequenceStart(record) => Some(&record.input_commitment),
+            StepRecord::TileFailed(record) => Some(&record.input_commitment),
             StepRecord::SequenceEnd(_) => None,
         }
     }
@@ -273,7 +310,7 @@ impl StepRecord {
             StepRecord::TileExec(record) => Some(&record.output_commitment),
             StepRecord::RecurTileExec(record) => Some(&record.output_commitment),
             StepRecord::RecurSequenceExec(record) => Some(&record.output_commitment),
-            StepRecord::SequenceStart(_) => None,
+            StepRecord::SequenceStart(_) | StepRecord::TileFailed(_) => None,
             StepRecord::SequenceEnd(record) => Some(&record.output_commitment),
         }
     }
@@ -284,6 +321,7 @@ impl StepRecord {
             StepRecord::RecurTileExec(record) => Some(&record.input_source_commitment),
             StepRecord::RecurSequenceExec(record) => Some(&record.input_source_commitment),
             StepRecord::SequenceStart(record) => Some(&record.input_source_commitment),
+            StepRecord::TileFailed(record) => Some(&record.input_source_commitment),
             StepRecord::SequenceEnd(_) => None,
         }
     }
@@ -294,6 +332,7 @@ impl StepRecord {
             StepRecord::RecurTileExec(record) => Some(&record.external_input_commitment),
             StepRecord::RecurSequenceExec(record) => Some(&record.external_input_commitment),
             StepRecord::SequenceStart(record) => Some(&record.external_input_commitment),
+            StepRecord::TileFailed(record) => Some(&record.external_input_commitment),
             StepRecord::SequenceEnd(_) => None,
         }
     }
@@ -318,6 +357,12 @@ impl StepRecord {
                 &record.internal_store_index_root_before,
                 &record.internal_store_index_root_after,
             )),
+            StepRecord::TileFailed(record) => Some((
+                &record.internal_store_root,
+                &record.internal_store_root,
+                &record.internal_store_index_root,
+                &record.internal_store_index_root,
+            )),
             StepRecord::SequenceStart(_) | StepRecord::SequenceEnd(_) => None,
         }
     }
@@ -328,11 +373,12 @@ impl StepRecord {
             StepRecord::TileExec(_)
                 | StepRecord::RecurTileExec(_)
                 | StepRecord::RecurSequenceExec(_)
+                | StepRecord::TileFailed(_)
         )
     }
 
     pub fn requires_replay_proof(&self) -> bool {
-        matches!(self, StepRecord::TileExec(_))
+        matches!(self, StepRecord::TileExec(_) | StepRecord::TileFailed(_))
     }
 }
 
@@ -392,4 +438,88 @@ pub enum TraceEvent {
     RecurTileIterationExec(FnCallRecord),
     RecurTileExec(FnCallRecord),
     RecurSequenceExec(FnCallRecord),
+
+    /// A tile (or recur iteration) that panicked; `output` is always `None`.
+    TileFailed(FnCallRecord, TileFailure),
+}
+
+/// How a tile failed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum TileFailureKind {
+    /// The tile body panicked.
+    Panic,
+    /// The Raster wrapper around the tile failed, e.g. while serializing its
+    /// output.
+    RuntimeError,
+    /// The tile ran past the wall-clock budget derived from its
+    /// `estimated_cycles` attribute.
+    TimeLimitExceeded,
+    /// The tile's heap usage peaked above its `max_memory` attribute.
+    MemoryLimitExceeded,
+}
+
+impl TileFailureKind {
+    /// Whether replaying the tile in the zkVM reproduces this failure, so a
+    /// fraud proof can end on it. Limit failures depend on the host that ran
+    /// the tile and runtime errors on the native wrapper around it.
+    pub fn is_replayable(self) -> bool {
+        matches!(self, TileFailureKind::Panic)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct TileFailure {
+    pub kind: TileFailureKind,
+    /// SHA-256 of the panic message.
+    pub message_hash: Vec<u8>,
+    /// Whether the tile ran as an iteration of a recur site.
+    pub recur_iteration: bool,
+}
+
+/// SHA-256 of a failure message, as recorded in a [`TileFailure`].
+pub fn failure_message_hash(message: &str) -> Vec<u8> {
+    Sha256::digest(message.as_bytes()).to_vec()
+}
+
+/// The failure message recorded for a panic `payload`.
+///
+/// String payloads are their own message and primitive payloads are encoded
+/// as `type(value)`. Any other payload is recorded under one fixed message:
+/// its type has no name that is stable between the native and the zkVM build
+/// of a tile.
+pub fn panic_payload_message(payload: &(dyn Any + Send)) -> String {
+    macro_rules! primitive {
+        ($($ty:ty),*) => {
+            $(
+                if let Some(value) = payload.downcast_ref::<$ty>() {
+                    return format!("{}({:?})", stringify!($ty), value);
+                }
+            )*
+        };
+    }
+
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+    if let Some(message) = payload.downcast_ref::<String>() {
+        return message.clone();
+    }
+    primitive!(bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+    "Box<dyn Any>".to_string()
+}
+
+impl TraceEvent {
+    pub fn fn_call_record_mut(&mut self) -> &mut FnCallRecord {
+        match self {
+            TraceEvent::SequenceStart(record)
+            | TraceEvent::SequenceEnd(record)
+            | TraceEvent::RecurSequenceStart(record)
+            | TraceEvent::RecurSequenceEnd(record)
+            | TraceEvent::TileExec(record)
+            | TraceEvent::RecurTileIterationExec(record)
+            | TraceEvent::RecurTileExec(record)
+            | TraceEvent::RecurSequenceExec(record)
+            | TraceEvent::TileFailed(record, _) => record,
+        }
+    }
 }
@@ -118,6 +118,8 @@ pub struct TransitionInput {
     pub internal_selection_witnesses: BTreeMap<String, SelectionWitness>,
     pub internal_store_witness: Option<InternalStoreWitness>,
     pub draft_transition_witness: Option<DraftTransitionWitness>,
+    /// Raster payload of the step output when the step is a branch condition.
+    pub branch_condition_witness: Option<Vec<u8>>,
 
     pub input_sources_witnesses: HashMap<StepRecord, Vec<u8>>,
 
@@ -140,6 +142,8 @@ pub struct Transition {
 /// Initial transition (first step in a window).
 #[derive(Clone, Serialize, Deserialize)]
 pub struct InitTransition {
+    /// Leaf the trace tree was seeded with before the first trace step.
+    pub seed: Vec<u8>,
     pub init_frontier: SerializableFrontier,
     pub init_internal_store_frontier: SerializableFrontier,
     pub init_internal_store_root: Vec<u8>,
@@ -156,12 +160,14 @@ pub enum TransitionState {
     Finished,
 }
 
-/// Journal produced by the transition guest (init state + current state + image id).
+/// Journal produced by the transition guest (init state + current state + image id
+/// + digest of the CFS the step was verified against).
 #[derive(Clone, Serialize, Deserialize)]
 pub struct TransitionJournal {
     pub init_state: InitTransition,
     pub current_state: TransitionState,
     pub transition_image_id: Vec<u8>,
+    pub cfs_digest: Vec<u8>,
     pub authorization_image_id: Vec<u8>,
     pub manifest_commitment: Vec<u8>,
 }
@@ -9,8 +9,10 @@ use proc_macro::TokenStream;
 use quote::{format_ident, quote, ToTokens};
 use syn::{
     parse::{Parse, ParseStream},
-    parse_macro_input, Attribute, Expr, ExprField, ExprIndex, FnArg, GenericArgument, ItemFn,
-    LitInt, Pat, Path, PathArguments, ReturnType, Token, Type,
+    parse_macro_input,
+    visit::Visit,
+    Attribute, Expr, ExprField, ExprIndex, FnArg, GenericArgument, ItemFn, LitInt, Pat, Path,
+    PathArguments, ReturnType, Token, Type,
 };
 
 #[derive(Clone)]
@@ -321,6 +323,21 @@ fn tile_call_binding_marker_ident(fn_name: &syn::Ident) -> syn::Ident {
     format_ident!("__RasterTileCallBinding_{}", fn_name)
 }
 
+fn tile_call_binding_marker_path(tile: &syn::Path) -> syn::Path {
+    hidden_sibling_path(tile, "__RasterTileCallBinding_")
+}
+
+/// Path to the hidden item `{prefix}{name}` generated next to the function
+/// `path` names, so qualified callees like `text::normalize` keep working.
+fn hidden_sibling_path(path: &syn::Path, prefix: &str) -> syn::Path {
+    let mut hidden = path.clone();
+    if let Some(last) = hidden.segments.last_mut() {
+        last.ident = format_ident!("{}{}", prefix, last.ident);
+        last.arguments = syn::PathArguments::None;
+    }
+    hidden
+}
+
 fn gen_auth_value_materialization(input: &ItemFn) -> proc_macro2::TokenStream {
     let params = extract_params(input);
     let profiling_enabled = cfg!(feature = "profiling");
@@ -1093,6 +1110,8 @@ enum RecurTileMode {
     OutputOnly,
     StateOnly,
     StateOutput,
+    /// `kind = map` tiles: no loop-carried handles, one output per item.
+    Map,
 }
 
 #[derive(Clone)]
@@ -1103,6 +1122,8 @@ struct RecurTileShape {
     state_inner: Option<Type>,
     extra_params: Vec<ParamInfo>,
     output_schema: Option<Type>,
+    /// Per-item output type of a map tile.
+    map_output: Option<Type>,
 }
 
 #[derive(Clone)]
@@ -1282,6 +1303,7 @@ fn validate_recur_tile_shape(input: &ItemFn, return_kind: &ProtocolReturnKind) -
                 }
             }
         }
+        RecurTileMode::Map => unreachable!("recur tile shapes are never map-shaped"),
     }
 
     RecurTileShape {
@@ -1291,6 +1313,41 @@ fn validate_recur_tile_shape(input: &ItemFn, return_kind: &ProtocolReturnKind) -
         state_inner,
         extra_params,
         output_schema,
+        map_output: None,
+    }
+}
+
+fn validate_map_tile_shape(input: &ItemFn, return_kind: &ProtocolReturnKind) -> RecurTileShape {
+    let params = extract_params(input);
+    let input_param = params
+        .first()
+        .filter(|param| recur_input_inner_type(&param.ty).is_some())
+        .cloned()
+        .unwrap_or_else(|| {
+            panic!("`#[tile(kind = map)]` tiles must start with `input: RecurInput<T>`")
+        });
+
+    let extra_params = params[1..].to_vec();
+    if extra_params.iter().any(|param| {
+        recur_state_inner_type(&param.ty).is_some()
+            || recur_output_inner_type(&param.ty).is_some()
+            || recur_input_inner_type(&param.ty).is_some()
+    }) {
+        panic!("`#[tile(kind = map)]` tiles carry no state or output between items and only support plain `args...` after `input`");
+    }
+
+    let ProtocolReturnKind::Value(map_output) = return_kind else {
+        panic!("`#[tile(kind = map)]` tiles must return a plain value for each item");
+    };
+
+    RecurTileShape {
+        mode: RecurTileMode::Map,
+        input_param,
+        state_param: None,
+        state_inner: None,
+        extra_params,
+        output_schema: None,
+        map_output: Some(map_output.clone()),
     }
 }
 
@@ -1417,6 +1474,7 @@ fn validate_recur_sequence_shape(input: &ItemFn) -> RecurSequenceShape {
                 panic!("`#[sequence(kind = recur)]` state+output functions must return `(RecurSequenceState<S>, RecurSequenceOutput<T>)` matching their parameters");
             }
         }
+        RecurTileMode::Map => unreachable!("recur sequences have no map mode"),
     }
 
     RecurSequenceShape {
@@ -1429,20 +1487,34 @@ fn validate_recur_sequence_shape(input: &ItemFn) -> RecurSequenceShape {
     }
 }
 
+/// Generate the hidden driver that `call_recur!` (or, for map tiles,
+/// `call_map!`) sites are rewritten to. Map drivers also run the tile body
+/// `implementation_name` directly, off the calling thread.
 fn gen_recur_driver_function(
     fn_name: &syn::Ident,
+    implementation_name: &syn::Ident,
     shape: &RecurTileShape,
 ) -> proc_macro2::TokenStream {
     let fn_name_str = fn_name.to_string();
-    let hidden_name = format_ident!("__raster_recur_auth_{}", fn_name);
+    let hidden_name = if shape.mode == RecurTileMode::Map {
+        format_ident!("__raster_map_auth_{}", fn_name)
+    } else {
+        format_ident!("__raster_recur_auth_{}", fn_name)
+    };
     let source_ident = format_ident!("__RasterRecurSource");
     let item_ty =
         recur_input_inner_type(&shape.input_param.ty).expect("validated recur input type");
-    let result_ty = shape
-        .output_schema
-        .as_ref()
-        .or(shape.state_inner.as_ref())
-        .expect("recur caller-visible result type");
+    let result_ty = match &shape.map_output {
+        Some(map_output) => quote! { ::raster::alloc::vec::Vec<#map_output> },
+        None => {
+            let result_ty = shape
+                .output_schema
+                .as_ref()
+                .or(shape.state_inner.as_ref())
+                .expect("recur caller-visible result type");
+            quote! { #result_ty }
+        }
+    };
     let extra_generic_idents: Vec<_> = shape
         .extra_params
         .iter()
@@ -1506,6 +1578,12 @@ fn gen_recur_driver_function(
                 #fn_name(input, state, output, #(#extra_materialized_idents),*)
             }
         },
+        RecurTileMode::Map => quote! {
+            {
+                #(#extra_arg_materialization)*
+                #fn_name(input, #(#extra_materialized_idents),*)
+            }
+        },
     };
     let state_wrapper = shape.state_param.as_ref().map(|param| {
         let state_ident = &param.ident;
@@ -1571,6 +1649,43 @@ fn gen_recur_driver_function(
                 )
             }
         }
+        RecurTileMode::Map => {
+            let map_output = shape.map_output.as_ref().expect("map output type");
+            // The bodies run on other threads, which cannot resolve auth
+            // values, so the arguments are materialized once up front.
+            let body_arg_idents: Vec<_> = shape
+                .extra_params
+                .iter()
+                .enumerate()
+                .map(|(index, _)| format_ident!("__raster_map_arg_{}", index))
+                .collect();
+            let body_arg_materialization: Vec<_> = shape
+                .extra_params
+                .iter()
+                .zip(body_arg_idents.iter())
+                .map(|(param, body_arg_ident)| {
+                    let name = &param.ident;
+                    let ty = &param.ty;
+                    quote! {
+                        let #body_arg_ident: #ty = ::raster::into_auth_value::<#ty, _>(#name.clone())
+                            .unwrap_or_else(|e| panic!("Failed to materialize auth value for map argument '{}': {}", stringify!(#name), e))
+                            .into_inner();
+                    }
+                })
+                .collect();
+            quote! {
+                {
+                    #(#body_arg_materialization)*
+                    ::raster::run_map_list::<#item_ty, #map_output, _, _>(
+                        input,
+                        #fn_name_str,
+                        ::core::module_path!(),
+                        |input| #implementation_name(input, #(::core::clone::Clone::clone(&#body_arg_idents)),*),
+                        move |input| #call_expr,
+                    )
+                }
+            }
+        }
     };
     let wrapper_generics = if extra_generic_idents.is_empty() {
         quote! { <#source_ident> }
@@ -1747,6 +1862,7 @@ fn gen_recur_driver_function(
                 ::raster::publish_trace_event(::raster::core::trace::TraceEvent::RecurTileExec(
                     ::raster::core::trace::FnCallRecord {
                         fn_name: ::raster::alloc::string::String::from(#fn_name_str),
+                        module_path: ::raster::alloc::string::String::from(::core::module_path!()),
                         input: __raster_input,
                         output: __raster_output,
                         draft_transition_witness: ::core::option::Option::None,
@@ -1840,6 +1956,7 @@ fn gen_recur_sequence_step_function(
                 );
             }
         }
+        RecurTileMode::Map => unreachable!("recur sequences have no map mode"),
     };
 
     quote! {
@@ -1852,6 +1969,7 @@ fn gen_recur_sequence_step_function(
                 #input_serialization
                 let mut __raster_record = ::raster::core::trace::FnCallRecord {
                     fn_name: ::raster::alloc::string::String::from(#fn_name_str),
+                    module_path: ::raster::alloc::string::String::from(::core::module_path!()),
                     input: __raster_input,
                     output: ::core::option::Option::None,
                     draft_transition_witness: ::core::option::Option::None,
@@ -1961,6 +2079,7 @@ fn gen_recur_sequence_driver_function(
                 #step_name(input, state, output, #(#extra_materialized_idents),*)
             }
         },
+        RecurTileMode::Map => unreachable!("recur sequences have no map mode"),
     };
     let state_wrapper = shape.state_param.as_ref().map(|param| {
         let state_ident = &param.ident;
@@ -2028,6 +2147,7 @@ fn gen_recur_sequence_driver_function(
                 )
             }
         }
+        RecurTileMode::Map => unreachable!("recur sequences have no map mode"),
     };
     let wrapper_generics = if extra_generic_idents.is_empty() {
         quote! { <#source_ident> }
@@ -2092,6 +2212,7 @@ fn gen_recur_sequence_driver_function(
                 ::raster::publish_trace_event(::raster::core::trace::TraceEvent::RecurSequenceExec(
                     ::raster::core::trace::FnCallRecord {
                         fn_name: ::raster::alloc::string::String::from(#fn_name_str),
+                        module_path: ::raster::alloc::string::String::from(::core::module_path!()),
                         input: __raster_input,
                         output: __raster_output,
                         draft_transition_witness: ::core::option::Option::None,
@@ -2153,6 +2274,7 @@ fn gen_replay_output_serialization(kind: &ProtocolReturnKind) -> proc_macro2::To
         let replay_output = ::raster::core::draft::TileReplayJournal {
             output_bytes: __raster_output_bytes,
             draft_transition: __raster_draft_transition,
+            failure: ::core::option::Option::None,
         };
         let output = ::raster::core::postcard::to_allocvec(&replay_output)
             .map_err(|e| ::raster::core::Error::Serialization(::raster::alloc::format!("Failed to serialize replay output: {}", e)))?;
@@ -2472,6 +2594,73 @@ fn gen_function_call(target_fn: &syn::Ident, input: &ItemFn) -> proc_macro2::Tok
     }
 }
 
+/// Wrap the tile call of the native entry point so a resumed run can take
+/// the output recorded before its checkpoint for this tile at its
+/// coordinates instead of running the body.
+/// Drafts and recur handles cannot be rebuilt from their serialized form, so
+/// tiles returning them always run. Map tiles also take the output their body
+/// already computed in parallel.
+fn gen_native_function_call(
+    function_call: &proc_macro2::TokenStream,
+    fn_name_str: &str,
+    return_kind: &ProtocolReturnKind,
+    output: &ReturnType,
+    map_tile: bool,
+) -> proc_macro2::TokenStream {
+    let return_ty = match (return_kind, output) {
+        (
+            ProtocolReturnKind::Unit
+            | ProtocolReturnKind::Value(_)
+            | ProtocolReturnKind::Fallible(_),
+            ReturnType::Type(_, ty),
+        ) => ty,
+        _ => return function_call.clone(),
+    };
+    let mapped_output = if map_tile {
+        quote! {
+            .or_else(|| ::raster::__private::take_mapped_tile_output::<#return_ty>(#fn_name_str))
+        }
+    } else {
+        quote! {}
+    };
+    quote! {
+        let result: #return_ty = match ::raster::__private::replayed_tile_output::<#return_ty>(
+            #fn_name_str,
+            ::core::module_path!(),
+            __raster_tile_execution_scope.coordinates(),
+        )#mapped_output {
+            ::core::option::Option::Some(result) => result,
+            ::core::option::Option::None => {
+                #function_call
+                result
+            }
+        };
+    }
+}
+
+/// Run `body` in the native entry point so that a panic in it is published
+/// as a `TileFailed` event of `kind` before it resumes. `body` ends in the
+/// value bound to `pattern`.
+fn gen_tile_failure_capture(
+    pattern: proc_macro2::TokenStream,
+    kind: &str,
+    body: proc_macro2::TokenStream,
+    fn_name_str: &str,
+) -> proc_macro2::TokenStream {
+    let kind = format_ident!("{}", kind);
+    quote! {
+        let #pattern = match ::raster::__private::catch_tile_failure(
+            ::raster::core::trace::TileFailureKind::#kind,
+            || { #body },
+        ) {
+            ::core::result::Result::Ok(value) => value,
+            ::core::result::Result::Err(failure) => {
+                failure.publish(#fn_name_str, ::core::module_path!(), __raster_input)
+            }
+        };
+    }
+}
+
 fn is_call_macro(expr_macro: &syn::ExprMacro) -> bool {
     expr_macro.mac.path.is_ident("call")
 }
@@ -2488,14 +2677,18 @@ fn is_call_recur_seq_macro(expr_macro: &syn::ExprMacro) -> bool {
     expr_macro.mac.path.is_ident("call_recur_seq")
 }
 
+fn is_call_map_macro(expr_macro: &syn::ExprMacro) -> bool {
+    expr_macro.mac.path.is_ident("call_map")
+}
+
 struct SequenceCallInput {
-    callee: syn::Ident,
+    callee: syn::Path,
     args: syn::punctuated::Punctuated<Expr, Token![,]>,
 }
 
 impl Parse for SequenceCallInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let callee: syn::Ident = input.parse()?;
+        let callee: syn::Path = input.parse()?;
         let mut args = syn::punctuated::Punctuated::new();
         if input.parse::<Option<Token![,]>>()?.is_some() {
             args = syn::punctuated::Punctuated::parse_terminated(input)?;
@@ -2505,7 +2698,7 @@ impl Parse for SequenceCallInput {
 }
 
 struct RecurCallInput {
-    tile: syn::Ident,
+    tile: syn::Path,
     input: Expr,
     chunk: Option<Expr>,
     state: Option<Expr>,
@@ -2513,8 +2706,15 @@ struct RecurCallInput {
     args: syn::punctuated::Punctuated<Expr, Token![,]>,
 }
 
+struct MapCallInput {
+    tile: syn::Path,
+    input: Expr,
+    chunk: Option<Expr>,
+    args: syn::punctuated::Punctuated<Expr, Token![,]>,
+}
+
 struct RecurSequenceCallInput {
-    sequence: syn::Ident,
+    sequence: syn::Path,
     input: Expr,
     state: Option<Expr>,
     output: Option<Expr>,
@@ -2553,7 +2753,7 @@ fn parse_optional_named_expr(input: ParseStream, expected: &str) -> syn::Result<
 impl Parse for RecurCallInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         parse_named_key(input, "tile")?;
-        let tile: syn::Ident = input.parse()?;
+        let tile: syn::Path = input.parse()?;
         input.parse::<Token![,]>()?;
 
         parse_named_key(input, "input")?;
@@ -2588,10 +2788,37 @@ impl Parse for RecurCallInput {
     }
 }
 
+impl Parse for MapCallInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parse_named_key(input, "tile")?;
+        let tile: syn::Path = input.parse()?;
+        input.parse::<Token![,]>()?;
+
+        parse_named_key(input, "input")?;
+        let input_expr: Expr = input.parse()?;
+        input.parse::<Token![,]>()?;
+
+        let chunk = parse_optional_named_expr(input, "chunk")?;
+
+        parse_named_key(input, "args")?;
+        let content;
+        syn::parenthesized!(content in input);
+        let args = syn::punctuated::Punctuated::parse_terminated(&content)?;
+        let _ = input.parse::<Option<Token![,]>>()?;
+
+        Ok(Self {
+            tile,
+            input: input_expr,
+            chunk,
+            args,
+        })
+    }
+}
+
 impl Parse for RecurSequenceCallInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         parse_named_key(input, "sequence")?;
-        let sequence: syn::Ident = input.parse()?;
+        let sequence: syn::Path = input.parse()?;
         input.parse::<Token![,]>()?;
 
         parse_named_key(input, "input")?;
@@ -2654,43 +2881,53 @@ impl Parse for RecurSequenceCallInput {
 fn rewrite_call_seq_macro(expr_macro: &syn::ExprMacro) -> Expr {
     let input =
         syn::parse2::<SequenceCallInput>(expr_macro.mac.tokens.clone()).unwrap_or_else(|_| {
-            panic!("call_seq! expects an identifier callee followed by zero or more arguments")
+            panic!("call_seq! expects a sequence path followed by zero or more arguments")
         });
-    let hidden = format_ident!("__raster_sequence_auth_{}", input.callee);
+    let hidden = hidden_sibling_path(&input.callee, "__raster_sequence_auth_");
     let args = input.args;
     syn::parse_quote! {
         #hidden(#args)
     }
 }
 
+/// The list source of a `call_recur!` or `call_map!` site, split into chunks
+/// when the site declares `chunk = N`.
+fn gen_list_source_expr(
+    source_expr: Expr,
+    chunk: Option<Expr>,
+    macro_name: &str,
+) -> proc_macro2::TokenStream {
+    let Some(chunk_expr) = chunk else {
+        return quote! { #source_expr };
+    };
+    // The chunk size is pinned into the CFS by static discovery, so it
+    // must be an integer literal — anything dynamic would let the
+    // executed chunking diverge from the declared schema.
+    if !matches!(
+        &chunk_expr,
+        Expr::Lit(expr_lit) if matches!(&expr_lit.lit, syn::Lit::Int(_))
+    ) {
+        panic!(
+            "{} `chunk = ...` must be an integer literal so it can be pinned in the CFS",
+            macro_name
+        );
+    }
+    quote! {
+        ::raster::chunk_auth_ref(
+            ::raster::into_auth_ref(#source_expr),
+            (#chunk_expr) as usize,
+        )
+    }
+}
+
 fn rewrite_call_recur_macro(expr_macro: &syn::ExprMacro) -> Expr {
     let input = syn::parse2::<RecurCallInput>(expr_macro.mac.tokens.clone()).unwrap_or_else(|_| {
         panic!(
             "call_recur! expects `tile = ...`, `input = ...`, optional `state = ...`, optional `output = ...`, and `args = (...)`"
         )
     });
-    let hidden = format_ident!("__raster_recur_auth_{}", input.tile);
-    let source_expr = input.input;
-    let input_expr = match input.chunk {
-        Some(chunk_expr) => {
-            // The chunk size is pinned into the CFS by static discovery, so it
-            // must be an integer literal — anything dynamic would let the
-            // executed chunking diverge from the declared schema.
-            if !matches!(
-                &chunk_expr,
-                Expr::Lit(expr_lit) if matches!(&expr_lit.lit, syn::Lit::Int(_))
-            ) {
-                panic!("call_recur! `chunk = ...` must be an integer literal so it can be pinned in the CFS");
-            }
-            quote! {
-                ::raster::chunk_auth_ref(
-                    ::raster::into_auth_ref(#source_expr),
-                    (#chunk_expr) as usize,
-                )
-            }
-        }
-        None => quote! { #source_expr },
-    };
+    let hidden = hidden_sibling_path(&input.tile, "__raster_recur_auth_");
+    let input_expr = gen_list_source_expr(input.input, input.chunk, "call_recur!");
     let state_expr = input.state;
     let output_expr = input.output;
     let args: Vec<_> = input.args.into_iter().collect();
@@ -2730,6 +2967,23 @@ fn rewrite_call_recur_macro(expr_macro: &syn::ExprMacro) -> Expr {
     }
 }
 
+fn rewrite_call_map_macro(expr_macro: &syn::ExprMacro) -> Expr {
+    let input = syn::parse2::<MapCallInput>(expr_macro.mac.tokens.clone()).unwrap_or_else(|_| {
+        panic!("call_map! expects `tile = ...`, `input = ...`, optional `chunk = ...`, and `args = (...)`")
+    });
+    let hidden = hidden_sibling_path(&input.tile, "__raster_map_auth_");
+    let input_expr = gen_list_source_expr(input.input, input.chunk, "call_map!");
+    let args: Vec<_> = input.args.into_iter().collect();
+    syn::parse_quote! {
+        {
+            let __raster_recur_site_scope = ::raster::__private::RecurSiteScopeGuard::enter();
+            let __raster_recur_result = #hidden(#input_expr #(, #args)*);
+            let _ = &__raster_recur_site_scope;
+            __raster_recur_result
+        }
+    }
+}
+
 fn rewrite_call_recur_seq_macro(expr_macro: &syn::ExprMacro) -> Expr {
     let input =
         syn::parse2::<RecurSequenceCallInput>(expr_macro.mac.tokens.clone()).unwrap_or_else(|_| {
@@ -2737,7 +2991,7 @@ fn rewrite_call_recur_seq_macro(expr_macro: &syn::ExprMacro) -> Expr {
                 "call_recur_seq! expects `sequence = ...`, `input = ...`, optional `state = ...`, optional `output = ...`, and `args = (...)`"
             )
         });
-    let hidden = format_ident!("__raster_recur_sequence_auth_{}", input.sequence);
+    let hidden = hidden_sibling_path(&input.sequence, "__raster_recur_sequence_auth_");
     let input_expr = input.input;
     let state_expr = input.state;
     let output_expr = input.output;
@@ -2779,11 +3033,9 @@ fn rewrite_call_recur_seq_macro(expr_macro: &syn::ExprMacro) -> Expr {
 }
 
 fn rewrite_call_macro(expr_macro: &syn::ExprMacro) -> Expr {
-    let input =
-        syn::parse2::<SequenceCallInput>(expr_macro.mac.tokens.clone()).unwrap_or_else(|_| {
-            panic!("call! expects an identifier callee followed by zero or more arguments")
-        });
-    let marker = tile_call_binding_marker_ident(&input.callee);
+    let input = syn::parse2::<SequenceCallInput>(expr_macro.mac.tokens.clone())
+        .unwrap_or_else(|_| panic!("call! expects a tile path followed by zero or more arguments"));
+    let marker = tile_call_binding_marker_path(&input.callee);
     let original = Expr::Macro(expr_macro.clone());
     syn::parse_quote! {
         ::raster::__private::bind_tile_call::<#marker, _>(#original)
@@ -2791,26 +3043,260 @@ fn rewrite_call_macro(expr_macro: &syn::ExprMacro) -> Expr {
 }
 
 fn rewrite_try_call_macro(expr_macro: &syn::ExprMacro) -> Expr {
-    let input =
-        syn::parse2::<SequenceCallInput>(expr_macro.mac.tokens.clone()).unwrap_or_else(|_| {
-            panic!("call! expects an identifier callee followed by zero or more arguments")
-        });
-    let marker = tile_call_binding_marker_ident(&input.callee);
+    let input = syn::parse2::<SequenceCallInput>(expr_macro.mac.tokens.clone())
+        .unwrap_or_else(|_| panic!("call! expects a tile path followed by zero or more arguments"));
+    let marker = tile_call_binding_marker_path(&input.callee);
     let original = Expr::Macro(expr_macro.clone());
     syn::parse_quote! {
         ::raster::__private::bind_tile_try_call::<#marker, _>(#original)
     }
 }
 
+/// Counts the canonical calls made by a piece of a sequence body, i.e. the sequence
+/// items it spans in the CFS.
+fn count_sequence_calls(visit: impl FnOnce(&mut SequenceCallCounter)) -> usize {
+    let mut counter = SequenceCallCounter(0);
+    visit(&mut counter);
+    counter.0
+}
+
+struct SequenceCallCounter(usize);
+
+impl<'ast> Visit<'ast> for SequenceCallCounter {
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        let segments: Vec<String> = mac
+            .path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect();
+        let name = match segments.as_slice() {
+            [name] => name.as_str(),
+            [prefix, name] if prefix == "raster" => name.as_str(),
+            _ => return,
+        };
+        if matches!(
+            name,
+            "call" | "call_seq" | "call_recur" | "call_recur_seq" | "call_map"
+        ) {
+            self.0 += 1;
+        }
+    }
+}
+
+/// Makes a branch condition the plain value of the tile output it is bound to.
+fn wrap_branch_condition(cond: &mut Expr) {
+    let condition = cond.clone();
+    *cond = match condition {
+        Expr::Path(_) => syn::parse_quote! { ::raster::__private::branch_value(&#condition) },
+        condition => syn::parse_quote! { ::raster::__private::branch_value(#condition) },
+    };
+}
+
+/// Wraps a branch arm so that the items of the arms around it, which were not taken,
+/// still consume their sequence coordinates.
+fn wrap_branch_arm(body: Expr, calls_before: usize, calls_after: usize) -> Expr {
+    let skip = |calls: usize| {
+        (calls > 0).then(|| {
+            let calls = calls as u32;
+            quote! { ::raster::__private::skip_branch_items(#calls); }
+        })
+    };
+    let skip_before = skip(calls_before);
+    match skip(calls_after) {
+        Some(skip_after) => syn::parse_quote! {
+            {
+                #skip_before
+                let __raster_branch_arm_value = #body;
+                #skip_after
+                __raster_branch_arm_value
+            }
+        },
+        None => syn::parse_quote! {
+            {
+                #skip_before
+                #body
+            }
+        },
+    }
+}
+
+/// A name bound by a destructuring `let` over a `call!` result, with the
+/// field / tuple-position path that reaches it inside the call output.
+struct DestructuredBinding {
+    ident: syn::Ident,
+    mutability: Option<Token![mut]>,
+    members: Vec<syn::Member>,
+}
+
+fn is_destructuring_pattern(pat: &Pat) -> bool {
+    match pat {
+        Pat::Type(pat_type) => is_destructuring_pattern(&pat_type.pat),
+        Pat::Paren(pat_paren) => is_destructuring_pattern(&pat_paren.pat),
+        Pat::Reference(pat_ref) => is_destructuring_pattern(&pat_ref.pat),
+        Pat::Tuple(_) | Pat::TupleStruct(_) | Pat::Struct(_) => true,
+        _ => false,
+    }
+}
+
+fn is_direct_call_macro(expr: &Expr) -> bool {
+    match expr {
+        Expr::Macro(expr_macro) => is_call_macro(expr_macro),
+        Expr::Paren(expr_paren) => is_direct_call_macro(&expr_paren.expr),
+        Expr::Group(expr_group) => is_direct_call_macro(&expr_group.expr),
+        _ => false,
+    }
+}
+
+fn collect_destructured_bindings(
+    pat: &Pat,
+    members: &mut Vec<syn::Member>,
+    out: &mut Vec<DestructuredBinding>,
+) -> syn::Result<()> {
+    let mut collect_positional = |elems: &mut dyn Iterator<Item = &Pat>| -> syn::Result<()> {
+        let mut elems = elems.enumerate();
+        for (index, elem) in elems.by_ref() {
+            if matches!(elem, Pat::Rest(_)) {
+                break;
+            }
+            members.push(syn::Member::Unnamed(syn::Index::from(index)));
+            collect_destructured_bindings(elem, members, out)?;
+            members.pop();
+        }
+        // Positions after `..` depend on the output's arity, which the macro cannot see.
+        if let Some((_, elem)) = elems.find(|(_, elem)| !matches!(elem, Pat::Wild(_))) {
+            return Err(syn::Error::new_spanned(
+                elem,
+                "destructured `call!` bindings after `..` are not supported",
+            ));
+        }
+        Ok(())
+    };
+
+    match pat {
+        Pat::Ident(pat_ident) => {
+            out.push(DestructuredBinding {
+                ident: pat_ident.ident.clone(),
+                mutability: pat_ident.mutability,
+                members: members.clone(),
+            });
+            if let Some((_, subpat)) = &pat_ident.subpat {
+                collect_destructured_bindings(subpat, members, out)?;
+            }
+            Ok(())
+        }
+        Pat::Type(pat_type) => collect_destructured_bindings(&pat_type.pat, members, out),
+        Pat::Paren(pat_paren) => collect_destructured_bindings(&pat_paren.pat, members, out),
+        Pat::Reference(pat_ref) => collect_destructured_bindings(&pat_ref.pat, members, out),
+        Pat::Wild(_) => Ok(()),
+        Pat::Tuple(pat_tuple) => collect_positional(&mut pat_tuple.elems.iter()),
+        Pat::TupleStruct(pat_tuple_struct) => {
+            collect_positional(&mut pat_tuple_struct.elems.iter())
+        }
+        Pat::Struct(pat_struct) => {
+            for field in &pat_struct.fields {
+                members.push(field.member.clone());
+                collect_destructured_bindings(&field.pat, members, out)?;
+                members.pop();
+            }
+            Ok(())
+        }
+        other => Err(syn::Error::new_spanned(
+            other,
+            "unsupported pattern in a destructured `call!` binding",
+        )),
+    }
+}
+
+/// Rewrites `let (a, b) = call!(split, x);` so that every bound name holds an
+/// `AuthRef` to its own selection of the single stored call output, keeping
+/// downstream `call!` inputs authenticated against the producer's commitment.
+fn rewrite_destructured_call_local(local: &mut syn::Local) {
+    let Some(init) = &mut local.init else {
+        return;
+    };
+    let mut bindings = Vec::new();
+    if let Err(error) = collect_destructured_bindings(&local.pat, &mut Vec::new(), &mut bindings) {
+        let error = error.to_compile_error();
+        init.expr = syn::parse_quote! { #error };
+        return;
+    }
+
+    let value_ty = match &local.pat {
+        Pat::Type(pat_type) => {
+            let ty = &pat_type.ty;
+            Some(quote! { : &#ty })
+        }
+        _ => None,
+    };
+    let names = bindings.iter().map(|binding| {
+        let mutability = &binding.mutability;
+        let ident = &binding.ident;
+        quote! { #mutability #ident }
+    });
+    let selections = bindings.iter().map(|binding| {
+        let segments = binding.members.iter().map(|member| match member {
+            syn::Member::Named(ident) => {
+                let name = ident.to_string();
+                quote! { ::raster::SelectorSegment::Field(::raster::alloc::string::String::from(#name)) }
+            }
+            syn::Member::Unnamed(index) => {
+                let value = index.index;
+                quote! { ::raster::SelectorSegment::Index(#value as u64) }
+            }
+        });
+        let members = &binding.members;
+        let ident = &binding.ident;
+        quote! {
+            ::raster::select_destructured(
+                &__raster_destructured,
+                ::raster::SelectorPath::new(::raster::alloc::vec![#(#segments),*]),
+                |__raster_value #value_ty| &__raster_value #(.#members)*,
+            )
+            .unwrap_or_else(|e| panic!("Failed to destructure call output into '{}': {}", stringify!(#ident), e))
+        }
+    });
+
+    let call = &init.expr;
+    init.expr = syn::parse_quote! {
+        {
+            let __raster_destructured = #call;
+            (#(#selections,)*)
+        }
+    };
+    local.pat = syn::parse_quote! { (#(#names,)*) };
+}
+
 fn rewrite_sequence_stmt(stmt: &mut syn::Stmt) {
     match stmt {
         syn::Stmt::Local(local) => {
+            let destructures_call = is_destructuring_pattern(&local.pat)
+                && local
+                    .init
+                    .as_ref()
+                    .is_some_and(|init| init.diverge.is_none() && is_direct_call_macro(&init.expr));
             if let Some(init) = &mut local.init {
+                if is_destructuring_pattern(&local.pat) {
+                    if let Expr::Try(expr_try) = init.expr.as_ref() {
+                        if is_direct_call_macro(&expr_try.expr) {
+                            let error = syn::Error::new_spanned(
+                                &init.expr,
+                                "fallible `call!(...)?` results cannot be destructured; bind the result to a name and `select!` from it instead",
+                            )
+                            .to_compile_error();
+                            init.expr = syn::parse_quote! { #error };
+                            return;
+                        }
+                    }
+                }
                 rewrite_sequence_expr(&mut init.expr);
                 if let Some((_, diverge)) = &mut init.diverge {
                     rewrite_sequence_expr(diverge);
                 }
             }
+            if destructures_call {
+                rewrite_destructured_call_local(local);
+            }
         }
         syn::Stmt::Item(_) => {}
         syn::Stmt::Macro(stmt_macro) => {
@@ -2836,6 +3322,11 @@ fn rewrite_sequence_stmt(stmt: &mut syn::Stmt) {
                 *stmt = syn::parse_quote! {
                     #rewritten;
                 };
+            } else if is_call_map_macro(&expr_macro) {
+                let rewritten = rewrite_call_map_macro(&expr_macro);
+                *stmt = syn::parse_quote! {
+                    #rewritten;
+                };
             }
         }
         syn::Stmt::Expr(expr, _) => rewrite_sequence_expr(expr),
@@ -2886,11 +3377,40 @@ fn rewrite_sequence_expr(expr: &mut Expr) {
         }
         Expr::Group(expr_group) => rewrite_sequence_expr(&mut expr_group.expr),
         Expr::If(expr_if) => {
+            let then_calls =
+                count_sequence_calls(|visitor| visitor.visit_block(&expr_if.then_branch));
+            let else_calls = count_sequence_calls(|visitor| {
+                if let Some((_, else_branch)) = &expr_if.else_branch {
+                    visitor.visit_expr(else_branch);
+                }
+            });
             rewrite_sequence_expr(&mut expr_if.cond);
             rewrite_sequence_block(&mut expr_if.then_branch);
             if let Some((_, else_branch)) = &mut expr_if.else_branch {
                 rewrite_sequence_expr(else_branch);
             }
+            if then_calls + else_calls > 0 {
+                match expr_if.cond.as_mut() {
+                    Expr::Let(expr_let) => wrap_branch_condition(expr_let.expr.as_mut()),
+                    cond => wrap_branch_condition(cond),
+                }
+                let then_body = Expr::Block(syn::ExprBlock {
+                    attrs: Vec::new(),
+                    label: None,
+                    block: expr_if.then_branch.clone(),
+                });
+                let then_arm = wrap_branch_arm(then_body, 0, else_calls);
+                expr_if.then_branch = syn::parse_quote!({ #then_arm });
+                let else_body = match expr_if.else_branch.take() {
+                    Some((_, else_branch)) => *else_branch,
+                    None => syn::parse_quote!({}),
+                };
+                let else_body = wrap_branch_arm(else_body, then_calls, 0);
+                expr_if.else_branch = Some((
+                    Default::default(),
+                    Box::new(syn::parse_quote!({ #else_body })),
+                ));
+            }
         }
         Expr::Index(expr_index) => {
             rewrite_sequence_expr(&mut expr_index.expr);
@@ -2909,9 +3429,16 @@ fn rewrite_sequence_expr(expr: &mut Expr) {
                 *expr = rewrite_call_recur_macro(expr_macro);
             } else if is_call_recur_seq_macro(expr_macro) {
                 *expr = rewrite_call_recur_seq_macro(expr_macro);
+            } else if is_call_map_macro(expr_macro) {
+                *expr = rewrite_call_map_macro(expr_macro);
             }
         }
         Expr::Match(expr_match) => {
+            let arm_calls: Vec<usize> = expr_match
+                .arms
+                .iter()
+                .map(|arm| count_sequence_calls(|visitor| visitor.visit_expr(&arm.body)))
+                .collect();
             rewrite_sequence_expr(&mut expr_match.expr);
             for arm in expr_match.arms.iter_mut() {
                 if let Some((_, guard)) = &mut arm.guard {
@@ -2919,6 +3446,18 @@ fn rewrite_sequence_expr(expr: &mut Expr) {
                 }
                 rewrite_sequence_expr(&mut arm.body);
             }
+            let total_calls: usize = arm_calls.iter().sum();
+            if total_calls > 0 {
+                wrap_branch_condition(&mut expr_match.expr);
+                let mut calls_before = 0;
+                for (arm, calls) in expr_match.arms.iter_mut().zip(arm_calls) {
+                    let body = (*arm.body).clone();
+                    let calls_after = total_calls - calls_before - calls;
+                    *arm.body = wrap_branch_arm(body, calls_before, calls_after);
+                    arm.comma.get_or_insert_with(Default::default);
+                    calls_before += calls;
+                }
+            }
         }
         Expr::MethodCall(expr_method_call) => {
             rewrite_sequence_expr(&mut expr_method_call.receiver);
@@ -2968,6 +3507,9 @@ fn rewrite_sequence_expr(expr: &mut Expr) {
                 } else if is_call_recur_seq_macro(expr_macro) {
                     expr_try.expr = Box::new(rewrite_call_recur_seq_macro(expr_macro));
                     return;
+                } else if is_call_map_macro(expr_macro) {
+                    expr_try.expr = Box::new(rewrite_call_map_macro(expr_macro));
+                    return;
                 }
             }
             rewrite_sequence_expr(&mut expr_try.expr);
@@ -2995,9 +3537,10 @@ fn rewrite_sequence_expr(expr: &mut Expr) {
 
 /// Parses tile attributes from the macro invocation.
 ///
-/// Uses named argument `kind` for tile type: `#[tile(kind = iter)]` or `#[tile(kind = recur)]`.
+/// Uses named argument `kind` for tile type: `#[tile(kind = iter)]`, `#[tile(kind = recur)]`
+/// or `#[tile(kind = map)]`.
 struct TileAttrs {
-    /// Tile type: "iter" (default) or "recur".
+    /// Tile type: "iter" (default), "recur" or "map".
     tile_type: String,
     estimated_cycles: Option<u64>,
     max_memory: Option<u64>,
@@ -3031,8 +3574,11 @@ impl TileAttrs {
                 let value = value.trim().trim_matches('"');
                 match key {
                     "kind" => match value {
-                        "iter" | "recur" => attrs.tile_type = value.to_string(),
-                        _ => panic!("Unknown tile kind '{}'. Valid kinds: iter, recur", value),
+                        "iter" | "recur" | "map" => attrs.tile_type = value.to_string(),
+                        _ => panic!(
+                            "Unknown tile kind '{}'. Valid kinds: iter, recur, map",
+                            value
+                        ),
                     },
                     "estimated_cycles" => {
                         attrs.estimated_cycles = value.parse().ok();
@@ -3061,8 +3607,9 @@ impl TileAttrs {
 /// # Attributes
 /// - `kind = iter` - Standard iterative tile (default if not specified)
 /// - `kind = recur` - Recursive tile for stateful computations
-/// - `estimated_cycles = N` - Expected cycle count for resource estimation
-/// - `max_memory = N` - Maximum memory usage in bytes
+/// - `kind = map` - Per-item tile for `call_map!`, whose items run in parallel natively
+/// - `estimated_cycles = N` - Expected cycle count; natively also a time limit
+/// - `max_memory = N` - Maximum heap usage in bytes, enforced natively
 /// - `description = "..."` - Human-readable description
 ///
 /// # Example
@@ -3096,30 +3643,69 @@ pub fn tile(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let function_wrapper_name = format_ident!("__raster_tile_entry_{}", fn_name_str);
     let function_replay_wrapper_name = format_ident!("__raster_tile_replay_entry_{}", fn_name_str);
+    let function_native_entry_name = format_ident!("__raster_tile_native_entry_{}", fn_name_str);
     let implementation_name = format_ident!("__raster_tile_impl_{}", fn_name_str);
     let call_binding_marker = tile_call_binding_marker_ident(fn_name);
 
     let attrs = TileAttrs::parse(attr);
-    let recur_shape = if attrs.tile_type == "recur" {
-        Some(validate_recur_tile_shape(&input_fn, &return_kind))
-    } else {
-        None
+    let map_tile = attrs.tile_type == "map";
+    if map_tile && (attrs.estimated_cycles.is_some() || attrs.max_memory.is_some()) {
+        panic!("`#[tile(kind = map)]` tiles cannot set `estimated_cycles` or `max_memory`: their bodies run on a thread pool, where tile limits are not enforced");
+    }
+    let recur_shape = match attrs.tile_type.as_str() {
+        "recur" => Some(validate_recur_tile_shape(&input_fn, &return_kind)),
+        "map" => Some(validate_map_tile_shape(&input_fn, &return_kind)),
+        _ => None,
     };
 
     // Generate optional metadata fields
-    let _estimated_cycles_expr = match attrs.estimated_cycles {
+    let estimated_cycles_expr = match attrs.estimated_cycles {
         Some(cycles) => quote! { ::core::option::Option::Some(#cycles) },
         None => quote! { ::core::option::Option::None },
     };
 
-    let _max_memory_expr = match attrs.max_memory {
+    let max_memory_expr = match attrs.max_memory {
         Some(memory) => quote! { ::core::option::Option::Some(#memory) },
         None => quote! { ::core::option::Option::None },
     };
 
+    // Tiles that declare limits run their body in a limit scope, checked once
+    // the body returns. Map tiles, whose bodies run on the pool, were rejected
+    // above.
+    let (tile_limit_enter, tile_limit_check) =
+        if attrs.estimated_cycles.is_some() || attrs.max_memory.is_some() {
+            (
+                quote! {
+                    let __raster_tile_limits = ::raster::__private::TileLimitScope::enter(
+                        #fn_name_str,
+                        ::core::module_path!(),
+                        #estimated_cycles_expr,
+                        #max_memory_expr,
+                        &__raster_input,
+                    );
+                },
+                quote! {
+                    if let ::core::result::Result::Err(failure) =
+                        __raster_tile_limits.finish(__raster_tile_execution_scope.coordinates())
+                    {
+                        failure.publish(#fn_name_str, ::core::module_path!(), __raster_input)
+                    }
+                },
+            )
+        } else {
+            (quote! {}, quote! {})
+        };
+
     // Generate deserialization and function call
     let inputs_deserialization = gen_inputs_deserialization(&input_fn);
     let function_call = gen_function_call(&implementation_name, &input_fn);
+    let native_function_call = gen_native_function_call(
+        &function_call,
+        &fn_name_str,
+        &return_kind,
+        &input_fn.sig.output,
+        map_tile,
+    );
     let output_serialization = gen_output_serialization();
     let replay_output_serialization = gen_replay_output_serialization(&return_kind);
     let trace_output_serialization = gen_tile_trace_output_serialization();
@@ -3142,7 +3728,7 @@ pub fn tile(attr: TokenStream, item: TokenStream) -> TokenStream {
         gen_tile_call_binding_marker(&call_binding_marker, &return_kind, &input_fn.sig.output);
     let recur_driver_function = recur_shape
         .as_ref()
-        .map(|shape| gen_recur_driver_function(fn_name, shape))
+        .map(|shape| gen_recur_driver_function(fn_name, &implementation_name, shape))
         .unwrap_or_else(|| quote! {});
 
     let mut exposed_sig = input_fn.sig.clone();
@@ -3167,6 +3753,72 @@ pub fn tile(attr: TokenStream, item: TokenStream) -> TokenStream {
         #implementation_sig #fn_body
     };
 
+    let native_tile_call = gen_tile_failure_capture(
+        quote! { result },
+        "Panic",
+        quote! {
+            #native_function_call
+            result
+        },
+        &fn_name_str,
+    );
+    let output_record = quote! {
+        let __raster_output = ::core::option::Option::Some(
+            ::raster::core::trace::FnOutput::new(
+                __raster_output_bytes,
+                ::raster::alloc::string::String::from(#output_type_expr),
+            ).with_raster(
+                ::raster::raster_trace_payload(&result)
+                    .unwrap_or_else(|e| panic!("Failed to build raster output payload: {}", e))
+            )
+        );
+    };
+    let output_capture = gen_tile_failure_capture(
+        quote! { (__raster_draft_transition_witness, __raster_output) },
+        "RuntimeError",
+        quote! {
+            #native_draft_capture_finish
+            #trace_output_serialization
+            #output_record
+            (__raster_draft_transition_witness, __raster_output)
+        },
+        &fn_name_str,
+    );
+    let profiled_output_capture = gen_tile_failure_capture(
+        quote! {
+            (
+                __raster_draft_transition_witness,
+                __raster_output,
+                __raster_output_record_build_start,
+            )
+        },
+        "RuntimeError",
+        quote! {
+            let __raster_draft_capture_finish_timer = ::raster::__private::profile_now();
+            #native_draft_capture_finish
+            __raster_draft_capture_ns = __raster_draft_capture_ns.saturating_add(
+                ::core::primitive::u64::try_from(__raster_draft_capture_finish_timer.elapsed().as_nanos())
+                    .unwrap_or(::core::primitive::u64::MAX)
+            );
+
+            let __raster_trace_output_start = ::raster::__private::profile_now();
+            #trace_output_serialization
+            __raster_trace_serialize_ns = __raster_trace_serialize_ns.saturating_add(
+                ::core::primitive::u64::try_from(__raster_trace_output_start.elapsed().as_nanos())
+                    .unwrap_or(::core::primitive::u64::MAX)
+            );
+
+            let __raster_output_record_build_start = ::raster::__private::profile_now();
+            #output_record
+            (
+                __raster_draft_transition_witness,
+                __raster_output,
+                __raster_output_record_build_start,
+            )
+        },
+        &fn_name_str,
+    );
+
     let original_function = if cfg!(feature = "profiling") {
         quote! {
             #(#fn_attrs)*
@@ -3200,39 +3852,19 @@ pub fn tile(attr: TokenStream, item: TokenStream) -> TokenStream {
                             .unwrap_or(::core::primitive::u64::MAX)
                     );
 
+                    #tile_limit_enter
                     let __raster_user_start = ::raster::__private::profile_now();
-                    #function_call
+                    #native_tile_call
                     let __raster_user_duration_ns =
                         ::core::primitive::u64::try_from(__raster_user_start.elapsed().as_nanos())
                             .unwrap_or(::core::primitive::u64::MAX);
+                    #tile_limit_check
 
-                    let __raster_draft_capture_finish_timer = ::raster::__private::profile_now();
-                    #native_draft_capture_finish
-                    __raster_draft_capture_ns = __raster_draft_capture_ns.saturating_add(
-                        ::core::primitive::u64::try_from(__raster_draft_capture_finish_timer.elapsed().as_nanos())
-                            .unwrap_or(::core::primitive::u64::MAX)
-                    );
-
-                    let __raster_trace_output_start = ::raster::__private::profile_now();
-                    #trace_output_serialization
-                    __raster_trace_serialize_ns = __raster_trace_serialize_ns.saturating_add(
-                        ::core::primitive::u64::try_from(__raster_trace_output_start.elapsed().as_nanos())
-                            .unwrap_or(::core::primitive::u64::MAX)
-                    );
-
-                    let __raster_output_record_build_start = ::raster::__private::profile_now();
-                    let __raster_output = ::core::option::Option::Some(
-                        ::raster::core::trace::FnOutput::new(
-                            __raster_output_bytes,
-                            ::raster::alloc::string::String::from(#output_type_expr),
-                        ).with_raster(
-                            ::raster::raster_trace_payload(&result)
-                                .unwrap_or_else(|e| panic!("Failed to build raster output payload: {}", e))
-                        )
-                    );
+                    #profiled_output_capture
 
                     let __raster_record = ::raster::core::trace::FnCallRecord {
                         fn_name: ::raster::alloc::string::String::from(#fn_name_str),
+                        module_path: ::raster::alloc::string::String::from(::core::module_path!()),
                         input: __raster_input,
                         output: __raster_output,
                         draft_transition_witness: __raster_draft_transition_witness,
@@ -3295,21 +3927,14 @@ pub fn tile(attr: TokenStream, item: TokenStream) -> TokenStream {
                     let __raster_tile_execution_scope = ::raster::__private::TileExecutionScopeGuard::enter();
                     #input_serialization
                     #native_draft_capture_start
-                    #function_call
-                    #native_draft_capture_finish
-                    #trace_output_serialization
-                    let __raster_output = ::core::option::Option::Some(
-                        ::raster::core::trace::FnOutput::new(
-                            __raster_output_bytes,
-                            ::raster::alloc::string::String::from(#output_type_expr),
-                        ).with_raster(
-                            ::raster::raster_trace_payload(&result)
-                                .unwrap_or_else(|e| panic!("Failed to build raster output payload: {}", e))
-                        )
-                    );
+                    #tile_limit_enter
+                    #native_tile_call
+                    #tile_limit_check
+                    #output_capture
 
                     let __raster_record = ::raster::core::trace::FnCallRecord {
                         fn_name: ::raster::alloc::string::String::from(#fn_name_str),
+                        module_path: ::raster::alloc::string::String::from(::core::module_path!()),
                         input: __raster_input,
                         output: __raster_output,
                         draft_transition_witness: __raster_draft_transition_witness,
@@ -3361,6 +3986,19 @@ pub fn tile(attr: TokenStream, item: TokenStream) -> TokenStream {
 
             Ok(output)
         }
+
+        // Native runners link this symbol, named after the tile's module path,
+        // so the tile is reachable even when its module is private or the
+        // crate is only a binary.
+        #[cfg(all(feature = "std", not(target_arch = "riscv32")))]
+        #[doc(hidden)]
+        #[unsafe(export_name = ::core::concat!("__raster_tile_native_entry::", ::core::module_path!(), "::", #fn_name_str))]
+        pub fn #function_native_entry_name(
+            input: &[u8],
+        ) -> ::core::result::Result<::raster::alloc::vec::Vec<u8>, ::raster::alloc::string::String> {
+            #function_replay_wrapper_name(input)
+                .map_err(|error| ::raster::alloc::string::ToString::to_string(&error))
+        }
     })
 }
 
@@ -3452,6 +4090,7 @@ fn gen_sequence_wrapped_body(
                 let __raster_sequence_start_event_publish_start = ::raster::__private::profile_now();
                 let mut __raster_record = ::raster::core::trace::FnCallRecord {
                     fn_name: ::raster::alloc::string::String::from(#fn_name_str),
+                    module_path: ::raster::alloc::string::String::from(::core::module_path!()),
                     input: __raster_input,
                     output: ::core::option::Option::None,
                     draft_transition_witness: ::core::option::Option::None,
@@ -3515,6 +4154,7 @@ fn gen_sequence_wrapped_body(
                 #input_serialization
                 let mut __raster_record = ::raster::core::trace::FnCallRecord {
                     fn_name: ::raster::alloc::string::String::from(#fn_name_str),
+                    module_path: ::raster::alloc::string::String::from(::core::module_path!()),
                     input: __raster_input,
                     output: ::core::option::Option::None,
                     draft_transition_witness: ::core::option::Option::None,
@@ -3714,8 +4354,7 @@ fn split_selector_expr(expr: Expr) -> (Expr, Vec<proc_macro2::TokenStream>) {
                         panic!("select! only supports integer literal indexes");
                     };
                     let value = expr_lit.lit.to_token_stream();
-                    segments
-                        .push(quote! { ::raster::SelectorSegment::Index((#value) as u64) });
+                    segments.push(quote! { ::raster::SelectorSegment::Index((#value) as u64) });
                 }
                 Expr::Range(expr_range) => {
                     let syn::RangeLimits::HalfOpen(_) = expr_range.limits else {
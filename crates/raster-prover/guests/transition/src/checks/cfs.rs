@@ -1,6 +1,7 @@
 //! Checks that a step record matches the control flow schema: coordinate
 //! ordering and per-argument input bindings.
 
+use raster_core::branching::select_committed_arm;
 use raster_core::cfs::{CfsCoordinates, CfsCursor, InputBinding, InputSource};
 use raster_core::trace::{ExternalData, FnInput, FnInputValue, InternalData, StepRecord};
 
@@ -204,6 +205,7 @@ pub fn verify_step_record_inputs(
             }
             InputBinding::PriorItemOutput {
                 intra_sequence_item_index,
+                selector,
             } => {
                 assert!(
                     *intra_sequence_item_index < item_coordinate as usize,
@@ -228,6 +230,14 @@ pub fn verify_step_record_inputs(
                         )
                     }
                 };
+                // The selection itself is proven against the source object root by
+                // the internal store checks; here it must be the one the CFS declares.
+                assert!(
+                    internal_meta.selector == *selector && internal_meta.selection.path == *selector,
+                    "Internal input selector for step {:?} arg {} does not match CFS binding",
+                    step_record,
+                    input_index,
+                );
                 match cfs_cursor
                     .try_get_item(&source_coordinates)
                     .expect("Expected prior item output coordinates to resolve in CFS")
@@ -258,6 +268,7 @@ pub fn get_next_expected_coordinates(
     cfs_cursor: &CfsCursor,
     step: &StepRecord,
     current_expected_coordinates: Option<&Vec<CfsCoordinates>>,
+    branch_condition_witness: Option<&Vec<u8>>,
 ) -> Vec<CfsCoordinates> {
     let coordinates = step.coordinates();
     if let Some(current_expected_coordinates) = current_expected_coordinates {
@@ -267,6 +278,26 @@ pub fn get_next_expected_coordinates(
         );
     }
 
+    // A failure ends the trace.
+    if let StepRecord::TileFailed(_) = step {
+        return Vec::new();
+    }
+
+    if let Some(branch) = cfs_cursor.try_get_branch(coordinates) {
+        let witness =
+            branch_condition_witness.expect("Branch condition step is missing its output witness");
+        let output_commitment = step
+            .output_commitment()
+            .expect("Branch condition step has no output commitment");
+        let arm_index = select_committed_arm(&branch.arms, witness, output_commitment)
+            .unwrap_or_else(|violation| {
+                panic!("Branch violation at step {:?}: {}", coordinates, violation)
+            });
+        return cfs_cursor
+            .try_get_branch_arm_next_coordinates(coordinates, arm_index)
+            .expect("Wrong branch arm coordinates");
+    }
+
     cfs_cursor
         .try_get_next_coordinates(coordinates)
         .expect("Wrong tile coordinates")
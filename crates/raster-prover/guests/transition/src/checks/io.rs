@@ -1,13 +1,15 @@
 //! Checks that recorded step commitments (input, input source, external
 //! inputs, output) match the provided witnesses, that external inputs are
 //! covered by the verified authorization journal, and that tile steps carry a
-//! verified replay proof whose output matches the recorded output witness.
+//! verified replay proof whose output matches the recorded output witness, or
+//! whose attested failure matches the recorded failure.
 
 use std::collections::BTreeMap;
 
 use risc0_zkvm::guest::env;
 
 use raster_core::authorization::AuthorizationJournal;
+use raster_core::draft::TileFailureAttestation;
 use raster_core::input::verify_selection_witness;
 use raster_core::trace::{ExternalInput, FnInput, StepRecord};
 
@@ -167,11 +169,37 @@ pub fn verify_step_record(
             .expect("Failed to encode replay journal for receipt verification");
         env::verify(replay_image_id_digest, &replay_journal_bytes)
             .expect("Failed to verify trace replay image id");
-        let output_bytes = output_witness_bytes.map(Vec::as_slice).unwrap_or(&[]);
+        verify_replay_outcome(step_record, replay_journal, output_witness_bytes);
+    }
+}
+
+/// Check the outcome attested by a verified replay journal against the step:
+/// a tile execution's output, or a failed tile's failure kind and message.
+pub fn verify_replay_outcome(
+    step_record: &StepRecord,
+    replay_journal: &raster_core::draft::TileReplayJournal,
+    output_witness_bytes: Option<&Vec<u8>>,
+) {
+    if let StepRecord::TileFailed(failed) = step_record {
+        let expected = TileFailureAttestation {
+            kind: failed.failure_kind,
+            message_hash: failed.message_hash.clone(),
+        };
         assert_eq!(
-            replay_journal.output_bytes.as_slice(),
-            output_bytes,
-            "Replay journal output bytes do not match recorded tile output witness",
+            replay_journal.failure.as_ref(),
+            Some(&expected),
+            "Replay journal does not attest the recorded tile failure",
         );
+        return;
     }
+    assert!(
+        replay_journal.failure.is_none(),
+        "Replay journal attests a failure for a tile that returned",
+    );
+    let output_bytes = output_witness_bytes.map(Vec::as_slice).unwrap_or(&[]);
+    assert_eq!(
+        replay_journal.output_bytes.as_slice(),
+        output_bytes,
+        "Replay journal output bytes do not match recorded tile output witness",
+    );
 }
@@ -32,6 +32,8 @@ use crate::merkle_tree::{
 /// Public parameters every step of the fraud proof runs under.
 pub struct PublicParams {
     pub cfs_cursor: CfsCursor,
+    /// Canonical digest of the CFS, committed in every journal of the window.
+    pub cfs_digest: Vec<u8>,
     pub transition_image_id: Vec<u8>,
 }
 
@@ -41,8 +43,11 @@ impl PublicParams {
     /// for `Next` steps — the previous journal (read in [`FraudProofWindowContext`]).
     pub fn read() -> Self {
         let cfs: ControlFlowSchema = env::read();
+        cfs.check_version()
+            .unwrap_or_else(|error| panic!("Rejected CFS: {}", error));
         let transition_image_id: Vec<u8> = env::read();
         Self {
+            cfs_digest: cfs.digest(),
             cfs_cursor: CfsCursor::new(cfs),
             transition_image_id,
         }
@@ -69,7 +74,7 @@ impl FraudProofWindowContext {
     ///
     /// - `Init`: start from the genesis state carried in the transition.
     /// - `Next`: read the previous journal, recursively verify its receipt
-    ///   against our own image id, and require state and manifest continuity.
+    ///   against our own image id, and require state, CFS and manifest continuity.
     pub fn proceed(
         params: &PublicParams,
         input: &TransitionInput,
@@ -90,6 +95,7 @@ impl FraudProofWindowContext {
                 let prev_journal: TransitionJournal = env::read();
                 verify_previous_journal(&prev_journal, &params.transition_image_id);
                 assert_state_continuity(&prev_journal, &transition);
+                assert_cfs_continuity(&prev_journal, &params.cfs_digest);
                 assert_manifest_continuity(&prev_journal, input);
 
                 let live = LiveTransition::resume(&transition);
@@ -134,6 +140,14 @@ fn assert_state_continuity(prev_journal: &TransitionJournal, transition: &Transi
     );
 }
 
+/// Every step of the fraud proof must run under the same CFS.
+fn assert_cfs_continuity(prev_journal: &TransitionJournal, cfs_digest: &[u8]) {
+    assert!(
+        prev_journal.cfs_digest == cfs_digest,
+        "CFS digest does not match the previous transition journal"
+    );
+}
+
 /// Every step of the fraud proof must be authorized against the same manifest.
 fn assert_manifest_continuity(prev_journal: &TransitionJournal, input: &TransitionInput) {
     assert!(
@@ -157,6 +171,13 @@ pub struct LiveTransition {
 impl LiveTransition {
     /// Genesis state for the first step of the window.
     fn genesis(init_transition: &InitTransition) -> Self {
+        // A window that starts at the first trace step builds on the seed
+        // leaf itself; later windows are bound to it through the journal.
+        assert!(
+            init_transition.init_frontier.position != 0
+                || init_transition.init_frontier.leaf == init_transition.seed,
+            "Initial frontier does not start from the commitment seed",
+        );
         let frontier = deserialize_frontier(&init_transition.init_frontier)
             .expect("Invalid frontier in input");
         let internal_store_frontier =
@@ -242,6 +263,7 @@ impl LiveTransition {
             cfs_cursor,
             &input.step_record,
             self.next_expected_coordinates.as_ref(),
+            input.branch_condition_witness.as_ref(),
         ));
         checks::drafts::verify_draft_transition(
             &input.step_record,
@@ -267,7 +289,9 @@ impl LiveTransition {
     /// The first window step is committed without comparison. Every later
     /// step must match the committed fingerprint at its index — except the
     /// final window item, which must diverge: that divergence is the fraud
-    /// being proven, and it transitions the machine to `Finished`.
+    /// being proven, and it transitions the machine to `Finished`. A failed
+    /// tile can be that item: its replay proof attests to the recorded
+    /// failure just as a tile execution's attests to its output.
     pub fn finalize(
         self,
         committed_fingerprint: &Fingerprint,
@@ -312,17 +336,19 @@ impl LiveTransition {
 }
 
 /// Commit the step's journal: the window's init state, the advanced state,
-/// and the image ids / manifest commitment the chain is verified against.
+/// and the image ids / CFS digest / manifest commitment the chain is verified
+/// against.
 pub fn commit_journal(
     init_state: InitTransition,
     current_state: TransitionState,
-    transition_image_id: Vec<u8>,
+    params: PublicParams,
     input: &TransitionInput,
 ) {
     let journal = TransitionJournal {
         init_state,
         current_state,
-        transition_image_id,
+        transition_image_id: params.transition_image_id,
+        cfs_digest: params.cfs_digest,
         authorization_image_id: input.authorization_image_id.clone(),
         manifest_commitment: input.authorization_journal.manifest_commitment.clone(),
     };
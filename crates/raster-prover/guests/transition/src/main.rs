@@ -52,7 +52,7 @@ fn main() {
     commit_journal(
         window_context.init_state,
         current_state,
-        params.transition_image_id,
+        params,
         &input,
     );
 }
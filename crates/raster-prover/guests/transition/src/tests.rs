@@ -4,8 +4,9 @@ use bridgetree::NonEmptyFrontier;
 
 use raster_core::authorization::AuthorizationJournal;
 use raster_core::cfs::{
-    CfsCoordinates, CfsCursor, ControlFlowSchema, InputBinding, InputSource, RecurTileItem,
-    SequenceChildItem, SequenceDef, SequenceItem, TileDef, TileItem,
+    BranchArm, BranchItem, BranchPattern, CfsCoordinates, CfsCursor, ControlFlowSchema,
+    InputBinding, InputSource, RecurTileItem, SequenceChildItem, SequenceDef, SequenceItem,
+    TileDef, TileItem, CFS_VERSION,
 };
 use raster_core::coordinate_index::{
     coordinate_index_membership_proof, coordinate_index_non_membership_proof, coordinate_index_root,
@@ -18,17 +19,19 @@ use raster_core::draft::{
 use raster_core::input::{SchemaField, SchemaFieldMode, SchemaNode, Selectable};
 use raster_core::trace::{
     ExternalData, ExternalInput, FnInput, FnInputArg, FnInputValue, InternalData,
-    SequenceEndRecord, SequenceStartRecord, StepRecord, TileExecRecord,
+    SequenceEndRecord, SequenceStartRecord, StepRecord, TileExecRecord, TileFailedRecord,
+    TileFailureKind,
 };
 use raster_core::transition::{
     InternalStoreEntry, InternalStoreLogWitness, InternalStoreReadWitness, InternalStoreWitness,
     InternalStoreWriteWitness, SerializableFrontier,
 };
 
-use crate::checks::cfs::verify_step_record_inputs;
+use crate::checks::cfs::{get_next_expected_coordinates, verify_step_record_inputs};
 use crate::checks::drafts::verify_draft_transition;
 use crate::checks::io::{
     external_input_commitment, input_source_commitment, verify_external_inputs, verify_io_witness,
+    verify_replay_outcome,
 };
 use crate::checks::store::{internal_store_leaf_hash, verify_internal_store_transition};
 use crate::merkle_tree::{
@@ -142,7 +145,7 @@ fn internal_input_witness(coordinates: CfsCoordinates, commitment: Vec<u8>) -> F
 
 fn producer_sequence_cfs() -> CfsCursor {
     CfsCursor::new(ControlFlowSchema {
-        version: "1.0".into(),
+        version: CFS_VERSION.into(),
         project: "test".into(),
         encoding: "postcard".into(),
         tiles: vec![
@@ -160,11 +163,13 @@ fn producer_sequence_cfs() -> CfsCursor {
                     }),
                     SequenceChildItem::Tile(TileItem {
                         id: "consumer".into(),
-                        sources: vec![InputBinding::PriorItemOutput {
-                            intra_sequence_item_index: 0,
-                        }],
+                        sources: vec![InputBinding::prior_item_output(0)],
                     }),
                 ],
+                branches: vec![],
+                input_types: vec![],
+                output_type: None,
+                path: String::new(),
             },
             SequenceDef {
                 id: "sub".into(),
@@ -173,6 +178,10 @@ fn producer_sequence_cfs() -> CfsCursor {
                     id: "producer".into(),
                     sources: vec![InputBinding::Direct(InputSource::Inline)],
                 })],
+                branches: vec![],
+                input_types: vec![],
+                output_type: None,
+                path: String::new(),
             },
         ],
     })
@@ -242,7 +251,7 @@ fn verify_step_record_inputs_accepts_sequence_descendant_producer_coordinates()
 
 fn chunked_recur_cfs(chunk: Option<u64>) -> CfsCursor {
     CfsCursor::new(ControlFlowSchema {
-        version: "1.0".into(),
+        version: CFS_VERSION.into(),
         project: "test".into(),
         encoding: "postcard".into(),
         tiles: vec![TileDef::iter("collect", 0, 1)],
@@ -254,6 +263,10 @@ fn chunked_recur_cfs(chunk: Option<u64>) -> CfsCursor {
                 sources: vec![],
                 chunk,
             })],
+            branches: vec![],
+            input_types: vec![],
+            output_type: None,
+            path: String::new(),
         }],
     })
 }
@@ -342,6 +355,125 @@ fn verify_step_record_inputs_requires_witness_for_declared_chunk() {
     verify_step_record_inputs(&cfs_cursor, &recur_iteration_step(0), None, None, None);
 }
 
+fn branching_cfs() -> CfsCursor {
+    let tile = |id: &str| {
+        SequenceChildItem::Tile(TileItem {
+            id: id.into(),
+            sources: vec![],
+        })
+    };
+    CfsCursor::new(ControlFlowSchema {
+        version: CFS_VERSION.into(),
+        project: "test".into(),
+        encoding: "postcard".into(),
+        tiles: vec![
+            TileDef::iter("check", 0, 1),
+            TileDef::iter("accept", 0, 0),
+            TileDef::iter("reject", 0, 0),
+        ],
+        sequences: vec![SequenceDef {
+            id: "main".into(),
+            input_sources: vec![],
+            items: vec![tile("check"), tile("accept"), tile("reject")],
+            branches: vec![BranchItem {
+                condition: 0,
+                start: 1,
+                arms: vec![
+                    BranchArm {
+                        pattern: BranchPattern::Bool(true),
+                        len: 1,
+                    },
+                    BranchArm {
+                        pattern: BranchPattern::Bool(false),
+                        len: 1,
+                    },
+                ],
+                variants: Vec::new(),
+            }],
+            input_types: vec![],
+            output_type: None,
+            path: String::new(),
+        }],
+    })
+}
+
+#[test]
+#[should_panic(expected = "Branch condition step is missing its output witness")]
+fn next_expected_coordinates_require_branch_condition_witness() {
+    let step = StepRecord::TileExec(TileExecRecord {
+        exec_index: 1,
+        tile_id: "check".into(),
+        sequence_id: "main".into(),
+        intra_sequence_index: 0,
+        coordinates: CfsCoordinates(vec![0]),
+        input_commitment: Vec::new(),
+        input_source_commitment: Vec::new(),
+        output_commitment: sha(b"out"),
+        external_input_commitment: Vec::new(),
+        internal_store_root_before: Vec::new(),
+        internal_store_root_after: Vec::new(),
+        internal_store_index_root_before: Vec::new(),
+        internal_store_index_root_after: Vec::new(),
+    });
+    get_next_expected_coordinates(&branching_cfs(), &step, None, None);
+}
+
+fn failed_check_step(message: &str) -> StepRecord {
+    StepRecord::TileFailed(TileFailedRecord {
+        exec_index: 1,
+        tile_id: "check".into(),
+        sequence_id: "main".into(),
+        intra_sequence_index: 0,
+        coordinates: CfsCoordinates(vec![0]),
+        input_commitment: Vec::new(),
+        input_source_commitment: Vec::new(),
+        external_input_commitment: Vec::new(),
+        internal_store_root: Vec::new(),
+        internal_store_index_root: Vec::new(),
+        failure_kind: TileFailureKind::Panic,
+        message_hash: sha(message.as_bytes()),
+    })
+}
+
+#[test]
+fn failed_branch_condition_ends_the_expected_coordinates() {
+    let step = failed_check_step("boom");
+    let next = get_next_expected_coordinates(
+        &branching_cfs(),
+        &step,
+        Some(&vec![CfsCoordinates(vec![0])]),
+        None,
+    );
+    assert!(next.is_empty());
+}
+
+#[test]
+fn replay_journal_attesting_the_recorded_failure_is_accepted() {
+    let journal = TileReplayJournal::failed(TileFailureKind::Panic, "boom");
+
+    verify_replay_outcome(&failed_check_step("boom"), &journal, None);
+}
+
+#[test]
+#[should_panic(expected = "Replay journal does not attest the recorded tile failure")]
+fn replay_journal_attesting_another_failure_is_rejected() {
+    let journal = TileReplayJournal::failed(TileFailureKind::Panic, "other");
+
+    verify_replay_outcome(&failed_check_step("boom"), &journal, None);
+}
+
+#[test]
+#[should_panic(expected = "Replay journal does not attest the recorded tile failure")]
+fn replay_journal_with_an_output_is_rejected_for_a_failed_tile() {
+    let journal = TileReplayJournal {
+        output_bytes: b"out".to_vec(),
+        draft_transition: None,
+        failure: None,
+    };
+
+    verify_replay_outcome(&failed_check_step("boom"), &journal, Some(&b"out".to_vec()));
+}
+
 #[test]
 #[should_panic(expected = "Tile input commitment does not match recorded input bytes")]
 fn verify_tile_commitments_reject_mismatched_input() {
@@ -920,6 +1052,7 @@ fn verify_draft_transition_tracks_multi_step_chain() {
                 },
             ],
         }),
+        failure: None,
     };
     verify_draft_transition(
         &draft_tile_step(1),
@@ -958,6 +1091,7 @@ fn verify_draft_transition_tracks_multi_step_chain() {
                 value: raster_core::draft::DraftValue::String("second".into()),
             }],
         }),
+        failure: None,
     };
     verify_draft_transition(
         &draft_tile_step(2),
@@ -1000,6 +1134,7 @@ fn verify_draft_transition_rejects_wrong_root_before() {
                 root_before: empty_root,
                 ops: Vec::new(),
             }),
+            failure: None,
         }),
         Some(&DraftTransitionWitness {
             pre_state: witness,
@@ -1029,6 +1164,7 @@ fn verify_draft_transition_rejects_wrong_schema_hash() {
                 root_before: empty_root,
                 ops: Vec::new(),
             }),
+            failure: None,
         }),
         Some(&DraftTransitionWitness {
             pre_state: witness,
@@ -1055,6 +1191,7 @@ fn verify_draft_transition_rejects_tampered_pre_state_witness() {
                 root_before: empty_root,
                 ops: Vec::new(),
             }),
+            failure: None,
         }),
         Some(&DraftTransitionWitness {
             pre_state: DraftStateWitness {
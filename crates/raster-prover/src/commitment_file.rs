@@ -0,0 +1,223 @@
+//! On-disk container for trace commitments.
+//!
+//! A commitment file opens with [`COMMITMENT_FILE_MAGIC`] and the format
+//! version as a little-endian `u32`, followed by the `postcard` encoding of a
+//! [`CommitmentFile`]. Besides the [`TraceCommitment`] itself the file records
+//! the run it was made for — tool version, project, input manifest — and
+//! repeats the commitment's parameters in a header that can be read and
+//! checked without knowing how the commitment is laid out.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{BitPackerError, Result};
+use crate::trace::{FraudProofConfig, TraceCommitment};
+
+/// Bytes every commitment file starts with.
+pub const COMMITMENT_FILE_MAGIC: [u8; 8] = *b"RSTRCMT\0";
+
+/// Version of the commitment file layout written by this build.
+pub const COMMITMENT_FILE_VERSION: u32 = 1;
+
+/// A trace commitment together with what is needed to check it later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitmentFile {
+    /// Format version read from the file's header; not part of the encoded
+    /// body.
+    #[serde(skip, default)]
+    pub format_version: u32,
+    /// Version of the Raster toolchain that wrote the file.
+    pub tool_version: String,
+    pub project_name: String,
+    /// [`ControlFlowSchema::digest`](raster_core::cfs::ControlFlowSchema::digest)
+    /// of the CFS the trace was produced under.
+    pub cfs_digest: Vec<u8>,
+    pub fraud_proof_config: FraudProofConfig,
+    pub seed: Vec<u8>,
+    /// Number of trace steps the commitment covers.
+    pub trace_len: u64,
+    /// SHA-256 of the input manifest the run was given, as committed by the
+    /// authorization guest.
+    pub manifest_commitment: Vec<u8>,
+    pub commitment: TraceCommitment,
+}
+
+impl CommitmentFile {
+    /// Wrap `commitment`, deriving the header from it.
+    pub fn new(
+        commitment: TraceCommitment,
+        fraud_proof_config: FraudProofConfig,
+        project_name: &str,
+        manifest_commitment: Vec<u8>,
+    ) -> Self {
+        Self {
+            format_version: COMMITMENT_FILE_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            project_name: project_name.to_string(),
+            cfs_digest: commitment.cfs_digest.clone(),
+            fraud_proof_config,
+            seed: commitment.seed.clone(),
+            trace_len: commitment.len() as u64,
+            manifest_commitment,
+            commitment,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let body = postcard::to_allocvec(self)
+            .map_err(|e| BitPackerError::SerializationError(e.to_string()))?;
+        let mut bytes = Vec::with_capacity(COMMITMENT_FILE_MAGIC.len() + 4 + body.len());
+        bytes.extend_from_slice(&COMMITMENT_FILE_MAGIC);
+        bytes.extend_from_slice(&COMMITMENT_FILE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+
+    /// Decode a commitment file and [`validate`](Self::validate) it.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let Some(rest) = bytes.strip_prefix(COMMITMENT_FILE_MAGIC.as_slice()) else {
+            return Err(BitPackerError::InvalidCommitment(
+                "Not a Raster commitment file (missing magic bytes)".to_string(),
+            ));
+        };
+        let Some((version, body)) = rest.split_first_chunk::<4>() else {
+            return Err(BitPackerError::InvalidCommitment(
+                "Commitment file ends before its format version".to_string(),
+            ));
+        };
+        let version = u32::from_le_bytes(*version);
+        if version != COMMITMENT_FILE_VERSION {
+            return Err(BitPackerError::InvalidCommitment(format!(
+                "Unsupported commitment file version {} (expected {})",
+                version, COMMITMENT_FILE_VERSION
+            )));
+        }
+
+        let mut file: Self = postcard::from_bytes(body)
+            .map_err(|e| BitPackerError::SerializationError(e.to_string()))?;
+        file.format_version = version;
+        file.validate()?;
+        Ok(file)
+    }
+
+    /// Check the commitment's own consistency and that the header describes
+    /// it.
+    pub fn validate(&self) -> Result<()> {
+        self.commitment.validate()?;
+
+        let header_mismatch = |field: &str| {
+            Err(BitPackerError::InvalidCommitment(format!(
+                "Header {} does not match the commitment",
+                field
+            )))
+        };
+        if self.cfs_digest != self.commitment.cfs_digest {
+            return header_mismatch("CFS digest");
+        }
+        if self.seed != self.commitment.seed {
+            return header_mismatch("seed");
+        }
+        if self.fraud_proof_config.window_size != self.commitment.window_size()
+            || self.fraud_proof_config.bits_per_item != self.commitment.fingerprint.bits_per_item()
+        {
+            return header_mismatch("fraud-proof window");
+        }
+        if self.trace_len != self.commitment.len() as u64 {
+            return header_mismatch("trace length");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use raster_core::cfs::{
+        CfsCoordinates, ControlFlowSchema, InputBinding, SequenceChildItem, SequenceDef, TileDef,
+        TileItem,
+    };
+    use raster_core::trace::{StepRecord, TileExecRecord, Trace};
+
+    use super::*;
+
+    fn make_commitment_file() -> CommitmentFile {
+        let mut cfs = ControlFlowSchema::new("test");
+        cfs.tiles.push(TileDef::iter("test_tile", 1, 1));
+        let mut main = SequenceDef::new("main");
+        main.items.push(SequenceChildItem::Tile(TileItem {
+            id: "test_tile".to_string(),
+            sources: vec![InputBinding::external()],
+        }));
+        cfs.sequences.push(main);
+
+        let trace = Trace(
+            (0..5)
+                .map(|index| {
+                    StepRecord::TileExec(TileExecRecord {
+                        exec_index: index,
+                        sequence_id: "main".to_string(),
+                        intra_sequence_index: index as u32,
+                        coordinates: CfsCoordinates(vec![0]),
+                        tile_id: "test_tile".to_string(),
+                        input_commitment: Vec::new(),
+                        input_source_commitment: Vec::new(),
+                        external_input_commitment: Vec::new(),
+                        output_commitment: index.to_le_bytes().to_vec(),
+                        internal_store_root_before: Vec::new(),
+                        internal_store_root_after: Vec::new(),
+                        internal_store_index_root_before: Vec::new(),
+                        internal_store_index_root_after: Vec::new(),
+                    })
+                })
+                .collect(),
+        );
+        let config = FraudProofConfig::from_window_size(2).expect("valid window size");
+        let commitment = TraceCommitment::from(&trace, &[3; 32], &cfs, config);
+
+        CommitmentFile::new(commitment, config, "test-project", vec![4; 32])
+    }
+
+    #[test]
+    fn commitment_file_round_trips_with_header() {
+        let file = make_commitment_file();
+        let bytes = file.to_bytes().expect("encodable commitment file");
+
+        assert!(bytes.starts_with(&COMMITMENT_FILE_MAGIC));
+        let decoded = CommitmentFile::from_bytes(&bytes).expect("valid commitment file");
+        assert_eq!(decoded.format_version, COMMITMENT_FILE_VERSION);
+        assert_eq!(decoded.tool_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(decoded.project_name, "test-project");
+        assert_eq!(decoded.seed, vec![3; 32]);
+        assert_eq!(decoded.trace_len, 5);
+        assert_eq!(decoded.fraud_proof_config.window_size, 2);
+        assert_eq!(decoded.manifest_commitment, vec![4; 32]);
+        assert_eq!(decoded.commitment.fingerprint, file.commitment.fingerprint);
+    }
+
+    #[test]
+    fn commitment_file_rejects_unknown_layouts() {
+        let file = make_commitment_file();
+        let bytes = file.to_bytes().expect("encodable commitment file");
+
+        // A bare postcard commitment, as written before the container existed.
+        let bare = postcard::to_allocvec(&file.commitment).expect("encodable commitment");
+        assert!(matches!(
+            CommitmentFile::from_bytes(&bare),
+            Err(BitPackerError::InvalidCommitment(_))
+        ));
+
+        let mut future_version = bytes.clone();
+        future_version[COMMITMENT_FILE_MAGIC.len()] += 1;
+        assert!(matches!(
+            CommitmentFile::from_bytes(&future_version),
+            Err(BitPackerError::InvalidCommitment(_))
+        ));
+
+        let mut inconsistent = file;
+        inconsistent.trace_len += 1;
+        let inconsistent = inconsistent.to_bytes().expect("encodable commitment file");
+        assert!(matches!(
+            CommitmentFile::from_bytes(&inconsistent),
+            Err(BitPackerError::InvalidCommitment(_))
+        ));
+    }
+}
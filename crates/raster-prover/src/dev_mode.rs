@@ -0,0 +1,140 @@
+//! Dev-mode proving for testing the fraud pipeline end to end.
+//!
+//! With the dev prover selected, the authorization, replay and transition
+//! guests still run in the RISC0 executor, so every journal and every
+//! assumption chained between receipts is checked as usual, but no proof is
+//! generated: receipts come back as RISC0 fake receipts
+//! (`InnerReceipt::Fake`). Raster verifiers refuse such receipts unless the
+//! dev prover is selected in the verifying process too.
+
+use crate::error::{BitPackerError, Result};
+
+/// Environment variable selecting the dev prover (`1`, `true` or `yes`).
+pub const DEV_PROVER_ENV: &str = "RASTER_DEV_PROVER";
+
+/// RISC0's own switch, which [`select_dev_prover`] keeps in step with
+/// [`DEV_PROVER_ENV`].
+const RISC0_DEV_MODE_ENV: &str = "RISC0_DEV_MODE";
+
+/// Whether the dev prover is selected for this process.
+pub fn dev_prover_enabled() -> bool {
+    std::env::var(DEV_PROVER_ENV)
+        .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Select the dev prover when `enable` is set or [`DEV_PROVER_ENV`] already
+/// selects it, and otherwise make sure RISC0 proves for real.
+///
+/// Must run before any other thread starts: it sets process environment
+/// variables. A stray `RISC0_DEV_MODE` is cleared, so fake receipts are only
+/// ever produced or accepted when Raster's own switch asks for them.
+pub fn select_dev_prover(enable: bool) -> bool {
+    let enabled = enable || dev_prover_enabled();
+    if enabled {
+        std::env::set_var(DEV_PROVER_ENV, "1");
+        std::env::set_var(RISC0_DEV_MODE_ENV, "1");
+    } else {
+        std::env::remove_var(RISC0_DEV_MODE_ENV);
+    }
+    enabled
+}
+
+/// Whether `receipt` is a dev-mode fake rather than a proof.
+pub fn is_fake_receipt(receipt: &risc0_zkvm::Receipt) -> bool {
+    matches!(receipt.inner, risc0_zkvm::InnerReceipt::Fake(_))
+}
+
+/// Refuse a fake `receipt` unless the dev prover is selected.
+pub fn check_receipt_kind(receipt: &risc0_zkvm::Receipt, what: &str) -> Result<()> {
+    check_receipt_kind_in(receipt, what, dev_prover_enabled())
+}
+
+/// Refuse a fake `receipt` unless `dev_prover` says the dev prover is
+/// selected.
+pub fn check_receipt_kind_in(
+    receipt: &risc0_zkvm::Receipt,
+    what: &str,
+    dev_prover: bool,
+) -> Result<()> {
+    if is_fake_receipt(receipt) && !dev_prover {
+        return Err(BitPackerError::InvalidFraudProof(format!(
+            "{} is a dev-mode fake receipt; set {}=1 or pass --dev-prover to accept it",
+            what, DEV_PROVER_ENV
+        )));
+    }
+    Ok(())
+}
+
+/// Run `f` with the dev prover selected or deselected, restoring both
+/// switches afterwards.
+///
+/// Tests share the process environment, so every test that depends on the
+/// selected prover goes through this lock.
+#[cfg(test)]
+pub(crate) fn with_dev_prover<R>(enable: bool, f: impl FnOnce() -> R) -> R {
+    static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    let _guard = ENV_LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let saved = [DEV_PROVER_ENV, RISC0_DEV_MODE_ENV].map(|name| (name, std::env::var_os(name)));
+    if enable {
+        std::env::set_var(DEV_PROVER_ENV, "1");
+    } else {
+        std::env::remove_var(DEV_PROVER_ENV);
+    }
+    select_dev_prover(enable);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+
+    for (name, value) in saved {
+        match value {
+            Some(value) => std::env::set_var(name, value),
+            None => std::env::remove_var(name),
+        }
+    }
+    result.unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkvm::{FakeReceipt, InnerReceipt, Receipt, ReceiptClaim};
+
+    use super::*;
+    use crate::TRANSITION_GUEST_ID;
+
+    fn fake_receipt() -> Receipt {
+        let claim = ReceiptClaim::ok(TRANSITION_GUEST_ID, Vec::new());
+        Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), Vec::new())
+    }
+
+    #[test]
+    fn fake_receipts_are_refused_outside_dev_mode() {
+        let receipt = fake_receipt();
+
+        assert!(is_fake_receipt(&receipt));
+        assert!(check_receipt_kind_in(&receipt, "Fraud proof", true).is_ok());
+        assert!(matches!(
+            check_receipt_kind_in(&receipt, "Fraud proof", false),
+            Err(BitPackerError::InvalidFraudProof(_))
+        ));
+    }
+
+    #[test]
+    fn receipt_kind_follows_the_selected_prover() {
+        let receipt = fake_receipt();
+
+        with_dev_prover(true, || {
+            assert!(dev_prover_enabled());
+            assert!(check_receipt_kind(&receipt, "Fraud proof").is_ok());
+        });
+        with_dev_prover(false, || {
+            assert!(!dev_prover_enabled());
+            assert!(matches!(
+                check_receipt_kind(&receipt, "Fraud proof"),
+                Err(BitPackerError::InvalidFraudProof(_))
+            ));
+        });
+    }
+}
@@ -22,6 +22,13 @@ pub enum BitPackerError {
     InvalidWindow(String),
     /// Structurally inconsistent trace commitment (e.g. from an untrusted file).
     InvalidCommitment(String),
+    /// Fraud proof receipt that does not prove fraud against a commitment.
+    InvalidFraudProof(String),
+    /// Trace commitment was built for a different control flow schema.
+    CfsMismatch {
+        committed: Vec<u8>,
+        expected: Vec<u8>,
+    },
     /// Trace is empty.
     EmptyTrace,
     /// IO error.
@@ -54,6 +61,20 @@ impl fmt::Display for BitPackerError {
             BitPackerError::InvalidCommitment(msg) => {
                 write!(f, "Invalid commitment: {}", msg)
             }
+            BitPackerError::InvalidFraudProof(msg) => {
+                write!(f, "Invalid fraud proof: {}", msg)
+            }
+            BitPackerError::CfsMismatch {
+                committed,
+                expected,
+            } => {
+                write!(
+                    f,
+                    "Commitment was built for CFS digest {} but the current CFS digest is {}",
+                    hex::encode(committed),
+                    hex::encode(expected)
+                )
+            }
             BitPackerError::EmptyTrace => {
                 write!(f, "Trace is empty")
             }
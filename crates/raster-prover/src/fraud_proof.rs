@@ -0,0 +1,344 @@
+//! Verification of fraud proofs against a disputed trace commitment.
+//!
+//! A fraud proof is the final receipt of the transition guest chain built by
+//! [`step_transitions`](crate::transition::step_transitions). Its journal
+//! carries the window's init state, and a `Finished` current state means the
+//! guest replayed every window step and saw the last one diverge from the
+//! committed fingerprint. Verifying the proof ties that journal to one
+//! commitment: the receipt must come from the transition guest, its external
+//! inputs must have been authorized by the authorization guest against the
+//! input manifest the commitment was made for, and the seed and window
+//! fingerprint it was checked against must be the commitment's own.
+
+use raster_core::transition::{TransitionJournal, TransitionState};
+
+use crate::authorization::authorization_guest_image_id;
+use crate::dev_mode::check_receipt_kind;
+use crate::error::{BitPackerError, Result};
+use crate::trace::TraceCommitment;
+use crate::transition::transition_guest_image_id;
+use crate::TRANSITION_GUEST_ID;
+
+/// What a verified fraud proof establishes about the disputed commitment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedFraudProof {
+    /// Trace index of the first step of the replayed window.
+    pub window_start: usize,
+    /// Number of trace steps the window covers.
+    pub window_size: usize,
+    /// Trace index of the step whose committed fingerprint is proven wrong.
+    pub divergence_position: usize,
+    /// Commitment of the input manifest the window's external inputs were
+    /// authorized against.
+    pub manifest_commitment: Vec<u8>,
+}
+
+/// Verify `receipt` as a fraud proof against the disputed `commitment`, made
+/// for the input manifest committed to as `manifest_commitment`.
+pub fn verify_fraud_proof(
+    receipt: &risc0_zkvm::Receipt,
+    commitment: &TraceCommitment,
+    manifest_commitment: &[u8],
+) -> Result<VerifiedFraudProof> {
+    check_receipt_kind(receipt, "Fraud proof")?;
+    receipt.verify(TRANSITION_GUEST_ID).map_err(|error| {
+        BitPackerError::InvalidFraudProof(format!(
+            "Receipt does not verify against the transition guest image: {}",
+            error
+        ))
+    })?;
+    let journal: TransitionJournal = receipt.journal.decode().map_err(|error| {
+        BitPackerError::InvalidFraudProof(format!(
+            "Failed to decode the transition journal: {}",
+            error
+        ))
+    })?;
+
+    check_fraud_proof_journal(&journal, commitment, manifest_commitment)
+}
+
+/// Check a transition journal taken from a verified receipt against the
+/// disputed `commitment` and the `manifest_commitment` of the input manifest
+/// it was made for.
+///
+/// The window starts at the trace index of its init frontier: the frontier
+/// holds the seed leaf and every step before the window. The guest only
+/// finishes once it has replayed as many steps as the window fingerprint
+/// covers, so the last of them is the divergent one.
+pub fn check_fraud_proof_journal(
+    journal: &TransitionJournal,
+    commitment: &TraceCommitment,
+    manifest_commitment: &[u8],
+) -> Result<VerifiedFraudProof> {
+    commitment.validate()?;
+
+    if journal.transition_image_id != transition_guest_image_id() {
+        return Err(BitPackerError::InvalidFraudProof(format!(
+            "Journal was produced for transition image {} but this verifier expects {}",
+            hex::encode(&journal.transition_image_id),
+            hex::encode(transition_guest_image_id())
+        )));
+    }
+    if journal.authorization_image_id != authorization_guest_image_id() {
+        return Err(BitPackerError::InvalidFraudProof(format!(
+            "Journal trusts authorization image {} but this verifier expects {}",
+            hex::encode(&journal.authorization_image_id),
+            hex::encode(authorization_guest_image_id())
+        )));
+    }
+    if !matches!(journal.current_state, TransitionState::Finished) {
+        return Err(BitPackerError::InvalidFraudProof(
+            "Transition chain has not finished, so it proves no divergence".to_string(),
+        ));
+    }
+    if journal.cfs_digest != commitment.cfs_digest {
+        return Err(BitPackerError::CfsMismatch {
+            committed: commitment.cfs_digest.clone(),
+            expected: journal.cfs_digest.clone(),
+        });
+    }
+    if journal.manifest_commitment != manifest_commitment {
+        return Err(BitPackerError::InvalidFraudProof(format!(
+            "Proof authorized its inputs against input manifest {} but the commitment was made \
+             for {}",
+            hex::encode(&journal.manifest_commitment),
+            hex::encode(manifest_commitment)
+        )));
+    }
+    if journal.init_state.seed != commitment.seed {
+        return Err(BitPackerError::InvalidFraudProof(format!(
+            "Proof replays a trace seeded with {} but the commitment's seed is {}",
+            hex::encode(&journal.init_state.seed),
+            hex::encode(&commitment.seed)
+        )));
+    }
+
+    let window_fingerprint = &journal.init_state.fingerprint;
+    let window_size = window_fingerprint.len();
+    if window_size != commitment.window_size() {
+        return Err(BitPackerError::InvalidFraudProof(format!(
+            "Proof replays a window of {} steps but the commitment's window size is {}",
+            window_size,
+            commitment.window_size()
+        )));
+    }
+    if window_fingerprint.bits_packer != commitment.fingerprint.bits_packer {
+        return Err(BitPackerError::InvalidFraudProof(format!(
+            "Proof packs {} fingerprint bits per step but the commitment packs {}",
+            window_fingerprint.bits_per_item(),
+            commitment.fingerprint.bits_per_item()
+        )));
+    }
+
+    let window_start = usize::try_from(journal.init_state.init_frontier.position)
+        .map_err(|_| BitPackerError::InvalidFraudProof("Window start overflows".to_string()))?;
+    let window_end = window_start.checked_add(window_size).ok_or_else(|| {
+        BitPackerError::InvalidFraudProof(format!(
+            "Proof window of {} steps starting at {} overflows",
+            window_size, window_start
+        ))
+    })?;
+    let committed_bits = commitment
+        .fingerprint
+        .bits_packer
+        .get_range(window_start, window_end, &commitment.fingerprint.bits)
+        .filter(|_| window_end <= commitment.fingerprint.len())
+        .ok_or_else(|| {
+            BitPackerError::InvalidFraudProof(format!(
+                "Proof window [{}, {}) lies outside the {} committed trace steps",
+                window_start,
+                window_end,
+                commitment.fingerprint.len()
+            ))
+        })?;
+    if committed_bits != window_fingerprint.bits {
+        return Err(BitPackerError::InvalidFraudProof(format!(
+            "Proof was checked against a window fingerprint that is not the commitment's \
+             fingerprint for steps [{}, {})",
+            window_start, window_end
+        )));
+    }
+
+    Ok(VerifiedFraudProof {
+        window_start,
+        window_size,
+        divergence_position: window_end - 1,
+        manifest_commitment: journal.manifest_commitment.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use raster_core::cfs::{
+        CfsCoordinates, ControlFlowSchema, InputBinding, SequenceChildItem, SequenceDef, TileDef,
+        TileItem,
+    };
+    use raster_core::trace::{StepRecord, TileExecRecord, Trace, TraceWindow};
+    use raster_core::transition::{InitTransition, SerializableFrontier};
+
+    use super::*;
+    use crate::trace::{FraudProofConfig, TraceVerifier, VerificationResult};
+
+    const SEED: [u8; 32] = [3; 32];
+    const MANIFEST_COMMITMENT: [u8; 32] = [4; 32];
+
+    fn make_tile_step(exec_index: u64, output: u64) -> StepRecord {
+        StepRecord::TileExec(TileExecRecord {
+            exec_index,
+            sequence_id: "main".to_string(),
+            intra_sequence_index: exec_index as u32,
+            coordinates: CfsCoordinates(vec![0]),
+            tile_id: "test_tile".to_string(),
+            input_commitment: Vec::new(),
+            input_source_commitment: Vec::new(),
+            external_input_commitment: Vec::new(),
+            output_commitment: output.to_le_bytes().to_vec(),
+            internal_store_root_before: Vec::new(),
+            internal_store_root_after: Vec::new(),
+            internal_store_index_root_before: Vec::new(),
+            internal_store_index_root_after: Vec::new(),
+        })
+    }
+
+    fn make_cfs() -> ControlFlowSchema {
+        let mut cfs = ControlFlowSchema::new("test");
+        cfs.tiles.push(TileDef::iter("test_tile", 1, 1));
+        let mut main = SequenceDef::new("main");
+        main.items.push(SequenceChildItem::Tile(TileItem {
+            id: "test_tile".to_string(),
+            sources: vec![InputBinding::external()],
+        }));
+        cfs.sequences.push(main);
+        cfs
+    }
+
+    /// A commitment to a six-step trace and the fraud window an auditor
+    /// finds when the fourth step's output differs.
+    fn disputed_commitment() -> (TraceCommitment, TraceWindow) {
+        let committed_trace = Trace((0..6).map(|i| make_tile_step(i, i)).collect());
+        let mut audited_trace = committed_trace.clone();
+        audited_trace[3] = make_tile_step(3, 999);
+
+        let cfs = make_cfs();
+        let config = FraudProofConfig {
+            window_size: 2,
+            bits_per_item: 16,
+        };
+        let commitment = TraceCommitment::from(&committed_trace, &SEED, &cfs, config);
+        let mut verifier =
+            TraceVerifier::new(commitment.clone(), &SEED, &cfs).expect("valid commitment");
+        let VerificationResult::Fraud(evidence) = verifier.verify(&audited_trace) else {
+            panic!("audited trace should diverge from the commitment");
+        };
+
+        (commitment, evidence.window)
+    }
+
+    /// The journal the transition guest commits when it finishes `window`.
+    fn finished_journal(commitment: &TraceCommitment, window: &TraceWindow) -> TransitionJournal {
+        let frontier = SerializableFrontier::from_bytes(&window.frontier).expect("window frontier");
+        TransitionJournal {
+            init_state: InitTransition {
+                seed: commitment.seed.clone(),
+                init_frontier: frontier.clone(),
+                init_internal_store_frontier: frontier,
+                init_internal_store_root: Vec::new(),
+                init_internal_store_index_root: Vec::new(),
+                active_drafts: Default::default(),
+                fingerprint: window.fingerprint.clone(),
+            },
+            current_state: TransitionState::Finished,
+            transition_image_id: transition_guest_image_id(),
+            cfs_digest: commitment.cfs_digest.clone(),
+            authorization_image_id: authorization_guest_image_id(),
+            manifest_commitment: MANIFEST_COMMITMENT.to_vec(),
+        }
+    }
+
+    #[test]
+    fn finished_journal_proves_divergence_at_the_audited_step() {
+        let (commitment, window) = disputed_commitment();
+        let journal = finished_journal(&commitment, &window);
+
+        let verified = check_fraud_proof_journal(&journal, &commitment, &MANIFEST_COMMITMENT)
+            .expect("valid fraud proof");
+
+        assert_eq!(
+            verified,
+            VerifiedFraudProof {
+                window_start: 2,
+                window_size: 2,
+                divergence_position: 3,
+                manifest_commitment: MANIFEST_COMMITMENT.to_vec(),
+            }
+        );
+    }
+
+    #[test]
+    fn journal_is_rejected_unless_it_matches_the_commitment() {
+        let (commitment, window) = disputed_commitment();
+
+        let mut unfinished = finished_journal(&commitment, &window);
+        unfinished.current_state = TransitionState::Init(unfinished.init_state.clone());
+        assert!(matches!(
+            check_fraud_proof_journal(&unfinished, &commitment, &MANIFEST_COMMITMENT),
+            Err(BitPackerError::InvalidFraudProof(_))
+        ));
+
+        let mut foreign_authorization = finished_journal(&commitment, &window);
+        foreign_authorization.authorization_image_id = vec![7; 32];
+        assert!(matches!(
+            check_fraud_proof_journal(&foreign_authorization, &commitment, &MANIFEST_COMMITMENT),
+            Err(BitPackerError::InvalidFraudProof(_))
+        ));
+
+        let mut other_cfs = finished_journal(&commitment, &window);
+        other_cfs.cfs_digest = vec![9; 32];
+        assert!(matches!(
+            check_fraud_proof_journal(&other_cfs, &commitment, &MANIFEST_COMMITMENT),
+            Err(BitPackerError::CfsMismatch { .. })
+        ));
+
+        let mut other_seed = finished_journal(&commitment, &window);
+        other_seed.init_state.seed = vec![5; 32];
+        assert!(matches!(
+            check_fraud_proof_journal(&other_seed, &commitment, &MANIFEST_COMMITMENT),
+            Err(BitPackerError::InvalidFraudProof(_))
+        ));
+
+        let mut forged_fingerprint = finished_journal(&commitment, &window);
+        forged_fingerprint.init_state.fingerprint.bits[0] ^= 1;
+        assert!(matches!(
+            check_fraud_proof_journal(&forged_fingerprint, &commitment, &MANIFEST_COMMITMENT),
+            Err(BitPackerError::InvalidFraudProof(_))
+        ));
+
+        let mut other_manifest = finished_journal(&commitment, &window);
+        other_manifest.manifest_commitment = vec![8; 32];
+        assert!(matches!(
+            check_fraud_proof_journal(&other_manifest, &commitment, &MANIFEST_COMMITMENT),
+            Err(BitPackerError::InvalidFraudProof(_))
+        ));
+
+        let mut shifted_window = finished_journal(&commitment, &window);
+        shifted_window.init_state.init_frontier.position += 1;
+        assert!(matches!(
+            check_fraud_proof_journal(&shifted_window, &commitment, &MANIFEST_COMMITMENT),
+            Err(BitPackerError::InvalidFraudProof(_))
+        ));
+    }
+
+    #[test]
+    fn journal_with_an_overflowing_window_is_rejected() {
+        let (commitment, window) = disputed_commitment();
+        let mut overflowing = finished_journal(&commitment, &window);
+        overflowing.init_state.init_frontier.position = usize::MAX as u64;
+
+        let Err(BitPackerError::InvalidFraudProof(message)) =
+            check_fraud_proof_journal(&overflowing, &commitment, &MANIFEST_COMMITMENT)
+        else {
+            panic!("overflowing window should be rejected");
+        };
+        assert!(message.contains("overflows"), "{}", message);
+    }
+}
@@ -0,0 +1,259 @@
+//! Self-contained fraud proof bundles.
+//!
+//! A bundle packs everything a third party needs to check a dispute offline:
+//! the disputed [`CommitmentFile`], the CFS and input manifest of the run, the
+//! authorization receipt, the final transition receipt and the guest image
+//! IDs both receipts verify against. Like a commitment file it opens with
+//! [`FRAUD_PROOF_BUNDLE_MAGIC`] and the format version as a little-endian
+//! `u32`, followed by the `postcard` encoding of a [`FraudProofBundle`].
+
+use raster_core::authorization::AuthorizationJournal;
+use raster_core::cfs::ControlFlowSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::authorization::authorization_guest_image_id;
+use crate::commitment_file::CommitmentFile;
+use crate::dev_mode::check_receipt_kind;
+use crate::error::{BitPackerError, Result};
+use crate::fraud_proof::{verify_fraud_proof, VerifiedFraudProof};
+use crate::transition::transition_guest_image_id;
+use crate::AUTHORIZATION_GUEST_ID;
+
+/// Bytes every fraud proof bundle starts with.
+pub const FRAUD_PROOF_BUNDLE_MAGIC: [u8; 8] = *b"RSTRFPB\0";
+
+/// Version of the bundle layout written by this build.
+pub const FRAUD_PROOF_BUNDLE_VERSION: u32 = 1;
+
+/// A fraud proof together with every artifact needed to verify it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FraudProofBundle {
+    /// The disputed commitment.
+    pub commitment: CommitmentFile,
+    pub cfs: ControlFlowSchema,
+    /// Input manifest bytes the run was given.
+    pub manifest: Vec<u8>,
+    /// Receipt of the authorization guest over `manifest`.
+    pub authorization_receipt: risc0_zkvm::Receipt,
+    /// Final receipt of the transition guest chain.
+    pub fraud_proof: risc0_zkvm::Receipt,
+    pub transition_image_id: Vec<u8>,
+    pub authorization_image_id: Vec<u8>,
+}
+
+impl FraudProofBundle {
+    /// Bundle a fraud proof with the guest image IDs of this build.
+    pub fn new(
+        commitment: CommitmentFile,
+        cfs: ControlFlowSchema,
+        manifest: Vec<u8>,
+        authorization_receipt: risc0_zkvm::Receipt,
+        fraud_proof: risc0_zkvm::Receipt,
+    ) -> Self {
+        Self {
+            commitment,
+            cfs,
+            manifest,
+            authorization_receipt,
+            fraud_proof,
+            transition_image_id: transition_guest_image_id(),
+            authorization_image_id: authorization_guest_image_id(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let body = postcard::to_allocvec(self)
+            .map_err(|e| BitPackerError::SerializationError(e.to_string()))?;
+        let mut bytes = Vec::with_capacity(FRAUD_PROOF_BUNDLE_MAGIC.len() + 4 + body.len());
+        bytes.extend_from_slice(&FRAUD_PROOF_BUNDLE_MAGIC);
+        bytes.extend_from_slice(&FRAUD_PROOF_BUNDLE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let Some(rest) = bytes.strip_prefix(FRAUD_PROOF_BUNDLE_MAGIC.as_slice()) else {
+            return Err(BitPackerError::InvalidFraudProof(
+                "Not a Raster fraud proof bundle (missing magic bytes)".to_string(),
+            ));
+        };
+        let Some((version, body)) = rest.split_first_chunk::<4>() else {
+            return Err(BitPackerError::InvalidFraudProof(
+                "Fraud proof bundle ends before its format version".to_string(),
+            ));
+        };
+        let version = u32::from_le_bytes(*version);
+        if version != FRAUD_PROOF_BUNDLE_VERSION {
+            return Err(BitPackerError::InvalidFraudProof(format!(
+                "Unsupported fraud proof bundle version {} (expected {})",
+                version, FRAUD_PROOF_BUNDLE_VERSION
+            )));
+        }
+
+        postcard::from_bytes(body).map_err(|e| BitPackerError::SerializationError(e.to_string()))
+    }
+
+    /// Check that the bundled artifacts belong together, without verifying
+    /// either receipt.
+    ///
+    /// The image IDs must be the ones this build trusts: a bundle naming other
+    /// guests could only be checked against images the verifier has not
+    /// audited.
+    pub fn check_artifacts(&self) -> Result<()> {
+        if self.transition_image_id != transition_guest_image_id() {
+            return Err(BitPackerError::InvalidFraudProof(format!(
+                "Bundle was made for transition image {} but this verifier expects {}",
+                hex::encode(&self.transition_image_id),
+                hex::encode(transition_guest_image_id())
+            )));
+        }
+        if self.authorization_image_id != authorization_guest_image_id() {
+            return Err(BitPackerError::InvalidFraudProof(format!(
+                "Bundle was made for authorization image {} but this verifier expects {}",
+                hex::encode(&self.authorization_image_id),
+                hex::encode(authorization_guest_image_id())
+            )));
+        }
+
+        self.commitment.validate()?;
+        let cfs_digest = self.cfs.digest();
+        if cfs_digest != self.commitment.cfs_digest {
+            return Err(BitPackerError::CfsMismatch {
+                committed: self.commitment.cfs_digest.clone(),
+                expected: cfs_digest,
+            });
+        }
+        if self.manifest_commitment() != self.commitment.manifest_commitment {
+            return Err(BitPackerError::InvalidFraudProof(
+                "Bundled input manifest is not the one the commitment was made for".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Verify the bundle: its artifacts, the authorization receipt over the
+    /// bundled manifest, and the fraud proof against the disputed commitment.
+    pub fn verify(&self) -> Result<VerifiedFraudProof> {
+        self.check_artifacts()?;
+        let manifest_commitment = self.manifest_commitment();
+
+        check_receipt_kind(&self.authorization_receipt, "Authorization receipt")?;
+        self.authorization_receipt
+            .verify(AUTHORIZATION_GUEST_ID)
+            .map_err(|error| {
+                BitPackerError::InvalidFraudProof(format!(
+                    "Authorization receipt does not verify against the authorization guest image: {}",
+                    error
+                ))
+            })?;
+        let authorization: AuthorizationJournal = self
+            .authorization_receipt
+            .journal
+            .decode()
+            .map_err(|error| {
+                BitPackerError::InvalidFraudProof(format!(
+                    "Failed to decode the authorization journal: {}",
+                    error
+                ))
+            })?;
+        if authorization.manifest_commitment != manifest_commitment {
+            return Err(BitPackerError::InvalidFraudProof(
+                "Authorization receipt covers a different input manifest".to_string(),
+            ));
+        }
+
+        verify_fraud_proof(
+            &self.fraud_proof,
+            &self.commitment.commitment,
+            &manifest_commitment,
+        )
+    }
+
+    /// SHA-256 of the bundled manifest, as the authorization guest commits it.
+    fn manifest_commitment(&self) -> Vec<u8> {
+        Sha256::digest(&self.manifest).to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use raster_core::cfs::TileDef;
+    use risc0_zkvm::{FakeReceipt, InnerReceipt, Receipt, ReceiptClaim};
+
+    use super::*;
+    use crate::test_fixtures::{single_tile_cfs, tile_exec_trace};
+    use crate::trace::{FraudProofConfig, TraceCommitment};
+    use crate::TRANSITION_GUEST_ID;
+
+    const MANIFEST: &[u8] = br#"{"name":"manifest"}"#;
+
+    fn fake_receipt(image_id: [u32; 8]) -> Receipt {
+        let claim = ReceiptClaim::ok(image_id, Vec::new());
+        Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), Vec::new())
+    }
+
+    fn make_bundle() -> FraudProofBundle {
+        let cfs = single_tile_cfs();
+        let config = FraudProofConfig::from_window_size(2).expect("valid window size");
+        let commitment = TraceCommitment::from(&tile_exec_trace(5), &[3; 32], &cfs, config);
+        let commitment = CommitmentFile::new(
+            commitment,
+            config,
+            "test-project",
+            Sha256::digest(MANIFEST).to_vec(),
+        );
+
+        FraudProofBundle::new(
+            commitment,
+            cfs,
+            MANIFEST.to_vec(),
+            fake_receipt(AUTHORIZATION_GUEST_ID),
+            fake_receipt(TRANSITION_GUEST_ID),
+        )
+    }
+
+    #[test]
+    fn bundle_round_trips_and_its_artifacts_belong_together() {
+        let bundle = make_bundle();
+        let bytes = bundle.to_bytes().expect("encodable bundle");
+
+        assert!(bytes.starts_with(&FRAUD_PROOF_BUNDLE_MAGIC));
+        let decoded = FraudProofBundle::from_bytes(&bytes).expect("valid bundle");
+        assert_eq!(decoded.cfs.digest(), bundle.cfs.digest());
+        assert_eq!(decoded.manifest, MANIFEST);
+        assert_eq!(decoded.transition_image_id, transition_guest_image_id());
+        decoded.check_artifacts().expect("consistent bundle");
+
+        let commitment_bytes = bundle.commitment.to_bytes().expect("encodable commitment");
+        assert!(matches!(
+            FraudProofBundle::from_bytes(&commitment_bytes),
+            Err(BitPackerError::InvalidFraudProof(_))
+        ));
+    }
+
+    #[test]
+    fn bundle_artifacts_are_rejected_unless_they_match_the_commitment() {
+        let mut other_manifest = make_bundle();
+        other_manifest.manifest = b"{}".to_vec();
+        assert!(matches!(
+            other_manifest.check_artifacts(),
+            Err(BitPackerError::InvalidFraudProof(_))
+        ));
+
+        let mut other_cfs = make_bundle();
+        other_cfs.cfs.tiles.push(TileDef::iter("other_tile", 1, 1));
+        assert!(matches!(
+            other_cfs.check_artifacts(),
+            Err(BitPackerError::CfsMismatch { .. })
+        ));
+
+        let mut other_guest = make_bundle();
+        other_guest.transition_image_id = vec![7; 32];
+        assert!(matches!(
+            other_guest.check_artifacts(),
+            Err(BitPackerError::InvalidFraudProof(_))
+        ));
+    }
+}
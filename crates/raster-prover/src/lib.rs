@@ -7,17 +7,28 @@
 //!
 //! - [`bit_packer::BitPacker`] - Pack hash bits into compact fingerprints
 //! - [`trace::TraceCommitment`] - Create incremental Merkle commitments
+//! - [`commitment_file::CommitmentFile`] - Versioned on-disk container for commitments
 //! - [`guest`] - RISC0 guest types for iterative trace verification
+//! - [`fraud_proof`] - Verification of fraud proof receipts against commitments
+//! - [`fraud_proof_bundle::FraudProofBundle`] - Single-file fraud proof with its artifacts
+//! - [`dev_mode`] - Executor-only fake proving for pipeline tests
 //! - [`error`] - Error types for the library
 
 include!(concat!(env!("OUT_DIR"), "/methods.rs"));
 
 pub mod authorization;
+pub mod commitment_file;
+pub mod dev_mode;
 pub mod error;
+pub mod fraud_proof;
+pub mod fraud_proof_bundle;
 pub mod precomputed;
 pub mod replay;
 pub mod trace;
 pub mod transition;
 pub mod utils;
 
+#[cfg(test)]
+mod test_fixtures;
+
 pub use error::{BitPackerError, Result};
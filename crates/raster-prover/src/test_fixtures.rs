@@ -0,0 +1,49 @@
+//! CFS and trace fixtures shared by the crate's unit tests.
+
+use raster_core::cfs::{
+    CfsCoordinates, ControlFlowSchema, InputBinding, SequenceChildItem, SequenceDef, TileDef,
+    TileItem,
+};
+use raster_core::trace::{StepRecord, TileExecRecord, Trace};
+
+/// A CFS whose `main` sequence runs `test_tile` on an external input.
+pub(crate) fn single_tile_cfs() -> ControlFlowSchema {
+    let mut cfs = ControlFlowSchema::new("test");
+    cfs.tiles.push(TileDef::iter("test_tile", 1, 1));
+    let mut main = SequenceDef::new("main");
+    main.items.push(SequenceChildItem::Tile(TileItem {
+        id: "test_tile".to_string(),
+        sources: vec![InputBinding::external()],
+    }));
+    cfs.sequences.push(main);
+    cfs
+}
+
+/// An execution of `test_tile` at `[0]` in `main` whose output commitment is
+/// the bytes of `output`.
+pub(crate) fn tile_exec_record(exec_index: u64, output: u64) -> TileExecRecord {
+    TileExecRecord {
+        exec_index,
+        sequence_id: "main".to_string(),
+        intra_sequence_index: exec_index as u32,
+        coordinates: CfsCoordinates(vec![0]),
+        tile_id: "test_tile".to_string(),
+        input_commitment: Vec::new(),
+        input_source_commitment: Vec::new(),
+        external_input_commitment: Vec::new(),
+        output_commitment: output.to_le_bytes().to_vec(),
+        internal_store_root_before: Vec::new(),
+        internal_store_root_after: Vec::new(),
+        internal_store_index_root_before: Vec::new(),
+        internal_store_index_root_after: Vec::new(),
+    }
+}
+
+pub(crate) fn tile_exec_step(exec_index: u64, output: u64) -> StepRecord {
+    StepRecord::TileExec(tile_exec_record(exec_index, output))
+}
+
+/// `len` executions of `test_tile`, the one at index `i` outputting `i`.
+pub(crate) fn tile_exec_trace(len: u64) -> Trace {
+    Trace((0..len).map(|index| tile_exec_step(index, index)).collect())
+}
@@ -138,7 +138,7 @@ pub const MAX_BITS_PER_ITEM: usize = 64;
 pub const MAX_FRAUD_PROOF_WINDOW_SIZE: usize = 1024;
 
 /// Parameters of the fraud-proof window a trace commitment is built with.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FraudProofConfig {
     pub window_size: usize,
     pub bits_per_item: usize,
@@ -184,6 +184,11 @@ impl FraudProofConfig {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TraceCommitment {
+    /// [`ControlFlowSchema::digest`] of the CFS the trace was produced under.
+    pub cfs_digest: Vec<u8>,
+    /// Leaf the trace tree is seeded with before the first step. It is
+    /// chosen per run, so fingerprints cannot be computed ahead of execution.
+    pub seed: Vec<u8>,
     pub fingerprint: Fingerprint,
     pub revealed_items: Vec<StepRecord>,
 }
@@ -192,6 +197,7 @@ impl TraceCommitment {
     pub fn from(
         trace: &Trace,
         seed: &[u8],
+        cfs: &ControlFlowSchema,
         fraud_proof_config: FraudProofConfig,
     ) -> TraceCommitment {
         assert!(
@@ -219,6 +225,8 @@ impl TraceCommitment {
         let fingerprint = fingerprint_acc.into_fingerprint();
 
         TraceCommitment {
+            cfs_digest: cfs.digest(),
+            seed: seed.to_vec(),
             fingerprint,
             revealed_items,
         }
@@ -229,6 +237,7 @@ impl TraceCommitment {
     pub fn try_from(
         trace: &Trace,
         seed: &[u8],
+        cfs: &ControlFlowSchema,
         fraud_proof_config: FraudProofConfig,
     ) -> Result<TraceCommitment> {
         if trace.is_empty() {
@@ -243,7 +252,19 @@ impl TraceCommitment {
                 fraud_proof_config.window_size + 1
             )));
         }
-        Ok(Self::from(trace, seed, fraud_proof_config))
+        Ok(Self::from(trace, seed, cfs, fraud_proof_config))
+    }
+
+    /// Reject a commitment that was built for a different CFS than `cfs`.
+    pub fn verify_cfs(&self, cfs: &ControlFlowSchema) -> Result<()> {
+        let expected = cfs.digest();
+        if self.cfs_digest != expected {
+            return Err(BitPackerError::CfsMismatch {
+                committed: self.cfs_digest.clone(),
+                expected,
+            });
+        }
+        Ok(())
     }
 
     /// Fraud-proof window size this commitment was built with.
@@ -259,6 +280,22 @@ impl TraceCommitment {
     /// or fingerprints is verification's job — only that its fields are
     /// consistent with each other.
     pub fn validate(&self) -> Result<()> {
+        if self.cfs_digest.len() != HASH_SIZE {
+            return Err(BitPackerError::InvalidCommitment(format!(
+                "CFS digest is {} bytes, expected {}",
+                self.cfs_digest.len(),
+                HASH_SIZE
+            )));
+        }
+
+        if self.seed.len() != HASH_SIZE {
+            return Err(BitPackerError::InvalidCommitment(format!(
+                "Seed is {} bytes, expected {}",
+                self.seed.len(),
+                HASH_SIZE
+            )));
+        }
+
         let bits_per_item = self.fingerprint.bits_per_item();
         if !(MIN_BITS_PER_ITEM..=MAX_BITS_PER_ITEM).contains(&bits_per_item) {
             return Err(BitPackerError::InvalidCommitment(format!(
@@ -520,6 +557,7 @@ fn resolve_inputs_sources(
             }
             InputBinding::PriorItemOutput {
                 intra_sequence_item_index,
+                ..
             } => {
                 if *intra_sequence_item_index >= item_coordinate as usize {
                     panic!(
@@ -673,6 +711,8 @@ pub struct TraceVerifier<'a> {
 
 #[derive(Debug, Clone)]
 pub struct FraudEvidence {
+    /// Seed of the disputed commitment's trace tree.
+    pub seed: Vec<u8>,
     pub window: TraceWindow,
     pub input_sources_witnesses: HashMap<StepRecord, Vec<u8>>,
 }
@@ -689,6 +729,7 @@ impl<'a> TraceVerifier<'a> {
         cfs: &'a ControlFlowSchema,
     ) -> Result<Self> {
         trace_commitment.validate()?;
+        trace_commitment.verify_cfs(cfs)?;
 
         let mut trace_tree = TraceTree::new(1);
         trace_tree.append(Bytes(seed.to_vec()));
@@ -787,6 +828,7 @@ impl<'a> TraceVerifier<'a> {
                 );
 
                 return VerificationResult::Fraud(FraudEvidence {
+                    seed: self.seed.clone(),
                     window: fraud_window,
                     input_sources_witnesses,
                 });
@@ -1015,14 +1057,17 @@ mod tests {
             make_tile_trace_item(4, 4),
         ]);
 
+        let cfs = make_test_cfs();
         let binded_trace = TraceCommitment::from(
             &items,
             &precomputed::EMPTY_TRIE_NODES[0],
+            &cfs,
             test_fraud_proof_config(),
         );
         let ref_binded_trace = TraceCommitment::from(
             &ref_items,
             &precomputed::EMPTY_TRIE_NODES[0],
+            &cfs,
             test_fraud_proof_config(),
         );
 
@@ -1040,9 +1085,11 @@ mod tests {
     #[test]
     fn test_try_from_empty_trace() {
         let items = Trace::new();
+        let cfs = make_test_cfs();
         let result = TraceCommitment::try_from(
             &items,
             &precomputed::EMPTY_TRIE_NODES[0],
+            &cfs,
             test_fraud_proof_config(),
         );
         assert!(matches!(result, Err(BitPackerError::EmptyTrace)));
@@ -1052,11 +1099,13 @@ mod tests {
     fn test_try_from_trace_shorter_than_window() {
         // The trace must be strictly longer than the window, so both a
         // shorter and an equal-length trace are rejected.
+        let cfs = make_test_cfs();
         for trace_len in [1, 2] {
             let items = Trace((0..trace_len).map(|i| make_tile_trace_item(i, i)).collect());
             let result = TraceCommitment::try_from(
                 &items,
                 &precomputed::EMPTY_TRIE_NODES[0],
+                &cfs,
                 test_fraud_proof_config(),
             );
             assert!(matches!(result, Err(BitPackerError::InvalidWindow(_))));
@@ -1161,12 +1210,13 @@ mod tests {
     #[test]
     fn test_verify_trace_returns_ok_for_matching_trace() {
         let trace = Trace((0..5).map(|i| make_tile_trace_item(i, i)).collect());
+        let cfs = make_test_cfs();
         let trace_commitment = TraceCommitment::from(
             &trace,
             &precomputed::EMPTY_TRIE_NODES[0],
+            &cfs,
             test_fraud_proof_config(),
         );
-        let cfs = make_test_cfs();
         let mut trace_verifier =
             TraceVerifier::new(trace_commitment, &precomputed::EMPTY_TRIE_NODES[0], &cfs)
                 .expect("valid commitment");
@@ -1175,18 +1225,54 @@ mod tests {
         assert!(matches!(verification_result, VerificationResult::Ok));
     }
 
+    #[test]
+    fn test_commitment_fingerprint_depends_on_seed() {
+        let trace = Trace((0..5).map(|i| make_tile_trace_item(i, i)).collect());
+        let cfs = make_test_cfs();
+        let run_seed = [7u8; 32];
+        let default_commitment = TraceCommitment::from(
+            &trace,
+            &precomputed::EMPTY_TRIE_NODES[0],
+            &cfs,
+            test_fraud_proof_config(),
+        );
+        let seeded_commitment =
+            TraceCommitment::from(&trace, &run_seed, &cfs, test_fraud_proof_config());
+
+        assert_eq!(seeded_commitment.seed, run_seed.to_vec());
+        assert_ne!(
+            seeded_commitment.fingerprint,
+            default_commitment.fingerprint
+        );
+
+        let mut trace_verifier =
+            TraceVerifier::new(seeded_commitment.clone(), &run_seed, &cfs).expect("valid seed");
+        assert!(matches!(
+            trace_verifier.verify(&trace),
+            VerificationResult::Ok
+        ));
+
+        let mut short_seed = seeded_commitment;
+        short_seed.seed.truncate(16);
+        assert!(matches!(
+            TraceVerifier::new(short_seed, &run_seed, &cfs),
+            Err(BitPackerError::InvalidCommitment(_))
+        ));
+    }
+
     #[test]
     fn test_verify_trace_returns_fraud_for_mismatched_trace() {
         let committed_trace = Trace((0..5).map(|i| make_tile_trace_item(i, i)).collect());
         let mut runtime_trace = committed_trace.clone();
         runtime_trace[2] = make_tile_trace_item(2, 999);
 
+        let cfs = make_test_cfs();
         let trace_commitment = TraceCommitment::from(
             &committed_trace,
             &precomputed::EMPTY_TRIE_NODES[0],
+            &cfs,
             test_fraud_proof_config(),
         );
-        let cfs = make_test_cfs();
         let mut trace_verifier =
             TraceVerifier::new(trace_commitment, &precomputed::EMPTY_TRIE_NODES[0], &cfs)
                 .expect("valid commitment");
@@ -1202,6 +1288,7 @@ mod tests {
         let valid = TraceCommitment::from(
             &trace,
             &precomputed::EMPTY_TRIE_NODES[0],
+            &cfs,
             test_fraud_proof_config(),
         );
 
@@ -1221,11 +1308,16 @@ mod tests {
         let mut truncated_bits = valid.clone();
         truncated_bits.fingerprint.bits.pop();
 
+        // CFS digest that is not a hash.
+        let mut truncated_cfs_digest = valid.clone();
+        truncated_cfs_digest.cfs_digest.pop();
+
         for malformed in [
             oversized_window,
             empty_window,
             bad_bit_packer,
             truncated_bits,
+            truncated_cfs_digest,
         ] {
             assert!(matches!(
                 TraceVerifier::new(malformed, &precomputed::EMPTY_TRIE_NODES[0], &cfs),
@@ -1234,6 +1326,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_verifier_rejects_commitment_for_another_cfs() {
+        let trace = Trace((0..5).map(|i| make_tile_trace_item(i, i)).collect());
+        let committed_cfs = make_test_cfs();
+        let trace_commitment = TraceCommitment::from(
+            &trace,
+            &precomputed::EMPTY_TRIE_NODES[0],
+            &committed_cfs,
+            test_fraud_proof_config(),
+        );
+
+        let mut audited_cfs = make_test_cfs();
+        audited_cfs.tiles.push(TileDef::iter("other_tile", 1, 1));
+
+        assert!(matches!(
+            TraceVerifier::new(
+                trace_commitment,
+                &precomputed::EMPTY_TRIE_NODES[0],
+                &audited_cfs
+            ),
+            Err(BitPackerError::CfsMismatch { .. })
+        ));
+    }
+
     #[test]
     fn test_verify_trace_returns_ok_for_producer_dependency() {
         let trace = Trace(vec![
@@ -1243,12 +1359,13 @@ mod tests {
             make_tile_trace_item_at(4, "main", 2, vec![2], "tail".to_string(), 1, 30),
             make_sequence_end_record(5, "main", vec![]),
         ]);
+        let cfs = make_producer_dependency_cfs();
         let trace_commitment = TraceCommitment::from(
             &trace,
             &precomputed::EMPTY_TRIE_NODES[0],
+            &cfs,
             test_fraud_proof_config(),
         );
-        let cfs = make_producer_dependency_cfs();
         let mut trace_verifier =
             TraceVerifier::new(trace_commitment, &precomputed::EMPTY_TRIE_NODES[0], &cfs)
                 .expect("valid commitment");
@@ -1267,12 +1384,13 @@ mod tests {
             make_tile_trace_item_at(5, "main", 1, vec![1], "tail".to_string(), 1, 20),
             make_sequence_end_record(6, "main", vec![]),
         ]);
+        let cfs = make_sequence_input_dependency_cfs();
         let trace_commitment = TraceCommitment::from(
             &trace,
             &precomputed::EMPTY_TRIE_NODES[0],
+            &cfs,
             test_fraud_proof_config(),
         );
-        let cfs = make_sequence_input_dependency_cfs();
         let mut trace_verifier =
             TraceVerifier::new(trace_commitment, &precomputed::EMPTY_TRIE_NODES[0], &cfs)
                 .expect("valid commitment");
@@ -1291,12 +1409,13 @@ mod tests {
             make_tile_trace_item_at(5, "main", 1, vec![1], "tail".to_string(), 1, 20),
             make_sequence_end_record(6, "main", vec![]),
         ]);
+        let cfs = make_nested_sequence_output_dependency_cfs();
         let trace_commitment = TraceCommitment::from(
             &trace,
             &precomputed::EMPTY_TRIE_NODES[0],
+            &cfs,
             test_fraud_proof_config(),
         );
-        let cfs = make_nested_sequence_output_dependency_cfs();
         let mut trace_verifier =
             TraceVerifier::new(trace_commitment, &precomputed::EMPTY_TRIE_NODES[0], &cfs)
                 .expect("valid commitment");
@@ -1320,12 +1439,13 @@ mod tests {
             make_tile_trace_item_at(3, "main", 2, vec![2], "tail".to_string(), 1, 30),
             make_sequence_end_record(4, "main", vec![]),
         ]);
+        let cfs = make_producer_dependency_cfs();
         let trace_commitment = TraceCommitment::from(
             &committed_trace,
             &precomputed::EMPTY_TRIE_NODES[0],
+            &cfs,
             test_fraud_proof_config(),
         );
-        let cfs = make_producer_dependency_cfs();
         let mut trace_verifier =
             TraceVerifier::new(trace_commitment, &precomputed::EMPTY_TRIE_NODES[0], &cfs)
                 .expect("valid commitment");
@@ -44,6 +44,7 @@ fn build_transition_input(
     step_record: &StepRecord,
     input_sources_witnesses: &HashMap<StepRecord, Vec<u8>>,
     recorded_step_io: &RecordedStepIo,
+    branch_condition_witnesses: &HashMap<StepRecord, Vec<u8>>,
     replayed_results: &HashMap<StepRecord, ReplayResult>,
     authorization_journal: &AuthorizationJournal,
 ) -> TransitionInput {
@@ -61,6 +62,7 @@ fn build_transition_input(
         .get(step_record)
         .cloned()
         .unwrap_or_else(|| panic!("Missing recorded I/O for transition step {:?}", step_record));
+    let branch_condition_witness = branch_condition_witnesses.get(step_record).cloned();
 
     if step_record.requires_replay_proof() {
         let Some(replay_result) = replayed_results.get(step_record) else {
@@ -84,6 +86,7 @@ fn build_transition_input(
             internal_selection_witnesses,
             internal_store_witness,
             draft_transition_witness,
+            branch_condition_witness,
             authorization_journal: authorization_journal.clone(),
             input_sources_witnesses: input_sources_witnesses.clone(),
         }
@@ -102,6 +105,7 @@ fn build_transition_input(
             internal_selection_witnesses,
             internal_store_witness,
             draft_transition_witness,
+            branch_condition_witness,
             authorization_journal: authorization_journal.clone(),
             input_sources_witnesses: input_sources_witnesses.clone(),
         }
@@ -115,6 +119,10 @@ fn image_id_bytes(image_id: [u32; 8]) -> Vec<u8> {
         .collect()
 }
 
+pub fn transition_guest_image_id() -> Vec<u8> {
+    image_id_bytes(TRANSITION_GUEST_ID)
+}
+
 fn empty_internal_store_frontier() -> SerializableFrontier {
     SerializableFrontier {
         position: 0,
@@ -140,6 +148,7 @@ fn internal_store_root(frontier: &SerializableFrontier) -> Vec<u8> {
 /// 3. Verify the output (including fingerprint) and update the frontier for the next iteration
 ///
 /// # Arguments
+/// * `seed` - The leaf the disputed commitment's trace tree was seeded with
 /// * `initial_frontier` - The frontier state before the first trace item
 /// * `trace_window` - The trace items to replay
 /// * `fingerprint` - The packed fingerprint u64s for verification
@@ -149,6 +158,7 @@ fn internal_store_root(frontier: &SerializableFrontier) -> Vec<u8> {
 /// # Returns
 /// A `TransitionReplayResult` with details about success or failure
 pub fn step_transitions(
+    seed: &[u8],
     initial_frontier: &SerializableFrontier,
     initial_internal_store_frontier: &SerializableFrontier,
     initial_internal_store_index_root: &[u8],
@@ -157,15 +167,17 @@ pub fn step_transitions(
     cfs: &ControlFlowSchema,
     input_sources_witnesses: &HashMap<StepRecord, Vec<u8>>,
     recorded_step_io: &RecordedStepIo,
+    branch_condition_witnesses: &HashMap<StepRecord, Vec<u8>>,
     replayed_results: &HashMap<StepRecord, ReplayResult>,
     authorization_journal: &AuthorizationJournal,
     authorization_receipt: &risc0_zkvm::Receipt,
 ) -> Option<risc0_zkvm::Receipt> {
     let prover = risc0_zkvm::default_prover();
 
-    let transition_image_id = image_id_bytes(TRANSITION_GUEST_ID);
+    let transition_image_id = transition_guest_image_id();
 
     let init_transition = InitTransition {
+        seed: seed.to_vec(),
         init_frontier: initial_frontier.clone(),
         init_internal_store_frontier: initial_internal_store_frontier.clone(),
         init_internal_store_root: internal_store_root(initial_internal_store_frontier),
@@ -184,6 +196,7 @@ pub fn step_transitions(
             step_record,
             input_sources_witnesses,
             recorded_step_io,
+            branch_condition_witnesses,
             replayed_results,
             authorization_journal,
         );
@@ -237,15 +250,22 @@ pub fn step_transitions(
 mod tests {
     use super::*;
     use crate::authorization::authorize_external_inputs;
+    use crate::dev_mode::{check_receipt_kind, is_fake_receipt, with_dev_prover};
+    use crate::fraud_proof::verify_fraud_proof;
     use crate::precomputed::EMPTY_TRIE_NODES;
+    use crate::trace::{FraudProofConfig, TraceCommitment, TraceVerifier, VerificationResult};
     use raster_core::authorization::{AuthorizationJournal, ManifestedInputs};
-    use raster_core::cfs::{CfsCoordinates, ControlFlowSchema, SequenceDef};
+    use raster_core::cfs::{
+        CfsCoordinates, ControlFlowSchema, SequenceChildItem, SequenceDef, TileDef, TileItem,
+    };
     use raster_core::coordinate_index::coordinate_index_root;
     use raster_core::draft::TileReplayJournal;
     use raster_core::fingerprint::{BitPacker, Fingerprint};
     use raster_core::trace::{
-        ExternalData, FnInput, SequenceEndRecord, SequenceStartRecord, TileExecRecord,
+        failure_message_hash, ExternalData, FnInput, SequenceEndRecord, SequenceStartRecord,
+        TileExecRecord, TileFailedRecord, TileFailureKind, Trace,
     };
+    use risc0_zkvm::{FakeReceipt, InnerReceipt, Receipt, ReceiptClaim};
     use sha2::{Digest, Sha256};
 
     fn external_input_commitment(external_input: &ExternalInput) -> Vec<u8> {
@@ -371,6 +391,7 @@ mod tests {
                     replay_journal: TileReplayJournal {
                         output_bytes: vec![11],
                         draft_transition: None,
+                        failure: None,
                     },
                 },
             ),
@@ -385,6 +406,7 @@ mod tests {
                     replay_journal: TileReplayJournal {
                         output_bytes: vec![22],
                         draft_transition: None,
+                        failure: None,
                     },
                 },
             ),
@@ -394,6 +416,7 @@ mod tests {
             &second_step,
             &HashMap::new(),
             &recorded_step_io,
+            &HashMap::new(),
             &replayed_results,
             &make_authorization_journal(),
         );
@@ -441,6 +464,7 @@ mod tests {
                 replay_journal: TileReplayJournal {
                     output_bytes: error_output.clone(),
                     draft_transition: None,
+                    failure: None,
                 },
             },
         )]);
@@ -449,6 +473,7 @@ mod tests {
             &tile_step,
             &HashMap::new(),
             &recorded_step_io,
+            &HashMap::new(),
             &replayed_results,
             &make_authorization_journal(),
         );
@@ -510,6 +535,7 @@ mod tests {
             &HashMap::new(),
             &recorded_step_io,
             &HashMap::new(),
+            &HashMap::new(),
             &make_authorization_journal(),
         );
         let end_input = build_transition_input(
@@ -517,6 +543,7 @@ mod tests {
             &HashMap::new(),
             &recorded_step_io,
             &HashMap::new(),
+            &HashMap::new(),
             &make_authorization_journal(),
         );
 
@@ -595,10 +622,12 @@ mod tests {
             internal_selection_witnesses: BTreeMap::new(),
             internal_store_witness: None,
             draft_transition_witness: None,
+            branch_condition_witness: None,
             authorization_journal: authorization,
             input_sources_witnesses: HashMap::new(),
         };
         let state = TransitionState::Init(InitTransition {
+            seed: crate::precomputed::EMPTY_TRIE_NODES[0].to_vec(),
             init_frontier: make_init_frontier(),
             init_internal_store_frontier: make_init_frontier(),
             init_internal_store_root: internal_store_root(&make_init_frontier()),
@@ -662,4 +691,201 @@ mod tests {
         )
         .is_err());
     }
+
+    #[test]
+    fn dev_prover_chains_guests_in_the_executor_and_emits_fake_receipts() {
+        let receipt = with_dev_prover(true, || {
+            let (authorization_receipt, authorization) =
+                authorize_external_inputs(&ManifestedInputs {
+                    manifest_bytes: Vec::new(),
+                    external_inputs_commitments: std::collections::BTreeMap::new(),
+                });
+            assert!(is_fake_receipt(&authorization_receipt));
+            // The executor still resolves every assumption the guest verifies.
+            assert!(
+                prove_single_transition_with_authorization(authorization.clone(), None).is_err()
+            );
+
+            let receipt = prove_single_transition_with_authorization(
+                authorization,
+                Some(authorization_receipt),
+            )
+            .expect("transition guest runs in the executor")
+            .receipt;
+            assert!(is_fake_receipt(&receipt));
+            receipt
+                .verify(TRANSITION_GUEST_ID)
+                .expect("fake receipt verifies in dev mode");
+            check_receipt_kind(&receipt, "Transition receipt").expect("dev prover accepts fakes");
+            receipt
+        });
+
+        with_dev_prover(false, || {
+            assert!(receipt.verify(TRANSITION_GUEST_ID).is_err());
+            assert!(matches!(
+                check_receipt_kind(&receipt, "Transition receipt"),
+                Err(crate::error::BitPackerError::InvalidFraudProof(_))
+            ));
+        });
+    }
+
+    /// A CFS whose `main` sequence runs the input-less tile `check`.
+    fn make_check_cfs() -> ControlFlowSchema {
+        let mut cfs = make_minimal_cfs();
+        cfs.tiles.push(TileDef::iter("check", 0, 1));
+        cfs.sequences[0]
+            .items
+            .push(SequenceChildItem::Tile(TileItem {
+                id: "check".to_string(),
+                sources: Vec::new(),
+            }));
+        cfs
+    }
+
+    /// `check` failing with `message` on the empty store of [`make_init_frontier`].
+    fn make_failed_check_step(message: &str) -> StepRecord {
+        StepRecord::TileFailed(TileFailedRecord {
+            exec_index: 2,
+            tile_id: "check".to_string(),
+            sequence_id: "main".to_string(),
+            intra_sequence_index: 0,
+            coordinates: CfsCoordinates(vec![0]),
+            input_commitment: Sha256::digest([]).to_vec(),
+            input_source_commitment: Sha256::digest(
+                empty_input_source_witness().source_witness_bytes(),
+            )
+            .to_vec(),
+            external_input_commitment: external_input_commitment(&ExternalInput::new()),
+            internal_store_root: internal_store_root(&make_init_frontier()),
+            internal_store_index_root: coordinate_index_root(&BTreeMap::new()),
+            failure_kind: TileFailureKind::Panic,
+            message_hash: failure_message_hash(message),
+        })
+    }
+
+    /// A dev-mode replay receipt attesting that `check` panicked with `message`.
+    fn make_failed_check_replay(message: &str) -> ReplayResult {
+        let image_id = [5u32; 8];
+        let replay_journal = TileReplayJournal::failed(TileFailureKind::Panic, message);
+        let journal_bytes = postcard::to_allocvec(&replay_journal).unwrap();
+        let claim = ReceiptClaim::ok(image_id, journal_bytes.clone());
+        let receipt = Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), journal_bytes);
+
+        ReplayResult {
+            fn_name: "check".to_string(),
+            receipt: postcard::to_allocvec(&receipt).unwrap(),
+            image_id: image_id_bytes(image_id),
+            input: Vec::new(),
+            output: Vec::new(),
+            replay_journal,
+        }
+    }
+
+    #[test]
+    fn fraud_proof_window_can_end_on_a_replayed_tile_failure() {
+        let cfs = make_check_cfs();
+        let seed = vec![3; 32];
+        let sequence_start = make_sequence_start_step();
+        let failed_check = make_failed_check_step("boom");
+        let StepRecord::TileFailed(failed) = &failed_check else {
+            unreachable!();
+        };
+        // The commitment claims `check` returned where it actually panicked,
+        // and the run went on to end the sequence.
+        let claimed_check = StepRecord::TileExec(TileExecRecord {
+            exec_index: failed.exec_index,
+            tile_id: failed.tile_id.clone(),
+            sequence_id: failed.sequence_id.clone(),
+            intra_sequence_index: failed.intra_sequence_index,
+            coordinates: failed.coordinates.clone(),
+            input_commitment: failed.input_commitment.clone(),
+            input_source_commitment: failed.input_source_commitment.clone(),
+            external_input_commitment: failed.external_input_commitment.clone(),
+            output_commitment: Sha256::digest(b"ok").to_vec(),
+            internal_store_root_before: failed.internal_store_root.clone(),
+            internal_store_root_after: failed.internal_store_root.clone(),
+            internal_store_index_root_before: failed.internal_store_index_root.clone(),
+            internal_store_index_root_after: failed.internal_store_index_root.clone(),
+        });
+        let config = FraudProofConfig {
+            window_size: 2,
+            bits_per_item: 16,
+        };
+        let commitment = TraceCommitment::from(
+            &Trace(vec![
+                sequence_start.clone(),
+                claimed_check,
+                StepRecord::SequenceEnd(SequenceEndRecord {
+                    exec_index: 3,
+                    sequence_id: "main".to_string(),
+                    coordinates: CfsCoordinates(vec![]),
+                    output_commitment: Sha256::digest(b"ok").to_vec(),
+                }),
+            ]),
+            &seed,
+            &cfs,
+            config,
+        );
+        let mut verifier =
+            TraceVerifier::new(commitment.clone(), &seed, &cfs).expect("valid commitment");
+        let VerificationResult::Fraud(evidence) =
+            verifier.verify(&Trace(vec![sequence_start.clone(), failed_check.clone()]))
+        else {
+            panic!("the failed tile should diverge from the commitment");
+        };
+        assert_eq!(evidence.window.items.last(), Some(&failed_check));
+
+        let recorded_io = |input: Vec<u8>| {
+            (
+                Some(input),
+                None,
+                Some(empty_input_source_witness()),
+                None,
+                ExternalInput::new(),
+                BTreeMap::new(),
+                BTreeMap::new(),
+                None,
+                None,
+            )
+        };
+        let recorded_step_io = HashMap::from([
+            (sequence_start, recorded_io(b"sequence-in".to_vec())),
+            (failed_check.clone(), recorded_io(Vec::new())),
+        ]);
+        let replayed_results = HashMap::from([(failed_check, make_failed_check_replay("boom"))]);
+
+        with_dev_prover(true, || {
+            let (authorization_receipt, authorization) =
+                authorize_external_inputs(&ManifestedInputs {
+                    manifest_bytes: Vec::new(),
+                    external_inputs_commitments: BTreeMap::new(),
+                });
+            let frontier = SerializableFrontier::from_bytes(&evidence.window.frontier)
+                .expect("window frontier");
+            let fraud_proof = step_transitions(
+                &seed,
+                &frontier,
+                &make_init_frontier(),
+                &coordinate_index_root(&BTreeMap::new()),
+                &evidence.window.items,
+                evidence.window.fingerprint.clone(),
+                &cfs,
+                &evidence.input_sources_witnesses,
+                &recorded_step_io,
+                &HashMap::new(),
+                &replayed_results,
+                &authorization,
+                &authorization_receipt,
+            )
+            .expect("the window has steps");
+
+            let verified = verify_fraud_proof(
+                &fraud_proof,
+                &commitment,
+                &authorization.manifest_commitment,
+            )
+            .expect("valid fraud proof");
+            assert_eq!(verified.divergence_position, 1);
+        });
+    }
 }
@@ -17,10 +17,12 @@ use raster_prover::trace::{
     serializable_frontier_from_trace_frontier, Bytes, TraceTree, TraceTreeFrontier,
 };
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::BTreeMap;
+use std::path::Path;
 use std::vec::Vec;
 
 use crate::input::{
@@ -29,8 +31,14 @@ use crate::input::{
     typed_value_from_tree, TreeValue,
 };
 use crate::raster_index::RasterIndex;
+use crate::tracing::checkpoint::ExecutionStateCheckpoint;
 use crate::Sha256Commitment;
 
+mod spill;
+
+pub use spill::INTERNAL_STORE_DIR_ENV;
+use spill::{SpillIndexStore, SpillStore};
+
 type Anchor = [u8; 32];
 
 #[derive(Debug, Clone)]
@@ -50,7 +58,7 @@ pub struct DraftCaptureSnapshot {
     op_count_before: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredInternalObject {
     pub reference: InternalRef,
     pub log_position: u64,
@@ -79,8 +87,64 @@ pub struct InternalWriteRecord {
 #[derive(Debug, Clone)]
 pub struct InternalStorageManager {
     frontier: TraceTreeFrontier,
-    objects: BTreeMap<CfsCoordinates, StoredInternalObject>,
-    coordinate_index: IncrementalCoordinateIndex,
+    objects: StoredObjects,
+    coordinate_index: CoordinateIndex,
+}
+
+/// Where an internal store keeps the objects written to it.
+#[derive(Debug, Clone)]
+enum StoredObjects {
+    Memory(BTreeMap<CfsCoordinates, StoredInternalObject>),
+    Spilled(SpillStore),
+}
+
+/// Where an internal store keeps its coordinate index.
+#[derive(Debug, Clone)]
+enum CoordinateIndex {
+    Memory(IncrementalCoordinateIndex),
+    Spilled(IncrementalCoordinateIndex<SpillIndexStore>),
+}
+
+impl CoordinateIndex {
+    fn root(&self) -> Vec<u8> {
+        match self {
+            Self::Memory(index) => index.root(),
+            Self::Spilled(index) => index.root(),
+        }
+    }
+
+    fn get(&self, coordinates: &CfsCoordinates) -> Result<Option<InternalStoreIndexValue>> {
+        match self {
+            Self::Memory(index) => Ok(index.get(coordinates).cloned()),
+            Self::Spilled(index) => index.try_get(coordinates).map_err(|error| {
+                Error::Other(format!(
+                    "Failed to read spilled internal store index at coordinates {:?}: {}",
+                    coordinates, error
+                ))
+            }),
+        }
+    }
+
+    fn insert(
+        &mut self,
+        coordinates: CfsCoordinates,
+        value: InternalStoreIndexValue,
+    ) -> Result<()> {
+        match self {
+            Self::Memory(index) => {
+                index.insert(coordinates, value);
+                Ok(())
+            }
+            Self::Spilled(index) => index
+                .try_insert(coordinates.clone(), value)
+                .map_err(|error| {
+                    Error::Other(format!(
+                        "Failed to spill internal store index entry at coordinates {:?}: {}",
+                        coordinates, error
+                    ))
+                }),
+        }
+    }
 }
 
 fn frontier_root(frontier: &TraceTreeFrontier) -> Vec<u8> {
@@ -264,7 +328,43 @@ fn draft_state_witness(state: &DraftRuntimeState) -> DraftStateWitness {
 }
 
 impl InternalStorageManager {
+    /// Create an empty store that keeps its objects in memory.
     pub fn new() -> Self {
+        Self::with_objects(
+            StoredObjects::Memory(BTreeMap::new()),
+            CoordinateIndex::Memory(IncrementalCoordinateIndex::new()),
+        )
+    }
+
+    /// Create an empty store, spilling it to the directory named by
+    /// [`INTERNAL_STORE_DIR_ENV`] when that is set.
+    pub fn from_env() -> Result<Self> {
+        match std::env::var_os(INTERNAL_STORE_DIR_ENV) {
+            Some(dir) => Self::with_spill_dir(&dir),
+            None => Ok(Self::new()),
+        }
+    }
+
+    /// Create an empty store that keeps its objects and coordinate index in
+    /// files in `dir`.
+    pub fn with_spill_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let create =
+            || Ok::<_, std::io::Error>((SpillStore::create(dir)?, SpillIndexStore::create(dir)?));
+        let (spill, index) = create().map_err(|error| {
+            Error::Other(format!(
+                "Failed to create internal store files in '{}': {}",
+                dir.display(),
+                error
+            ))
+        })?;
+        Ok(Self::with_objects(
+            StoredObjects::Spilled(spill),
+            CoordinateIndex::Spilled(IncrementalCoordinateIndex::with_store(index)),
+        ))
+    }
+
+    fn with_objects(objects: StoredObjects, coordinate_index: CoordinateIndex) -> Self {
         let mut tree = TraceTree::new(1);
         tree.append(Bytes(EMPTY_TRIE_NODES[0].to_vec()));
         let frontier = tree
@@ -273,8 +373,37 @@ impl InternalStorageManager {
             .expect("internal store frontier should exist after seed append");
         Self {
             frontier,
-            objects: BTreeMap::new(),
-            coordinate_index: IncrementalCoordinateIndex::new(),
+            objects,
+            coordinate_index,
+        }
+    }
+
+    /// Number of objects written to the store.
+    pub fn object_count(&self) -> u64 {
+        match &self.objects {
+            StoredObjects::Memory(objects) => objects.len() as u64,
+            StoredObjects::Spilled(spill) => spill.len(),
+        }
+    }
+
+    fn stored_object(
+        &self,
+        coordinates: &CfsCoordinates,
+    ) -> Result<Option<Cow<'_, StoredInternalObject>>> {
+        match &self.objects {
+            StoredObjects::Memory(objects) => Ok(objects.get(coordinates).map(Cow::Borrowed)),
+            StoredObjects::Spilled(spill) => {
+                let Some(index_value) = self.coordinate_index.get(coordinates)? else {
+                    return Ok(None);
+                };
+                let stored = spill.get(index_value.log_position).map_err(|error| {
+                    Error::Other(format!(
+                        "Failed to read spilled internal store object at coordinates {:?}: {}",
+                        coordinates, error
+                    ))
+                })?;
+                Ok(Some(Cow::Owned(stored)))
+            }
         }
     }
 
@@ -299,9 +428,9 @@ impl InternalStorageManager {
         bytes: &[u8],
         coordinates: CfsCoordinates,
         raster: Option<RasterPayload>,
-    ) -> InternalWriteRecord {
+    ) -> Result<InternalWriteRecord> {
         assert!(
-            !self.coordinate_index.contains_key(&coordinates),
+            self.coordinate_index.get(&coordinates)?.is_none(),
             "Duplicate internal store write at coordinates {:?}",
             coordinates
         );
@@ -315,28 +444,35 @@ impl InternalStorageManager {
         };
         let leaf_hash: Vec<u8> = Sha256Commitment::from(entry.to_bytes().as_slice()).into();
 
-        self.frontier.append(Bytes(leaf_hash));
-        let log_position: u64 = self.frontier.position().into();
+        // Write the object first, so a failed spill leaves the store's roots
+        // untouched.
+        let log_position = u64::from(self.frontier.position()) + 1;
+        let reference = InternalRef::new(coordinates.clone(), entry.object_commitment.clone());
+        let stored = StoredInternalObject {
+            reference,
+            log_position,
+            bytes: bytes.to_vec(),
+            raster,
+        };
+        match &mut self.objects {
+            StoredObjects::Memory(objects) => {
+                objects.insert(coordinates.clone(), stored);
+            }
+            StoredObjects::Spilled(spill) => spill.append(&stored).map_err(|error| {
+                Error::Other(format!(
+                    "Failed to spill internal store object at coordinates {:?}: {}",
+                    coordinates, error
+                ))
+            })?,
+        }
         let index_value = InternalStoreIndexValue {
             log_position,
             object_commitment: entry.object_commitment.clone(),
         };
-        self.coordinate_index
-            .insert(coordinates.clone(), index_value);
-
-        let reference = InternalRef::new(coordinates.clone(), entry.object_commitment.clone());
-
-        self.objects.insert(
-            coordinates,
-            StoredInternalObject {
-                reference,
-                log_position,
-                bytes: bytes.to_vec(),
-                raster,
-            },
-        );
+        self.coordinate_index.insert(coordinates, index_value)?;
+        self.frontier.append(Bytes(leaf_hash));
 
-        InternalWriteRecord {
+        Ok(InternalWriteRecord {
             entry,
             log_position,
             store_root_before,
@@ -344,14 +480,14 @@ impl InternalStorageManager {
             index_root_before,
             index_root_after: self.current_index_root(),
             frontier_after: serializable_frontier_from_trace_frontier(self.frontier.clone()),
-        }
+        })
     }
 
     pub fn resolve<T: DeserializeOwned>(
         &self,
         reference: &InternalRef,
     ) -> Result<InternalValue<T>> {
-        let stored = self.objects.get(&reference.coordinates).ok_or_else(|| {
+        let stored = self.stored_object(&reference.coordinates)?.ok_or_else(|| {
             Error::Other(format!(
                 "Missing internal store object at coordinates {:?}",
                 reference.coordinates
@@ -425,8 +561,8 @@ impl InternalStorageManager {
         })
     }
 
-    fn verify_reference(&self, reference: &InternalRef) -> Result<&StoredInternalObject> {
-        let stored = self.objects.get(&reference.coordinates).ok_or_else(|| {
+    fn verify_reference(&self, reference: &InternalRef) -> Result<Cow<'_, StoredInternalObject>> {
+        let stored = self.stored_object(&reference.coordinates)?.ok_or_else(|| {
             Error::Other(format!(
                 "Missing internal store object at coordinates {:?}",
                 reference.coordinates
@@ -449,13 +585,20 @@ impl InternalStorageManager {
         Ok(stored)
     }
 
+    /// Raster payload bytes of the object written at `coordinates`, if any.
+    pub fn raster_bytes_at(&self, coordinates: &CfsCoordinates) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .stored_object(coordinates)?
+            .and_then(|stored| stored.raster.as_ref().map(|raster| raster.bytes.clone())))
+    }
+
     pub fn selection_witness(
         &self,
         reference: &InternalRef,
         selector: &SelectorPath,
     ) -> Result<SelectionWitness> {
         let stored = self.verify_reference(reference)?;
-        let raster = Self::require_raster(stored, &reference.coordinates)?;
+        let raster = Self::require_raster(&stored, &reference.coordinates)?;
         let index = RasterIndex::from_bytes(&raster.index_bytes)?;
         let selection = index.select(selector)?;
         selection_witness_from_raster_selection(&raster.bytes, selector, selection)
@@ -467,7 +610,7 @@ impl InternalStorageManager {
         selector: &SelectorPath,
     ) -> Result<InternalValue<T>> {
         let stored = self.verify_reference(reference)?;
-        let raster = Self::require_raster(stored, &reference.coordinates)?;
+        let raster = Self::require_raster(&stored, &reference.coordinates)?;
         let index = RasterIndex::from_bytes(&raster.index_bytes)?;
         let selection = index.locate(selector)?;
         let tree = tree_value_from_raster_location(&index, &raster.bytes, &selection)?;
@@ -583,6 +726,15 @@ impl SequenceExecutionContext {
             .expect("Corrupted recur execution context");
     }
 
+    fn skip_child_items(&mut self, count: u32) -> Result<()> {
+        let frame = self
+            .stack
+            .last_mut()
+            .ok_or_else(|| Error::Other("Branch arms require active sequence context".into()))?;
+        frame.next_child_index += count;
+        Ok(())
+    }
+
     fn reserve_execution_coordinates(&mut self) -> Result<CfsCoordinates> {
         if let Some(recur_frame) = self.recur_stack.last_mut() {
             let mut coordinates = recur_frame.site_coordinates.clone();
@@ -642,8 +794,10 @@ impl SequenceExecutionContext {
 }
 
 std::thread_local! {
-    static THREAD_INTERNAL_STORAGE: RefCell<InternalStorageManager> =
-        RefCell::new(InternalStorageManager::new());
+    /// Created by the first write on the thread, as opening a spill
+    /// directory can fail.
+    static THREAD_INTERNAL_STORAGE: RefCell<Option<InternalStorageManager>> =
+        const { RefCell::new(None) };
     static THREAD_SEQUENCE_CONTEXT: RefCell<SequenceExecutionContext> =
         RefCell::new(SequenceExecutionContext::default());
     static THREAD_ACTIVE_EXECUTION_COORDINATES: RefCell<Vec<CfsCoordinates>> = RefCell::new(Vec::new());
@@ -654,7 +808,7 @@ std::thread_local! {
 
 fn reset_thread_storage() {
     THREAD_INTERNAL_STORAGE.with(|storage| {
-        *storage.borrow_mut() = InternalStorageManager::new();
+        storage.borrow_mut().take();
     });
     THREAD_ACTIVE_EXECUTION_COORDINATES.with(|coordinates| {
         coordinates.borrow_mut().clear();
@@ -667,6 +821,29 @@ fn reset_thread_storage() {
     });
 }
 
+/// Run `f` on the current thread's store, creating it if nothing has been
+/// written on the thread yet.
+fn with_thread_storage_mut<R>(
+    f: impl FnOnce(&mut InternalStorageManager) -> Result<R>,
+) -> Result<R> {
+    THREAD_INTERNAL_STORAGE.with(|storage| {
+        let mut storage = storage.borrow_mut();
+        if storage.is_none() {
+            *storage = Some(InternalStorageManager::from_env()?);
+        }
+        f(storage.as_mut().expect("thread internal store exists"))
+    })
+}
+
+/// Run `f` on the current thread's store, or on an empty one if nothing has
+/// been written on the thread yet.
+fn with_thread_storage<R>(f: impl FnOnce(&InternalStorageManager) -> R) -> R {
+    THREAD_INTERNAL_STORAGE.with(|storage| match storage.borrow().as_ref() {
+        Some(storage) => f(storage),
+        None => f(&InternalStorageManager::new()),
+    })
+}
+
 pub fn enter_sequence_scope(_sequence_id: &str) {
     THREAD_SEQUENCE_CONTEXT.with(|context| {
         let mut context = context.borrow_mut();
@@ -703,8 +880,62 @@ pub fn exit_recur_sequence_iteration_scope() {
     });
 }
 
+/// Advance the current sequence past `count` items of a branch arm that was
+/// not taken, keeping execution coordinates aligned with the CFS item indices.
+pub fn skip_sequence_items(count: u32) -> Result<()> {
+    THREAD_SEQUENCE_CONTEXT.with(|context| context.borrow_mut().skip_child_items(count))
+}
+
 pub fn global_internal_store_snapshot() -> InternalStoreSnapshot {
-    THREAD_INTERNAL_STORAGE.with(|storage| storage.borrow().snapshot())
+    with_thread_storage(InternalStorageManager::snapshot)
+}
+
+/// Capture the execution state of the current thread for a checkpoint.
+pub(crate) fn execution_state_checkpoint() -> ExecutionStateCheckpoint {
+    let (store_root, store_index_root, store_object_count) = with_thread_storage(|storage| {
+        (
+            storage.current_root(),
+            storage.current_index_root(),
+            storage.object_count(),
+        )
+    });
+    let (sequence_stack, recur_stack) = THREAD_SEQUENCE_CONTEXT.with(|context| {
+        let context = context.borrow();
+        (
+            context
+                .stack
+                .iter()
+                .map(|frame| {
+                    (
+                        frame.coordinates.clone(),
+                        frame.next_child_index,
+                        frame.next_synthetic_index,
+                    )
+                })
+                .collect(),
+            context
+                .recur_stack
+                .iter()
+                .map(|frame| (frame.site_coordinates.clone(), frame.next_iteration_index))
+                .collect(),
+        )
+    });
+    let draft_anchors = THREAD_DRAFT_STORAGE.with(|drafts| {
+        drafts
+            .borrow()
+            .iter()
+            .map(|(anchor, state)| (*anchor, state.current_root))
+            .collect()
+    });
+
+    ExecutionStateCheckpoint {
+        store_root,
+        store_index_root,
+        store_object_count,
+        sequence_stack,
+        recur_stack,
+        draft_anchors,
+    }
 }
 
 pub fn create_draft<S>() -> Result<(Anchor, [u8; 32])>
@@ -900,12 +1131,8 @@ fn store_internal_value_at_coordinates<T: Serialize>(
         ))
     })?;
     let raster_payload = Some(raster_payload_for_value(value)?);
-    THREAD_INTERNAL_STORAGE.with(|storage| {
-        let write = storage.borrow_mut().append_serialized_bytes(
-            &bytes,
-            coordinates.clone(),
-            raster_payload,
-        );
+    with_thread_storage_mut(|storage| {
+        let write = storage.append_serialized_bytes(&bytes, coordinates.clone(), raster_payload)?;
         Ok(InternalRef::new(coordinates, write.entry.object_commitment))
     })
 }
@@ -974,6 +1201,11 @@ impl Drop for TileExecutionScopeGuard {
     }
 }
 
+/// Coordinates of the innermost tile executing on this thread.
+pub(crate) fn active_execution_coordinates() -> Option<CfsCoordinates> {
+    THREAD_ACTIVE_EXECUTION_COORDINATES.with(|active| active.borrow().last().cloned())
+}
+
 pub fn publish_pending_output_coordinates(coordinates: CfsCoordinates) {
     THREAD_PENDING_OUTPUT_COORDINATES.with(|pending| {
         *pending.borrow_mut() = Some(coordinates);
@@ -1032,14 +1264,14 @@ where
 pub fn resolve_internal_value<T: DeserializeOwned>(
     reference: &InternalRef,
 ) -> Result<InternalValue<T>> {
-    THREAD_INTERNAL_STORAGE.with(|storage| storage.borrow().resolve(reference))
+    with_thread_storage(|storage| storage.resolve(reference))
 }
 
 pub fn select_stored_internal_value<T: DeserializeOwned>(
     reference: &InternalRef,
     selector: &SelectorPath,
 ) -> Result<InternalValue<T>> {
-    THREAD_INTERNAL_STORAGE.with(|storage| storage.borrow().select(reference, selector))
+    with_thread_storage(|storage| storage.select(reference, selector))
 }
 
 pub fn resolve_internal_ok_value<T: DeserializeOwned>(
@@ -1112,8 +1344,10 @@ mod tests {
         let mut manager = InternalStorageManager::new();
         let coordinates = CfsCoordinates(vec![1, 2, 3]);
 
-        manager.append_serialized_bytes(b"first", coordinates.clone(), None);
-        manager.append_serialized_bytes(b"second", coordinates, None);
+        manager
+            .append_serialized_bytes(b"first", coordinates.clone(), None)
+            .expect("first write should store");
+        let _ = manager.append_serialized_bytes(b"second", coordinates, None);
     }
 
     #[test]
@@ -1128,6 +1362,84 @@ mod tests {
         assert_eq!(reference.commitment, resolved.selection.source_root_hash);
     }
 
+    #[test]
+    fn spilled_store_matches_in_memory_store() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let mut memory = InternalStorageManager::new();
+        let mut spilled =
+            InternalStorageManager::with_spill_dir(dir.path()).expect("spilled store should open");
+
+        // Enough objects that the spilled index's hash tables grow.
+        let mut references = Vec::new();
+        for index in 0..24u32 {
+            let value = vec![format!("item {}", index); index as usize + 1];
+            let bytes = raster_core::postcard::to_allocvec(&value).expect("value should encode");
+            let raster = raster_payload_for_value(&value).expect("raster payload should encode");
+            let coordinates = CfsCoordinates(vec![index, 1]);
+
+            let in_memory = memory
+                .append_serialized_bytes(&bytes, coordinates.clone(), Some(raster.clone()))
+                .expect("in-memory object should store");
+            let on_disk = spilled
+                .append_serialized_bytes(&bytes, coordinates.clone(), Some(raster))
+                .expect("spilled object should store");
+            assert_eq!(in_memory.store_root_after, on_disk.store_root_after);
+            assert_eq!(in_memory.index_root_after, on_disk.index_root_after);
+            references.push(InternalRef::new(
+                coordinates,
+                on_disk.entry.object_commitment,
+            ));
+        }
+
+        assert_eq!(memory.current_root(), spilled.current_root());
+        assert_eq!(memory.current_index_root(), spilled.current_index_root());
+        assert_eq!(spilled.object_count(), 24);
+
+        let copy = spilled.clone();
+        let selector = SelectorPath::new(vec![raster_core::input::SelectorSegment::Index(1)]);
+        for reference in &references {
+            let expected: InternalValue<Vec<String>> = memory
+                .resolve(reference)
+                .expect("in-memory object should resolve");
+            for store in [&spilled, &copy] {
+                let resolved: InternalValue<Vec<String>> = store
+                    .resolve(reference)
+                    .expect("spilled object should resolve");
+                assert_eq!(resolved.value, expected.value);
+                assert_eq!(resolved.bytes, expected.bytes);
+                assert_eq!(
+                    store
+                        .raster_bytes_at(&reference.coordinates)
+                        .expect("spilled raster bytes should read"),
+                    memory
+                        .raster_bytes_at(&reference.coordinates)
+                        .expect("in-memory raster bytes should read")
+                );
+            }
+        }
+        let reference = references.last().expect("objects were stored");
+        assert_eq!(
+            spilled
+                .selection_witness(reference, &selector)
+                .expect("spilled selection witness"),
+            memory
+                .selection_witness(reference, &selector)
+                .expect("in-memory selection witness"),
+        );
+    }
+
+    #[test]
+    fn missing_spill_dir_is_an_error() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let missing = dir.path().join("missing");
+
+        let error = InternalStorageManager::with_spill_dir(&missing)
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("Failed to create internal store files"));
+    }
+
     #[test]
     fn failed_finalize_removes_draft_anchor() {
         let _guard = SequenceScopeGuard::enter("failed_finalize_removes_draft_anchor");
@@ -0,0 +1,361 @@
+//! Disk-backed object storage for the internal store.
+//!
+//! A spilled store appends every object to a log file and records where each
+//! one starts in a fixed-width index file, one record per store log position.
+//! Its coordinate index keeps its node hashes, and where each entry starts in
+//! an entry log, in hash tables on disk. All files are unnamed temporaries in
+//! the spill directory, so they go away with the store, and all are read
+//! through memory maps. The store's roots are still computed by
+//! [`InternalStorageManager`](super::InternalStorageManager) from the entries
+//! it appends, so they do not depend on where objects live.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use memmap2::{Mmap, MmapMut};
+use raster_core::cfs::CfsCoordinates;
+use raster_core::coordinate_index::CoordinateIndexStore;
+use raster_core::transition::InternalStoreIndexValue;
+
+use super::StoredInternalObject;
+
+/// Directory in which the internal store spills its objects. When unset,
+/// objects are kept in memory.
+pub const INTERNAL_STORE_DIR_ENV: &str = "RASTER_INTERNAL_STORE_DIR";
+
+/// Bytes per index record: the object's log offset and length, little endian.
+const INDEX_RECORD_LEN: u64 = 16;
+
+/// Chunk size used when copying a store's files.
+const COPY_CHUNK_LEN: u64 = 1 << 20;
+
+/// Slots in a new hash table file.
+const INITIAL_TABLE_SLOTS: u64 = 1 << 12;
+
+/// An append-only file read through a memory map, remapped when a read
+/// reaches past the end of the current mapping.
+#[derive(Debug)]
+struct AppendFile {
+    file: File,
+    len: u64,
+    map: RefCell<Option<Mmap>>,
+}
+
+impl AppendFile {
+    fn create(dir: &Path) -> io::Result<Self> {
+        Ok(Self {
+            file: tempfile::tempfile_in(dir)?,
+            len: 0,
+            map: RefCell::new(None),
+        })
+    }
+
+    fn append(&mut self, bytes: &[u8]) -> io::Result<u64> {
+        let offset = self.len;
+        self.file.write_all(bytes)?;
+        self.len += bytes.len() as u64;
+        Ok(offset)
+    }
+
+    fn read(&self, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+        let end = offset
+            .checked_add(len)
+            .filter(|end| *end <= self.len)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        let mut map = self.map.borrow_mut();
+        if map.as_ref().is_none_or(|map| (map.len() as u64) < end) {
+            // The file is private to this store and only ever appended to, so
+            // mapped bytes never change underneath the mapping.
+            *map = Some(unsafe { Mmap::map(&self.file)? });
+        }
+        let map = map.as_ref().expect("spill file mapping exists");
+        Ok(map[offset as usize..end as usize].to_vec())
+    }
+
+    fn copy_into(&self, dir: &Path) -> io::Result<Self> {
+        let mut copy = Self::create(dir)?;
+        let mut offset = 0;
+        while offset < self.len {
+            let len = COPY_CHUNK_LEN.min(self.len - offset);
+            copy.append(&self.read(offset, len)?)?;
+            offset += len;
+        }
+        Ok(copy)
+    }
+}
+
+/// Objects of one internal store, kept on disk.
+#[derive(Debug)]
+pub(super) struct SpillStore {
+    dir: PathBuf,
+    log: AppendFile,
+    index: AppendFile,
+}
+
+impl SpillStore {
+    pub(super) fn create(dir: &Path) -> io::Result<Self> {
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            log: AppendFile::create(dir)?,
+            index: AppendFile::create(dir)?,
+        })
+    }
+
+    pub(super) fn len(&self) -> u64 {
+        self.index.len / INDEX_RECORD_LEN
+    }
+
+    /// Append `object`, which must be the next object in store log order.
+    pub(super) fn append(&mut self, object: &StoredInternalObject) -> io::Result<()> {
+        assert_eq!(
+            object.log_position,
+            self.len() + 1,
+            "Spilled internal store objects must be appended in log order"
+        );
+        let bytes = raster_core::postcard::to_allocvec(object).map_err(invalid_data)?;
+        let offset = self.log.append(&bytes)?;
+        let mut record = [0u8; INDEX_RECORD_LEN as usize];
+        record[..8].copy_from_slice(&offset.to_le_bytes());
+        record[8..].copy_from_slice(&(bytes.len() as u64).to_le_bytes());
+        self.index.append(&record)?;
+        Ok(())
+    }
+
+    /// The object stored at store log position `log_position`.
+    pub(super) fn get(&self, log_position: u64) -> io::Result<StoredInternalObject> {
+        let record_index = log_position
+            .checked_sub(1)
+            .filter(|index| *index < self.len())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        let record = self
+            .index
+            .read(record_index * INDEX_RECORD_LEN, INDEX_RECORD_LEN)?;
+        let offset = u64::from_le_bytes(record[..8].try_into().expect("8-byte offset"));
+        let len = u64::from_le_bytes(record[8..].try_into().expect("8-byte length"));
+        let bytes = self.log.read(offset, len)?;
+        raster_core::postcard::from_bytes(&bytes).map_err(invalid_data)
+    }
+}
+
+impl Clone for SpillStore {
+    fn clone(&self) -> Self {
+        let copy = self
+            .log
+            .copy_into(&self.dir)
+            .and_then(|log| Ok((log, self.index.copy_into(&self.dir)?)));
+        let (log, index) = copy.unwrap_or_else(|error| {
+            panic!(
+                "Failed to copy spilled internal store in '{}': {}",
+                self.dir.display(),
+                error
+            )
+        });
+        Self {
+            dir: self.dir.clone(),
+            log,
+            index,
+        }
+    }
+}
+
+fn invalid_data(error: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+/// A hash table of fixed-width keys and values in a memory-mapped file, with
+/// linear probing. A slot is an occupied flag followed by the key and the
+/// value; the table doubles once it is half full.
+#[derive(Debug)]
+struct SlotTable<const K: usize, const V: usize> {
+    dir: PathBuf,
+    map: MmapMut,
+    slots: u64,
+    len: u64,
+}
+
+impl<const K: usize, const V: usize> SlotTable<K, V> {
+    const SLOT_LEN: usize = 1 + K + V;
+
+    fn create(dir: &Path) -> io::Result<Self> {
+        Self::with_slots(dir, INITIAL_TABLE_SLOTS)
+    }
+
+    fn with_slots(dir: &Path, slots: u64) -> io::Result<Self> {
+        let file = tempfile::tempfile_in(dir)?;
+        file.set_len(slots * Self::SLOT_LEN as u64)?;
+        // The file is private to this table and only written through this
+        // mapping.
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            map,
+            slots,
+            len: 0,
+        })
+    }
+
+    fn slot(&self, index: u64) -> &[u8] {
+        let start = index as usize * Self::SLOT_LEN;
+        &self.map[start..start + Self::SLOT_LEN]
+    }
+
+    /// Index of the slot holding `key`, or of the free slot it would go in.
+    fn find(&self, key: &[u8; K]) -> u64 {
+        // FNV-1a; the keys are hashes or prefixes of hashes already.
+        let hash = key.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+        let mut index = hash & (self.slots - 1);
+        loop {
+            let slot = self.slot(index);
+            if slot[0] == 0 || slot[1..=K] == key[..] {
+                return index;
+            }
+            index = (index + 1) & (self.slots - 1);
+        }
+    }
+
+    fn get(&self, key: &[u8; K]) -> Option<[u8; V]> {
+        let slot = self.slot(self.find(key));
+        (slot[0] != 0).then(|| slot[1 + K..].try_into().expect("slot value width"))
+    }
+
+    fn insert(&mut self, key: &[u8; K], value: &[u8; V]) -> io::Result<()> {
+        if (self.len + 1) * 2 > self.slots {
+            self.grow()?;
+        }
+        let start = self.find(key) as usize * Self::SLOT_LEN;
+        let slot = &mut self.map[start..start + Self::SLOT_LEN];
+        if slot[0] == 0 {
+            slot[0] = 1;
+            slot[1..=K].copy_from_slice(key);
+            self.len += 1;
+        }
+        slot[1 + K..].copy_from_slice(value);
+        Ok(())
+    }
+
+    fn grow(&mut self) -> io::Result<()> {
+        let mut grown = Self::with_slots(&self.dir, self.slots * 2)?;
+        for index in 0..self.slots {
+            let slot = self.slot(index);
+            if slot[0] != 0 {
+                let key = slot[1..=K].try_into().expect("slot key width");
+                let value = slot[1 + K..].try_into().expect("slot value width");
+                grown.insert(key, value)?;
+            }
+        }
+        *self = grown;
+        Ok(())
+    }
+
+    fn copy(&self) -> io::Result<Self> {
+        let mut copy = Self::with_slots(&self.dir, self.slots)?;
+        copy.map.copy_from_slice(&self.map);
+        copy.len = self.len;
+        Ok(copy)
+    }
+}
+
+/// Entries and node hashes of a spilled store's coordinate index.
+#[derive(Debug)]
+pub(super) struct SpillIndexStore {
+    dir: PathBuf,
+    /// Encoded entries, found by index key through the log offset and
+    /// length in `entry_slots`.
+    entries: AppendFile,
+    entry_slots: SlotTable<32, 16>,
+    /// Node hashes by depth and key prefix.
+    nodes: SlotTable<34, 32>,
+}
+
+impl SpillIndexStore {
+    pub(super) fn create(dir: &Path) -> io::Result<Self> {
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            entries: AppendFile::create(dir)?,
+            entry_slots: SlotTable::create(dir)?,
+            nodes: SlotTable::create(dir)?,
+        })
+    }
+
+    fn node_key(depth: usize, prefix: &[u8; 32]) -> [u8; 34] {
+        let mut key = [0u8; 34];
+        key[..2].copy_from_slice(&(depth as u16).to_le_bytes());
+        key[2..].copy_from_slice(prefix);
+        key
+    }
+}
+
+impl CoordinateIndexStore for SpillIndexStore {
+    type Error = io::Error;
+
+    fn entry(
+        &self,
+        key: &[u8; 32],
+    ) -> io::Result<Option<(CfsCoordinates, InternalStoreIndexValue)>> {
+        let Some(slot) = self.entry_slots.get(key) else {
+            return Ok(None);
+        };
+        let offset = u64::from_le_bytes(slot[..8].try_into().expect("8-byte offset"));
+        let len = u64::from_le_bytes(slot[8..].try_into().expect("8-byte length"));
+        let bytes = self.entries.read(offset, len)?;
+        raster_core::postcard::from_bytes(&bytes)
+            .map(Some)
+            .map_err(invalid_data)
+    }
+
+    fn insert_entry(
+        &mut self,
+        key: [u8; 32],
+        coordinates: CfsCoordinates,
+        value: InternalStoreIndexValue,
+    ) -> io::Result<()> {
+        let bytes =
+            raster_core::postcard::to_allocvec(&(coordinates, value)).map_err(invalid_data)?;
+        let offset = self.entries.append(&bytes)?;
+        let mut slot = [0u8; 16];
+        slot[..8].copy_from_slice(&offset.to_le_bytes());
+        slot[8..].copy_from_slice(&(bytes.len() as u64).to_le_bytes());
+        self.entry_slots.insert(&key, &slot)
+    }
+
+    fn node(&self, depth: usize, prefix: &[u8; 32]) -> io::Result<Option<Vec<u8>>> {
+        Ok(self
+            .nodes
+            .get(&Self::node_key(depth, prefix))
+            .map(|hash| hash.to_vec()))
+    }
+
+    fn insert_node(&mut self, depth: usize, prefix: [u8; 32], hash: Vec<u8>) -> io::Result<()> {
+        let hash: [u8; 32] = hash
+            .try_into()
+            .map_err(|_| invalid_data("Coordinate index node hash is not 32 bytes"))?;
+        self.nodes.insert(&Self::node_key(depth, &prefix), &hash)
+    }
+}
+
+impl Clone for SpillIndexStore {
+    fn clone(&self) -> Self {
+        let copy = self.entries.copy_into(&self.dir).and_then(|entries| {
+            Ok(Self {
+                dir: self.dir.clone(),
+                entries,
+                entry_slots: self.entry_slots.copy()?,
+                nodes: self.nodes.copy()?,
+            })
+        });
+        copy.unwrap_or_else(|error| {
+            panic!(
+                "Failed to copy spilled internal store index in '{}': {}",
+                self.dir.display(),
+                error
+            )
+        })
+    }
+}
@@ -8,6 +8,8 @@
 mod external_storage;
 pub mod input;
 mod internal_storage;
+pub mod limits;
+pub mod parallel;
 pub mod profiling;
 mod raster_index;
 pub mod tracing;
@@ -22,20 +24,29 @@ pub use internal_storage::{
     exit_recur_sequence_iteration_scope, exit_recur_site_scope, exit_sequence_scope,
     finalize_draft, finalize_empty_draft, finish_draft_step_capture,
     global_internal_store_snapshot, publish_pending_output_coordinates, resolve_internal_ok_value,
-    resolve_internal_value, select_stored_internal_value, store_execution_output_value,
-    store_internal_value, DraftCaptureSnapshot, InternalStorageManager, InternalStoreSnapshot,
-    InternalWriteRecord, StoredInternalObject, TileExecutionScopeGuard,
+    resolve_internal_value, select_stored_internal_value, skip_sequence_items,
+    store_execution_output_value, store_internal_value, DraftCaptureSnapshot,
+    InternalStorageManager, InternalStoreSnapshot, InternalWriteRecord, StoredInternalObject,
+    TileExecutionScopeGuard, INTERNAL_STORE_DIR_ENV,
 };
+pub use limits::{TileLimitBreach, TileLimitScope, TrackingAllocator, TILE_NS_PER_CYCLE_ENV};
+pub use parallel::{map_in_parallel, take_mapped_tile_output, with_mapped_output, MappedOutput};
 pub use profiling::{
     begin_sequence_profile, finish_sequence_profile, record_tile_output_store_profile,
     record_tile_profile, ExecutionProfile, ProfileRecord, ProfileStreamEvent,
-    SequenceProfileRecord, SequenceProfileSelfBreakdown, TileProfileOverheadBreakdown,
-    TileProfileRecord, PROFILE_PATH_ENV, PROFILE_RUN_ID_ENV, PROFILE_STREAM_PATH_ENV,
+    SequenceProfileRecord, SequenceProfileSelfBreakdown, TileCycleCount,
+    TileProfileOverheadBreakdown, TileProfileRecord, PROFILE_PATH_ENV, PROFILE_RUN_ID_ENV,
+    PROFILE_STREAM_PATH_ENV,
 };
 pub use tracing::{
+    checkpoint::{
+        replayed_tile_output, replayed_tile_output_count, Checkpoint, ExecutionStateCheckpoint,
+        CHECKPOINT_INTERVAL_ENV, CHECKPOINT_PATH_ENV, RESUME_ENV,
+    },
     commitment::Sha256Commitment,
+    failure::{catch_tile_failure, CaughtTileFailure},
     finish, init, init_with, publish_trace_event,
     publishers::{BinaryTraceEventPublisher, JsonTraceEventPublisher, Publisher},
-    recorder::TraceRecorder,
+    recorder::{RecorderViolation, RecorderViolationKind, TraceRecorder},
     RecurTraceScopeGuard, TraceFormat, TRACE_FORMAT_ENV, TRACE_PATH_ENV,
 };
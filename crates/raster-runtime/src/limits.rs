@@ -0,0 +1,615 @@
+//! Native enforcement of the `estimated_cycles` and `max_memory` tile
+//! attributes.
+//!
+//! A tile that declares either attribute runs its body inside a
+//! [`TileLimitScope`]:
+//!
+//! - `estimated_cycles` becomes a wall-clock budget: the cycle count times a
+//!   calibrated host cost per cycle, with headroom for the estimate being
+//!   rough. A watchdog thread fails the run if the tile is still running when
+//!   the budget expires, and a tile that returns late fails on its own thread.
+//! - `max_memory` caps the tile's heap usage. The allocation that takes the
+//!   tile past it fails the run. Heap usage is only visible when
+//!   [`TrackingAllocator`] is the program's global allocator.
+//!
+//! A breach fails the tile with `TimeLimitExceeded` or `MemoryLimitExceeded`.
+//! A tile that returns over its time budget fails on its own thread, and the
+//! breach is recorded in the execution profile. A breach found while the tile
+//! is still running cannot unwind it, so its `TileFailed` event is published,
+//! the trace is finished and the run stops.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use raster_core::cfs::CfsCoordinates;
+use raster_core::trace::{FnInput, TileFailureKind};
+use serde::{Deserialize, Serialize};
+
+use crate::tracing::failure::{publish_failure, CaughtTileFailure};
+
+/// Host nanoseconds per zkVM cycle used for time budgets, overriding the
+/// calibration. `0` disables time limits.
+pub const TILE_NS_PER_CYCLE_ENV: &str = "RASTER_TILE_NS_PER_CYCLE";
+
+/// Factor between the calibrated cost of `estimated_cycles` and the budget.
+const TIME_BUDGET_HEADROOM: f64 = 10.0;
+/// Smallest time budget, so that scheduling noise cannot fail a cheap tile.
+const MIN_TIME_BUDGET: Duration = Duration::from_millis(10);
+const CALIBRATION_ITERATIONS: u64 = 1 << 20;
+/// Guest instructions in one iteration of the calibration loop.
+const CALIBRATION_CYCLES_PER_ITERATION: f64 = 4.0;
+/// Exit code of a run stopped by a tile limit.
+const LIMIT_EXIT_CODE: i32 = 101;
+
+/// A tile limit that was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TileLimitBreach {
+    Time { budget_ns: u64, elapsed_ns: u64 },
+    Memory { limit_bytes: u64, peak_bytes: u64 },
+}
+
+impl TileLimitBreach {
+    pub fn failure_kind(&self) -> TileFailureKind {
+        match self {
+            Self::Time { .. } => TileFailureKind::TimeLimitExceeded,
+            Self::Memory { .. } => TileFailureKind::MemoryLimitExceeded,
+        }
+    }
+}
+
+impl fmt::Display for TileLimitBreach {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Time {
+                budget_ns,
+                elapsed_ns,
+            } => write!(
+                f,
+                "ran for {:?}, over its {:?} time budget",
+                Duration::from_nanos(*elapsed_ns),
+                Duration::from_nanos(*budget_ns)
+            ),
+            Self::Memory {
+                limit_bytes,
+                peak_bytes,
+            } => write!(
+                f,
+                "peaked at {} heap bytes, over its {} byte limit",
+                peak_bytes, limit_bytes
+            ),
+        }
+    }
+}
+
+/// Global allocator that counts heap usage per thread, so that tiles can be
+/// held to their `max_memory`.
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: raster::TrackingAllocator = raster::TrackingAllocator::new();
+/// ```
+pub struct TrackingAllocator<A = System> {
+    inner: A,
+}
+
+impl TrackingAllocator<System> {
+    pub const fn new() -> Self {
+        Self { inner: System }
+    }
+}
+
+impl Default for TrackingAllocator<System> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A> TrackingAllocator<A> {
+    /// Count allocations made through `inner`.
+    pub const fn wrap(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+static TRACKING_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+std::thread_local! {
+    /// Bytes allocated minus bytes freed by this thread.
+    static HEAP_LIVE: Cell<i64> = const { Cell::new(0) };
+    /// Memory limits of the limit scopes running on this thread, innermost
+    /// last, and their number. The allocator only looks at the limits when
+    /// the count is non-zero.
+    static MEMORY_LIMITS: RefCell<Vec<MemoryLimit>> = const { RefCell::new(Vec::new()) };
+    static MEMORY_LIMIT_COUNT: Cell<usize> = const { Cell::new(0) };
+    /// Number of reasons not to check memory limits on this thread right now.
+    static MEMORY_LIMITS_PAUSED: Cell<usize> = const { Cell::new(0) };
+}
+
+struct MemoryLimit {
+    fn_name: &'static str,
+    module_path: &'static str,
+    input: Option<FnInput>,
+    recur_iteration: bool,
+    limit_bytes: u64,
+    baseline: i64,
+}
+
+impl MemoryLimit {
+    fn used_bytes(&self, live: i64) -> u64 {
+        u64::try_from(live.saturating_sub(self.baseline)).unwrap_or(0)
+    }
+}
+
+fn track_alloc(size: usize) {
+    TRACKING_INSTALLED.store(true, Ordering::Relaxed);
+    let Ok(live) = HEAP_LIVE.try_with(|live| {
+        let value = live.get().saturating_add(size as i64);
+        live.set(value);
+        value
+    }) else {
+        return;
+    };
+    check_memory_limits(live);
+}
+
+/// Stop the run if `live` heap bytes take a tile on this thread past its
+/// `max_memory`.
+fn check_memory_limits(live: i64) {
+    if MEMORY_LIMIT_COUNT.try_with(Cell::get).unwrap_or(0) == 0
+        || MEMORY_LIMITS_PAUSED.try_with(Cell::get).unwrap_or(1) > 0
+    {
+        return;
+    }
+    let breached = MEMORY_LIMITS.try_with(|limits| {
+        let limits = limits.try_borrow().ok()?;
+        limits
+            .iter()
+            .position(|limit| limit.used_bytes(live) > limit.limit_bytes)
+    });
+    if let Ok(Some(index)) = breached {
+        exceed_memory_limit(index, live);
+    }
+}
+
+#[cold]
+fn exceed_memory_limit(index: usize, live: i64) -> ! {
+    // Reporting the breach allocates; nothing it does can breach again.
+    MEMORY_LIMITS_PAUSED.with(|paused| paused.set(paused.get() + 1));
+    MEMORY_LIMITS.with(|limits| {
+        let limits = limits.borrow();
+        let limit = &limits[index];
+        let breach = TileLimitBreach::Memory {
+            limit_bytes: limit.limit_bytes,
+            peak_bytes: limit.used_bytes(live),
+        };
+        stop_run(
+            breach,
+            limit.fn_name,
+            limit.module_path,
+            limit.input.clone(),
+            limit.recur_iteration,
+        )
+    })
+}
+
+/// Suspends memory limit checks on this thread while the runtime holds the
+/// trace and checkpoint locks, which reporting a breach takes. A limit passed
+/// meanwhile stops the run when the pause ends.
+pub(crate) struct MemoryLimitPause(());
+
+pub(crate) fn pause_memory_limits() -> MemoryLimitPause {
+    let _ = MEMORY_LIMITS_PAUSED.try_with(|paused| paused.set(paused.get() + 1));
+    MemoryLimitPause(())
+}
+
+impl Drop for MemoryLimitPause {
+    fn drop(&mut self) {
+        let _ = MEMORY_LIMITS_PAUSED.try_with(|paused| paused.set(paused.get() - 1));
+        if let Ok(live) = HEAP_LIVE.try_with(Cell::get) {
+            check_memory_limits(live);
+        }
+    }
+}
+
+fn track_dealloc(size: usize) {
+    let _ = HEAP_LIVE.try_with(|live| live.set(live.get().saturating_sub(size as i64)));
+}
+
+// SAFETY: every call is forwarded to `inner` unchanged; the bookkeeping only
+// touches const-initialized thread locals, which neither allocate nor unwind.
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            track_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            track_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        track_dealloc(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            track_dealloc(layout.size());
+            track_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// The limits of one running tile; see the module documentation.
+pub struct TileLimitScope {
+    fn_name: &'static str,
+    start: Instant,
+    time_budget: Option<Duration>,
+    watchdog_ticket: Option<u64>,
+    memory_limited: bool,
+}
+
+impl TileLimitScope {
+    pub fn enter(
+        fn_name: &'static str,
+        module_path: &'static str,
+        estimated_cycles: Option<u64>,
+        max_memory: Option<u64>,
+        input: &Option<FnInput>,
+    ) -> Self {
+        let time_budget = estimated_cycles.and_then(|cycles| time_budget(cycles, ns_per_cycle()));
+        if let Some(limit_bytes) = max_memory {
+            if !TRACKING_INSTALLED.load(Ordering::Relaxed) {
+                warn_untracked_memory(fn_name);
+            }
+            MEMORY_LIMITS.with(|limits| {
+                let mut limits = limits.borrow_mut();
+                limits.push(MemoryLimit {
+                    fn_name,
+                    module_path,
+                    input: input.clone(),
+                    recur_iteration: crate::tracing::in_recur_iteration(),
+                    limit_bytes,
+                    baseline: 0,
+                });
+                // Only what the tile allocates from here on counts.
+                if let Some(limit) = limits.last_mut() {
+                    limit.baseline = HEAP_LIVE.with(Cell::get);
+                }
+            });
+            MEMORY_LIMIT_COUNT.with(|count| count.set(count.get() + 1));
+        }
+        let start = Instant::now();
+        let watchdog_ticket = time_budget.map(|budget| {
+            watchdog().arm(ArmedTile {
+                ticket: 0,
+                deadline: start + budget,
+                budget,
+                fn_name,
+                module_path,
+                input: input.clone(),
+                recur_iteration: crate::tracing::in_recur_iteration(),
+            })
+        });
+        Self {
+            fn_name,
+            start,
+            time_budget,
+            watchdog_ticket,
+            memory_limited: max_memory.is_some(),
+        }
+    }
+
+    /// Check the time budget once the tile body has returned, recording a
+    /// breach in the profile and returning the tile's failure.
+    pub fn finish(self, coordinates: &CfsCoordinates) -> Result<(), CaughtTileFailure> {
+        let elapsed = self.start.elapsed();
+        let Some(budget) = self.time_budget.filter(|budget| elapsed > *budget) else {
+            return Ok(());
+        };
+        let breach = TileLimitBreach::Time {
+            budget_ns: u64::try_from(budget.as_nanos()).unwrap_or(u64::MAX),
+            elapsed_ns: u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX),
+        };
+        crate::profiling::record_tile_limit_breach(
+            self.fn_name,
+            coordinates.clone(),
+            u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX),
+            breach,
+        );
+        let message = breach_message(self.fn_name, breach);
+        eprintln!("{}", message);
+        Err(CaughtTileFailure::new(breach.failure_kind(), message))
+    }
+}
+
+impl Drop for TileLimitScope {
+    fn drop(&mut self) {
+        if let Some(ticket) = self.watchdog_ticket.take() {
+            watchdog().disarm(ticket);
+        }
+        if self.memory_limited {
+            let _ = MEMORY_LIMIT_COUNT.try_with(|count| count.set(count.get() - 1));
+            let _ = MEMORY_LIMITS.try_with(|limits| limits.borrow_mut().pop());
+        }
+    }
+}
+
+fn warn_untracked_memory(fn_name: &str) {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+    if !WARNED.swap(true, Ordering::Relaxed) {
+        eprintln!(
+            "warning: tile `{}` sets max_memory, but raster::TrackingAllocator is not the global \
+             allocator; memory limits are not enforced",
+            fn_name
+        );
+    }
+}
+
+/// Wall-clock budget for `cycles`, or `None` when time limits are disabled.
+fn time_budget(cycles: u64, ns_per_cycle: f64) -> Option<Duration> {
+    if ns_per_cycle <= 0.0 {
+        return None;
+    }
+    let budget_ns = cycles as f64 * ns_per_cycle * TIME_BUDGET_HEADROOM;
+    Some(Duration::from_nanos(budget_ns.min(u64::MAX as f64) as u64).max(MIN_TIME_BUDGET))
+}
+
+fn ns_per_cycle() -> f64 {
+    static NS_PER_CYCLE: OnceLock<f64> = OnceLock::new();
+    *NS_PER_CYCLE.get_or_init(|| configured_ns_per_cycle().unwrap_or_else(calibrate_ns_per_cycle))
+}
+
+/// The cost per cycle set in the environment. A value that is not a number
+/// is ignored with a warning.
+fn configured_ns_per_cycle() -> Option<f64> {
+    let value = std::env::var(TILE_NS_PER_CYCLE_ENV).ok()?;
+    parse_ns_per_cycle(&value).or_else(|| {
+        eprintln!(
+            "warning: ignoring {}='{}', which is not a number of nanoseconds; calibrating \
+             the cost per cycle instead",
+            TILE_NS_PER_CYCLE_ENV, value
+        );
+        None
+    })
+}
+
+fn parse_ns_per_cycle(value: &str) -> Option<f64> {
+    value.trim().parse::<f64>().ok().filter(|ns| !ns.is_nan())
+}
+
+/// Time a dependent multiply-add loop to estimate what one guest cycle costs
+/// on this host.
+fn calibrate_ns_per_cycle() -> f64 {
+    let start = Instant::now();
+    let mut value = 0u64;
+    for iteration in 0..CALIBRATION_ITERATIONS {
+        value = black_box(value.wrapping_mul(31).wrapping_add(iteration));
+    }
+    black_box(value);
+    start.elapsed().as_nanos() as f64
+        / (CALIBRATION_ITERATIONS as f64 * CALIBRATION_CYCLES_PER_ITERATION)
+}
+
+struct ArmedTile {
+    ticket: u64,
+    deadline: Instant,
+    budget: Duration,
+    fn_name: &'static str,
+    module_path: &'static str,
+    input: Option<FnInput>,
+    recur_iteration: bool,
+}
+
+#[derive(Default)]
+struct WatchdogState {
+    next_ticket: u64,
+    armed: Vec<ArmedTile>,
+}
+
+struct Watchdog {
+    state: Mutex<WatchdogState>,
+    wake: Condvar,
+}
+
+fn watchdog() -> &'static Watchdog {
+    static WATCHDOG: OnceLock<Watchdog> = OnceLock::new();
+    WATCHDOG.get_or_init(|| {
+        std::thread::Builder::new()
+            .name("raster-tile-watchdog".to_string())
+            .spawn(|| watchdog().run())
+            .unwrap_or_else(|error| panic!("Failed to start the tile watchdog: {}", error));
+        Watchdog {
+            state: Mutex::new(WatchdogState::default()),
+            wake: Condvar::new(),
+        }
+    })
+}
+
+impl Watchdog {
+    fn lock(&self) -> MutexGuard<'_, WatchdogState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn arm(&self, mut tile: ArmedTile) -> u64 {
+        let mut state = self.lock();
+        state.next_ticket += 1;
+        tile.ticket = state.next_ticket;
+        let ticket = tile.ticket;
+        state.armed.push(tile);
+        self.wake.notify_one();
+        ticket
+    }
+
+    fn disarm(&self, ticket: u64) {
+        self.lock().armed.retain(|tile| tile.ticket != ticket);
+    }
+
+    fn run(&self) {
+        let mut state = self.lock();
+        loop {
+            let now = Instant::now();
+            let next = state.armed.iter().min_by_key(|tile| tile.deadline);
+            state = match next {
+                None => self
+                    .wake
+                    .wait(state)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+                Some(tile) if tile.deadline <= now => expire(tile),
+                Some(tile) => {
+                    let timeout = tile.deadline - now;
+                    self.wake
+                        .wait_timeout(state, timeout)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .0
+                }
+            };
+        }
+    }
+}
+
+/// Fail a tile that outlived its budget. The tile's thread cannot be unwound
+/// from here, so the run is stopped on its behalf; the watchdog lock stays
+/// held so the tile cannot return and publish anything after its failure.
+fn expire(tile: &ArmedTile) -> ! {
+    let breach = TileLimitBreach::Time {
+        budget_ns: u64::try_from(tile.budget.as_nanos()).unwrap_or(u64::MAX),
+        elapsed_ns: u64::try_from((Instant::now() - tile.deadline + tile.budget).as_nanos())
+            .unwrap_or(u64::MAX),
+    };
+    stop_run(
+        breach,
+        tile.fn_name,
+        tile.module_path,
+        tile.input.clone(),
+        tile.recur_iteration,
+    )
+}
+
+fn breach_message(fn_name: &str, breach: TileLimitBreach) -> String {
+    format!("tile `{}` {}", fn_name, breach)
+}
+
+/// Publish the `TileFailed` event of a tile that breached a limit while
+/// running, finish the trace and exit. Only the first breach stops the run;
+/// a thread that breaches a limit while it is stopping waits for the exit.
+fn stop_run(
+    breach: TileLimitBreach,
+    fn_name: &str,
+    module_path: &str,
+    input: Option<FnInput>,
+    recur_iteration: bool,
+) -> ! {
+    static STOPPING: AtomicBool = AtomicBool::new(false);
+    if STOPPING.swap(true, Ordering::SeqCst) {
+        loop {
+            std::thread::park();
+        }
+    }
+    let message = breach_message(fn_name, breach);
+    eprintln!("{}; stopping the run", message);
+    publish_failure(
+        breach.failure_kind(),
+        &message,
+        fn_name,
+        module_path,
+        input,
+        recur_iteration,
+    );
+    crate::tracing::finish_stopped_run();
+    std::process::exit(LIMIT_EXIT_CODE)
+}
+
+#[cfg(test)]
+#[global_allocator]
+static TEST_ALLOCATOR: TrackingAllocator = TrackingAllocator::new();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_budget_scales_cycles_with_headroom_and_floor() {
+        assert_eq!(time_budget(1_000, 0.0), None);
+        assert_eq!(time_budget(1_000, 1.0), Some(MIN_TIME_BUDGET));
+        assert_eq!(
+            time_budget(100_000_000, 0.5),
+            Some(Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn malformed_ns_per_cycle_is_ignored() {
+        assert_eq!(parse_ns_per_cycle(" 2.5 "), Some(2.5));
+        assert_eq!(parse_ns_per_cycle("0"), Some(0.0));
+        assert_eq!(parse_ns_per_cycle("fast"), None);
+        assert_eq!(parse_ns_per_cycle("NaN"), None);
+    }
+
+    #[test]
+    fn heap_usage_within_max_memory_passes() {
+        let scope = TileLimitScope::enter("small", module_path!(), None, Some(4096), &None);
+        drop(black_box(vec![0u8; 1024]));
+        assert!(scope.finish(&CfsCoordinates(vec![0])).is_ok());
+    }
+
+    /// Set in the child process that
+    /// `allocation_past_max_memory_stops_the_run` runs.
+    const STOP_CHILD_ENV: &str = "RASTER_LIMITS_TEST_STOP_CHILD";
+
+    #[test]
+    fn allocation_past_max_memory_stops_the_run() {
+        if std::env::var_os(STOP_CHILD_ENV).is_some() {
+            let _scope = TileLimitScope::enter("large", module_path!(), None, Some(4096), &None);
+            drop(black_box(vec![0u8; 64 * 1024]));
+            unreachable!("the allocation stops the run");
+        }
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "limits::tests::allocation_past_max_memory_stops_the_run",
+                "--exact",
+                "--nocapture",
+            ])
+            .env(STOP_CHILD_ENV, "1")
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(LIMIT_EXIT_CODE), "{}", stderr);
+        assert!(
+            stderr.contains("tile `large` peaked at 65536 heap bytes, over its 4096 byte limit"),
+            "{}",
+            stderr
+        );
+    }
+
+    #[test]
+    fn late_tile_fails_with_its_time_breach() {
+        let breach = TileLimitBreach::Time {
+            budget_ns: 10_000_000,
+            elapsed_ns: 25_000_000,
+        };
+        let failure = CaughtTileFailure::new(breach.failure_kind(), breach_message("slow", breach));
+        assert_eq!(failure.kind(), TileFailureKind::TimeLimitExceeded);
+        assert_eq!(
+            failure.message(),
+            "tile `slow` ran for 25ms, over its 10ms time budget"
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! Parallel execution of `kind = map` tile bodies.
+//!
+//! Internal storage, coordinate reservation and trace publishing are
+//! thread-local, so a map site runs in two phases. [`map_in_parallel`] runs
+//! the plain tile bodies for every item on the rayon pool. The site then runs
+//! each iteration through its usual traced wrapper, in index order on the
+//! calling thread, under [`with_mapped_output`]: the wrapper takes the body's
+//! result from [`take_mapped_tile_output`] instead of running it again.
+//! Coordinates, stored outputs and trace events are therefore exactly those
+//! of a sequential run.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+
+use rayon::prelude::*;
+
+/// Result of a map tile body: its output, or the payload of its panic.
+pub type MappedOutput<O> = Result<O, Box<dyn Any + Send>>;
+
+std::thread_local! {
+    static MAPPED_OUTPUT: RefCell<Option<Box<dyn Any>>> = const { RefCell::new(None) };
+}
+
+/// Run `body` on every item on the rayon pool, returning the results in item
+/// order. A panicking body does not stop the others; its payload is kept so
+/// that the panic can be raised again when its iteration is traced.
+pub fn map_in_parallel<T, O>(
+    items: &[T],
+    body: impl Fn(usize, &T) -> O + Sync,
+) -> Vec<MappedOutput<O>>
+where
+    T: Sync,
+    O: Send,
+{
+    items
+        .par_iter()
+        .enumerate()
+        .map(|(index, item)| panic::catch_unwind(AssertUnwindSafe(|| body(index, item))))
+        .collect()
+}
+
+/// Run `step` with `output` available to the map tile it executes.
+pub fn with_mapped_output<O: 'static, R>(output: MappedOutput<O>, step: impl FnOnce() -> R) -> R {
+    struct ClearOnDrop;
+
+    impl Drop for ClearOnDrop {
+        fn drop(&mut self) {
+            MAPPED_OUTPUT.with(|slot| slot.borrow_mut().take());
+        }
+    }
+
+    MAPPED_OUTPUT.with(|slot| *slot.borrow_mut() = Some(Box::new(output)));
+    let _clear = ClearOnDrop;
+    step()
+}
+
+/// The precomputed output of the map tile being executed, or `None` outside
+/// [`with_mapped_output`]. A body that panicked panics again here.
+pub fn take_mapped_tile_output<O: 'static>(fn_name: &str) -> Option<O> {
+    let output = MAPPED_OUTPUT.with(|slot| slot.borrow_mut().take())?;
+    let output = output.downcast::<MappedOutput<O>>().unwrap_or_else(|_| {
+        panic!(
+            "Precomputed output of map tile '{}' has an unexpected type",
+            fn_name
+        )
+    });
+    match *output {
+        Ok(output) => Some(output),
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapped_outputs_keep_item_order_and_panics() {
+        let items: Vec<u64> = (0..64).collect();
+        let outputs = map_in_parallel(&items, |index, item| {
+            assert_ne!(index, 7, "item seven fails");
+            item * 2
+        });
+
+        assert_eq!(outputs.len(), items.len());
+        assert!(outputs[7].is_err());
+        for (index, output) in outputs.into_iter().enumerate() {
+            let expected = with_mapped_output(output, || {
+                panic::catch_unwind(|| take_mapped_tile_output::<u64>("double")).ok()
+            });
+            if index == 7 {
+                assert_eq!(expected, None);
+            } else {
+                assert_eq!(expected, Some(Some(index as u64 * 2)));
+            }
+        }
+        assert_eq!(take_mapped_tile_output::<u64>("double"), None);
+    }
+}
@@ -1,5 +1,8 @@
 use raster_core::cfs::CfsCoordinates;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::limits::TileLimitBreach;
 
 #[cfg(feature = "profiling")]
 use std::cell::RefCell;
@@ -43,6 +46,67 @@ impl ExecutionProfile {
             records,
         }
     }
+
+    /// Attach zkVM cycle counts to the tile records at their coordinates,
+    /// adding a cycles-only record for an invocation the profile has none for.
+    pub fn record_tile_cycles(&mut self, counts: impl IntoIterator<Item = TileCycleCount>) {
+        let mut tile_records: HashMap<(String, CfsCoordinates), usize> = self
+            .records
+            .iter()
+            .enumerate()
+            .filter_map(|(index, record)| match record {
+                ProfileRecord::Tile(record) => {
+                    Some(((record.tile_id.clone(), record.coordinates.clone()), index))
+                }
+                ProfileRecord::Sequence(_) => None,
+            })
+            .collect();
+
+        for count in counts {
+            let key = (count.tile_id, count.coordinates);
+            if let Some(&index) = tile_records.get(&key) {
+                if let ProfileRecord::Tile(record) = &mut self.records[index] {
+                    record.cycles = Some(count.cycles);
+                    record.proof_cycles = Some(count.proof_cycles);
+                }
+                continue;
+            }
+            let (tile_id, coordinates) = key.clone();
+            let invocation_index = u64::try_from(self.records.len()).unwrap_or(u64::MAX);
+            tile_records.insert(key, self.records.len());
+            self.records.push(ProfileRecord::Tile(TileProfileRecord {
+                invocation_index,
+                tile_id,
+                depth: coordinates.0.len() as u32,
+                coordinates,
+                total_duration_ns: 0,
+                user_duration_ns: 0,
+                raster_overhead_ns: 0,
+                external_input_resolve_ns: 0,
+                internal_input_resolve_ns: 0,
+                output_store_ns: 0,
+                trace_serialize_ns: 0,
+                draft_capture_ns: 0,
+                scope_enter_ns: 0,
+                output_record_build_ns: 0,
+                trace_event_publish_ns: 0,
+                output_coordinate_publish_ns: 0,
+                other_wrapper_ns: 0,
+                cycles: Some(count.cycles),
+                proof_cycles: Some(count.proof_cycles),
+                limit_breach: None,
+            }));
+        }
+    }
+}
+
+/// zkVM cycle counts measured for one tile invocation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileCycleCount {
+    pub tile_id: String,
+    pub coordinates: CfsCoordinates,
+    pub cycles: u64,
+    pub proof_cycles: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -92,6 +156,18 @@ pub struct TileProfileRecord {
     pub output_coordinate_publish_ns: u64,
     #[serde(default)]
     pub other_wrapper_ns: u64,
+    /// zkVM cycles of the invocation, filled in after the run when its
+    /// recorded input is executed on the RISC0 backend.
+    #[serde(default)]
+    pub cycles: Option<u64>,
+    /// `cycles` padded to the segment size proving would pay for.
+    #[serde(default)]
+    pub proof_cycles: Option<u64>,
+    /// The `estimated_cycles` or `max_memory` limit the invocation exceeded.
+    /// Such a record has no overhead breakdown: the tile failed before its
+    /// output was handled.
+    #[serde(default)]
+    pub limit_breach: Option<TileLimitBreach>,
 }
 
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
@@ -499,6 +575,9 @@ pub fn record_tile_profile(
             trace_event_publish_ns: overhead_breakdown.trace_event_publish_ns,
             output_coordinate_publish_ns: overhead_breakdown.output_coordinate_publish_ns,
             other_wrapper_ns: overhead_breakdown.other_wrapper_ns,
+            cycles: None,
+            proof_cycles: None,
+            limit_breach: None,
         });
         state.records.push(record.clone());
         state
@@ -517,6 +596,59 @@ pub fn record_tile_profile(
 ) {
 }
 
+/// Record a tile that failed by exceeding one of its limits, after running
+/// for `user_duration_ns`.
+#[cfg(feature = "profiling")]
+pub(crate) fn record_tile_limit_breach(
+    tile_id: &str,
+    coordinates: CfsCoordinates,
+    user_duration_ns: u64,
+    breach: TileLimitBreach,
+) {
+    PROFILER_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if !state.enabled() {
+            return;
+        }
+
+        let invocation_index = state.next_invocation_index();
+        let depth = state.active_sequences.len() as u32;
+        if let Some(parent) = state.active_sequences.last_mut() {
+            parent.child_duration_ns = parent.child_duration_ns.saturating_add(user_duration_ns);
+        }
+
+        let record = ProfileRecord::Tile(TileProfileRecord {
+            invocation_index,
+            tile_id: tile_id.to_string(),
+            depth,
+            coordinates,
+            total_duration_ns: user_duration_ns,
+            user_duration_ns,
+            raster_overhead_ns: 0,
+            external_input_resolve_ns: 0,
+            internal_input_resolve_ns: 0,
+            output_store_ns: 0,
+            trace_serialize_ns: 0,
+            draft_capture_ns: 0,
+            scope_enter_ns: 0,
+            output_record_build_ns: 0,
+            trace_event_publish_ns: 0,
+            output_coordinate_publish_ns: 0,
+            other_wrapper_ns: 0,
+            cycles: None,
+            proof_cycles: None,
+            limit_breach: Some(breach),
+        });
+        state.records.push(record.clone());
+        state
+            .send_stream_event(ProfileStreamEvent::Record(record))
+            .unwrap_or_else(|error| panic!("Failed to stream tile limit breach: {}", error));
+    });
+}
+
+#[cfg(not(feature = "profiling"))]
+pub(crate) fn record_tile_limit_breach(_: &str, _: CfsCoordinates, _: u64, _: TileLimitBreach) {}
+
 #[cfg(feature = "profiling")]
 pub fn record_tile_output_store_profile(output_store_ns: u64) {
     PROFILER_STATE.with(|state| {
@@ -797,6 +929,70 @@ mod tests {
         assert_eq!(record.self_duration_ns, 1_000_000);
     }
 
+    #[test]
+    fn tile_cycles_attach_to_records_by_coordinates() {
+        let tile_record = |tile_id: &str, index: u32| {
+            ProfileRecord::Tile(TileProfileRecord {
+                invocation_index: u64::from(index),
+                tile_id: tile_id.to_string(),
+                depth: 1,
+                coordinates: CfsCoordinates(vec![index]),
+                total_duration_ns: 10,
+                user_duration_ns: 10,
+                raster_overhead_ns: 0,
+                external_input_resolve_ns: 0,
+                internal_input_resolve_ns: 0,
+                output_store_ns: 0,
+                trace_serialize_ns: 0,
+                draft_capture_ns: 0,
+                scope_enter_ns: 0,
+                output_record_build_ns: 0,
+                trace_event_publish_ns: 0,
+                output_coordinate_publish_ns: 0,
+                other_wrapper_ns: 0,
+                cycles: None,
+                proof_cycles: None,
+                limit_breach: None,
+            })
+        };
+        let mut profile = ExecutionProfile::new(
+            vec![tile_record("tile_a", 0), tile_record("tile_b", 1)],
+            None,
+            None,
+        );
+        let count = |tile_id: &str, index: u32, cycles: u64| TileCycleCount {
+            tile_id: tile_id.to_string(),
+            coordinates: CfsCoordinates(vec![index]),
+            cycles,
+            proof_cycles: cycles * 2,
+        };
+
+        profile.record_tile_cycles([
+            count("tile_b", 1, 200),
+            count("tile_c", 2, 300),
+            count("tile_a", 0, 100),
+        ]);
+
+        let cycles: Vec<_> = profile
+            .records
+            .iter()
+            .map(|record| match record {
+                ProfileRecord::Tile(record) => {
+                    (record.tile_id.as_str(), record.cycles, record.proof_cycles)
+                }
+                ProfileRecord::Sequence(_) => panic!("expected only tile records"),
+            })
+            .collect();
+        assert_eq!(
+            cycles,
+            vec![
+                ("tile_a", Some(100), Some(200)),
+                ("tile_b", Some(200), Some(400)),
+                ("tile_c", Some(300), Some(600)),
+            ]
+        );
+    }
+
     #[test]
     fn older_sequence_records_deserialize_with_default_breakdown() {
         let bytes = br#"{
@@ -1,4 +1,6 @@
+pub mod checkpoint;
 pub mod commitment;
+pub mod failure;
 pub mod publishers;
 pub mod recorder;
 
@@ -8,6 +10,7 @@ use crate::tracing::publishers::{
     BinaryTraceEventPublisher, JsonTraceEventPublisher, Publisher, GLOBAL_PUBLISHER,
 };
 use std::cell::Cell;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Once;
 
@@ -59,7 +62,8 @@ impl FromStr for TraceFormat {
 ///
 /// `cargo raster run` sets `RASTER_TRACE_PATH`, which enables trace capture
 /// for the CLI. `RASTER_TRACE_FORMAT` selects the file format and defaults to
-/// `binary`. Plain Rust runs stay quiet by default.
+/// `binary`. `RASTER_CHECKPOINT_PATH` enables checkpoints and resuming (see
+/// [`checkpoint`]). Plain Rust runs stay quiet by default.
 ///
 /// This function should be called once at the start of your program.
 /// Subsequent calls will have no effect.
@@ -70,19 +74,28 @@ pub fn init() {
     }
 
     if let Some(trace_path) = std::env::var_os(TRACE_PATH_ENV) {
-        match trace_format_from_env() {
+        let trace_path = PathBuf::from(trace_path);
+        let format = trace_format_from_env();
+        let resume_offset = checkpoint::init_from_env(format, &trace_path);
+        match format {
             TraceFormat::Binary => {
-                let publisher = BinaryTraceEventPublisher::from_path(trace_path.into())
-                    .unwrap_or_else(|error| {
-                        panic!("Failed to initialize binary trace publisher: {}", error)
-                    });
+                let publisher = match resume_offset {
+                    Some(offset) => BinaryTraceEventPublisher::resume_from_path(trace_path, offset),
+                    None => BinaryTraceEventPublisher::from_path(trace_path),
+                }
+                .unwrap_or_else(|error| {
+                    panic!("Failed to initialize binary trace publisher: {}", error)
+                });
                 install_publisher(publisher);
             }
             TraceFormat::Json => {
-                let publisher = JsonTraceEventPublisher::from_path(trace_path.into())
-                    .unwrap_or_else(|error| {
-                        panic!("Failed to initialize JSON trace publisher: {}", error)
-                    });
+                let publisher = match resume_offset {
+                    Some(offset) => JsonTraceEventPublisher::resume_from_path(trace_path, offset),
+                    None => JsonTraceEventPublisher::from_path(trace_path),
+                }
+                .unwrap_or_else(|error| {
+                    panic!("Failed to initialize JSON trace publisher: {}", error)
+                });
                 install_publisher(publisher);
             }
         }
@@ -122,31 +135,55 @@ pub fn finish() {
     if let Some(publisher) = GLOBAL_PUBLISHER.get() {
         publisher.finish();
     }
+    checkpoint::finish();
     if let Err(error) = crate::profiling::finish() {
         panic!("Failed to write Raster execution profile: {}", error);
     }
 }
 
+/// Finish the trace of a run stopped by a tile failure. The checkpoint is
+/// kept, so that the run can be resumed.
+pub(crate) fn finish_stopped_run() {
+    if let Some(publisher) = GLOBAL_PUBLISHER.get() {
+        publisher.finish();
+    }
+}
+
 // Internal function used by the generated code from the #[tile] and #[sequence] macros.
 // This is not part of the public API.
 
 #[doc(hidden)]
 pub fn publish_trace_event(event: TraceEvent) {
+    let _pause = crate::limits::pause_memory_limits();
     if let Some(publisher) = GLOBAL_PUBLISHER.get() {
         let event = RECUR_TRACE_DEPTH.with(|depth| {
             if depth.get() > 0 {
                 match event {
                     TraceEvent::TileExec(record) => TraceEvent::RecurTileIterationExec(record),
+                    TraceEvent::TileFailed(record, mut failure) => {
+                        failure.recur_iteration = true;
+                        TraceEvent::TileFailed(record, failure)
+                    }
                     other => other,
                 }
             } else {
                 event
             }
         });
+        if checkpoint::replay_event(&event) {
+            return;
+        }
+        let tile_execution = checkpoint::is_tile_execution(&event);
         publisher.publish(event);
+        checkpoint::record_published_event(publisher.as_ref(), tile_execution);
     }
 }
 
+/// Whether tiles run on this thread are published as recur iterations.
+pub(crate) fn in_recur_iteration() -> bool {
+    RECUR_TRACE_DEPTH.with(|depth| depth.get() > 0)
+}
+
 #[doc(hidden)]
 pub struct RecurTraceScopeGuard;
 
@@ -0,0 +1,600 @@
+//! Checkpoints for resuming interrupted native runs.
+//!
+//! Every `RASTER_CHECKPOINT_INTERVAL` tile executions the runtime flushes the
+//! trace and writes a checkpoint next to it: the number of trace events and
+//! bytes written so far and the execution state of the program at that point
+//! (internal store roots, sequence and recur frames, open draft anchors).
+//!
+//! The program's own call stack cannot be restored, so a resumed run starts
+//! over and fast-forwards to the checkpoint. Until it gets there, every
+//! published event must match the corresponding frame of the existing trace
+//! byte for byte and is not written again, and tiles returning plain values
+//! take their output from the trace instead of running their body. A map
+//! site does not run those items' bodies on the pool either. Tiles that
+//! return drafts or recur handles still run, since those cannot be rebuilt
+//! from their serialized form. Once the checkpointed event is reached
+//! the execution state is compared with the checkpoint and new events are
+//! appended where the trace left off, so an interrupted and resumed run
+//! produces the same trace as an uninterrupted one.
+//!
+//! A checkpoint holds only the roots of the internal store and summaries of
+//! the sequence and recur stacks, not the store's objects or the frames
+//! themselves. The resumed run rebuilds both by replaying the program from
+//! its start: it skips the bodies of tiles whose output is in the trace, but
+//! still writes every stored object and enters every frame before the
+//! checkpoint.
+//!
+//! A recorded output is only handed to a tile if the next tile execution in
+//! the trace has the same function name and module path, and the event that
+//! tile then publishes must be that execution, published at the coordinates
+//! the output was handed out for.
+
+use raster_core::cfs::CfsCoordinates;
+use raster_core::trace::TraceEvent;
+use raster_core::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Take};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::publishers::Publisher;
+use super::TraceFormat;
+
+pub const CHECKPOINT_PATH_ENV: &str = "RASTER_CHECKPOINT_PATH";
+pub const CHECKPOINT_INTERVAL_ENV: &str = "RASTER_CHECKPOINT_INTERVAL";
+pub const RESUME_ENV: &str = "RASTER_RESUME";
+
+const CHECKPOINT_VERSION: u32 = 1;
+
+/// Progress of a run at the last checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub version: u32,
+    /// Trace format of the run (`binary` or `json`).
+    pub trace_format: String,
+    /// Number of trace events written before the checkpoint.
+    pub trace_event_count: u64,
+    /// Length of the trace file in bytes at the checkpoint.
+    pub trace_offset: u64,
+    /// Number of tile executions before the checkpoint.
+    pub tile_exec_count: u64,
+    pub state: ExecutionStateCheckpoint,
+}
+
+/// Runtime state that a resumed run must reproduce at the checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionStateCheckpoint {
+    /// Root of the internal store log.
+    pub store_root: Vec<u8>,
+    /// Root of the internal store coordinate index.
+    pub store_index_root: Vec<u8>,
+    pub store_object_count: u64,
+    /// Coordinates, next child index and next synthetic index of each active
+    /// sequence, outermost first.
+    pub sequence_stack: Vec<(CfsCoordinates, u32, u32)>,
+    /// Site coordinates and next iteration index of each active recur site.
+    pub recur_stack: Vec<(CfsCoordinates, u32)>,
+    /// Anchor and current root of each open draft.
+    pub draft_anchors: Vec<([u8; 32], [u8; 32])>,
+}
+
+impl Checkpoint {
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path)?;
+        let checkpoint: Self = serde_json::from_slice(&bytes)?;
+        if checkpoint.version != CHECKPOINT_VERSION {
+            return Err(Error::Other(format!(
+                "Unsupported checkpoint version {} in {} (expected {})",
+                checkpoint.version,
+                path.display(),
+                CHECKPOINT_VERSION
+            )));
+        }
+        Ok(checkpoint)
+    }
+
+    /// Write the checkpoint through a temporary file so a crash while
+    /// writing leaves the previous checkpoint intact.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+}
+
+/// Frames of the trace written before the checkpoint, read back while a
+/// resumed run fast-forwards.
+struct TracePrefix {
+    reader: Take<BufReader<File>>,
+    format: TraceFormat,
+    /// Frames read ahead to find the output of the next tile execution.
+    pending: VecDeque<Vec<u8>>,
+}
+
+impl TracePrefix {
+    fn open(path: &Path, format: TraceFormat, offset: u64) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        Ok(Self {
+            reader: BufReader::new(file).take(offset),
+            format,
+            pending: VecDeque::new(),
+        })
+    }
+
+    fn read_frame(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        match self.format {
+            TraceFormat::Binary => {
+                let mut len_bytes = [0u8; 4];
+                if self.reader.limit() == 0 {
+                    return Ok(None);
+                }
+                self.reader.read_exact(&mut len_bytes)?;
+                let mut frame = vec![0u8; u32::from_le_bytes(len_bytes) as usize];
+                self.reader.read_exact(&mut frame)?;
+                Ok(Some(frame))
+            }
+            TraceFormat::Json => {
+                let mut line = Vec::new();
+                if self.reader.read_until(b'\n', &mut line)? == 0 {
+                    return Ok(None);
+                }
+                if line.last() == Some(&b'\n') {
+                    line.pop();
+                }
+                Ok(Some(line))
+            }
+        }
+    }
+
+    fn next_frame(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        match self.pending.pop_front() {
+            Some(frame) => Ok(Some(frame)),
+            None => self.read_frame(),
+        }
+    }
+
+    /// Output bytes of the next tile execution in the prefix, if it belongs
+    /// to `fn_name` in `module_path`, with the number of frames before it.
+    fn next_tile_output(&mut self, fn_name: &str, module_path: &str) -> Option<(u64, Vec<u8>)> {
+        let mut index = 0;
+        loop {
+            if index == self.pending.len() {
+                let frame = self.read_frame().ok()??;
+                self.pending.push_back(frame);
+            }
+            match decode_event(self.format, &self.pending[index]).ok()? {
+                TraceEvent::TileExec(record) | TraceEvent::RecurTileIterationExec(record) => {
+                    if record.fn_name != fn_name || record.module_path != module_path {
+                        return None;
+                    }
+                    return record.output.map(|output| (index as u64, output.data));
+                }
+                _ => index += 1,
+            }
+        }
+    }
+
+    /// Number of consecutive tile executions of `fn_name` in `module_path`
+    /// with a recorded output at the start of the prefix, up to `limit`.
+    fn count_tile_outputs(&mut self, fn_name: &str, module_path: &str, limit: usize) -> usize {
+        let mut count = 0;
+        let mut index = 0;
+        while count < limit {
+            if index == self.pending.len() {
+                match self.read_frame() {
+                    Ok(Some(frame)) => self.pending.push_back(frame),
+                    _ => break,
+                }
+            }
+            match decode_event(self.format, &self.pending[index]) {
+                Ok(TraceEvent::TileExec(record) | TraceEvent::RecurTileIterationExec(record)) => {
+                    if record.fn_name != fn_name
+                        || record.module_path != module_path
+                        || record.output.is_none()
+                    {
+                        break;
+                    }
+                    count += 1;
+                }
+                Ok(_) => {}
+                Err(_) => break,
+            }
+            index += 1;
+        }
+        count
+    }
+}
+
+fn encode_event(format: TraceFormat, event: &TraceEvent) -> Vec<u8> {
+    match format {
+        TraceFormat::Binary => raster_core::postcard::to_allocvec(event)
+            .unwrap_or_else(|error| panic!("Failed to serialize trace event: {}", error)),
+        TraceFormat::Json => serde_json::to_vec(event)
+            .unwrap_or_else(|error| panic!("Failed to serialize trace event: {}", error)),
+    }
+}
+
+fn decode_event(format: TraceFormat, frame: &[u8]) -> Result<TraceEvent> {
+    match format {
+        TraceFormat::Binary => raster_core::postcard::from_bytes(frame)
+            .map_err(|error| Error::Serialization(error.to_string())),
+        TraceFormat::Json => Ok(serde_json::from_slice(frame)?),
+    }
+}
+
+pub(crate) fn is_tile_execution(event: &TraceEvent) -> bool {
+    matches!(
+        event,
+        TraceEvent::TileExec(_) | TraceEvent::RecurTileIterationExec(_)
+    )
+}
+
+struct Resume {
+    checkpoint: Checkpoint,
+    prefix: TracePrefix,
+    /// Index of the trace event whose output was handed to a tile, with the
+    /// coordinates of that tile.
+    handed_out: Option<(u64, CfsCoordinates)>,
+}
+
+struct Checkpointer {
+    format: TraceFormat,
+    /// Where checkpoints are written, with the number of tile executions
+    /// between them.
+    output: Option<(PathBuf, u64)>,
+    trace_event_count: u64,
+    tile_exec_count: u64,
+    resume: Option<Resume>,
+}
+
+static CHECKPOINTER: Mutex<Option<Checkpointer>> = Mutex::new(None);
+
+/// Read the checkpoint settings from the environment. Returns the trace
+/// offset to continue writing from when resuming.
+pub(crate) fn init_from_env(format: TraceFormat, trace_path: &Path) -> Option<u64> {
+    let checkpoint_path = std::env::var_os(CHECKPOINT_PATH_ENV).map(PathBuf::from)?;
+    let interval = std::env::var(CHECKPOINT_INTERVAL_ENV)
+        .ok()
+        .map(|value| {
+            value
+                .parse::<u64>()
+                .unwrap_or_else(|_| panic!("{CHECKPOINT_INTERVAL_ENV} must be a number"))
+        })
+        .unwrap_or(0);
+
+    let resume = std::env::var_os(RESUME_ENV).is_some().then(|| {
+        let checkpoint = Checkpoint::load(&checkpoint_path)
+            .unwrap_or_else(|error| panic!("Failed to load checkpoint: {}", error));
+        if checkpoint.trace_format != format.as_str() {
+            panic!(
+                "Checkpoint was written for a {} trace, not {}",
+                checkpoint.trace_format,
+                format.as_str()
+            );
+        }
+        let prefix = TracePrefix::open(trace_path, format, checkpoint.trace_offset)
+            .unwrap_or_else(|error| panic!("Failed to open trace to resume: {}", error));
+        Resume {
+            checkpoint,
+            prefix,
+            handed_out: None,
+        }
+    });
+    let offset = resume.as_ref().map(|resume| resume.checkpoint.trace_offset);
+
+    *CHECKPOINTER.lock().expect("Checkpointer mutex poisoned") = Some(Checkpointer {
+        format,
+        output: (interval > 0).then_some((checkpoint_path, interval)),
+        trace_event_count: 0,
+        tile_exec_count: 0,
+        resume,
+    });
+    offset
+}
+
+/// While fast-forwarding a resumed run, check `event` against the trace
+/// written before the checkpoint. Returns `true` if the event is already in
+/// the trace and must not be published again.
+pub(crate) fn replay_event(event: &TraceEvent) -> bool {
+    CHECKPOINTER
+        .lock()
+        .expect("Checkpointer mutex poisoned")
+        .as_mut()
+        .is_some_and(|checkpointer| checkpointer.replay_event(event))
+}
+
+impl Checkpointer {
+    /// See [`replay_event`]. The replay starts at the first event of the
+    /// trace, not at the checkpoint.
+    fn replay_event(&mut self, event: &TraceEvent) -> bool {
+        let Some(resume) = self.resume.as_mut() else {
+            return false;
+        };
+
+        let index = self.trace_event_count;
+        let expected = resume
+            .prefix
+            .next_frame()
+            .unwrap_or_else(|error| panic!("Failed to read trace to resume: {}", error))
+            .unwrap_or_else(|| {
+                panic!(
+                    "Resumed run diverged from checkpoint: trace ends at event {} before the \
+                     checkpoint at event {}",
+                    index, resume.checkpoint.trace_event_count
+                )
+            });
+        if encode_event(self.format, event) != expected {
+            panic!(
+                "Resumed run diverged from checkpoint: trace event {} differs from the \
+                 interrupted run",
+                index
+            );
+        }
+
+        self.trace_event_count += 1;
+        if is_tile_execution(event) {
+            self.tile_exec_count += 1;
+        }
+        if self.trace_event_count == resume.checkpoint.trace_event_count {
+            let state = crate::internal_storage::execution_state_checkpoint();
+            if state != resume.checkpoint.state {
+                panic!(
+                    "Resumed run diverged from checkpoint: execution state at trace event {} \
+                     differs from the interrupted run",
+                    index
+                );
+            }
+            self.resume = None;
+        }
+        true
+    }
+}
+
+/// Count a published event and write a checkpoint when due.
+pub(crate) fn record_published_event(publisher: &dyn Publisher, tile_execution: bool) {
+    let mut guard = CHECKPOINTER.lock().expect("Checkpointer mutex poisoned");
+    let Some(checkpointer) = guard.as_mut() else {
+        return;
+    };
+
+    checkpointer.trace_event_count += 1;
+    if !tile_execution {
+        return;
+    }
+    checkpointer.tile_exec_count += 1;
+
+    let Some((path, interval)) = &checkpointer.output else {
+        return;
+    };
+    if checkpointer.tile_exec_count % interval != 0 {
+        return;
+    }
+    let Some(trace_offset) = publisher.flush() else {
+        return;
+    };
+    let checkpoint = Checkpoint {
+        version: CHECKPOINT_VERSION,
+        trace_format: checkpointer.format.as_str().to_string(),
+        trace_event_count: checkpointer.trace_event_count,
+        trace_offset,
+        tile_exec_count: checkpointer.tile_exec_count,
+        state: crate::internal_storage::execution_state_checkpoint(),
+    };
+    checkpoint
+        .save(path)
+        .unwrap_or_else(|error| panic!("Failed to write checkpoint: {}", error));
+}
+
+/// Output of the execution of tile `fn_name` in `module_path` at
+/// `coordinates` recorded before the checkpoint of a resumed run, or `None`
+/// if the tile has to run.
+pub fn replayed_tile_output<T: DeserializeOwned>(
+    fn_name: &str,
+    module_path: &str,
+    coordinates: &CfsCoordinates,
+) -> Option<T> {
+    let _pause = crate::limits::pause_memory_limits();
+    let mut guard = CHECKPOINTER.lock().expect("Checkpointer mutex poisoned");
+    let checkpointer = guard.as_mut()?;
+    let resume = checkpointer.resume.as_mut()?;
+    let (offset, bytes) = resume.prefix.next_tile_output(fn_name, module_path)?;
+    let output = raster_core::postcard::from_bytes(&bytes).ok()?;
+    resume.handed_out = Some((
+        checkpointer.trace_event_count + offset,
+        coordinates.clone(),
+    ));
+    Some(output)
+}
+
+/// Number of the next `limit` executions of tile `fn_name` in `module_path`
+/// whose outputs a resumed run will take from the trace, in a row. A map site
+/// runs no body for that many leading items.
+pub fn replayed_tile_output_count(fn_name: &str, module_path: &str, limit: usize) -> usize {
+    let _pause = crate::limits::pause_memory_limits();
+    let mut guard = CHECKPOINTER.lock().expect("Checkpointer mutex poisoned");
+    let Some(resume) = guard
+        .as_mut()
+        .and_then(|checkpointer| checkpointer.resume.as_mut())
+    else {
+        return 0;
+    };
+    resume
+        .prefix
+        .count_tile_outputs(fn_name, module_path, limit)
+}
+
+/// Check that a resumed run reached its checkpoint and remove the checkpoint
+/// of a run that completed.
+pub(crate) fn finish() {
+    let checkpointer = CHECKPOINTER
+        .lock()
+        .expect("Checkpointer mutex poisoned")
+        .take();
+    let Some(checkpointer) = checkpointer else {
+        return;
+    };
+    if let Some(resume) = checkpointer.resume {
+        panic!(
+            "Resumed run diverged from checkpoint: program finished after {} trace events, \
+             before the checkpoint at event {}",
+            checkpointer.trace_event_count, resume.checkpoint.trace_event_count
+        );
+    }
+    if let Some((path, _)) = checkpointer.output {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracing::publishers::{BinaryTraceEventPublisher, JsonTraceEventPublisher};
+    use raster_core::trace::{FnCallRecord, FnOutput};
+
+    fn tile_event(fn_name: &str, output: u64) -> TraceEvent {
+        TraceEvent::TileExec(FnCallRecord {
+            fn_name: fn_name.into(),
+            module_path: "app::tiles".into(),
+            input: None,
+            output: Some(FnOutput::new(
+                raster_core::postcard::to_allocvec(&output).unwrap(),
+                "u64",
+            )),
+            draft_transition_witness: None,
+        })
+    }
+
+    fn sequence_event() -> TraceEvent {
+        TraceEvent::SequenceStart(FnCallRecord {
+            fn_name: "main".into(),
+            module_path: String::new(),
+            input: None,
+            output: None,
+            draft_transition_witness: None,
+        })
+    }
+
+    fn write_trace(format: TraceFormat, path: &Path, resume_at: Option<u64>) -> u64 {
+        let publisher: Box<dyn Publisher> = match (format, resume_at) {
+            (TraceFormat::Binary, None) => {
+                Box::new(BinaryTraceEventPublisher::from_path(path.to_path_buf()).unwrap())
+            }
+            (TraceFormat::Binary, Some(offset)) => Box::new(
+                BinaryTraceEventPublisher::resume_from_path(path.to_path_buf(), offset).unwrap(),
+            ),
+            (TraceFormat::Json, None) => {
+                Box::new(JsonTraceEventPublisher::from_path(path.to_path_buf()).unwrap())
+            }
+            (TraceFormat::Json, Some(offset)) => Box::new(
+                JsonTraceEventPublisher::resume_from_path(path.to_path_buf(), offset).unwrap(),
+            ),
+        };
+        if resume_at.is_none() {
+            publisher.publish(sequence_event());
+            publisher.publish(tile_event("double", 4));
+        }
+        let offset = publisher.flush().unwrap();
+        publisher.publish(tile_event("square", 16));
+        publisher.finish();
+        offset
+    }
+
+    #[test]
+    fn resumed_publisher_appends_after_the_checkpoint_offset() {
+        for format in [TraceFormat::Binary, TraceFormat::Json] {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("trace");
+            let offset = write_trace(format, &path, None);
+            let complete = fs::read(&path).unwrap();
+
+            // The interrupted run may have written past the checkpoint.
+            write_trace(format, &path, Some(offset));
+            assert_eq!(fs::read(&path).unwrap(), complete);
+        }
+    }
+
+    #[test]
+    fn trace_prefix_yields_frames_and_tile_outputs_up_to_the_offset() {
+        for format in [TraceFormat::Binary, TraceFormat::Json] {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("trace");
+            let offset = write_trace(format, &path, None);
+
+            let mut prefix = TracePrefix::open(&path, format, offset).unwrap();
+            assert_eq!(prefix.next_tile_output("square", "app::tiles"), None);
+            assert_eq!(prefix.next_tile_output("double", "other::tiles"), None);
+            let (offset, output) = prefix.next_tile_output("double", "app::tiles").unwrap();
+            assert_eq!(offset, 1);
+            assert_eq!(
+                raster_core::postcard::from_bytes::<u64>(&output).unwrap(),
+                4
+            );
+
+            assert_eq!(prefix.count_tile_outputs("square", "app::tiles", 4), 0);
+            assert_eq!(prefix.count_tile_outputs("double", "app::tiles", 4), 1);
+            assert_eq!(prefix.count_tile_outputs("double", "app::tiles", 0), 0);
+
+            let first = prefix.next_frame().unwrap().unwrap();
+            assert_eq!(first, encode_event(format, &sequence_event()));
+            let second = prefix.next_frame().unwrap().unwrap();
+            assert_eq!(second, encode_event(format, &tile_event("double", 4)));
+            assert_eq!(prefix.next_frame().unwrap(), None);
+        }
+    }
+
+    /// A checkpointer resuming the trace at `path` from its checkpoint after
+    /// the first two events.
+    fn resuming_checkpointer(format: TraceFormat, path: &Path, offset: u64) -> Checkpointer {
+        let checkpoint = Checkpoint {
+            version: CHECKPOINT_VERSION,
+            trace_format: format.as_str().to_string(),
+            trace_event_count: 2,
+            trace_offset: offset,
+            tile_exec_count: 1,
+            state: crate::internal_storage::execution_state_checkpoint(),
+        };
+        Checkpointer {
+            format,
+            output: None,
+            trace_event_count: 0,
+            tile_exec_count: 0,
+            resume: Some(Resume {
+                checkpoint,
+                prefix: TracePrefix::open(path, format, offset).unwrap(),
+                handed_out: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn resume_replays_the_trace_from_its_start() {
+        for format in [TraceFormat::Binary, TraceFormat::Json] {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("trace");
+            let offset = write_trace(format, &path, None);
+
+            // Events before the checkpoint are replayed, not written again,
+            // and the first event published after it is new.
+            let mut checkpointer = resuming_checkpointer(format, &path, offset);
+            assert!(checkpointer.replay_event(&sequence_event()));
+            assert!(checkpointer.resume.is_some());
+            assert!(checkpointer.replay_event(&tile_event("double", 4)));
+            assert!(checkpointer.resume.is_none());
+            assert!(!checkpointer.replay_event(&tile_event("square", 16)));
+            assert_eq!(checkpointer.trace_event_count, 2);
+
+            // A run cannot pick up at the checkpointed event: the replay
+            // expects the trace's first event.
+            let mut checkpointer = resuming_checkpointer(format, &path, offset);
+            let skipped_prefix = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                checkpointer.replay_event(&tile_event("double", 4))
+            }));
+            assert!(skipped_prefix.is_err());
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Tile failures as trace events.
+//!
+//! The `#[tile]` wrapper runs the tile body (and its own output handling)
+//! under [`catch_tile_failure`]. A panic is turned into a
+//! [`TraceEvent::TileFailed`] carrying the tile's input, which is published
+//! and flushed before the panic resumes, so the trace of a failed run ends
+//! with the failing step instead of being cut off.
+
+use std::any::Any;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+
+use raster_core::trace::{
+    failure_message_hash, panic_payload_message, FnCallRecord, FnInput, TileFailure,
+    TileFailureKind, TraceEvent,
+};
+
+use super::publishers::GLOBAL_PUBLISHER;
+
+std::thread_local! {
+    /// Set while a published failure unwinds through the tiles enclosing the
+    /// one that failed.
+    static FAILURE_PUBLISHED: Cell<bool> = const { Cell::new(false) };
+    /// Number of [`catch_tile_failure`] calls running on this thread.
+    static CATCH_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A panic caught while running a tile.
+pub struct CaughtTileFailure {
+    pub(crate) kind: TileFailureKind,
+    pub(crate) payload: Box<dyn Any + Send>,
+}
+
+impl CaughtTileFailure {
+    /// A failure that did not come from a panic.
+    pub(crate) fn new(kind: TileFailureKind, message: String) -> Self {
+        Self {
+            kind,
+            payload: Box::new(message),
+        }
+    }
+
+    pub fn kind(&self) -> TileFailureKind {
+        self.kind
+    }
+
+    /// The failure message recorded for the panic payload.
+    pub fn message(&self) -> String {
+        panic_payload_message(self.payload.as_ref())
+    }
+
+    /// Publish the failure of `fn_name` on `input` and resume the panic.
+    ///
+    /// Only the innermost failing tile is published: a tile that fails
+    /// because a tile it called failed resumes the panic without publishing.
+    /// Once the outermost tile resumes the panic the thread is clear again,
+    /// so a later failure on it, after the panic was caught, is published.
+    pub fn publish(self, fn_name: &str, module_path: &str, input: Option<FnInput>) -> ! {
+        let outermost = CATCH_DEPTH.with(Cell::get) == 0;
+        if !FAILURE_PUBLISHED.with(|published| published.replace(!outermost)) {
+            publish_failure(
+                self.kind,
+                &self.message(),
+                fn_name,
+                module_path,
+                input,
+                false,
+            );
+        }
+        panic::resume_unwind(self.payload)
+    }
+}
+
+/// Publish a `TileFailed` event and flush the trace.
+///
+/// `recur_iteration` marks the failure as a recur iteration even when the
+/// calling thread is not inside a recur site, for failures published on
+/// behalf of another thread.
+pub(crate) fn publish_failure(
+    kind: TileFailureKind,
+    message: &str,
+    fn_name: &str,
+    module_path: &str,
+    input: Option<FnInput>,
+    recur_iteration: bool,
+) {
+    let failure = TileFailure {
+        kind,
+        message_hash: failure_message_hash(message),
+        recur_iteration,
+    };
+    super::publish_trace_event(TraceEvent::TileFailed(
+        FnCallRecord {
+            fn_name: fn_name.to_string(),
+            module_path: module_path.to_string(),
+            input,
+            output: None,
+            draft_transition_witness: None,
+        },
+        failure,
+    ));
+    if let Some(publisher) = GLOBAL_PUBLISHER.get() {
+        publisher.flush();
+    }
+}
+
+/// Run `f`, catching a panic as a failure of kind `kind`.
+pub fn catch_tile_failure<T>(
+    kind: TileFailureKind,
+    f: impl FnOnce() -> T,
+) -> Result<T, CaughtTileFailure> {
+    CATCH_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCH_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    result.map_err(|payload| CaughtTileFailure { kind, payload })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caught_failure_keeps_kind_and_message() {
+        let failure = catch_tile_failure(TileFailureKind::Panic, || -> u32 {
+            panic!("division by {}", 0)
+        })
+        .expect_err("the closure panics");
+        assert_eq!(failure.kind(), TileFailureKind::Panic);
+        assert_eq!(failure.message(), "division by 0");
+
+        let value = catch_tile_failure(TileFailureKind::RuntimeError, || 7).ok();
+        assert_eq!(value, Some(7));
+    }
+
+    #[test]
+    fn non_string_payloads_are_recorded_by_type_and_value() {
+        let failure = catch_tile_failure(TileFailureKind::Panic, || -> u32 {
+            panic::panic_any(7_i32)
+        })
+        .expect_err("the closure panics");
+        assert_eq!(failure.message(), "i32(7)");
+
+        let failure = catch_tile_failure(TileFailureKind::Panic, || -> u32 {
+            panic::panic_any(vec![1_u8])
+        })
+        .expect_err("the closure panics");
+        assert_eq!(failure.message(), "Box<dyn Any>");
+    }
+
+    #[test]
+    fn failures_on_a_thread_are_published_after_an_earlier_one_was_caught() {
+        let outer = catch_tile_failure(TileFailureKind::Panic, || {
+            let inner = catch_tile_failure(TileFailureKind::Panic, || -> u32 { panic!("inner") })
+                .expect_err("the inner tile panics");
+            inner.publish("inner", module_path!(), None)
+        })
+        .expect_err("the panic resumes through the outer tile");
+        assert!(FAILURE_PUBLISHED.with(Cell::get));
+
+        let caught = panic::catch_unwind(AssertUnwindSafe(|| {
+            outer.publish("outer", module_path!(), None)
+        }));
+        assert!(caught.is_err());
+        assert!(!FAILURE_PUBLISHED.with(Cell::get));
+    }
+}
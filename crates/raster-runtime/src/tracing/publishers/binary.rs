@@ -1,7 +1,7 @@
 use raster_core::trace::TraceEvent;
 
-use std::fs::{self, File};
-use std::io::{BufWriter, Write};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 use std::sync::mpsc::{self, SyncSender};
 use std::sync::Mutex;
@@ -11,6 +11,8 @@ use super::Publisher;
 
 enum TraceWriterMessage {
     Event(TraceEvent),
+    /// Flush buffered frames and report the length of the trace file.
+    Flush(SyncSender<std::io::Result<u64>>),
     Shutdown,
 }
 
@@ -26,9 +28,23 @@ impl BinaryTraceEventPublisher {
         }
 
         let file = File::create(path)?;
+        Ok(Self::from_file(file, 0))
+    }
+
+    /// Continue the trace at `path` after its first `offset` bytes, dropping
+    /// anything written past them.
+    pub fn resume_from_path(path: PathBuf, offset: u64) -> std::io::Result<Self> {
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        file.set_len(offset)?;
+        file.seek(SeekFrom::End(0))?;
+        Ok(Self::from_file(file, offset))
+    }
+
+    fn from_file(file: File, offset: u64) -> Self {
         let (sender, receiver) = mpsc::sync_channel(4096);
         let join_handle = std::thread::spawn(move || -> std::io::Result<()> {
             let mut writer = BufWriter::new(file);
+            let mut written = offset;
             while let Ok(message) = receiver.recv() {
                 match message {
                     TraceWriterMessage::Event(event) => {
@@ -42,6 +58,10 @@ impl BinaryTraceEventPublisher {
                         })?;
                         writer.write_all(&len.to_le_bytes())?;
                         writer.write_all(&bytes)?;
+                        written += 4 + bytes.len() as u64;
+                    }
+                    TraceWriterMessage::Flush(reply) => {
+                        let _ = reply.send(writer.flush().map(|()| written));
                     }
                     TraceWriterMessage::Shutdown => break,
                 }
@@ -50,10 +70,10 @@ impl BinaryTraceEventPublisher {
             Ok(())
         });
 
-        Ok(Self {
+        Self {
             sender: Mutex::new(Some(sender)),
             join_handle: Mutex::new(Some(join_handle)),
-        })
+        }
     }
 }
 
@@ -71,6 +91,25 @@ impl Publisher for BinaryTraceEventPublisher {
             .unwrap_or_else(|error| panic!("Failed to queue trace event: {}", error));
     }
 
+    fn flush(&self) -> Option<u64> {
+        let (reply, response) = mpsc::sync_channel(1);
+        {
+            let sender = self
+                .sender
+                .lock()
+                .expect("Trace writer sender mutex poisoned");
+            sender
+                .as_ref()?
+                .send(TraceWriterMessage::Flush(reply))
+                .unwrap_or_else(|error| panic!("Failed to flush trace writer: {}", error));
+        }
+        let written = response
+            .recv()
+            .unwrap_or_else(|error| panic!("Trace writer stopped while flushing: {}", error))
+            .unwrap_or_else(|error| panic!("Failed to flush binary trace writer: {}", error));
+        Some(written)
+    }
+
     fn finish(&self) {
         let sender = self
             .sender
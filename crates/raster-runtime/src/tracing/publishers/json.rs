@@ -1,20 +1,25 @@
 use raster_core::trace::TraceEvent;
 
-use std::fs::{self, File};
-use std::io::{BufWriter, Write};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 use std::sync::Mutex;
 
 use super::Publisher;
 
 pub struct JsonTraceEventPublisher<W: Write + Send> {
-    writer: Mutex<W>,
+    /// The writer and the number of bytes written through it.
+    writer: Mutex<(W, u64)>,
 }
 
 impl<W: Write + Send> JsonTraceEventPublisher<W> {
     pub fn new(writer: W) -> Self {
+        Self::with_offset(writer, 0)
+    }
+
+    fn with_offset(writer: W, offset: u64) -> Self {
         Self {
-            writer: Mutex::new(writer),
+            writer: Mutex::new((writer, offset)),
         }
     }
 }
@@ -28,18 +33,36 @@ impl JsonTraceEventPublisher<BufWriter<File>> {
         let file = File::create(path)?;
         Ok(Self::new(BufWriter::new(file)))
     }
+
+    /// Continue the trace at `path` after its first `offset` bytes, dropping
+    /// anything written past them.
+    pub fn resume_from_path(path: PathBuf, offset: u64) -> std::io::Result<Self> {
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        file.set_len(offset)?;
+        file.seek(SeekFrom::End(0))?;
+        Ok(Self::with_offset(BufWriter::new(file), offset))
+    }
 }
 
 impl<W: Write + Send + Sync> Publisher for JsonTraceEventPublisher<W> {
     fn publish(&self, event: TraceEvent) {
         let json_str = serde_json::to_string(&event).expect("Failed to serialize trace event");
         let mut writer_guard = self.writer.lock().expect("Writer mutex poisoned");
-        writeln!(writer_guard, "{json_str}").expect("Failed to write trace event");
+        let (writer, written) = &mut *writer_guard;
+        writeln!(writer, "{json_str}").expect("Failed to write trace event");
+        *written += json_str.len() as u64 + 1;
+    }
+
+    fn flush(&self) -> Option<u64> {
+        let mut writer_guard = self.writer.lock().expect("Writer mutex poisoned");
+        let (writer, written) = &mut *writer_guard;
+        writer.flush().expect("Failed to flush writer");
+        Some(*written)
     }
 
     fn finish(&self) {
         let mut writer_guard = self.writer.lock().expect("Writer mutex poisoned");
-        writer_guard.flush().expect("Failed to flush writer");
+        writer_guard.0.flush().expect("Failed to flush writer");
     }
 }
 
@@ -66,6 +89,7 @@ mod tests {
     fn test_event() -> TraceEvent {
         TraceEvent::SequenceStart(FnCallRecord {
             fn_name: "main".into(),
+            module_path: String::new(),
             input: None,
             output: None,
             draft_transition_witness: None,
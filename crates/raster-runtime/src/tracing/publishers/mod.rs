@@ -14,5 +14,11 @@ pub(crate) static GLOBAL_PUBLISHER: OnceLock<Box<dyn Publisher>> = OnceLock::new
 pub trait Publisher: Send + Sync {
     fn publish(&self, event: TraceEvent);
 
+    /// Flush published events to the trace file and return its length in
+    /// bytes, or `None` if the publisher cannot be resumed from a checkpoint.
+    fn flush(&self) -> Option<u64> {
+        None
+    }
+
     fn finish(&self);
 }
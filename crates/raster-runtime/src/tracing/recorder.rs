@@ -1,21 +1,165 @@
+use raster_core::branching::{select_arm, BranchViolation};
 use raster_core::cfs::{
     CfsCoordinates, CfsCursor, ControlFlowSchema, SequenceChildId, SequenceChildItem,
 };
+use raster_core::chunking::ChunkViolation;
 use raster_core::draft::DraftTransitionWitness;
 use raster_core::input::{InternalRef, SelectionWitness, SelectorPath};
 use raster_core::trace::{
-    ExternalInput, FnInput, InternalInput, RecurSequenceExecRecord, RecurTileExecRecord,
-    SequenceEndRecord, SequenceStartRecord, StepRecord, TileExecRecord, TraceEvent,
+    ExternalInput, FnCallRecord, FnInput, InternalInput, RecurSequenceExecRecord,
+    RecurTileExecRecord, SequenceEndRecord, SequenceStartRecord, StepRecord, TileExecRecord,
+    TileFailedRecord, TraceEvent,
 };
 use sha2::{Digest, Sha256};
 
 use std::collections::{HashMap, VecDeque};
+use std::fmt;
 
 use crate::internal_storage::{InternalStorageManager, InternalWriteRecord};
 use crate::tracing::commitment::Sha256Commitment;
 
 pub type SequenceId = String;
 
+/// A trace event that does not follow the CFS.
+///
+/// Returned by [`TraceRecorder::record`]; the recorder is left mid-event and
+/// must not record further events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecorderViolation {
+    /// Sequence the event ran in; empty when it ran outside any sequence.
+    pub sequence_id: SequenceId,
+    /// Function named by the event.
+    pub step: String,
+    /// Coordinates of the step, or of its enclosing sequence when the CFS has
+    /// no place for it.
+    pub coordinates: CfsCoordinates,
+    pub kind: RecorderViolationKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecorderViolationKind {
+    /// The sequence has no item for the step at this point; `expected` lists
+    /// the items it can still run.
+    UnexpectedStep { expected: Vec<SequenceChildId> },
+    /// A tile or recur site ran outside of any sequence.
+    NoSequence,
+    /// The step breaks the branch structure of its sequence.
+    Branch(BranchViolation),
+    /// A recur iteration breaks the declared chunk size of its site.
+    Chunk(ChunkViolation),
+    /// A sequence ended that is not the innermost started one.
+    UnmatchedEnd,
+    /// Another step ran while the recur site `site_id` was still iterating.
+    InterruptedRecur { site_id: String },
+    /// The event lacks the input the CFS rules are checked against.
+    MissingInput,
+    /// The step was recorded after a tile failed, which ends the trace.
+    AfterFailure,
+    /// The step's output could not be written to the internal store.
+    InternalStore(String),
+}
+
+impl RecorderViolation {
+    fn new(
+        sequence_id: &str,
+        step: &str,
+        coordinates: &CfsCoordinates,
+        kind: RecorderViolationKind,
+    ) -> Self {
+        Self {
+            sequence_id: sequence_id.to_string(),
+            step: step.to_string(),
+            coordinates: coordinates.clone(),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for RecorderViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            RecorderViolationKind::UnexpectedStep { expected } => {
+                write!(f, "`{}` is not expected here", self.step)?;
+                if expected.is_empty() {
+                    write!(f, "; the sequence has no items left")
+                } else {
+                    let expected: Vec<String> = expected.iter().map(|id| id.to_string()).collect();
+                    write!(f, "; expected one of: {}", expected.join(", "))
+                }
+            }
+            RecorderViolationKind::NoSequence => {
+                write!(f, "`{}` ran outside of any sequence", self.step)
+            }
+            RecorderViolationKind::Branch(violation) => {
+                write!(f, "branch violation: {}", violation)
+            }
+            RecorderViolationKind::Chunk(violation) => {
+                write!(
+                    f,
+                    "recur chunking violation for `{}`: {}",
+                    self.step, violation
+                )
+            }
+            RecorderViolationKind::UnmatchedEnd => {
+                write!(
+                    f,
+                    "end of sequence `{}` without a matching start",
+                    self.step
+                )
+            }
+            RecorderViolationKind::InterruptedRecur { site_id } => write!(
+                f,
+                "`{}` ran before recur site `{}` completed",
+                self.step, site_id
+            ),
+            RecorderViolationKind::MissingInput => {
+                write!(f, "`{}` was recorded without its input", self.step)
+            }
+            RecorderViolationKind::AfterFailure => {
+                write!(f, "`{}` ran after a tile failed", self.step)
+            }
+            RecorderViolationKind::InternalStore(error) => {
+                write!(
+                    f,
+                    "output of `{}` could not be stored: {}",
+                    self.step, error
+                )
+            }
+        }?;
+        write!(f, " (at {:?}", self.coordinates)?;
+        if !self.sequence_id.is_empty() {
+            write!(f, " in sequence `{}`", self.sequence_id)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl std::error::Error for RecorderViolation {}
+
+/// Coordinates of `child_id` in the sequence at `parent_coordinates`, at or
+/// after its current item.
+fn child_coordinates(
+    cfs_cursor: &CfsCursor,
+    sequence_id: &str,
+    parent_coordinates: &CfsCoordinates,
+    parent_current_index: u32,
+    child_id: SequenceChildId,
+) -> Result<CfsCoordinates, RecorderViolation> {
+    cfs_cursor
+        .try_get_child_coordinates(parent_coordinates, parent_current_index, &child_id)
+        .ok_or_else(|| {
+            RecorderViolation::new(
+                sequence_id,
+                child_id.id(),
+                parent_coordinates,
+                RecorderViolationKind::UnexpectedStep {
+                    expected: cfs_cursor
+                        .remaining_children(parent_coordinates, parent_current_index),
+                },
+            )
+        })
+}
+
 #[derive(Debug, Clone)]
 pub struct SequenceCallstack {
     callstack: VecDeque<SequenceState>,
@@ -27,11 +171,77 @@ pub struct SequenceState {
     id: SequenceId,
     current_index: u32,
     parent_coordinates: CfsCoordinates,
+    /// Branch arms selected so far and not yet completed, innermost last.
+    taken_arms: Vec<TakenBranchArm>,
+}
+
+#[derive(Debug, Clone)]
+struct TakenBranchArm {
+    arm_index: usize,
+    /// Item index one past the arm.
+    arm_end: usize,
+    /// Item index one past the whole branch.
+    branch_end: usize,
+}
+
+impl SequenceState {
+    fn new(id: SequenceId, parent_coordinates: CfsCoordinates) -> Self {
+        Self {
+            id,
+            current_index: 0,
+            parent_coordinates,
+            taken_arms: Vec::new(),
+        }
+    }
+
+    /// Enter the arm selected by a branch condition.
+    fn take_branch_arm(&mut self, arm_start: usize, arm: TakenBranchArm) {
+        self.current_index = arm_start.try_into().expect("Index too large");
+        self.taken_arms.push(arm);
+        self.leave_completed_branch_arms();
+    }
+
+    /// Jump past the remaining (untaken) arms of every branch whose taken arm
+    /// has run to completion.
+    fn leave_completed_branch_arms(&mut self) {
+        while let Some(arm) = self.taken_arms.last() {
+            if (self.current_index as usize) < arm.arm_end {
+                break;
+            }
+            self.current_index = arm.branch_end.try_into().expect("Index too large");
+            self.taken_arms.pop();
+        }
+    }
+
+    /// Reject the `step` item at `coordinates` if it lies outside the
+    /// innermost taken arm.
+    fn check_within_taken_arm(
+        &self,
+        coordinates: &CfsCoordinates,
+        step: &str,
+    ) -> Result<(), RecorderViolation> {
+        let (Some(arm), Some(&item_index)) = (self.taken_arms.last(), coordinates.last()) else {
+            return Ok(());
+        };
+        if item_index as usize >= arm.arm_end {
+            return Err(RecorderViolation::new(
+                &self.id,
+                step,
+                coordinates,
+                RecorderViolationKind::Branch(BranchViolation::OutsideTakenArm {
+                    arm_index: arm.arm_index,
+                    item_index: item_index as usize,
+                }),
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
 struct RecurExecutionState {
     site_id: String,
+    sequence_id: SequenceId,
     sequence_coordinates: CfsCoordinates,
     site_coordinates: CfsCoordinates,
     intra_sequence_index: u32,
@@ -43,6 +253,56 @@ struct RecurExecutionState {
     last_chunk_len: Option<u64>,
 }
 
+impl RecurExecutionState {
+    /// The violation of `step` running before this site completed.
+    fn interrupted_by(&self, step: &str) -> RecorderViolation {
+        RecorderViolation::new(
+            &self.sequence_id,
+            step,
+            &self.site_coordinates,
+            RecorderViolationKind::InterruptedRecur {
+                site_id: self.site_id.clone(),
+            },
+        )
+    }
+
+    /// Reject `step` unless it continues this site from `sequence_coordinates`.
+    fn check_continued_by(
+        &self,
+        step: &str,
+        sequence_coordinates: &CfsCoordinates,
+    ) -> Result<(), RecorderViolation> {
+        if self.site_id != step || &self.sequence_coordinates != sequence_coordinates {
+            return Err(self.interrupted_by(step));
+        }
+        Ok(())
+    }
+
+    /// The violation of an iteration of this site breaking its chunk size.
+    fn chunk_violation(
+        &self,
+        coordinates: &CfsCoordinates,
+        violation: ChunkViolation,
+    ) -> RecorderViolation {
+        RecorderViolation::new(
+            &self.sequence_id,
+            &self.site_id,
+            coordinates,
+            RecorderViolationKind::Chunk(violation),
+        )
+    }
+}
+
+/// The violation of `step` running outside of any sequence.
+fn outside_sequence(step: &str) -> RecorderViolation {
+    RecorderViolation::new(
+        "",
+        step,
+        &CfsCoordinates(vec![]),
+        RecorderViolationKind::NoSequence,
+    )
+}
+
 impl SequenceCallstack {
     fn new() -> Self {
         SequenceCallstack {
@@ -51,47 +311,63 @@ impl SequenceCallstack {
         }
     }
 
-    fn push(&mut self, sequence_id: SequenceId, cfs_cursor: &CfsCursor) {
-        let parent_current_index = self
+    fn push(
+        &mut self,
+        sequence_id: SequenceId,
+        cfs_cursor: &CfsCursor,
+    ) -> Result<(), RecorderViolation> {
+        let (parent_id, parent_current_index) = self
             .callstack
             .back()
-            .map(|p| p.current_index.try_into().expect("Index too large"))
-            .unwrap_or(0);
+            .map(|p| (p.id.as_str(), p.current_index))
+            .unwrap_or(("", 0));
 
         let parent_sequence_coords = self.current_sequence_coordinates.clone();
 
-        self.current_sequence_coordinates = cfs_cursor.get_child_coordinates(
+        let coordinates = child_coordinates(
+            cfs_cursor,
+            parent_id,
             &parent_sequence_coords,
             parent_current_index,
             SequenceChildId::Sequence(sequence_id.clone()),
-        );
+        )?;
 
         if let Some(parent) = self.callstack.back_mut() {
+            parent.check_within_taken_arm(&coordinates, &sequence_id)?;
             parent.current_index += 1;
+            parent.leave_completed_branch_arms();
         }
+        self.current_sequence_coordinates = coordinates;
 
-        let sequence_execution_state = SequenceState {
-            id: sequence_id,
-            current_index: 0,
-            parent_coordinates: parent_sequence_coords,
-        };
+        let sequence_execution_state = SequenceState::new(sequence_id, parent_sequence_coords);
         self.callstack.push_back(sequence_execution_state);
+        Ok(())
     }
 
-    fn pop(&mut self) -> Option<SequenceState> {
-        let popped = self.callstack.pop_back()?;
-        self.current_sequence_coordinates = popped.parent_coordinates.clone();
-        Some(popped)
+    /// Leave the innermost sequence, which must be `sequence_id`.
+    fn pop(&mut self, sequence_id: &str) -> Result<SequenceState, RecorderViolation> {
+        match self.callstack.pop_back() {
+            Some(popped) if popped.id == sequence_id => {
+                self.current_sequence_coordinates = popped.parent_coordinates.clone();
+                Ok(popped)
+            }
+            unmatched => {
+                let innermost = unmatched.map(|state| state.id).unwrap_or_default();
+                Err(RecorderViolation::new(
+                    &innermost,
+                    sequence_id,
+                    &self.current_sequence_coordinates,
+                    RecorderViolationKind::UnmatchedEnd,
+                ))
+            }
+        }
     }
 
     fn push_at_coordinates(&mut self, sequence_id: SequenceId, coordinates: CfsCoordinates) {
         let parent_coordinates = self.current_sequence_coordinates.clone();
         self.current_sequence_coordinates = coordinates;
-        self.callstack.push_back(SequenceState {
-            id: sequence_id,
-            current_index: 0,
-            parent_coordinates,
-        });
+        self.callstack
+            .push_back(SequenceState::new(sequence_id, parent_coordinates));
     }
 
     fn last_mut(&mut self) -> Option<&mut SequenceState> {
@@ -153,7 +429,7 @@ impl StepWitnessStore {
         coordinates: CfsCoordinates,
         event: TraceEvent,
         internal_write: Option<InternalWriteRecord>,
-    ) {
+    ) -> Result<(), RecorderViolation> {
         match event {
             TraceEvent::SequenceStart(trace_item) | TraceEvent::RecurSequenceStart(trace_item) => {
                 self.0.insert(
@@ -178,17 +454,19 @@ impl StepWitnessStore {
                 );
             }
             TraceEvent::SequenceEnd(trace_item) | TraceEvent::RecurSequenceEnd(trace_item) => {
-                let trace_io = self.0.get_mut(&coordinates).unwrap_or_else(|| {
-                    panic!(
-                        "Missing step witness entry for SequenceEnd at coordinates {:?}. Expected a matching SequenceStart to be recorded first.",
-                        coordinates
-                    )
-                });
+                let Some(trace_io) = self.0.get_mut(&coordinates) else {
+                    return Err(RecorderViolation::new(
+                        &trace_item.fn_name,
+                        &trace_item.fn_name,
+                        &coordinates,
+                        RecorderViolationKind::UnmatchedEnd,
+                    ));
+                };
                 trace_io.output_data = trace_item.output.as_ref().map(|output| output.data.clone());
                 trace_io.internal_write = internal_write;
                 trace_io.draft_transition_witness = trace_item.draft_transition_witness;
             }
-            TraceEvent::TileExec(trace_item) => {
+            TraceEvent::TileExec(trace_item) | TraceEvent::TileFailed(trace_item, _) => {
                 self.0.insert(
                     coordinates,
                     StepWitnessData {
@@ -241,6 +519,7 @@ impl StepWitnessStore {
                 );
             }
         }
+        Ok(())
     }
 
     pub fn get(&self, coordinates: &CfsCoordinates) -> Option<&StepWitnessData> {
@@ -266,19 +545,24 @@ pub struct TraceRecorder {
     cfs_cursor: CfsCursor,
     witness_store: StepWitnessStore,
     internal_storage: InternalStorageManager,
+    failed: bool,
 }
 
 impl TraceRecorder {
-    pub fn new(cfs: ControlFlowSchema) -> Self {
-        Self {
+    /// Create a recorder for traces of `cfs`, rejecting schemas of an
+    /// unsupported format version.
+    pub fn new(cfs: ControlFlowSchema) -> raster_core::Result<Self> {
+        cfs.check_version()?;
+        Ok(Self {
             exec_index: 0,
             sequence_callstack: SequenceCallstack::new(),
             active_recur: None,
             active_recur_sequence: HashMap::new(),
             cfs_cursor: CfsCursor::new(cfs),
             witness_store: StepWitnessStore::new(),
-            internal_storage: InternalStorageManager::new(),
-        }
+            internal_storage: InternalStorageManager::from_env()?,
+            failed: false,
+        })
     }
 
     pub fn input_data_at(&self, coordinates: &CfsCoordinates) -> Option<Option<Vec<u8>>> {
@@ -309,6 +593,18 @@ impl TraceRecorder {
         self.internal_storage.selection_witness(reference, selector)
     }
 
+    /// Raster bytes of a branch condition's output, for steps whose
+    /// coordinates are the condition of a CFS branch.
+    pub fn branch_condition_witness_at(
+        &self,
+        coordinates: &CfsCoordinates,
+    ) -> raster_core::Result<Option<Vec<u8>>> {
+        if self.cfs_cursor.try_get_branch(coordinates).is_none() {
+            return Ok(None);
+        }
+        self.internal_storage.raster_bytes_at(coordinates)
+    }
+
     pub fn io_data_at(
         &self,
         coordinates: &CfsCoordinates,
@@ -322,14 +618,34 @@ impl TraceRecorder {
         })
     }
 
-    pub fn record(&mut self, event: TraceEvent) -> StepRecord {
+    /// Place `event` in the CFS and turn it into its step record, or report
+    /// how it breaks the CFS.
+    pub fn record(&mut self, mut event: TraceEvent) -> Result<StepRecord, RecorderViolation> {
+        // Events name functions as written; the CFS may know them by their
+        // module-qualified id.
+        let fn_call_record = event.fn_call_record_mut();
+        let id = self
+            .cfs_cursor
+            .resolve_id(&fn_call_record.module_path, &fn_call_record.fn_name);
+        if id != fn_call_record.fn_name {
+            fn_call_record.fn_name = id.to_string();
+        }
+        if self.failed {
+            return Err(RecorderViolation::new(
+                "",
+                &fn_call_record.fn_name,
+                &self.sequence_callstack.current_sequence_coordinates,
+                RecorderViolationKind::AfterFailure,
+            ));
+        }
+
         self.exec_index += 1;
         let exec_index = self.exec_index;
 
         let step_record = match event.clone() {
             TraceEvent::SequenceStart(fn_call_record) => {
                 self.sequence_callstack
-                    .push(fn_call_record.fn_name.clone(), &self.cfs_cursor);
+                    .push(fn_call_record.fn_name.clone(), &self.cfs_cursor)?;
 
                 let coordinates = self.sequence_callstack.current_sequence_coordinates.clone();
 
@@ -356,24 +672,22 @@ impl TraceRecorder {
                     external_input_commitment,
                 };
 
-                self.witness_store.insert(coordinates, event.clone(), None);
+                self.witness_store
+                    .insert(coordinates, event.clone(), None)?;
 
                 StepRecord::SequenceStart(record)
             }
             TraceEvent::SequenceEnd(fn_call_record) => {
                 let sequence_coordinates =
                     self.sequence_callstack.current_sequence_coordinates.clone();
-                assert!(
-                    self.active_recur.is_none(),
-                    "Sequence ended while RecurTile site trace was still active"
-                );
-                assert!(
-                    !self
-                        .active_recur_sequence
-                        .keys()
-                        .any(|(coordinates, _)| coordinates == &sequence_coordinates),
-                    "Sequence ended while RecurSequence site trace was still active"
-                );
+                let active_site = self.active_recur.as_ref().or_else(|| {
+                    self.active_recur_sequence
+                        .values()
+                        .find(|state| state.sequence_coordinates == sequence_coordinates)
+                });
+                if let Some(active_site) = active_site {
+                    return Err(active_site.interrupted_by(&fn_call_record.fn_name));
+                }
 
                 let output = fn_call_record.output;
                 let output_commitment = output
@@ -388,37 +702,42 @@ impl TraceRecorder {
                     output_commitment,
                 };
 
-                self.sequence_callstack
-                    .pop()
-                    .expect("Corrupted sequence stack");
+                self.sequence_callstack.pop(&fn_call_record.fn_name)?;
 
-                self.witness_store.insert(sequence_coordinates, event, None);
+                self.witness_store
+                    .insert(sequence_coordinates, event, None)?;
 
                 StepRecord::SequenceEnd(record)
             }
             TraceEvent::RecurSequenceStart(fn_call_record) => {
                 let parent_sequence_coordinates =
                     self.sequence_callstack.current_sequence_coordinates.clone();
-                let parent_current_index = self
+                let parent_state = self
                     .sequence_callstack
                     .last_mut()
-                    .expect("RecurSequence can't start without sequence context")
-                    .current_index;
+                    .ok_or_else(|| outside_sequence(&fn_call_record.fn_name))?;
+                let parent_id = parent_state.id.clone();
+                let parent_current_index = parent_state.current_index;
 
                 let recur_key = (
                     parent_sequence_coordinates.clone(),
                     fn_call_record.fn_name.clone(),
                 );
                 if !self.active_recur_sequence.contains_key(&recur_key) {
-                    let site_coordinates = self.cfs_cursor.get_child_coordinates(
+                    let site_coordinates = child_coordinates(
+                        &self.cfs_cursor,
+                        &parent_id,
                         &parent_sequence_coordinates,
                         parent_current_index,
                         SequenceChildId::RecurSequence(fn_call_record.fn_name.clone()),
-                    );
+                    )?;
+                    parent_state
+                        .check_within_taken_arm(&site_coordinates, &fn_call_record.fn_name)?;
                     self.active_recur_sequence.insert(
                         recur_key.clone(),
                         RecurExecutionState {
                             site_id: fn_call_record.fn_name.clone(),
+                            sequence_id: parent_id,
                             sequence_coordinates: parent_sequence_coordinates.clone(),
                             site_coordinates,
                             intra_sequence_index: parent_current_index,
@@ -433,14 +752,6 @@ impl TraceRecorder {
                     .active_recur_sequence
                     .get_mut(&recur_key)
                     .expect("RecurSequence state should exist after insertion");
-                assert_eq!(
-                    recur_state.sequence_coordinates, parent_sequence_coordinates,
-                    "RecurSequence iteration switched parent sequence coordinates mid-stream",
-                );
-                assert_eq!(
-                    recur_state.site_id, fn_call_record.fn_name,
-                    "RecurSequence iteration switched site id mid-stream",
-                );
 
                 let mut iteration_coordinates = recur_state.site_coordinates.clone();
                 iteration_coordinates.push(recur_state.next_iteration_index);
@@ -474,15 +785,14 @@ impl TraceRecorder {
                 };
 
                 self.witness_store
-                    .insert(iteration_coordinates, event.clone(), None);
+                    .insert(iteration_coordinates, event.clone(), None)?;
 
                 StepRecord::SequenceStart(record)
             }
             TraceEvent::RecurSequenceEnd(fn_call_record) => {
-                assert!(
-                    self.active_recur.is_none(),
-                    "RecurSequence iteration ended while RecurTile trace was still active"
-                );
+                if let Some(active_recur) = &self.active_recur {
+                    return Err(active_recur.interrupted_by(&fn_call_record.fn_name));
+                }
                 let sequence_coordinates =
                     self.sequence_callstack.current_sequence_coordinates.clone();
 
@@ -499,52 +809,52 @@ impl TraceRecorder {
                     output_commitment,
                 };
 
-                self.sequence_callstack
-                    .pop()
-                    .expect("Corrupted recur sequence stack");
+                self.sequence_callstack.pop(&fn_call_record.fn_name)?;
 
                 self.witness_store
-                    .insert(sequence_coordinates, event.clone(), None);
+                    .insert(sequence_coordinates, event.clone(), None)?;
 
                 StepRecord::SequenceEnd(record)
             }
             TraceEvent::TileExec(fn_call_record) => {
-                assert!(
-                    self.active_recur.is_none(),
-                    "Ordinary tile execution cannot occur while recur iterations are active"
-                );
-                let sequence_coordinates =
-                    self.sequence_callstack.current_sequence_coordinates.clone();
+                let (sequence_id, parent_current_index, tile_coordinates) =
+                    self.place_tile(&fn_call_record.fn_name)?;
                 let current_sequence_state = self
                     .sequence_callstack
                     .last_mut()
-                    .expect("Tile can't be called without sequence context");
-
-                let sequence_id = current_sequence_state.id.clone();
-                let parent_current_index = current_sequence_state.current_index;
-
-                let mut candidate_coordinates = sequence_coordinates.clone();
-                candidate_coordinates.push(
-                    parent_current_index
-                        .try_into()
-                        .expect("Sequence coordinate out of bound u8"),
-                );
-                let child_id = match self.cfs_cursor.try_get_item(&candidate_coordinates) {
-                    Some(raster_core::cfs::SequenceChildItem::RecurTile(item))
-                        if item.id == fn_call_record.fn_name =>
-                    {
-                        SequenceChildId::RecurTile(fn_call_record.fn_name.clone())
-                    }
-                    _ => SequenceChildId::Tile(fn_call_record.fn_name.clone()),
-                };
+                    .expect("A placed tile runs in a sequence");
 
-                let tile_coordinates = self.cfs_cursor.get_child_coordinates(
-                    &sequence_coordinates,
-                    parent_current_index,
-                    child_id,
-                );
-
-                current_sequence_state.current_index += 1;
+                // A branch condition selects which of the following arms runs.
+                if let Some(branch) = self.cfs_cursor.try_get_branch(&tile_coordinates) {
+                    let raster_bytes = fn_call_record
+                        .output
+                        .as_ref()
+                        .and_then(|output| output.raster.as_ref())
+                        .map(|raster| raster.bytes.as_slice())
+                        .unwrap_or_default();
+                    let arm_index =
+                        select_arm(&branch.arms, raster_bytes).map_err(|violation| {
+                            RecorderViolation::new(
+                                &sequence_id,
+                                &fn_call_record.fn_name,
+                                &tile_coordinates,
+                                RecorderViolationKind::Branch(violation),
+                            )
+                        })?;
+                    let arm_range = branch
+                        .arm_range(arm_index)
+                        .expect("Selected arm must exist");
+                    current_sequence_state.take_branch_arm(
+                        arm_range.start,
+                        TakenBranchArm {
+                            arm_index,
+                            arm_end: arm_range.end,
+                            branch_end: branch.end(),
+                        },
+                    );
+                } else {
+                    current_sequence_state.leave_completed_branch_arms();
+                }
 
                 let input = fn_call_record.input;
                 let input_commitment = input
@@ -561,13 +871,24 @@ impl TraceRecorder {
                     .unwrap_or_default();
 
                 let output = fn_call_record.output;
-                let internal_write = output.as_ref().map(|output| {
-                    self.internal_storage.append_serialized_bytes(
-                        &output.data,
-                        tile_coordinates.clone(),
-                        output.raster.clone(),
-                    )
-                });
+                let internal_write = output
+                    .as_ref()
+                    .map(|output| {
+                        self.internal_storage.append_serialized_bytes(
+                            &output.data,
+                            tile_coordinates.clone(),
+                            output.raster.clone(),
+                        )
+                    })
+                    .transpose()
+                    .map_err(|error| {
+                        RecorderViolation::new(
+                            &sequence_id,
+                            &fn_call_record.fn_name,
+                            &tile_coordinates,
+                            RecorderViolationKind::InternalStore(error.to_string()),
+                        )
+                    })?;
                 let output_commitment = internal_write
                     .as_ref()
                     .map(|write| write.entry.object_commitment.clone())
@@ -602,87 +923,13 @@ impl TraceRecorder {
                 };
 
                 self.witness_store
-                    .insert(tile_coordinates, event.clone(), internal_write);
+                    .insert(tile_coordinates, event.clone(), internal_write)?;
 
                 StepRecord::TileExec(record)
             }
             TraceEvent::RecurTileIterationExec(fn_call_record) => {
-                let sequence_coordinates =
-                    self.sequence_callstack.current_sequence_coordinates.clone();
-                let current_sequence_state = self
-                    .sequence_callstack
-                    .last_mut()
-                    .expect("RecurTile can't be called without sequence context");
-
-                let sequence_id = current_sequence_state.id.clone();
-                let recur_state = self.active_recur.get_or_insert_with(|| {
-                    let parent_current_index = current_sequence_state.current_index;
-                    let site_coordinates = self.cfs_cursor.get_child_coordinates(
-                        &sequence_coordinates,
-                        parent_current_index,
-                        SequenceChildId::RecurTile(fn_call_record.fn_name.clone()),
-                    );
-                    let declared_chunk = match self.cfs_cursor.try_get_item(&site_coordinates) {
-                        Some(SequenceChildItem::RecurTile(item)) => item.chunk,
-                        _ => None,
-                    };
-                    RecurExecutionState {
-                        site_id: fn_call_record.fn_name.clone(),
-                        sequence_coordinates: sequence_coordinates.clone(),
-                        site_coordinates,
-                        intra_sequence_index: parent_current_index,
-                        next_iteration_index: 0,
-                        declared_chunk,
-                        last_chunk_len: None,
-                    }
-                });
-                assert_eq!(
-                    recur_state.sequence_coordinates, sequence_coordinates,
-                    "RecurTile iteration switched parent sequence coordinates mid-stream",
-                );
-                assert_eq!(
-                    recur_state.site_id, fn_call_record.fn_name,
-                    "RecurTile iteration switched site id mid-stream",
-                );
-
-                let mut tile_coordinates = recur_state.site_coordinates.clone();
-                tile_coordinates.push(recur_state.next_iteration_index);
-                recur_state.next_iteration_index += 1;
-
-                if let Some(declared) = recur_state.declared_chunk {
-                    let chunk_len = fn_call_record
-                        .input
-                        .as_ref()
-                        .map(|input| input.data())
-                        .and_then(raster_core::chunking::iteration_chunk_len)
-                        .unwrap_or_else(|| {
-                            panic!(
-                                "Recur iteration at {:?} for '{}' has no decodable chunk length",
-                                tile_coordinates, recur_state.site_id
-                            )
-                        });
-                    if let Err(violation) =
-                        raster_core::chunking::check_iteration_chunk_len(declared, chunk_len)
-                    {
-                        panic!(
-                            "Recur chunking violation at {:?} for '{}': {}",
-                            tile_coordinates, recur_state.site_id, violation
-                        );
-                    }
-                    if let Some(previous) = recur_state.last_chunk_len {
-                        if let Err(violation) =
-                            raster_core::chunking::check_previous_chunk_was_full(
-                                declared, previous,
-                            )
-                        {
-                            panic!(
-                                "Recur chunking violation at {:?} for '{}': {}",
-                                tile_coordinates, recur_state.site_id, violation
-                            );
-                        }
-                    }
-                    recur_state.last_chunk_len = Some(chunk_len);
-                }
+                let (sequence_id, intra_sequence_index, tile_coordinates) =
+                    self.place_recur_iteration(&fn_call_record)?;
 
                 let input = fn_call_record.input;
                 let input_commitment = input
@@ -699,13 +946,24 @@ impl TraceRecorder {
                     .unwrap_or_default();
 
                 let output = fn_call_record.output;
-                let internal_write = output.as_ref().map(|output| {
-                    self.internal_storage.append_serialized_bytes(
-                        &output.data,
-                        tile_coordinates.clone(),
-                        output.raster.clone(),
-                    )
-                });
+                let internal_write = output
+                    .as_ref()
+                    .map(|output| {
+                        self.internal_storage.append_serialized_bytes(
+                            &output.data,
+                            tile_coordinates.clone(),
+                            output.raster.clone(),
+                        )
+                    })
+                    .transpose()
+                    .map_err(|error| {
+                        RecorderViolation::new(
+                            &sequence_id,
+                            &fn_call_record.fn_name,
+                            &tile_coordinates,
+                            RecorderViolationKind::InternalStore(error.to_string()),
+                        )
+                    })?;
                 let output_commitment = internal_write
                     .as_ref()
                     .map(|write| write.entry.object_commitment.clone())
@@ -715,7 +973,7 @@ impl TraceRecorder {
                     exec_index,
                     tile_id: fn_call_record.fn_name,
                     sequence_id,
-                    intra_sequence_index: recur_state.intra_sequence_index,
+                    intra_sequence_index,
                     coordinates: tile_coordinates.clone(),
                     input_commitment,
                     input_source_commitment,
@@ -740,7 +998,7 @@ impl TraceRecorder {
                 };
 
                 self.witness_store
-                    .insert(tile_coordinates, event.clone(), internal_write);
+                    .insert(tile_coordinates, event.clone(), internal_write)?;
 
                 StepRecord::TileExec(record)
             }
@@ -750,37 +1008,41 @@ impl TraceRecorder {
                 let current_sequence_state = self
                     .sequence_callstack
                     .last_mut()
-                    .expect("RecurTile site can't be recorded without sequence context");
+                    .ok_or_else(|| outside_sequence(&fn_call_record.fn_name))?;
                 let sequence_id = current_sequence_state.id.clone();
                 let parent_current_index = current_sequence_state.current_index;
 
-                let recur_state = self.active_recur.take().unwrap_or_else(|| {
-                    let site_coordinates = self.cfs_cursor.get_child_coordinates(
-                        &sequence_coordinates,
-                        parent_current_index,
-                        SequenceChildId::RecurTile(fn_call_record.fn_name.clone()),
-                    );
-                    RecurExecutionState {
-                        site_id: fn_call_record.fn_name.clone(),
-                        sequence_coordinates: sequence_coordinates.clone(),
-                        site_coordinates,
-                        intra_sequence_index: parent_current_index,
-                        next_iteration_index: 0,
-                        declared_chunk: None,
-                        last_chunk_len: None,
+                let recur_state = match self.active_recur.take() {
+                    Some(recur_state) => {
+                        recur_state
+                            .check_continued_by(&fn_call_record.fn_name, &sequence_coordinates)?;
+                        recur_state
                     }
-                });
-
-                assert_eq!(
-                    recur_state.sequence_coordinates, sequence_coordinates,
-                    "RecurTile completion switched parent sequence coordinates mid-stream",
-                );
-                assert_eq!(
-                    recur_state.site_id, fn_call_record.fn_name,
-                    "RecurTile completion site id does not match active RecurTile stream",
-                );
+                    None => {
+                        let site_coordinates = child_coordinates(
+                            &self.cfs_cursor,
+                            &sequence_id,
+                            &sequence_coordinates,
+                            parent_current_index,
+                            SequenceChildId::RecurTile(fn_call_record.fn_name.clone()),
+                        )?;
+                        current_sequence_state
+                            .check_within_taken_arm(&site_coordinates, &fn_call_record.fn_name)?;
+                        RecurExecutionState {
+                            site_id: fn_call_record.fn_name.clone(),
+                            sequence_id: sequence_id.clone(),
+                            sequence_coordinates: sequence_coordinates.clone(),
+                            site_coordinates,
+                            intra_sequence_index: parent_current_index,
+                            next_iteration_index: 0,
+                            declared_chunk: None,
+                            last_chunk_len: None,
+                        }
+                    }
+                };
 
                 current_sequence_state.current_index += 1;
+                current_sequence_state.leave_completed_branch_arms();
 
                 let input = fn_call_record.input;
                 let input_commitment = input
@@ -797,13 +1059,24 @@ impl TraceRecorder {
                     .unwrap_or_default();
 
                 let output = fn_call_record.output;
-                let internal_write = output.as_ref().map(|output| {
-                    self.internal_storage.append_serialized_bytes(
-                        &output.data,
-                        recur_state.site_coordinates.clone(),
-                        output.raster.clone(),
-                    )
-                });
+                let internal_write = output
+                    .as_ref()
+                    .map(|output| {
+                        self.internal_storage.append_serialized_bytes(
+                            &output.data,
+                            recur_state.site_coordinates.clone(),
+                            output.raster.clone(),
+                        )
+                    })
+                    .transpose()
+                    .map_err(|error| {
+                        RecorderViolation::new(
+                            &sequence_id,
+                            &fn_call_record.fn_name,
+                            &recur_state.site_coordinates,
+                            RecorderViolationKind::InternalStore(error.to_string()),
+                        )
+                    })?;
                 let output_commitment = internal_write
                     .as_ref()
                     .map(|write| write.entry.object_commitment.clone())
@@ -841,7 +1114,7 @@ impl TraceRecorder {
                     recur_state.site_coordinates.clone(),
                     event.clone(),
                     internal_write,
-                );
+                )?;
 
                 StepRecord::RecurTileExec(record)
             }
@@ -851,22 +1124,26 @@ impl TraceRecorder {
                 let current_sequence_state = self
                     .sequence_callstack
                     .last_mut()
-                    .expect("RecurSequence site can't be recorded without sequence context");
+                    .ok_or_else(|| outside_sequence(&fn_call_record.fn_name))?;
                 let sequence_id = current_sequence_state.id.clone();
                 let parent_current_index = current_sequence_state.current_index;
 
                 let recur_key = (sequence_coordinates.clone(), fn_call_record.fn_name.clone());
-                let recur_state = self
-                    .active_recur_sequence
-                    .remove(&recur_key)
-                    .unwrap_or_else(|| {
-                        let site_coordinates = self.cfs_cursor.get_child_coordinates(
+                let recur_state = match self.active_recur_sequence.remove(&recur_key) {
+                    Some(recur_state) => recur_state,
+                    None => {
+                        let site_coordinates = child_coordinates(
+                            &self.cfs_cursor,
+                            &sequence_id,
                             &sequence_coordinates,
                             parent_current_index,
                             SequenceChildId::RecurSequence(fn_call_record.fn_name.clone()),
-                        );
+                        )?;
+                        current_sequence_state
+                            .check_within_taken_arm(&site_coordinates, &fn_call_record.fn_name)?;
                         RecurExecutionState {
                             site_id: fn_call_record.fn_name.clone(),
+                            sequence_id: sequence_id.clone(),
                             sequence_coordinates: sequence_coordinates.clone(),
                             site_coordinates,
                             intra_sequence_index: parent_current_index,
@@ -874,18 +1151,11 @@ impl TraceRecorder {
                             declared_chunk: None,
                             last_chunk_len: None,
                         }
-                    });
-
-                assert_eq!(
-                    recur_state.sequence_coordinates, sequence_coordinates,
-                    "RecurSequence completion switched parent sequence coordinates mid-stream",
-                );
-                assert_eq!(
-                    recur_state.site_id, fn_call_record.fn_name,
-                    "RecurSequence completion site id does not match active RecurSequence stream",
-                );
+                    }
+                };
 
                 current_sequence_state.current_index += 1;
+                current_sequence_state.leave_completed_branch_arms();
 
                 let input = fn_call_record.input;
                 let input_commitment = input
@@ -902,13 +1172,24 @@ impl TraceRecorder {
                     .unwrap_or_default();
 
                 let output = fn_call_record.output;
-                let internal_write = output.as_ref().map(|output| {
-                    self.internal_storage.append_serialized_bytes(
-                        &output.data,
-                        recur_state.site_coordinates.clone(),
-                        output.raster.clone(),
-                    )
-                });
+                let internal_write = output
+                    .as_ref()
+                    .map(|output| {
+                        self.internal_storage.append_serialized_bytes(
+                            &output.data,
+                            recur_state.site_coordinates.clone(),
+                            output.raster.clone(),
+                        )
+                    })
+                    .transpose()
+                    .map_err(|error| {
+                        RecorderViolation::new(
+                            &sequence_id,
+                            &fn_call_record.fn_name,
+                            &recur_state.site_coordinates,
+                            RecorderViolationKind::InternalStore(error.to_string()),
+                        )
+                    })?;
                 let output_commitment = internal_write
                     .as_ref()
                     .map(|write| write.entry.object_commitment.clone())
@@ -946,13 +1227,185 @@ impl TraceRecorder {
                     recur_state.site_coordinates.clone(),
                     event.clone(),
                     internal_write,
-                );
+                )?;
 
                 StepRecord::RecurSequenceExec(record)
             }
+            TraceEvent::TileFailed(fn_call_record, failure) => {
+                let (sequence_id, intra_sequence_index, tile_coordinates) =
+                    if failure.recur_iteration {
+                        self.place_recur_iteration(&fn_call_record)?
+                    } else {
+                        self.place_tile(&fn_call_record.fn_name)?
+                    };
+
+                let input = fn_call_record.input;
+                let input_commitment = input
+                    .as_ref()
+                    .map(|input| Sha256Commitment::from(input).into())
+                    .unwrap_or_default();
+                let external_input_commitment = input
+                    .as_ref()
+                    .map(|input| external_input_commitment(input.external()))
+                    .unwrap_or_default();
+                let input_source_commitment = input
+                    .as_ref()
+                    .map(input_source_commitment)
+                    .unwrap_or_default();
+
+                let snapshot = self.internal_storage.snapshot();
+                let record = TileFailedRecord {
+                    exec_index,
+                    tile_id: fn_call_record.fn_name,
+                    sequence_id,
+                    intra_sequence_index,
+                    coordinates: tile_coordinates.clone(),
+                    input_commitment,
+                    input_source_commitment,
+                    external_input_commitment,
+                    internal_store_root: snapshot.root,
+                    internal_store_index_root: snapshot.index_root,
+                    failure_kind: failure.kind,
+                    message_hash: failure.message_hash,
+                };
+
+                self.witness_store
+                    .insert(tile_coordinates, event.clone(), None)?;
+                self.failed = true;
+
+                StepRecord::TileFailed(record)
+            }
+        };
+
+        Ok(step_record)
+    }
+
+    /// Coordinates of a tile run as the next item of the current sequence.
+    /// Returns the sequence id, the tile's index in the sequence and its
+    /// coordinates.
+    fn place_tile(
+        &mut self,
+        fn_name: &str,
+    ) -> Result<(SequenceId, u32, CfsCoordinates), RecorderViolation> {
+        if let Some(active_recur) = &self.active_recur {
+            return Err(active_recur.interrupted_by(fn_name));
+        }
+        let sequence_coordinates = self.sequence_callstack.current_sequence_coordinates.clone();
+        let current_sequence_state = self
+            .sequence_callstack
+            .last_mut()
+            .ok_or_else(|| outside_sequence(fn_name))?;
+
+        let sequence_id = current_sequence_state.id.clone();
+        let parent_current_index = current_sequence_state.current_index;
+
+        let mut candidate_coordinates = sequence_coordinates.clone();
+        candidate_coordinates.push(
+            parent_current_index
+                .try_into()
+                .expect("Sequence coordinate out of bound u8"),
+        );
+        let child_id = match self.cfs_cursor.try_get_item(&candidate_coordinates) {
+            Some(raster_core::cfs::SequenceChildItem::RecurTile(item)) if item.id == fn_name => {
+                SequenceChildId::RecurTile(fn_name.to_string())
+            }
+            _ => SequenceChildId::Tile(fn_name.to_string()),
         };
 
-        step_record
+        let tile_coordinates = child_coordinates(
+            &self.cfs_cursor,
+            &sequence_id,
+            &sequence_coordinates,
+            parent_current_index,
+            child_id,
+        )?;
+        current_sequence_state.check_within_taken_arm(&tile_coordinates, fn_name)?;
+
+        current_sequence_state.current_index += 1;
+
+        Ok((sequence_id, parent_current_index, tile_coordinates))
+    }
+
+    /// Coordinates of the next iteration of the recur tile site in the
+    /// current sequence, starting the site if none is active. Returns the
+    /// sequence id, the site's index in the sequence and the iteration's
+    /// coordinates.
+    fn place_recur_iteration(
+        &mut self,
+        fn_call_record: &FnCallRecord,
+    ) -> Result<(SequenceId, u32, CfsCoordinates), RecorderViolation> {
+        let sequence_coordinates = self.sequence_callstack.current_sequence_coordinates.clone();
+        let current_sequence_state = self
+            .sequence_callstack
+            .last_mut()
+            .ok_or_else(|| outside_sequence(&fn_call_record.fn_name))?;
+
+        let sequence_id = current_sequence_state.id.clone();
+        let recur_state = match &mut self.active_recur {
+            Some(recur_state) => {
+                recur_state.check_continued_by(&fn_call_record.fn_name, &sequence_coordinates)?;
+                recur_state
+            }
+            None => {
+                let parent_current_index = current_sequence_state.current_index;
+                let site_coordinates = child_coordinates(
+                    &self.cfs_cursor,
+                    &sequence_id,
+                    &sequence_coordinates,
+                    parent_current_index,
+                    SequenceChildId::RecurTile(fn_call_record.fn_name.clone()),
+                )?;
+                current_sequence_state
+                    .check_within_taken_arm(&site_coordinates, &fn_call_record.fn_name)?;
+                let declared_chunk = match self.cfs_cursor.try_get_item(&site_coordinates) {
+                    Some(SequenceChildItem::RecurTile(item)) => item.chunk,
+                    _ => None,
+                };
+                self.active_recur.insert(RecurExecutionState {
+                    site_id: fn_call_record.fn_name.clone(),
+                    sequence_id: sequence_id.clone(),
+                    sequence_coordinates: sequence_coordinates.clone(),
+                    site_coordinates,
+                    intra_sequence_index: parent_current_index,
+                    next_iteration_index: 0,
+                    declared_chunk,
+                    last_chunk_len: None,
+                })
+            }
+        };
+
+        let mut tile_coordinates = recur_state.site_coordinates.clone();
+        tile_coordinates.push(recur_state.next_iteration_index);
+        recur_state.next_iteration_index += 1;
+
+        if let Some(declared) = recur_state.declared_chunk {
+            let Some(input) = fn_call_record.input.as_ref() else {
+                return Err(RecorderViolation::new(
+                    &sequence_id,
+                    &recur_state.site_id,
+                    &tile_coordinates,
+                    RecorderViolationKind::MissingInput,
+                ));
+            };
+            let chunk_len =
+                raster_core::chunking::iteration_chunk_len(input.data()).ok_or_else(|| {
+                    recur_state.chunk_violation(&tile_coordinates, ChunkViolation::Undecodable)
+                })?;
+            raster_core::chunking::check_iteration_chunk_len(declared, chunk_len)
+                .map_err(|violation| recur_state.chunk_violation(&tile_coordinates, violation))?;
+            if let Some(previous) = recur_state.last_chunk_len {
+                raster_core::chunking::check_previous_chunk_was_full(declared, previous).map_err(
+                    |violation| recur_state.chunk_violation(&tile_coordinates, violation),
+                )?;
+            }
+            recur_state.last_chunk_len = Some(chunk_len);
+        }
+
+        Ok((
+            sequence_id,
+            recur_state.intra_sequence_index,
+            tile_coordinates,
+        ))
     }
 }
 
@@ -960,19 +1413,21 @@ impl TraceRecorder {
 mod tests {
     use super::*;
     use raster_core::cfs::{
-        RecurSequenceItem, RecurTileItem, SequenceChildItem, SequenceDef, TileDef, TileItem,
+        BranchArm, BranchItem, BranchPattern, RecurSequenceItem, RecurTileItem, SequenceChildItem,
+        SequenceDef, TileDef, TileItem, CFS_VERSION,
     };
-    use raster_core::trace::FnCallRecord;
+    use raster_core::trace::{FnCallRecord, TileFailure, TileFailureKind};
 
     fn recorder_with_recur_site() -> TraceRecorder {
         TraceRecorder::new(ControlFlowSchema {
-            version: "1.0".to_string(),
+            version: CFS_VERSION.to_string(),
             project: "test".to_string(),
             encoding: "postcard".to_string(),
             tiles: vec![TileDef::iter("recur", 0, 0), TileDef::iter("after", 0, 0)],
             sequences: vec![SequenceDef {
                 id: "main".to_string(),
                 input_sources: vec![],
+                branches: vec![],
                 items: vec![
                     SequenceChildItem::RecurTile(RecurTileItem {
                         id: "recur".to_string(),
@@ -984,13 +1439,17 @@ mod tests {
                         sources: vec![],
                     }),
                 ],
+                input_types: vec![],
+                output_type: None,
+                path: String::new(),
             }],
         })
+        .expect("supported CFS version")
     }
 
     fn recorder_with_recur_sequence_site() -> TraceRecorder {
         TraceRecorder::new(ControlFlowSchema {
-            version: "1.0".to_string(),
+            version: CFS_VERSION.to_string(),
             project: "test".to_string(),
             encoding: "postcard".to_string(),
             tiles: vec![TileDef::iter("inner", 0, 0), TileDef::iter("after", 0, 0)],
@@ -998,6 +1457,7 @@ mod tests {
                 SequenceDef {
                     id: "main".to_string(),
                     input_sources: vec![],
+                    branches: vec![],
                     items: vec![
                         SequenceChildItem::RecurSequence(RecurSequenceItem {
                             id: "child".to_string(),
@@ -1008,64 +1468,79 @@ mod tests {
                             sources: vec![],
                         }),
                     ],
+                    input_types: vec![],
+                    output_type: None,
+                    path: String::new(),
                 },
                 SequenceDef {
                     id: "child".to_string(),
                     input_sources: vec![],
+                    branches: vec![],
                     items: vec![SequenceChildItem::Tile(TileItem {
                         id: "inner".to_string(),
                         sources: vec![],
                     })],
+                    input_types: vec![],
+                    output_type: None,
+                    path: String::new(),
                 },
             ],
         })
+        .expect("supported CFS version")
     }
 
     #[test]
-    #[should_panic(
-        expected = "Missing step witness entry for SequenceEnd at coordinates CfsCoordinates([]). Expected a matching SequenceStart to be recorded first."
-    )]
     fn sequence_end_without_matching_start_reports_coordinates() {
         let mut store = StepWitnessStore::new();
-        store.insert(
-            CfsCoordinates(vec![]),
-            TraceEvent::SequenceEnd(FnCallRecord {
-                fn_name: "main".to_string(),
-                input: None,
-                output: None,
-                draft_transition_witness: None,
-            }),
-            None,
-        );
+        let violation = store
+            .insert(
+                CfsCoordinates(vec![]),
+                TraceEvent::SequenceEnd(FnCallRecord {
+                    fn_name: "main".to_string(),
+                    module_path: String::new(),
+                    input: None,
+                    output: None,
+                    draft_transition_witness: None,
+                }),
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(violation.kind, RecorderViolationKind::UnmatchedEnd);
+        assert_eq!(violation.coordinates, CfsCoordinates(vec![]));
     }
 
     fn recorder_with_chunked_recur_site(chunk: u64) -> TraceRecorder {
         TraceRecorder::new(ControlFlowSchema {
-            version: "1.0".to_string(),
+            version: CFS_VERSION.to_string(),
             project: "test".to_string(),
             encoding: "postcard".to_string(),
             tiles: vec![TileDef::iter("recur", 0, 0)],
             sequences: vec![SequenceDef {
                 id: "main".to_string(),
                 input_sources: vec![],
+                branches: vec![],
                 items: vec![SequenceChildItem::RecurTile(RecurTileItem {
                     id: "recur".to_string(),
                     sources: vec![],
                     chunk: Some(chunk),
                 })],
+                input_types: vec![],
+                output_type: None,
+                path: String::new(),
             }],
         })
+        .expect("supported CFS version")
     }
 
     /// Iteration event whose input data mirrors the chunked recur ABI:
     /// a tuple leading with `RecurInput<Vec<String>> { value, index, len }`.
     fn chunked_iteration_event(elements: usize) -> TraceEvent {
         let chunk: Vec<String> = (0..elements).map(|i| format!("line-{}", i)).collect();
-        let data =
-            raster_core::postcard::to_allocvec(&((chunk, 0u64, 2u64), "title".to_string()))
-                .unwrap();
+        let data = raster_core::postcard::to_allocvec(&((chunk, 0u64, 2u64), "title".to_string()))
+            .unwrap();
         TraceEvent::RecurTileIterationExec(FnCallRecord {
             fn_name: "recur".to_string(),
+            module_path: String::new(),
             input: Some(raster_core::trace::FnInput {
                 data,
                 values: vec![],
@@ -1079,95 +1554,180 @@ mod tests {
     }
 
     fn start_main(recorder: &mut TraceRecorder) {
-        recorder.record(TraceEvent::SequenceStart(FnCallRecord {
-            fn_name: "main".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
+        recorder
+            .record(TraceEvent::SequenceStart(FnCallRecord {
+                fn_name: "main".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
     }
 
     #[test]
     fn chunked_recur_accepts_full_then_short_final_chunk() {
         let mut recorder = recorder_with_chunked_recur_site(2);
         start_main(&mut recorder);
-        recorder.record(chunked_iteration_event(2));
-        recorder.record(chunked_iteration_event(2));
-        recorder.record(chunked_iteration_event(1));
+        recorder.record(chunked_iteration_event(2)).unwrap();
+        recorder.record(chunked_iteration_event(2)).unwrap();
+        recorder.record(chunked_iteration_event(1)).unwrap();
     }
 
     #[test]
-    #[should_panic(expected = "exceeds declared chunk size")]
     fn chunked_recur_rejects_oversized_chunk() {
         let mut recorder = recorder_with_chunked_recur_site(2);
         start_main(&mut recorder);
-        recorder.record(chunked_iteration_event(3));
+        let violation = recorder.record(chunked_iteration_event(3)).unwrap_err();
+        assert_eq!(
+            violation.kind,
+            RecorderViolationKind::Chunk(ChunkViolation::Oversized {
+                declared: 2,
+                actual: 3
+            })
+        );
+        assert_eq!(violation.coordinates, CfsCoordinates(vec![0, 0]));
     }
 
     #[test]
-    #[should_panic(expected = "empty chunk")]
     fn chunked_recur_rejects_empty_chunk() {
         let mut recorder = recorder_with_chunked_recur_site(2);
         start_main(&mut recorder);
-        recorder.record(chunked_iteration_event(0));
+        let violation = recorder.record(chunked_iteration_event(0)).unwrap_err();
+        assert_eq!(
+            violation.kind,
+            RecorderViolationKind::Chunk(ChunkViolation::Empty)
+        );
     }
 
     #[test]
-    #[should_panic(expected = "smaller than declared chunk size")]
     fn chunked_recur_rejects_short_non_final_chunk() {
         let mut recorder = recorder_with_chunked_recur_site(2);
         start_main(&mut recorder);
-        recorder.record(chunked_iteration_event(1));
-        recorder.record(chunked_iteration_event(2));
+        recorder.record(chunked_iteration_event(1)).unwrap();
+        let violation = recorder.record(chunked_iteration_event(2)).unwrap_err();
+        assert_eq!(
+            violation.kind,
+            RecorderViolationKind::Chunk(ChunkViolation::ShortNonFinal {
+                declared: 2,
+                actual: 1
+            })
+        );
     }
 
     #[test]
-    #[should_panic(expected = "no decodable chunk length")]
     fn chunked_recur_rejects_missing_input() {
         let mut recorder = recorder_with_chunked_recur_site(2);
         start_main(&mut recorder);
-        recorder.record(TraceEvent::RecurTileIterationExec(FnCallRecord {
-            fn_name: "recur".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
+        let violation = recorder
+            .record(TraceEvent::RecurTileIterationExec(FnCallRecord {
+                fn_name: "recur".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap_err();
+        assert_eq!(violation.kind, RecorderViolationKind::MissingInput);
+        assert_eq!(violation.sequence_id, "main");
+    }
+
+    #[test]
+    fn unexpected_step_lists_the_remaining_items() {
+        let mut recorder = recorder_with_recur_site();
+        start_main(&mut recorder);
+        let violation = recorder
+            .record(TraceEvent::TileExec(FnCallRecord {
+                fn_name: "stray".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap_err();
+        assert_eq!(
+            violation.kind,
+            RecorderViolationKind::UnexpectedStep {
+                expected: vec![
+                    SequenceChildId::RecurTile("recur".to_string()),
+                    SequenceChildId::Tile("after".to_string()),
+                ],
+            }
+        );
+        assert_eq!(violation.sequence_id, "main");
+        assert_eq!(
+            violation.to_string(),
+            "`stray` is not expected here; expected one of: recur tile `recur`, \
+             tile `after` (at CfsCoordinates([]) in sequence `main`)"
+        );
+    }
+
+    #[test]
+    fn sequence_end_must_close_the_innermost_sequence() {
+        let mut recorder = recorder_with_recur_site();
+        start_main(&mut recorder);
+        let violation = recorder
+            .record(TraceEvent::SequenceEnd(FnCallRecord {
+                fn_name: "other".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap_err();
+        assert_eq!(violation.kind, RecorderViolationKind::UnmatchedEnd);
+        assert_eq!(violation.sequence_id, "main");
     }
 
     #[test]
     fn recur_iterations_and_site_completion_get_distinct_coordinates() {
         let mut recorder = recorder_with_recur_site();
-        recorder.record(TraceEvent::SequenceStart(FnCallRecord {
-            fn_name: "main".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
+        recorder
+            .record(TraceEvent::SequenceStart(FnCallRecord {
+                fn_name: "main".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
 
-        let iter0 = recorder.record(TraceEvent::RecurTileIterationExec(FnCallRecord {
-            fn_name: "recur".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
-        let iter1 = recorder.record(TraceEvent::RecurTileIterationExec(FnCallRecord {
-            fn_name: "recur".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
-        let site = recorder.record(TraceEvent::RecurTileExec(FnCallRecord {
-            fn_name: "recur".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
-        let after = recorder.record(TraceEvent::TileExec(FnCallRecord {
-            fn_name: "after".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
+        let iter0 = recorder
+            .record(TraceEvent::RecurTileIterationExec(FnCallRecord {
+                fn_name: "recur".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
+        let iter1 = recorder
+            .record(TraceEvent::RecurTileIterationExec(FnCallRecord {
+                fn_name: "recur".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
+        let site = recorder
+            .record(TraceEvent::RecurTileExec(FnCallRecord {
+                fn_name: "recur".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
+        let after = recorder
+            .record(TraceEvent::TileExec(FnCallRecord {
+                fn_name: "after".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
 
         assert_eq!(iter0.coordinates(), &CfsCoordinates(vec![0, 0]));
         assert_eq!(iter1.coordinates(), &CfsCoordinates(vec![0, 1]));
@@ -1176,63 +1736,130 @@ mod tests {
     }
 
     #[test]
-    fn recur_sequence_iterations_restore_parent_coordinates_before_site_completion() {
-        let mut recorder = recorder_with_recur_sequence_site();
-        recorder.record(TraceEvent::SequenceStart(FnCallRecord {
-            fn_name: "main".to_string(),
+    fn failed_recur_iteration_is_placed_and_ends_the_trace() {
+        let mut recorder = recorder_with_recur_site();
+        start_main(&mut recorder);
+        let call = |fn_name: &str| FnCallRecord {
+            fn_name: fn_name.to_string(),
+            module_path: String::new(),
             input: None,
             output: None,
             draft_transition_witness: None,
-        }));
+        };
 
-        let iter0_start = recorder.record(TraceEvent::RecurSequenceStart(FnCallRecord {
-            fn_name: "child".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
-        let iter0_inner = recorder.record(TraceEvent::TileExec(FnCallRecord {
-            fn_name: "inner".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
-        let iter0_end = recorder.record(TraceEvent::RecurSequenceEnd(FnCallRecord {
-            fn_name: "child".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
-        let iter1_start = recorder.record(TraceEvent::RecurSequenceStart(FnCallRecord {
-            fn_name: "child".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
-        let iter1_inner = recorder.record(TraceEvent::TileExec(FnCallRecord {
-            fn_name: "inner".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
-        let iter1_end = recorder.record(TraceEvent::RecurSequenceEnd(FnCallRecord {
-            fn_name: "child".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
-        let site = recorder.record(TraceEvent::RecurSequenceExec(FnCallRecord {
-            fn_name: "child".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
-        let after = recorder.record(TraceEvent::TileExec(FnCallRecord {
-            fn_name: "after".to_string(),
-            input: None,
-            output: None,
-            draft_transition_witness: None,
-        }));
+        recorder
+            .record(TraceEvent::RecurTileIterationExec(call("recur")))
+            .unwrap();
+        let failed = recorder
+            .record(TraceEvent::TileFailed(
+                call("recur"),
+                TileFailure {
+                    kind: TileFailureKind::Panic,
+                    message_hash: vec![7; 32],
+                    recur_iteration: true,
+                },
+            ))
+            .unwrap();
+        let StepRecord::TileFailed(record) = &failed else {
+            panic!("expected a failed step, got {failed:?}");
+        };
+        assert_eq!(record.coordinates, CfsCoordinates(vec![0, 1]));
+        assert_eq!(record.failure_kind, TileFailureKind::Panic);
+        let (root_before, root_after, _, _) = failed.internal_store_roots().unwrap();
+        assert_eq!(root_before, root_after);
+        assert!(failed.output_commitment().is_none());
+
+        let violation = recorder
+            .record(TraceEvent::TileExec(call("after")))
+            .unwrap_err();
+        assert_eq!(violation.kind, RecorderViolationKind::AfterFailure);
+    }
+
+    #[test]
+    fn recur_sequence_iterations_restore_parent_coordinates_before_site_completion() {
+        let mut recorder = recorder_with_recur_sequence_site();
+        recorder
+            .record(TraceEvent::SequenceStart(FnCallRecord {
+                fn_name: "main".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
+
+        let iter0_start = recorder
+            .record(TraceEvent::RecurSequenceStart(FnCallRecord {
+                fn_name: "child".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
+        let iter0_inner = recorder
+            .record(TraceEvent::TileExec(FnCallRecord {
+                fn_name: "inner".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
+        let iter0_end = recorder
+            .record(TraceEvent::RecurSequenceEnd(FnCallRecord {
+                fn_name: "child".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
+        let iter1_start = recorder
+            .record(TraceEvent::RecurSequenceStart(FnCallRecord {
+                fn_name: "child".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
+        let iter1_inner = recorder
+            .record(TraceEvent::TileExec(FnCallRecord {
+                fn_name: "inner".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
+        let iter1_end = recorder
+            .record(TraceEvent::RecurSequenceEnd(FnCallRecord {
+                fn_name: "child".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
+        let site = recorder
+            .record(TraceEvent::RecurSequenceExec(FnCallRecord {
+                fn_name: "child".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
+        let after = recorder
+            .record(TraceEvent::TileExec(FnCallRecord {
+                fn_name: "after".to_string(),
+                module_path: String::new(),
+                input: None,
+                output: None,
+                draft_transition_witness: None,
+            }))
+            .unwrap();
 
         assert_eq!(iter0_start.coordinates(), &CfsCoordinates(vec![0, 0]));
         assert_eq!(iter0_inner.coordinates(), &CfsCoordinates(vec![0, 0, 0]));
@@ -1243,4 +1870,165 @@ mod tests {
         assert_eq!(site.coordinates(), &CfsCoordinates(vec![0]));
         assert_eq!(after.coordinates(), &CfsCoordinates(vec![1]));
     }
+
+    fn recorder_with_branch() -> TraceRecorder {
+        let tile = |id: &str| {
+            SequenceChildItem::Tile(TileItem {
+                id: id.to_string(),
+                sources: vec![],
+            })
+        };
+        TraceRecorder::new(ControlFlowSchema {
+            version: CFS_VERSION.to_string(),
+            project: "test".to_string(),
+            encoding: "postcard".to_string(),
+            tiles: vec![
+                TileDef::iter("check", 0, 1),
+                TileDef::iter("accept", 0, 0),
+                TileDef::iter("reject", 0, 0),
+                TileDef::iter("after", 0, 0),
+            ],
+            sequences: vec![SequenceDef {
+                id: "main".to_string(),
+                input_sources: vec![],
+                branches: vec![BranchItem {
+                    condition: 0,
+                    start: 1,
+                    arms: vec![
+                        BranchArm {
+                            pattern: BranchPattern::Bool(true),
+                            len: 1,
+                        },
+                        BranchArm {
+                            pattern: BranchPattern::Bool(false),
+                            len: 1,
+                        },
+                    ],
+                    variants: Vec::new(),
+                }],
+                items: vec![tile("check"), tile("accept"), tile("reject"), tile("after")],
+                input_types: vec![],
+                output_type: None,
+                path: String::new(),
+            }],
+        })
+        .expect("supported CFS version")
+    }
+
+    fn tile_event(fn_name: &str, output: Option<bool>) -> TraceEvent {
+        TraceEvent::TileExec(FnCallRecord {
+            fn_name: fn_name.to_string(),
+            module_path: String::new(),
+            input: None,
+            output: output.map(|value| {
+                let (bytes, index_bytes, _) = crate::input::encode_raster_value(&value).unwrap();
+                raster_core::trace::FnOutput::new(vec![u8::from(value)], "bool").with_raster(
+                    raster_core::trace::RasterPayload {
+                        bytes,
+                        index_bytes,
+                        root_hash: Default::default(),
+                    },
+                )
+            }),
+            draft_transition_witness: None,
+        })
+    }
+
+    fn run_branch(condition: bool, arm_tile: &str) -> Vec<CfsCoordinates> {
+        let mut recorder = recorder_with_branch();
+        start_main(&mut recorder);
+        [
+            tile_event("check", Some(condition)),
+            tile_event(arm_tile, None),
+            tile_event("after", None),
+        ]
+        .into_iter()
+        .map(|event| recorder.record(event).unwrap().coordinates().clone())
+        .collect()
+    }
+
+    #[test]
+    fn branch_condition_selects_arm_and_skips_the_others() {
+        assert_eq!(
+            run_branch(true, "accept"),
+            vec![
+                CfsCoordinates(vec![0]),
+                CfsCoordinates(vec![1]),
+                CfsCoordinates(vec![3]),
+            ]
+        );
+        assert_eq!(
+            run_branch(false, "reject"),
+            vec![
+                CfsCoordinates(vec![0]),
+                CfsCoordinates(vec![2]),
+                CfsCoordinates(vec![3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn branch_rejects_items_of_untaken_arm() {
+        let mut recorder = recorder_with_branch();
+        start_main(&mut recorder);
+        recorder.record(tile_event("check", Some(true))).unwrap();
+        let violation = recorder.record(tile_event("reject", None)).unwrap_err();
+        assert_eq!(
+            violation.kind,
+            RecorderViolationKind::Branch(BranchViolation::OutsideTakenArm {
+                arm_index: 0,
+                item_index: 2,
+            })
+        );
+        assert_eq!(violation.coordinates, CfsCoordinates(vec![2]));
+    }
+
+    #[test]
+    fn colliding_tile_names_resolve_to_module_qualified_ids() {
+        let tile = |id: &str| {
+            SequenceChildItem::Tile(TileItem {
+                id: id.to_string(),
+                sources: vec![],
+            })
+        };
+        let mut recorder = TraceRecorder::new(ControlFlowSchema {
+            version: CFS_VERSION.to_string(),
+            project: "test".to_string(),
+            encoding: "postcard".to_string(),
+            tiles: vec![
+                TileDef::iter("normalize", 0, 0).with_path("normalize"),
+                TileDef::iter("audio::normalize", 0, 0).with_path("audio::normalize"),
+            ],
+            sequences: vec![SequenceDef {
+                id: "main".to_string(),
+                input_sources: vec![],
+                branches: vec![],
+                items: vec![tile("normalize"), tile("audio::normalize")],
+                input_types: vec![],
+                output_type: None,
+                path: "main".to_string(),
+            }],
+        })
+        .expect("supported CFS version");
+        start_main(&mut recorder);
+
+        let mut record = |module_path: &str| {
+            let step = recorder
+                .record(TraceEvent::TileExec(FnCallRecord {
+                    fn_name: "normalize".to_string(),
+                    module_path: module_path.to_string(),
+                    input: None,
+                    output: None,
+                    draft_transition_witness: None,
+                }))
+                .unwrap();
+            match step {
+                StepRecord::TileExec(record) => record.tile_id,
+                other => panic!("expected a tile step, got {:?}", other),
+            }
+        };
+
+        assert_eq!(record("test"), "normalize");
+        assert_eq!(record("test::audio"), "audio::normalize");
+    }
 }
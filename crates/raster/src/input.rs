@@ -1426,6 +1426,37 @@ where
     }
 }
 
+/// Binds one name of a destructured `let` in a sequence — e.g. `a` in
+/// `let (a, b) = call!(split, x)` — to a selection of the call's stored output.
+///
+/// The selection is taken directly from the raster-encoded output, so the bound
+/// value carries a selection witness against the producer's commitment. Tuple
+/// parts need not implement `Selectable`; `project` names the selected part only
+/// so its type can be inferred.
+#[doc(hidden)]
+pub fn select_destructured<Current, Selected>(
+    source: &AuthRef<Current>,
+    selector: SelectorPath,
+    project: fn(&Current) -> &Selected,
+) -> raster_core::Result<AuthRef<Selected>>
+where
+    Selected: DeserializeOwned + Serialize + 'static,
+{
+    let _ = project;
+    match source {
+        AuthRef::Internal(binding) => Ok(AuthRef::Internal(DeferredAuthInternal {
+            reference: binding.reference.clone(),
+            resolve: Rc::new(move |reference| {
+                select_stored_internal_value::<Selected>(&reference, &selector)
+            }),
+            marker: PhantomData,
+        })),
+        AuthRef::Inline(_) | AuthRef::External(_) => Err(raster_core::Error::Other(
+            "destructuring is only supported for stored call! outputs".into(),
+        )),
+    }
+}
+
 impl<T> IntoAuthRef<T> for T
 where
     T: Serialize,
@@ -2064,6 +2095,75 @@ where
     }
 }
 
+/// Drive a `call_map!` site: `body` runs the map tile's body for every item
+/// in parallel, then `step` runs the traced tile for each item in order,
+/// reusing the body's result. The outputs are stored at their iterations'
+/// coordinates and collected, by index, into the site's result.
+///
+/// When a resumed run replays the site, the leading items whose outputs are
+/// in the trace run no body: their steps take the outputs from the trace.
+#[doc(hidden)]
+pub fn run_map_list<T, O, Body, Step>(
+    source: AuthRef<Vec<T>>,
+    fn_name: &str,
+    module_path: &str,
+    body: Body,
+    mut step: Step,
+) -> AuthRef<Vec<O>>
+where
+    T: DeserializeOwned + Serialize + Selectable + Clone + Send + Sync + 'static,
+    O: DeserializeOwned + Serialize + Send + 'static,
+    Body: Fn(RecurInput<T>) -> O + Sync,
+    Step: FnMut(RecurInput<T>) -> O,
+{
+    #[cfg(feature = "std")]
+    {
+        let items = resolve_recur_list(&source)
+            .unwrap_or_else(|error| panic!("Failed to resolve map list source: {}", error));
+        let len = items.len() as u64;
+        let replayed =
+            raster_runtime::replayed_tile_output_count(fn_name, module_path, items.len());
+        let mut computed = raster_runtime::map_in_parallel(&items[replayed..], |offset, value| {
+            let index = (replayed + offset) as u64;
+            body(RecurInput::new(value.clone(), index, len))
+        })
+        .into_iter();
+
+        let mut outputs = Vec::with_capacity(items.len());
+        for (index, value) in items.into_iter().enumerate() {
+            let input = RecurInput::new(value, index as u64, len);
+            let output = if index < replayed {
+                // The step takes the item's output from the trace.
+                step(input)
+            } else {
+                let computed = computed
+                    .next()
+                    .expect("a body result for every item after the replayed ones");
+                raster_runtime::with_mapped_output(computed, || step(input))
+            };
+            raster_runtime::store_execution_output_value(&output).unwrap_or_else(|error| {
+                panic!(
+                    "Failed to store map tile output in internal storage: {}",
+                    error
+                )
+            });
+            outputs.push(output);
+        }
+
+        crate::__private::bind_infallible_call(outputs)
+    }
+
+    #[cfg(not(feature = "std"))]
+    {
+        let _ = source;
+        let _ = fn_name;
+        let _ = module_path;
+        let _ = body;
+        let _ = step;
+        panic!("Map list execution requires the `std` feature")
+    }
+}
+
 #[doc(hidden)]
 pub fn run_recur_list_with_state<T, State, S, Step, Output>(
     source: AuthRef<Vec<T>>,
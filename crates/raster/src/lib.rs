@@ -18,13 +18,13 @@ pub use raster_core::draft;
 pub mod input;
 pub use input::{
     auth_ref_result_trace, auth_ref_trace, chunk_auth_ref, draft_replay_handle,
-    draft_replay_transition, finalize,
-    into_auth_ref, into_auth_value, into_draft, materialize_auth_result, materialize_auth_return,
-    new_draft, raster_trace_payload, resolve_external_value, resolve_internal_ok_value,
-    resolve_internal_value, resolve_typed_external_value, restore_draft_from_replay_handle,
-    run_recur_list, run_recur_list_state, run_recur_list_with_state, run_recur_sequence_list,
-    run_recur_sequence_list_state, run_recur_sequence_list_with_state, select_source,
-    select_stored_internal_value, selector_path, serialize_draft_replay_handle,
+    draft_replay_transition, finalize, into_auth_ref, into_auth_value, into_draft,
+    materialize_auth_result, materialize_auth_return, new_draft, raster_trace_payload,
+    resolve_external_value, resolve_internal_ok_value, resolve_internal_value,
+    resolve_typed_external_value, restore_draft_from_replay_handle, run_map_list, run_recur_list,
+    run_recur_list_state, run_recur_list_with_state, run_recur_sequence_list,
+    run_recur_sequence_list_state, run_recur_sequence_list_with_state, select_destructured,
+    select_source, select_stored_internal_value, selector_path, serialize_draft_replay_handle,
     serialize_draft_trace, typed_external, typed_internal, typed_internal_with_resolver,
     typed_selector_path, Anchor, AuthRef, AuthRefTrace, AuthValue, Draft, DraftAppendField,
     DraftSetField, ExternalRef, ExternalSelection, ExternalValue, InternalRef, InternalValue,
@@ -60,7 +60,7 @@ pub mod runtime {
 
 // Runtime helpers are only available with std feature.
 #[cfg(feature = "std")]
-pub use raster_runtime::{finish, init, init_with, publish_trace_event};
+pub use raster_runtime::{finish, init, init_with, publish_trace_event, TrackingAllocator};
 
 #[cfg(feature = "std")]
 pub mod utils;
@@ -307,6 +307,81 @@ pub mod __private {
         panic!("Tile output coordinates require the `std` feature")
     }
 
+    #[cfg(feature = "std")]
+    pub fn replayed_tile_output<T>(
+        fn_name: &str,
+        module_path: &str,
+        coordinates: &crate::core::cfs::CfsCoordinates,
+    ) -> Option<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        raster_runtime::replayed_tile_output(fn_name, module_path, coordinates)
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub fn replayed_tile_output<T>(
+        _: &str,
+        _: &str,
+        _: &crate::core::cfs::CfsCoordinates,
+    ) -> Option<T> {
+        None
+    }
+
+    #[cfg(feature = "std")]
+    pub fn take_mapped_tile_output<T: 'static>(fn_name: &str) -> Option<T> {
+        raster_runtime::take_mapped_tile_output(fn_name)
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub fn take_mapped_tile_output<T>(_: &str) -> Option<T> {
+        None
+    }
+
+    #[cfg(feature = "std")]
+    pub use raster_runtime::{catch_tile_failure, CaughtTileFailure};
+
+    #[cfg(not(feature = "std"))]
+    pub enum CaughtTileFailure {}
+
+    #[cfg(not(feature = "std"))]
+    impl CaughtTileFailure {
+        pub fn publish(self, _: &str, _: &str, _: Option<crate::core::trace::FnInput>) -> ! {
+            match self {}
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub fn catch_tile_failure<T>(
+        _: crate::core::trace::TileFailureKind,
+        f: impl FnOnce() -> T,
+    ) -> Result<T, CaughtTileFailure> {
+        Ok(f())
+    }
+
+    #[cfg(feature = "std")]
+    pub use raster_runtime::TileLimitScope;
+
+    #[cfg(not(feature = "std"))]
+    pub struct TileLimitScope;
+
+    #[cfg(not(feature = "std"))]
+    impl TileLimitScope {
+        pub fn enter(
+            _: &'static str,
+            _: &'static str,
+            _: Option<u64>,
+            _: Option<u64>,
+            _: &Option<crate::core::trace::FnInput>,
+        ) -> Self {
+            Self
+        }
+
+        pub fn finish(self, _: &crate::core::cfs::CfsCoordinates) -> Result<(), CaughtTileFailure> {
+            Ok(())
+        }
+    }
+
     #[doc(hidden)]
     pub struct RecurSiteScopeGuard;
 
@@ -331,6 +406,68 @@ pub mod __private {
         }
     }
 
+    #[cfg(feature = "std")]
+    pub fn skip_branch_items(count: u32) {
+        raster_runtime::skip_sequence_items(count)
+            .unwrap_or_else(|error| panic!("Failed to skip branch arm items: {}", error));
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub fn skip_branch_items(_: u32) {}
+
+    /// Value a sequence `if` / `match` branches on.
+    #[diagnostic::on_unimplemented(
+        message = "sequence branches must be conditioned on a `bool` or a `call!` output, found `{Self}`",
+        note = "the CFS build additionally requires the condition to come from the `call!` right before the branch"
+    )]
+    pub trait BranchValue {
+        type Value;
+
+        fn into_branch_value(self) -> Self::Value;
+    }
+
+    impl BranchValue for bool {
+        type Value = bool;
+
+        fn into_branch_value(self) -> bool {
+            self
+        }
+    }
+
+    impl BranchValue for &bool {
+        type Value = bool;
+
+        fn into_branch_value(self) -> bool {
+            *self
+        }
+    }
+
+    impl<T> BranchValue for crate::AuthRef<T>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        type Value = T;
+
+        fn into_branch_value(self) -> T {
+            crate::materialize_auth_return::<T, _>(self)
+        }
+    }
+
+    impl<T> BranchValue for &crate::AuthRef<T>
+    where
+        T: Clone + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        type Value = T;
+
+        fn into_branch_value(self) -> T {
+            crate::materialize_auth_return::<T, _>(self.clone())
+        }
+    }
+
+    pub fn branch_value<Condition: BranchValue>(condition: Condition) -> Condition::Value {
+        condition.into_branch_value()
+    }
+
     #[doc(hidden)]
     pub struct RecurSequenceIterationScopeGuard;
 
@@ -492,8 +629,12 @@ pub mod __private {
 /// let greeting = call!(greet, name);
 /// let result = call!(exclaim, greeting);
 /// let checked = call!(maybe_echo_name, result)?;
+/// let clean = call!(text::normalize, checked);
 /// ```
 ///
+/// The callee may be a path; tiles sharing a name across modules must be called
+/// through their module path so the compiler can tell them apart.
+///
 /// On `std` + non-riscv32 targets, the underlying tile function's `#[tile]` wrapper
 /// handles trace emission (`TraceEvent::Tile`). On `no_std` / riscv32 targets,
 /// this expands to a plain function call with no overhead.
@@ -502,10 +643,10 @@ pub mod __private {
 /// tile invocations to ensure the compiler can extract calls reliably.
 #[macro_export]
 macro_rules! call {
-    ($tile:ident $(,)?) => {
+    ($tile:path $(,)?) => {
         $tile()
     };
-    ($tile:ident, $($args:expr),+ $(,)?) => {
+    ($tile:path, $($args:expr),+ $(,)?) => {
         $tile($($args),+)
     };
 }
@@ -558,10 +699,10 @@ macro_rules! new {
 /// sequence invocations.
 #[macro_export]
 macro_rules! call_seq {
-    ($seq:ident $(,)?) => {
+    ($seq:path $(,)?) => {
         $seq()
     };
-    ($seq:ident, $($args:expr),+ $(,)?) => {
+    ($seq:path, $($args:expr),+ $(,)?) => {
         $seq($($args),+)
     };
 }
@@ -581,6 +722,25 @@ macro_rules! call_recur {
     };
 }
 
+/// Canonical list-call primitive for invoking a map tile inside a sequence.
+///
+/// `call_map!` is only valid inside `#[sequence]` functions, where the sequence macro
+/// rewrites it into a hidden driver that runs a `kind = map` tile once per item of a
+/// selectable list source and collects the outputs, by index, into a `Vec`. Map tiles
+/// carry no state between iterations, so their bodies run in parallel on the rayon pool
+/// (sized by `RAYON_NUM_THREADS`); the trace is the same as a sequential run's.
+///
+/// # Usage
+/// ```ignore
+/// let lengths = call_map!(tile = line_len, input = lines, args = ());
+/// ```
+#[macro_export]
+macro_rules! call_map {
+    ($($tt:tt)*) => {
+        compile_error!("call_map! can only be used inside #[sequence] functions")
+    };
+}
+
 /// Canonical recursive list-call primitive for invoking a recur sequence inside a sequence.
 ///
 /// `call_recur_seq!` is only valid inside `#[sequence]` functions, where the
@@ -625,13 +785,13 @@ pub mod prelude {
 
     pub use crate::exec::Result;
     pub use crate::{
-        call, call_recur, call_recur_seq, call_seq, external, finalize, internal, into_auth_ref,
-        into_draft, materialize_auth_result, materialize_auth_return, new, select, sequence, tile,
-        Anchor, AuthRef, AuthValue, Draft, ExternalSelection, ExternalValue, InternalRef,
-        InternalValue, IntoAuthRef, IntoAuthValue, IntoDraft, ListProofDirection, ListProofSibling,
-        Op, RecurControl, RecurInput, RecurOutput, RecurSequenceInput, RecurSequenceOutput,
-        RecurSequenceState, RecurState, Schema, SchemaField, SchemaFieldMode, SchemaNode,
-        SelectSource, Selectable, SelectedPayload, SelectionProof, SelectionProofStep,
+        call, call_map, call_recur, call_recur_seq, call_seq, external, finalize, internal,
+        into_auth_ref, into_draft, materialize_auth_result, materialize_auth_return, new, select,
+        sequence, tile, Anchor, AuthRef, AuthValue, Draft, ExternalSelection, ExternalValue,
+        InternalRef, InternalValue, IntoAuthRef, IntoAuthValue, IntoDraft, ListProofDirection,
+        ListProofSibling, Op, RecurControl, RecurInput, RecurOutput, RecurSequenceInput,
+        RecurSequenceOutput, RecurSequenceState, RecurState, Schema, SchemaField, SchemaFieldMode,
+        SchemaNode, SelectSource, Selectable, SelectedPayload, SelectionProof, SelectionProofStep,
         SelectorPath, SelectorSegment, TypedExternalBinding, TypedInternalBinding,
         TypedSelectorPath,
     };
@@ -397,7 +397,8 @@ fn nested_sequence_trace_records_terminal_err_outputs() {
             | TraceEvent::RecurTileExec(_)
             | TraceEvent::RecurSequenceStart(_)
             | TraceEvent::RecurSequenceEnd(_)
-            | TraceEvent::RecurSequenceExec(_) => false,
+            | TraceEvent::RecurSequenceExec(_)
+            | TraceEvent::TileFailed(..) => false,
         })
         .collect();
 
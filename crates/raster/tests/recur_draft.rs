@@ -282,6 +282,11 @@ fn count_until_limit_state_only(
     }
 }
 
+#[tile(kind = map)]
+fn offset_line_len(input: RecurInput<String>, offset: u64) -> u64 {
+    input.value().len() as u64 + offset
+}
+
 #[tile]
 fn prefix_line(line: String, prefix: String) -> String {
     format!("{}{}", prefix, line)
@@ -395,6 +400,18 @@ fn state_only_empty_input() -> MaxLenState {
     )
 }
 
+#[sequence]
+fn compute_offset_line_lens(offset: u64) -> Vec<u64> {
+    let lines: Vec<String> = (0..32).map(|len| "x".repeat(len)).collect();
+    let source = raster::store_internal_value(&lines).expect("list source should store");
+
+    call_map!(
+        tile = offset_line_len,
+        input = internal!(Vec<String>, source),
+        args = (offset,)
+    )
+}
+
 #[sequence]
 fn build_prefixed_lines_with_recur_sequence() -> LineBundle {
     let source = raster::store_internal_value(&vec![
@@ -436,6 +453,10 @@ fn run_state_only_empty_input() -> MaxLenState {
     materialize_auth_return::<MaxLenState, _>(__raster_sequence_auth_state_only_empty_input())
 }
 
+fn run_compute_offset_line_lens(offset: u64) -> Vec<u64> {
+    materialize_auth_return::<Vec<u64>, _>(__raster_sequence_auth_compute_offset_line_lens(offset))
+}
+
 fn run_build_prefixed_lines_with_recur_sequence() -> LineBundle {
     materialize_auth_return::<LineBundle, _>(
         __raster_sequence_auth_build_prefixed_lines_with_recur_sequence(),
@@ -546,6 +567,37 @@ fn call_recur_state_only_empty_input_returns_initial_state() {
     assert_eq!(result.max_len, 0);
 }
 
+#[test]
+fn call_map_collects_outputs_in_item_order() {
+    let result = run_compute_offset_line_lens(100);
+
+    assert_eq!(result, (100..132).collect::<Vec<u64>>());
+}
+
+#[test]
+fn call_map_traces_each_item_in_order() {
+    let (result, events) = capture_trace_events(|| run_compute_offset_line_lens(7));
+    let outputs: Vec<u64> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceEvent::RecurTileIterationExec(record) if record.fn_name == "offset_line_len" => {
+                let output = record.output.as_ref().expect("map iteration output");
+                Some(raster::core::postcard::from_bytes(&output.data).expect("u64 output"))
+            }
+            _ => None,
+        })
+        .collect();
+    let site_events = events
+        .iter()
+        .filter(|event| {
+            matches!(event, TraceEvent::RecurTileExec(record) if record.fn_name == "offset_line_len")
+        })
+        .count();
+
+    assert_eq!(outputs, result);
+    assert_eq!(site_events, 1);
+}
+
 #[test]
 fn call_recur_seq_orchestrates_tiles_per_item() {
     let result = run_build_prefixed_lines_with_recur_sequence();
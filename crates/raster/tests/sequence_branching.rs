@@ -0,0 +1,172 @@
+use raster::core::trace::TraceEvent;
+use raster::materialize_auth_return;
+use raster::prelude::*;
+use raster_runtime::{init_with, Publisher};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Once};
+use std::thread::ThreadId;
+
+static TRACE_CAPTURE_LOCK: Mutex<()> = Mutex::new(());
+static TRACE_INIT: Once = Once::new();
+static TRACE_EVENTS: Mutex<Vec<TraceEvent>> = Mutex::new(Vec::new());
+static TRACE_CAPTURE_ACTIVE: AtomicBool = AtomicBool::new(false);
+static TRACE_CAPTURE_THREAD: Mutex<Option<ThreadId>> = Mutex::new(None);
+
+struct TestPublisher;
+
+impl Publisher for TestPublisher {
+    fn publish(&self, event: TraceEvent) {
+        let current_thread = std::thread::current().id();
+        let capture_thread = TRACE_CAPTURE_THREAD.lock().unwrap().to_owned();
+        if TRACE_CAPTURE_ACTIVE.load(Ordering::SeqCst) && capture_thread == Some(current_thread) {
+            TRACE_EVENTS.lock().unwrap().push(event);
+        }
+    }
+
+    fn finish(&self) {}
+}
+
+fn capture_trace_events<F, T>(f: F) -> (T, Vec<TraceEvent>)
+where
+    F: FnOnce() -> T,
+{
+    let _guard = TRACE_CAPTURE_LOCK.lock().unwrap();
+    TRACE_INIT.call_once(|| init_with(TestPublisher));
+    TRACE_EVENTS.lock().unwrap().clear();
+    *TRACE_CAPTURE_THREAD.lock().unwrap() = Some(std::thread::current().id());
+    TRACE_CAPTURE_ACTIVE.store(true, Ordering::SeqCst);
+
+    let result = f();
+    let events = TRACE_EVENTS.lock().unwrap().clone();
+    TRACE_CAPTURE_ACTIVE.store(false, Ordering::SeqCst);
+    *TRACE_CAPTURE_THREAD.lock().unwrap() = None;
+    (result, events)
+}
+
+fn executed_tiles(events: &[TraceEvent]) -> Vec<String> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            TraceEvent::TileExec(record) => Some(record.fn_name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+enum Verdict {
+    Accept,
+    Reject,
+    Defer,
+}
+
+impl Selectable for Verdict {
+    fn schema() -> SchemaNode {
+        SchemaNode::Leaf {
+            type_name: "Verdict".into(),
+        }
+    }
+}
+
+#[tile]
+fn is_even(n: u64) -> bool {
+    n.is_multiple_of(2)
+}
+
+#[tile]
+fn halve(n: u64) -> u64 {
+    n / 2
+}
+
+#[tile]
+fn triple_plus_one(n: u64) -> u64 {
+    3 * n + 1
+}
+
+#[tile]
+fn judge(n: u64) -> Verdict {
+    match n {
+        0 => Verdict::Defer,
+        n if n < 10 => Verdict::Accept,
+        _ => Verdict::Reject,
+    }
+}
+
+#[tile]
+fn label(n: u64) -> String {
+    format!("#{}", n)
+}
+
+#[sequence]
+fn collatz_step(n: u64) -> InternalRef {
+    let even = call!(is_even, n.clone());
+    let next = if even {
+        call!(halve, n.clone())
+    } else {
+        call!(triple_plus_one, n)
+    };
+    let labelled = call!(label, next);
+    labelled.reference().clone()
+}
+
+#[sequence]
+fn judged_label(n: u64) -> InternalRef {
+    let verdict = call!(judge, n.clone());
+    match verdict {
+        Verdict::Accept => {
+            call!(halve, n.clone());
+        }
+        Verdict::Reject => {
+            call!(triple_plus_one, n.clone());
+        }
+        _ => {}
+    }
+    let labelled = call!(label, n);
+    labelled.reference().clone()
+}
+
+fn run_collatz_step(n: u64) -> InternalRef {
+    materialize_auth_return::<InternalRef, _>(__raster_sequence_auth_collatz_step(n))
+}
+
+fn run_judged_label(n: u64) -> InternalRef {
+    materialize_auth_return::<InternalRef, _>(__raster_sequence_auth_judged_label(n))
+}
+
+#[test]
+fn if_branch_runs_only_the_taken_arm() {
+    let (_, events) = capture_trace_events(|| run_collatz_step(6));
+    assert_eq!(executed_tiles(&events), vec!["is_even", "halve", "label"]);
+
+    let (_, events) = capture_trace_events(|| run_collatz_step(5));
+    assert_eq!(
+        executed_tiles(&events),
+        vec!["is_even", "triple_plus_one", "label"]
+    );
+}
+
+#[test]
+fn items_after_a_branch_keep_their_cfs_position() {
+    let taken_then = run_collatz_step(6);
+    let taken_else = run_collatz_step(5);
+
+    // `is_even` is item 0 and each arm holds one item, so `label` is item 3.
+    assert_eq!(taken_then.coordinates, taken_else.coordinates);
+    assert_eq!(taken_then.coordinates.0.last(), Some(&3));
+}
+
+#[test]
+fn match_branch_selects_arm_by_variant() {
+    let (_, events) = capture_trace_events(|| run_judged_label(4));
+    assert_eq!(executed_tiles(&events), vec!["judge", "halve", "label"]);
+
+    let (_, events) = capture_trace_events(|| run_judged_label(12));
+    assert_eq!(
+        executed_tiles(&events),
+        vec!["judge", "triple_plus_one", "label"]
+    );
+
+    let (_, events) = capture_trace_events(|| run_judged_label(0));
+    assert_eq!(executed_tiles(&events), vec!["judge", "label"]);
+}
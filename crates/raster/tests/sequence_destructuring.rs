@@ -0,0 +1,172 @@
+use raster::core::trace::TraceEvent;
+use raster::prelude::*;
+use raster::{materialize_auth_return, resolve_internal_value};
+use raster_runtime::{init_with, Publisher};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Once};
+use std::thread::ThreadId;
+
+static TRACE_CAPTURE_LOCK: Mutex<()> = Mutex::new(());
+static TRACE_INIT: Once = Once::new();
+static TRACE_EVENTS: Mutex<Vec<TraceEvent>> = Mutex::new(Vec::new());
+static TRACE_CAPTURE_ACTIVE: AtomicBool = AtomicBool::new(false);
+static TRACE_CAPTURE_THREAD: Mutex<Option<ThreadId>> = Mutex::new(None);
+
+struct TestPublisher;
+
+impl Publisher for TestPublisher {
+    fn publish(&self, event: TraceEvent) {
+        let current_thread = std::thread::current().id();
+        let capture_thread = TRACE_CAPTURE_THREAD.lock().unwrap().to_owned();
+        if TRACE_CAPTURE_ACTIVE.load(Ordering::SeqCst) && capture_thread == Some(current_thread) {
+            TRACE_EVENTS.lock().unwrap().push(event);
+        }
+    }
+
+    fn finish(&self) {}
+}
+
+fn capture_trace_events<F, T>(f: F) -> (T, Vec<TraceEvent>)
+where
+    F: FnOnce() -> T,
+{
+    let _guard = TRACE_CAPTURE_LOCK.lock().unwrap();
+    TRACE_INIT.call_once(|| init_with(TestPublisher));
+    TRACE_EVENTS.lock().unwrap().clear();
+    *TRACE_CAPTURE_THREAD.lock().unwrap() = Some(std::thread::current().id());
+    TRACE_CAPTURE_ACTIVE.store(true, Ordering::SeqCst);
+
+    let result = f();
+    let events = TRACE_EVENTS.lock().unwrap().clone();
+    TRACE_CAPTURE_ACTIVE.store(false, Ordering::SeqCst);
+    *TRACE_CAPTURE_THREAD.lock().unwrap() = None;
+    (result, events)
+}
+
+fn tile_input_selectors(events: &[TraceEvent], fn_name: &str) -> Vec<(String, SelectorPath)> {
+    events
+        .iter()
+        .find_map(|event| match event {
+            TraceEvent::TileExec(record) if record.fn_name == fn_name => record.input.as_ref(),
+            _ => None,
+        })
+        .expect("tile input should be recorded")
+        .internal
+        .iter()
+        .map(|(name, data)| {
+            assert_eq!(data.selector, data.selection.path);
+            (name.clone(), data.selector.clone())
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Summary {
+    total: u64,
+    caption: String,
+}
+
+#[tile]
+fn split(n: u64) -> (u64, (String, bool)) {
+    (n * 2, (format!("n={}", n), n > 10))
+}
+
+#[tile]
+fn summarize(n: u64) -> Summary {
+    Summary {
+        total: n + 1,
+        caption: format!("sum of {}", n),
+    }
+}
+
+#[tile]
+fn describe(value: u64, caption: String) -> String {
+    format!("{}: {}", caption, value)
+}
+
+#[sequence]
+fn split_and_describe(n: u64) -> InternalRef {
+    let (doubled, (caption, _)) = call!(split, n);
+    let described = call!(describe, doubled, caption);
+    described.reference().clone()
+}
+
+#[sequence]
+fn summarize_and_describe(n: u64) -> InternalRef {
+    let Summary { total, caption } = call!(summarize, n);
+    let described = call!(describe, total, caption);
+    described.reference().clone()
+}
+
+fn run_split_and_describe(n: u64) -> (String, Vec<TraceEvent>) {
+    capture_trace_events(|| {
+        let reference =
+            materialize_auth_return::<InternalRef, _>(__raster_sequence_auth_split_and_describe(n));
+        resolve_internal_value::<String>(reference)
+            .unwrap()
+            .into_inner()
+    })
+}
+
+fn run_summarize_and_describe(n: u64) -> (String, Vec<TraceEvent>) {
+    capture_trace_events(|| {
+        let reference = materialize_auth_return::<InternalRef, _>(
+            __raster_sequence_auth_summarize_and_describe(n),
+        );
+        resolve_internal_value::<String>(reference)
+            .unwrap()
+            .into_inner()
+    })
+}
+
+#[test]
+fn tuple_destructuring_binds_selections_of_the_call_output() {
+    let (described, events) = run_split_and_describe(4);
+    assert_eq!(described, "n=4: 8");
+
+    assert_eq!(
+        tile_input_selectors(&events, "describe"),
+        vec![
+            (
+                "caption".to_string(),
+                SelectorPath::new(vec![SelectorSegment::Index(1), SelectorSegment::Index(0)])
+            ),
+            (
+                "value".to_string(),
+                SelectorPath::new(vec![SelectorSegment::Index(0)])
+            ),
+        ]
+    );
+}
+
+#[test]
+fn struct_destructuring_binds_field_selections_of_the_call_output() {
+    let (described, events) = run_summarize_and_describe(4);
+    assert_eq!(described, "sum of 4: 5");
+
+    assert_eq!(
+        tile_input_selectors(&events, "describe"),
+        vec![
+            (
+                "caption".to_string(),
+                SelectorPath::new(vec![SelectorSegment::Field("caption".into())])
+            ),
+            (
+                "value".to_string(),
+                SelectorPath::new(vec![SelectorSegment::Field("total".into())])
+            ),
+        ]
+    );
+}
+
+#[test]
+fn destructuring_an_inline_value_is_an_error() {
+    let source: AuthRef<(u64, String)> = (4, "four".to_string()).into_auth_ref();
+    let selected = raster::select_destructured(
+        &source,
+        SelectorPath::new(vec![SelectorSegment::Index(0)]),
+        |value: &(u64, String)| &value.0,
+    );
+    assert!(selected.is_err());
+}
@@ -0,0 +1,8 @@
+use raster::prelude::*;
+
+#[tile(kind = map, max_memory = 4096)]
+fn line_len(input: RecurInput<String>) -> u64 {
+    input.value().len() as u64
+}
+
+fn main() {}
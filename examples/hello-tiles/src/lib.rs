@@ -179,6 +179,13 @@ pub fn compute_recur_max_line_len(
     state
 }
 
+/// Map: measure each line independently. The lines are measured in parallel
+/// in native runs and collected, in order, into a `Vec<u64>`.
+#[tile(kind = map)]
+pub fn measure_line(input: RecurInput<String>) -> u64 {
+    input.value().len() as u64
+}
+
 /// State+output recur: use loop-carried state to stop building output early.
 #[tile(kind = recur)]
 pub fn build_limited_recur_greeting(
@@ -155,6 +155,13 @@ fn main() {
     let recur_max_line_len = select!(u64, recur_line_stats.max_len);
     call!(fibonacci, recur_max_line_len);
 
+    let line_lengths = call_map!(
+        tile = measure_line,
+        input = address_lines.clone(),
+        args = ()
+    );
+    println!("map line lengths: {:?}", line_lengths);
+
     let limited_recur_greeting = call_recur!(
         tile = build_limited_recur_greeting,
         input = address_lines,